@@ -0,0 +1,117 @@
+use anyhow::{Context, Result};
+use hyper::{Body, Client, Method, Request};
+use serde_json::Value;
+use std::time::Duration;
+
+/// Default port of the mock PLC admin API
+pub const DEFAULT_ADMIN_PORT: u16 = 9502;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Client for the mock PLC's HTTP admin API
+pub struct ChaosClient {
+    base: String,
+}
+
+impl ChaosClient {
+    /// Accepts `host:port`, or a bare host using the default admin port
+    pub fn new(target: &str) -> Self {
+        let target = target.trim_start_matches("http://").trim_end_matches('/');
+        let base = if target.contains(':') {
+            format!("http://{}", target)
+        } else {
+            format!("http://{}:{}", target, DEFAULT_ADMIN_PORT)
+        };
+        Self { base }
+    }
+
+    pub async fn status(&self) -> Result<Value> {
+        self.call(Method::GET, "/chaos", None).await
+    }
+
+    pub async fn enable(
+        &self,
+        interval_secs: Option<u64>,
+        max_drift: Option<u16>,
+    ) -> Result<Value> {
+        let body = serde_json::json!({ "intervalSecs": interval_secs, "maxDrift": max_drift });
+        self.call(Method::POST, "/chaos/enable", Some(body)).await
+    }
+
+    pub async fn disable(&self) -> Result<Value> {
+        self.call(Method::POST, "/chaos/disable", None).await
+    }
+
+    pub async fn drift(&self, register: Option<u16>, amount: Option<i32>) -> Result<Value> {
+        let body = serde_json::json!({ "register": register, "amount": amount });
+        self.call(Method::POST, "/chaos/drift", Some(body)).await
+    }
+
+    pub async fn latency(&self, delay_ms: u64, jitter_ms: u64, percent: u8) -> Result<Value> {
+        let body =
+            serde_json::json!({ "delayMs": delay_ms, "jitterMs": jitter_ms, "percent": percent });
+        self.call(Method::POST, "/chaos/latency", Some(body)).await
+    }
+
+    pub async fn connections(
+        &self,
+        drop_percent: u8,
+        refuse_for_secs: u64,
+        refuse_every_secs: u64,
+    ) -> Result<Value> {
+        let body = serde_json::json!({
+            "dropPercent": drop_percent,
+            "refuseForSecs": refuse_for_secs,
+            "refuseEverySecs": refuse_every_secs,
+        });
+        self.call(Method::POST, "/chaos/connections", Some(body))
+            .await
+    }
+
+    pub async fn corruption(&self, percent: u8) -> Result<Value> {
+        let body = serde_json::json!({ "percent": percent });
+        self.call(Method::POST, "/chaos/corruption", Some(body))
+            .await
+    }
+
+    pub async fn outage(&self, secs: u64) -> Result<Value> {
+        let body = serde_json::json!({ "secs": secs });
+        self.call(Method::POST, "/chaos/outage", Some(body)).await
+    }
+
+    pub async fn reboot(&self, secs: u64) -> Result<Value> {
+        let body = serde_json::json!({ "secs": secs });
+        self.call(Method::POST, "/chaos/reboot", Some(body)).await
+    }
+
+    async fn call(&self, method: Method, path: &str, body: Option<Value>) -> Result<Value> {
+        let url = format!("{}{}", self.base, path);
+        let mut builder = Request::builder().method(method).uri(&url);
+        let body = match body {
+            Some(body) => {
+                builder = builder.header("content-type", "application/json");
+                Body::from(serde_json::to_vec(&body)?)
+            }
+            None => Body::empty(),
+        };
+        let request = builder.body(body)?;
+
+        let response = tokio::time::timeout(REQUEST_TIMEOUT, Client::new().request(request))
+            .await
+            .with_context(|| format!("Timed out contacting mock PLC admin API at {}", url))?
+            .with_context(|| format!("Failed to reach mock PLC admin API at {}", url))?;
+
+        let status = response.status();
+        let bytes = hyper::body::to_bytes(response.into_body()).await?;
+        if !status.is_success() {
+            anyhow::bail!(
+                "{} returned {}: {}",
+                url,
+                status,
+                String::from_utf8_lossy(&bytes)
+            );
+        }
+
+        serde_json::from_slice(&bytes).context("Invalid response from mock PLC admin API")
+    }
+}
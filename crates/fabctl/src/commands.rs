@@ -1,8 +1,49 @@
-use crate::k8s_client::K8sClient;
-use crate::output::{print_plc_table, print_status_summary, StatusStyle};
-use anyhow::Result;
+use crate::chaos::ChaosClient;
+use crate::completion::{self, Shell};
+use crate::config::{self, FabctlConfig};
+use crate::diff;
+use crate::doctor::{run_checks, CheckStatus};
+use crate::export::PlcSource;
+use crate::k8s_client::{describe_manifest, K8sClient, PlcEvent};
+use crate::manifests::{
+    self, OperatorManifestOptions, DEFAULT_IMAGE_REPOSITORY, METRICS_PORT, METRICS_SERVICE_NAME,
+};
+use crate::migrate;
+use crate::output::{
+    format_clock, format_timestamp, manifests_yaml, print_correction_requests, print_diff_report,
+    print_doctor_report, print_event_table, print_fleet_summary, print_group_table,
+    print_history_table, print_manifests, print_migration_report, print_phase_transitions,
+    print_plc_table, print_plc_table_highlighted, print_register_diff, print_scan_results,
+    print_snapshots, print_status_summary, print_templated, print_top, print_validation_report,
+    print_value_chart, print_verify_report, GroupHealth, PhaseTransition, StatusStyle, TimeDisplay,
+    ValuePoint,
+};
+use crate::plugins;
+use crate::prom::Scrape;
+use crate::report::{self, DocumentFormat};
+use crate::retry::ClientOptions;
+use crate::scan::{self, ScanOptions};
+use crate::snapshot;
+use crate::summary::FleetSummary;
+use crate::template::Template;
+use crate::validate::validate_paths;
+use crate::verify;
+use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
 use colored::*;
+use fabgitops_modbus::{Encoding, PLCClient};
+use fabgitops_types::crd::{
+    ApprovalDecision, CorrectionPolicy, CorrectionRequestState, Decision, IndustrialPLC,
+    IndustrialPLCSpec, IndustrialPLCStatus, MaintenanceWindow, PLCGroup, PLCPhase, PLCSnapshot,
+    PLCSnapshotSpec, ValueEncoding, Weekday, PLC_NAME_LABEL,
+};
+use fabgitops_types::{HOLD_UNTIL_ANNOTATION, MAX_HOLD_SECS, PROTECTED_ANNOTATION};
+use kube::ResourceExt;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
 
 #[derive(Parser)]
 #[command(name = "fabctl")]
@@ -16,9 +57,82 @@ pub struct Cli {
     #[arg(short, long, global = true, default_value = "default")]
     pub namespace: String,
 
-    /// Output format
-    #[arg(short, long, global = true, value_enum, default_value = "table")]
-    pub output: OutputFormat,
+    /// Output format: table, json, yaml, template=TEMPLATE with
+    /// {{.field.path}} placeholders (get-status and list only), or markdown
+    /// or html (report only)
+    #[arg(short, long, global = true, default_value = "table", value_parser = parse_output)]
+    pub output: Output,
+
+    /// Kubeconfig context to use
+    #[arg(long, global = true)]
+    pub context: Option<String>,
+
+    /// Apply the defaults of a profile from the fabctl config
+    #[arg(long, global = true)]
+    pub profile: Option<String>,
+
+    /// Show timestamps in UTC instead of as relative ages
+    #[arg(long, global = true, conflicts_with = "local")]
+    pub utc: bool,
+
+    /// Show timestamps in local time instead of as relative ages
+    #[arg(long, global = true)]
+    pub local: bool,
+
+    /// Make mutating commands (sync --force, group sync --force, set, delete,
+    /// snapshot restore, maintenance, clone, hold, approve, reject,
+    /// migrate --write, uninstall) ask before changing anything; --yes is
+    /// refused
+    #[arg(long, global = true)]
+    pub require_confirmation: bool,
+
+    /// Give up on a Kubernetes API request after this long (0 waits forever)
+    #[arg(long, global = true, default_value = "30s", value_parser = parse_duration)]
+    pub request_timeout: Duration,
+
+    /// Retry Kubernetes API requests this many times on transient errors
+    #[arg(long, global = true, default_value_t = 3)]
+    pub retries: u32,
+
+    /// Kubernetes API requests per second (0 disables rate limiting)
+    #[arg(long, global = true, default_value_t = 20.0)]
+    pub qps: f64,
+
+    /// Kubernetes API requests allowed in a burst above --qps
+    #[arg(long, global = true, default_value_t = 40)]
+    pub burst: u32,
+}
+
+impl Cli {
+    /// The fixed output format. Commands that do not render templates or
+    /// documents are refused up front when given one, so they never see it.
+    pub fn format(&self) -> OutputFormat {
+        match self.output {
+            Output::Format(format) => format,
+            Output::Template(_) | Output::Document(_) => OutputFormat::Table,
+        }
+    }
+
+    /// How tables should show timestamps
+    pub fn time_display(&self) -> TimeDisplay {
+        if self.utc {
+            TimeDisplay::Utc
+        } else if self.local {
+            TimeDisplay::Local
+        } else {
+            TimeDisplay::Relative
+        }
+    }
+
+    /// How the Kubernetes client should time out, retry and rate limit
+    pub fn client_options(&self) -> ClientOptions {
+        ClientOptions {
+            timeout: (!self.request_timeout.is_zero()).then_some(self.request_timeout),
+            retries: self.retries,
+            qps: self.qps,
+            burst: self.burst,
+        }
+    }
 }
 
 #[derive(Subcommand)]
@@ -26,14 +140,42 @@ pub enum Commands {
     /// Get status of all managed PLCs
     GetStatus {
         /// Filter by PLC name
-        #[arg(short, long)]
+        #[arg(long)]
         name: Option<String>,
+
+        /// Sort the listed PLCs; only by name together with --limit
+        #[arg(long, value_enum, default_value = "name")]
+        sort_by: SortKey,
+
+        /// Fetch at most this many PLCs (one page, in name order)
+        #[arg(long)]
+        limit: Option<u32>,
+
+        /// Continue token printed by a previous --limit call
+        #[arg(long = "continue", requires = "limit")]
+        continue_token: Option<String>,
+
+        /// Read PLCs from an exported file instead of the cluster
+        #[arg(long, value_name = "EXPORT")]
+        from_file: Option<PathBuf>,
     },
 
     /// Get detailed information about a specific PLC
     Describe {
         /// Name of the PLC resource
         name: String,
+
+        /// Also show the Kubernetes Events recorded for the PLC
+        #[arg(long)]
+        show_events: bool,
+
+        /// Also show the drift and correction history from its status
+        #[arg(long)]
+        show_history: bool,
+
+        /// Read the PLC from an exported file instead of the cluster
+        #[arg(long, value_name = "EXPORT")]
+        from_file: Option<PathBuf>,
     },
 
     /// Manually trigger a sync (reconciliation)
@@ -48,245 +190,3534 @@ pub enum Commands {
 
     /// Watch PLC status in real-time
     Watch {
+        /// Follow one PLC in a detail view instead of the whole namespace
+        name: Option<String>,
+
         /// Refresh interval in seconds
         #[arg(short, long, default_value = "2")]
         interval: u64,
+
+        /// Ring the terminal bell when a PLC enters DriftDetected or Failed
+        #[arg(long)]
+        bell: bool,
+
+        /// Flash the rows of PLCs that just entered DriftDetected or Failed
+        #[arg(long)]
+        flash: bool,
+
+        /// Hide PLCs that are in sync
+        #[arg(long, conflicts_with = "name")]
+        only_problems: bool,
     },
 
     /// List all managed PLCs
     List,
 
-    /// Show version information
-    Version,
-}
+    /// Inspect and sync PLCGroups
+    Group {
+        #[command(subcommand)]
+        action: GroupAction,
+    },
 
-#[derive(Clone, Copy, Debug, clap::ValueEnum)]
-pub enum OutputFormat {
-    Table,
-    Json,
-    Yaml,
-}
+    /// Set or clear recurring maintenance windows (corrections are deferred)
+    Maintenance {
+        #[command(subcommand)]
+        action: MaintenanceAction,
+    },
 
-/// Execute the get-status command
-pub async fn cmd_get_status(
-    client: &K8sClient,
-    namespace: &str,
-    name_filter: Option<&str>,
-    format: OutputFormat,
-) -> Result<()> {
-    let plcs: Vec<operator::crd::IndustrialPLC> = client.list_plcs(namespace).await?;
+    /// Capture, list, and restore blocks of PLC registers
+    Snapshot {
+        #[command(subcommand)]
+        action: SnapshotAction,
+    },
 
-    let filtered: Vec<_> = if let Some(name) = name_filter {
-        plcs.into_iter()
-            .filter(|p: &operator::crd::IndustrialPLC| {
-                p.metadata
-                    .name
-                    .as_ref()
-                    .map(|n| n.contains(name))
-                    .unwrap_or(false)
-            })
-            .collect()
-    } else {
-        plcs
-    };
+    /// Create a new PLC from an existing one's spec, overriding selected fields
+    Clone {
+        /// PLC resource to copy
+        source: String,
 
-    match format {
-        OutputFormat::Table => print_plc_table(&filtered),
-        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&filtered)?),
-        OutputFormat::Yaml => println!("{}", serde_yaml::to_string(&filtered)?),
-    }
+        /// Name of the new PLC resource
+        name: String,
 
-    Ok(())
-}
+        /// Device address of the new PLC
+        #[arg(long)]
+        device: Option<String>,
 
-/// Execute the describe command
-pub async fn cmd_describe(client: &K8sClient, namespace: &str, name: &str) -> Result<()> {
-    let plc = client.get_plc(namespace, name).await?;
+        /// Modbus TCP port of the new PLC
+        #[arg(long)]
+        port: Option<u16>,
 
-    println!(
-        "{}",
-        "╔════════════════════════════════════════════════════════════╗".bright_blue()
-    );
-    println!(
-        "{}",
-        "║              Industrial PLC Resource Details               ║".bright_blue()
-    );
-    println!(
-        "{}",
-        "╚════════════════════════════════════════════════════════════╝".bright_blue()
-    );
-    println!();
+        /// Target register of the new PLC
+        #[arg(long)]
+        register: Option<u16>,
 
-    // Metadata
-    println!("{}", "📋 Metadata:".bold().underline());
-    println!(
-        "  Name:        {}",
-        plc.metadata.name.as_deref().unwrap_or("N/A").cyan()
-    );
-    println!(
-        "  Namespace:   {}",
-        plc.metadata.namespace.as_deref().unwrap_or("N/A")
-    );
-    println!(
-        "  Created:     {}",
-        plc.metadata
-            .creation_timestamp
-            .as_ref()
-            .map(|t| t.0.to_string())
-            .unwrap_or_default()
-    );
-    println!();
+        /// Target value of the new PLC
+        #[arg(long)]
+        value: Option<u16>,
 
-    // Spec
-    println!("{}", "⚙️  Specification:".bold().underline());
-    println!("  Device Address:  {}", plc.spec.device_address.cyan());
-    println!("  Port:            {}", plc.spec.port);
-    println!("  Target Register: {}", plc.spec.target_register);
-    println!(
-        "  Target Value:    {}",
-        plc.spec.target_value.to_string().green()
-    );
-    println!("  Poll Interval:   {}s", plc.spec.poll_interval_secs);
-    println!(
-        "  Auto Correct:    {}",
-        if plc.spec.auto_correct {
-            "✓ enabled".green()
-        } else {
-            "✗ disabled".red()
-        }
-    );
-    if !plc.spec.tags.is_empty() {
-        println!("  Tags:            {}", plc.spec.tags.join(", "));
-    }
-    println!();
+        /// Labels to set on the new PLC, e.g. line=4 (repeatable)
+        #[arg(short, long, value_parser = parse_label)]
+        label: Vec<(String, String)>,
 
-    // Status
-    if let Some(status) = plc.status {
-        let style = if status.in_sync {
-            StatusStyle::Success
-        } else if matches!(status.phase, operator::crd::PLCPhase::DriftDetected) {
-            StatusStyle::Warning
-        } else {
-            StatusStyle::Error
-        };
+        /// Print the manifest instead of creating the resource
+        #[arg(long)]
+        dry_run: bool,
+    },
 
-        print_status_summary(&status, style);
-    } else {
-        println!("{}", "⚠️  No status available".yellow());
-    }
+    /// Delete a PLC, unless it is protected by the fabgitops.io/protected annotation
+    Delete {
+        /// Name of the PLC resource
+        name: String,
+    },
 
-    Ok(())
-}
+    /// Change the target value of one PLC or every PLC matching a selector
+    Set {
+        /// Name of the PLC resource
+        #[arg(required_unless_present = "selector", conflicts_with = "selector")]
+        name: Option<String>,
 
-/// Execute the sync command
-pub async fn cmd_sync(client: &K8sClient, namespace: &str, name: &str, force: bool) -> Result<()> {
-    use indicatif::{ProgressBar, ProgressStyle};
+        /// Label selector, e.g. line=4,app=fabgitops
+        #[arg(short = 'l', long)]
+        selector: Option<String>,
 
-    println!("{}", "🔄 Triggering manual sync...".cyan());
+        /// New target value
+        #[arg(long)]
+        value: u16,
 
-    let spinner = ProgressBar::new_spinner();
-    spinner.set_style(
-        ProgressStyle::default_spinner()
-            .template("{spinner:.cyan} {msg}")
-            .unwrap(),
-    );
-    spinner.set_message("Annotating resource...");
+        /// Show the change without applying it
+        #[arg(long)]
+        dry_run: bool,
 
-    client.trigger_reconcile(namespace, name, force).await?;
+        /// Skip the confirmation prompt
+        #[arg(short, long)]
+        yes: bool,
+    },
 
-    spinner.finish_with_message(format!("{}", "✓ Sync triggered successfully!".green()));
+    /// Hold a PLC at the value it reads, so a technician's adjustment is not
+    /// corrected before the Git change lands
+    Hold {
+        /// Name of the PLC resource
+        name: String,
 
-    // Show updated status
-    println!();
-    println!("{}", "Fetching updated status...".dimmed());
-    tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+        /// How long to hold the value (e.g. 30m, 2h; at most 12h)
+        #[arg(long = "for", default_value = "30m", value_parser = parse_duration)]
+        duration: Duration,
 
-    cmd_describe(client, namespace, name).await?;
+        /// Why the value is held, shown in the operator's event
+        #[arg(long)]
+        reason: Option<String>,
 
-    Ok(())
-}
+        /// End the hold now
+        #[arg(long, conflicts_with_all = ["duration", "reason"])]
+        release: bool,
+    },
 
-/// Execute the watch command
-pub async fn cmd_watch(client: &K8sClient, namespace: &str, interval_secs: u64) -> Result<()> {
-    use std::io::stdout;
+    /// Validate IndustrialPLC manifests offline (no cluster access needed)
+    Validate {
+        /// Manifest files or directories to check (repeatable)
+        #[arg(short = 'f', long = "filename", required = true)]
+        files: Vec<PathBuf>,
+    },
 
-    println!("{}", "👁️  Watching PLC status (Ctrl+C to exit)...".cyan());
-    println!();
+    /// Rewrite IndustrialPLCs that use deprecated fields to the current schema
+    Migrate {
+        /// Manifest files or directories to rewrite (repeatable); without
+        /// any, the PLCs in the namespace are migrated
+        #[arg(short = 'f', long = "filename")]
+        files: Vec<PathBuf>,
 
-    let mut stdout = stdout();
+        /// Apply the changes; without it they are only shown
+        #[arg(long)]
+        write: bool,
+    },
 
-    loop {
-        // Clear screen using ANSI escape codes
-        print!("\x1B[2J\x1B[1;1H");
+    /// Generate manifests from the compiled resource types
+    Gen {
+        #[command(subcommand)]
+        target: GenTarget,
+    },
 
-        // Print header
-        println!(
-            "{}",
-            "╔════════════════════════════════════════════════════════════╗".bright_blue()
-        );
-        println!(
-            "{}",
-            "║           FabGitOps Live Dashboard (fabctl watch)          ║".bright_blue()
-        );
-        println!(
-            "{}",
-            "╚════════════════════════════════════════════════════════════╝".bright_blue()
-        );
-        println!(
-            "  Namespace: {} | Refresh: {}s | Press Ctrl+C to exit",
-            namespace.cyan(),
-            interval_secs
-        );
-        println!();
+    /// Discover Modbus TCP devices on a subnet and draft manifests for them
+    Scan {
+        /// Subnet in CIDR notation (e.g. 10.20.0.0/24) or a single address
+        target: String,
 
-        // Fetch and display
-        match client.list_plcs(namespace).await {
-            Ok(plcs) => print_plc_table(&plcs),
-            Err(e) => println!("{} {}", "Error:".red().bold(), e),
-        }
+        /// Modbus TCP port to probe
+        #[arg(short, long, default_value = "502")]
+        port: u16,
 
-        println!();
-        println!(
-            "{}",
-            format!(
-                "Last updated: {}",
-                chrono::Local::now().format("%Y-%m-%d %H:%M:%S")
-            )
-            .dimmed()
-        );
+        /// Register to read from each device, used as the drafted target value
+        #[arg(short, long)]
+        register: Option<u16>,
 
-        // Flush stdout
-        use std::io::Write;
-        stdout.flush()?;
+        /// Per-host connect and read timeout in milliseconds
+        #[arg(long, default_value = "500")]
+        timeout_ms: u64,
 
-        tokio::time::sleep(tokio::time::Duration::from_secs(interval_secs)).await;
-    }
-}
+        /// Maximum number of hosts probed at once
+        #[arg(long, default_value = "64")]
+        concurrency: usize,
 
-/// Execute the list command
-pub async fn cmd_list(client: &K8sClient, namespace: &str) -> Result<()> {
-    let plcs = client.list_plcs(namespace).await?;
+        /// Write drafted IndustrialPLC manifests to this file without prompting
+        #[arg(long)]
+        manifests: Option<PathBuf>,
+    },
 
-    println!("{}", "Managed Industrial PLCs".bold().underline());
-    println!();
+    /// Control drift scenarios on a mock PLC through its admin API
+    Chaos {
+        #[command(subcommand)]
+        action: ChaosAction,
 
-    for plc in plcs {
-        let name = plc.metadata.name.as_deref().unwrap_or("unknown");
-        let status_icon = if plc.status.as_ref().map(|s| s.in_sync).unwrap_or(false) {
-            "✓".green()
-        } else {
-            "✗".red()
-        };
+        /// Mock PLC admin API address (host:port)
+        #[arg(long, global = true, default_value = "localhost:9502")]
+        target: String,
+    },
 
-        println!(
-            "{} {} @ {}:{}",
-            status_icon,
-            name.cyan(),
-            plc.spec.device_address,
-            plc.spec.port
-        );
-    }
+    /// Install the CRD, RBAC, and operator Deployment into the current cluster
+    Install {
+        /// Operator image tag to deploy
+        #[arg(long, default_value = "latest")]
+        version: String,
+
+        /// Full operator image reference (overrides --version)
+        #[arg(long)]
+        image: Option<String>,
+
+        /// Validate everything server-side without persisting any changes
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Seconds to wait for the CRD and operator to become ready
+        #[arg(long, default_value = "120")]
+        timeout: u64,
+    },
+
+    /// Remove the operator from the current cluster
+    Uninstall {
+        /// Also delete all IndustrialPLC resources and the CRD
+        #[arg(long)]
+        delete_crs: bool,
+
+        /// Seconds to wait for IndustrialPLC resources to be removed
+        #[arg(long, default_value = "120")]
+        timeout: u64,
+
+        /// Skip the confirmation prompt of --delete-crs
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Diagnose the FabGitOps installation and device connectivity
+    Doctor {
+        /// Namespace the operator runs in (defaults to --namespace)
+        #[arg(long)]
+        operator_namespace: Option<String>,
+    },
+
+    /// Summarize the fleet: phases, top drifting PLCs, unreachable devices
+    /// and the recent correction rate
+    Summary {
+        /// Number of most-drifting PLCs to list
+        #[arg(long, default_value = "5")]
+        top: usize,
+
+        /// Window for the correction rate (e.g. 30m, 1h, 1d)
+        #[arg(long, value_parser = parse_duration, default_value = "1h")]
+        window: Duration,
+
+        /// Namespace the operator runs in (defaults to --namespace)
+        #[arg(long)]
+        operator_namespace: Option<String>,
+    },
+
+    /// Show live operator and fleet metrics
+    Top {
+        /// Namespace the operator runs in (defaults to --namespace)
+        #[arg(long)]
+        operator_namespace: Option<String>,
+
+        /// Refresh interval in seconds
+        #[arg(short, long, default_value = "5")]
+        interval: u64,
+
+        /// Number of slowest devices to show
+        #[arg(long, default_value = "10")]
+        limit: usize,
+    },
+
+    /// Show the drift and correction timeline of a PLC
+    History {
+        /// Name of the PLC resource
+        name: String,
+
+        /// Only show events newer than this (e.g. 30m, 24h, 7d)
+        #[arg(long, value_parser = parse_duration)]
+        since: Option<Duration>,
+    },
+
+    /// Sample a PLC's value at a fixed interval and write it as CSV
+    Record {
+        /// Name of the PLC resource
+        name: String,
+
+        /// Time between samples (e.g. 500ms, 1s, 1m)
+        #[arg(short, long, default_value = "1s", value_parser = parse_duration)]
+        interval: Duration,
+
+        /// Stop after this long (e.g. 10m); runs until Ctrl+C when omitted
+        #[arg(short, long, value_parser = parse_duration)]
+        duration: Option<Duration>,
+
+        /// CSV file to write (defaults to stdout)
+        #[arg(long)]
+        out: Option<PathBuf>,
+
+        /// Read the register over Modbus instead of from the resource status
+        #[arg(long)]
+        direct: bool,
+    },
+
+    /// Chart recent values of a PLC against its desired value
+    Plot {
+        /// Name of the PLC resource
+        name: String,
+
+        /// Time window to chart (e.g. 15m, 1h, 1d)
+        #[arg(long, default_value = "1h", value_parser = parse_duration)]
+        since: Duration,
+
+        /// Keep sampling the resource status and redraw the chart
+        #[arg(long)]
+        live: bool,
+
+        /// Time between samples in --live mode
+        #[arg(short, long, default_value = "2s", value_parser = parse_duration)]
+        interval: Duration,
+
+        /// Chart width in columns
+        #[arg(long, default_value = "60")]
+        width: usize,
+
+        /// Chart height in rows
+        #[arg(long, default_value = "12")]
+        height: usize,
+    },
+
+    /// Render a shareable status report for pasting into tickets
+    /// (-o markdown or -o html)
+    Report {
+        /// Name of the PLC resource
+        #[arg(required_unless_present = "all", conflicts_with = "all")]
+        name: Option<String>,
+
+        /// Report on every PLC in the namespace
+        #[arg(long)]
+        all: bool,
+
+        /// Namespace the operator runs in, for the metrics excerpt
+        /// (defaults to --namespace)
+        #[arg(long)]
+        operator_namespace: Option<String>,
+    },
+
+    /// Manage CorrectionRequests raised under the ManualApproval policy
+    Approvals {
+        #[command(subcommand)]
+        action: ApprovalsAction,
+    },
+
+    /// Approve a pending CorrectionRequest
+    Approve {
+        /// Name of the CorrectionRequest
+        request: String,
+
+        /// Optional note recorded with the approval
+        #[arg(long)]
+        reason: Option<String>,
+    },
+
+    /// Reject a pending CorrectionRequest
+    Reject {
+        /// Name of the CorrectionRequest
+        request: String,
+
+        /// Why the correction was rejected
+        #[arg(long, required = true)]
+        reason: String,
+    },
+
+    /// Manage fabctl-<name> plugins found on PATH
+    Plugin {
+        #[command(subcommand)]
+        action: PluginAction,
+    },
+
+    /// Compare manifests in Git with the cluster and live device values
+    Verify {
+        /// Git repository holding the PLC manifests (URL or local path)
+        #[arg(long)]
+        git_repo: String,
+
+        /// Directory or file inside the repository to read manifests from
+        #[arg(long, default_value = ".")]
+        path: PathBuf,
+
+        /// Branch or tag to check out instead of the default branch
+        #[arg(long = "ref")]
+        git_ref: Option<String>,
+
+        /// Compare Git with the cluster only, without reading devices
+        #[arg(long)]
+        no_device: bool,
+
+        /// Timeout for each device read, in milliseconds
+        #[arg(long, default_value = "2000")]
+        timeout_ms: u64,
+    },
+
+    /// Preview the spec changes applying manifests would make to the cluster
+    Diff {
+        /// Manifest files or directories to compare with the cluster
+        #[arg(short = 'f', long = "filename", required = true)]
+        files: Vec<PathBuf>,
+
+        /// Compare with PLCs exported to a file instead of the cluster
+        #[arg(long, value_name = "EXPORT")]
+        from_file: Option<PathBuf>,
+    },
+
+    /// Manage named defaults stored in ~/.config/fabctl/config.yaml
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+
+    /// Show version information
+    Version,
+
+    /// Print a shell script completing commands, flags and resource names
+    Completion {
+        /// Shell to write the script for
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+
+    /// Candidates for the last of `words`, for the completion scripts
+    #[command(name = "__complete", hide = true)]
+    Complete {
+        /// Arguments after `fabctl`, ending with the one being completed
+        #[arg(allow_hyphen_values = true, trailing_var_arg = true)]
+        words: Vec<String>,
+    },
+
+    /// Any other command runs the `fabctl-<command>` plugin from PATH
+    #[command(external_subcommand)]
+    External(Vec<String>),
+}
+
+#[derive(Subcommand)]
+pub enum PluginAction {
+    /// List plugins available on PATH
+    List,
+}
+
+#[derive(Subcommand)]
+pub enum ConfigAction {
+    /// Create or update a profile from --namespace, --context and
+    /// --require-confirmation
+    SetProfile {
+        /// Profile name
+        name: String,
+
+        /// Stop requiring confirmation under this profile
+        #[arg(long, conflicts_with = "require_confirmation")]
+        no_require_confirmation: bool,
+    },
+
+    /// Make a profile the default for every command
+    UseProfile {
+        /// Profile name
+        name: String,
+    },
+
+    /// List profiles
+    GetProfiles,
+
+    /// Delete a profile
+    DeleteProfile {
+        /// Profile name
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ApprovalsAction {
+    /// List correction requests (pending only unless --all)
+    List {
+        /// Include decided and superseded requests
+        #[arg(long)]
+        all: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum GroupAction {
+    /// List groups with aggregated member health
+    List,
+    /// Show a group's definition and the status of each member
+    Describe {
+        /// Name of the PLCGroup
+        name: String,
+    },
+    /// Trigger a sync on every member of a group
+    Sync {
+        /// Name of the PLCGroup
+        name: String,
+
+        /// Force correction even if auto-correct is disabled
+        #[arg(short, long)]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum MaintenanceAction {
+    /// Replace the maintenance windows of one PLC or all PLCs
+    Set {
+        /// Name of the PLC resource
+        #[arg(required_unless_present = "all", conflicts_with = "all")]
+        name: Option<String>,
+
+        /// Apply to every PLC in the namespace
+        #[arg(long)]
+        all: bool,
+
+        /// Opening time of day (HH:MM)
+        #[arg(long, value_parser = parse_time_of_day)]
+        from: String,
+
+        /// Closing time of day (HH:MM); earlier than --from spans midnight
+        #[arg(long, value_parser = parse_time_of_day)]
+        to: String,
+
+        /// Days the window opens on, e.g. sat,sun (every day when omitted)
+        #[arg(long, value_delimiter = ',', value_parser = parse_weekday)]
+        days: Vec<Weekday>,
+
+        /// Site offset from UTC, e.g. +02:00 (defaults to this machine's offset)
+        #[arg(long, value_parser = parse_utc_offset, allow_hyphen_values = true)]
+        utc_offset: Option<i32>,
+    },
+    /// Remove all maintenance windows from one PLC or all PLCs
+    Clear {
+        /// Name of the PLC resource
+        #[arg(required_unless_present = "all", conflicts_with = "all")]
+        name: Option<String>,
+
+        /// Apply to every PLC in the namespace
+        #[arg(long)]
+        all: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SnapshotAction {
+    /// Read a block of registers from a PLC and store it as a PLCSnapshot
+    Create {
+        /// Name of the PLC resource
+        plc: String,
+
+        /// Inclusive register range, e.g. 4000-4050
+        #[arg(long, value_parser = snapshot::parse_register_range)]
+        range: (u16, u16),
+
+        /// Snapshot name (defaults to <plc>-<timestamp>)
+        #[arg(long)]
+        name: Option<String>,
+    },
+    /// List stored snapshots
+    List {
+        /// Only show snapshots of this PLC
+        #[arg(long)]
+        plc: Option<String>,
+    },
+    /// Write a snapshot's values back to its PLC after previewing the diff
+    Restore {
+        /// Name of the PLCSnapshot
+        snapshot: String,
+
+        /// Show the diff without writing anything
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Skip the confirmation prompt
+        #[arg(short, long)]
+        yes: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ChaosAction {
+    /// Show chaos settings and the current register value
+    Status,
+    /// Start drifting the register at random
+    Enable {
+        /// Seconds between drifts
+        #[arg(long)]
+        interval: Option<u64>,
+
+        /// Largest drift applied at once
+        #[arg(long)]
+        max_drift: Option<u16>,
+    },
+    /// Stop drifting the register
+    Disable,
+    /// Apply a single drift immediately
+    Drift {
+        /// Amount to add to the register (random when omitted)
+        #[arg(long, allow_hyphen_values = true)]
+        amount: Option<i32>,
+
+        /// Holding register to drift (one of the chaos registers when omitted)
+        #[arg(long)]
+        register: Option<u16>,
+    },
+    /// Delay Modbus responses; zero delay and jitter turns it off
+    Latency {
+        /// Fixed delay in milliseconds
+        #[arg(long, default_value = "0")]
+        delay_ms: u64,
+
+        /// Random extra delay of up to this many milliseconds
+        #[arg(long, default_value = "0")]
+        jitter_ms: u64,
+
+        /// Percentage of responses to delay
+        #[arg(long, default_value = "100", value_parser = clap::value_parser!(u8).range(0..=100))]
+        percent: u8,
+    },
+    /// Drop connections mid-transaction and refuse new ones; zeros turn it off
+    Connections {
+        /// Percentage of requests answered by closing the connection
+        #[arg(long, default_value = "0", value_parser = clap::value_parser!(u8).range(0..=100))]
+        drop_percent: u8,
+
+        /// Refuse new connections for this many seconds of every cycle
+        #[arg(long, default_value = "0")]
+        refuse_for: u64,
+
+        /// Length of the refusal cycle in seconds
+        #[arg(long, default_value = "60")]
+        refuse_every: u64,
+    },
+    /// Send malformed responses (wrong length, garbage payload, or wrong
+    /// function code); zero turns it off
+    Corruption {
+        /// Percentage of responses to corrupt
+        #[arg(long, value_parser = clap::value_parser!(u8).range(0..=100))]
+        percent: u8,
+    },
+    /// Refuse every new connection for a while, starting now
+    Outage {
+        /// Length of the outage in seconds
+        #[arg(long, default_value = "30", value_parser = clap::value_parser!(u64).range(1..))]
+        secs: u64,
+    },
+    /// Power-cycle the device: drop every connection, stay silent, then
+    /// come back with registers at their initial values
+    Reboot {
+        /// Seconds the device stays silent
+        #[arg(long, default_value = "10", value_parser = clap::value_parser!(u64).range(1..))]
+        secs: u64,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum GenTarget {
+    /// Print the IndustrialPLC CustomResourceDefinition
+    Crd,
+
+    /// Print the operator ServiceAccount, RBAC, Deployment, and Service
+    Operator {
+        /// Operator container image
+        #[arg(long, default_value_t = format!("{}:latest", DEFAULT_IMAGE_REPOSITORY))]
+        image: String,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum SortKey {
+    /// Alphabetical by name
+    Name,
+    /// Most drift events first
+    Drifts,
+    /// Worst phase first (Failed, DriftDetected, Correcting, ...)
+    Phase,
+    /// Most recently updated first
+    LastUpdate,
+}
+
+#[derive(Clone, Copy, Debug, clap::ValueEnum)]
+pub enum OutputFormat {
+    Table,
+    Json,
+    Yaml,
+}
+
+/// What `-o` asked for
+#[derive(Clone, Debug)]
+pub enum Output {
+    Format(OutputFormat),
+    /// `template=TEMPLATE`, rendered once per PLC
+    Template(Template),
+    /// `markdown` or `html`, a document for `fabctl report`
+    Document(DocumentFormat),
+}
+
+/// Execute the get-status command
+pub async fn cmd_get_status(
+    source: &PlcSource<'_>,
+    namespace: &str,
+    name_filter: Option<&str>,
+    sort_by: SortKey,
+    page: Option<(u32, Option<&str>)>,
+    output: &Output,
+) -> Result<()> {
+    // A page is an arbitrary slice of the fleet for any other order
+    if page.is_some() && sort_by != SortKey::Name {
+        let key = clap::ValueEnum::to_possible_value(&sort_by)
+            .map(|v| v.get_name().to_string())
+            .unwrap_or_default();
+        anyhow::bail!(
+            "--sort-by {} would only sort one page; drop --limit to sort every PLC",
+            key
+        );
+    }
+    let (plcs, next) = match page {
+        Some((limit, token)) => source.list_plcs_page(namespace, limit, token).await?,
+        None => (source.list_plcs(namespace).await?, None),
+    };
+
+    let mut filtered: Vec<_> = if let Some(name) = name_filter {
+        plcs.into_iter()
+            .filter(|p: &fabgitops_types::crd::IndustrialPLC| {
+                p.metadata
+                    .name
+                    .as_ref()
+                    .map(|n| n.contains(name))
+                    .unwrap_or(false)
+            })
+            .collect()
+    } else {
+        plcs
+    };
+    sort_plcs(&mut filtered, sort_by);
+
+    match output {
+        // Documents are refused up front for everything but report
+        Output::Format(OutputFormat::Table) | Output::Document(_) => print_plc_table(&filtered),
+        Output::Format(OutputFormat::Json) => {
+            println!("{}", serde_json::to_string_pretty(&filtered)?)
+        }
+        Output::Format(OutputFormat::Yaml) => println!("{}", serde_yaml::to_string(&filtered)?),
+        Output::Template(template) => print_templated(&filtered, template)?,
+    }
+
+    // Keep stdout parseable; the token is a hint for the next invocation
+    if let Some(token) = next {
+        eprintln!(
+            "{} More PLCs available, fetch the next page with: --continue {}",
+            "→".dimmed(),
+            token
+        );
+    }
+
+    Ok(())
+}
+
+/// Sort PLCs in place; ties are broken by name
+fn sort_plcs(plcs: &mut [IndustrialPLC], key: SortKey) {
+    fn phase_rank(plc: &IndustrialPLC) -> u8 {
+        match plc.status.as_ref().map(|s| &s.phase) {
+            Some(PLCPhase::Failed) => 0,
+            Some(PLCPhase::DriftDetected) => 1,
+            Some(PLCPhase::Correcting) => 2,
+            Some(PLCPhase::Connecting) => 3,
+            Some(PLCPhase::Pending | PLCPhase::Unscheduled) | None => 4,
+            Some(PLCPhase::Connected) => 5,
+        }
+    }
+
+    plcs.sort_by(|a, b| {
+        let by_key = match key {
+            SortKey::Name => std::cmp::Ordering::Equal,
+            SortKey::Drifts => {
+                let drifts = |p: &IndustrialPLC| p.status.as_ref().map_or(0, |s| s.drift_events);
+                drifts(b).cmp(&drifts(a))
+            }
+            SortKey::Phase => phase_rank(a).cmp(&phase_rank(b)),
+            SortKey::LastUpdate => {
+                let updated = |p: &IndustrialPLC| {
+                    p.status
+                        .as_ref()
+                        .and_then(|s| s.last_update.as_deref())
+                        .and_then(|t| chrono::DateTime::parse_from_rfc3339(t).ok())
+                };
+                updated(b).cmp(&updated(a))
+            }
+        };
+        by_key.then_with(|| a.name_any().cmp(&b.name_any()))
+    });
+}
+
+/// Sections `fabctl describe` adds after the resource itself
+pub struct DescribeOptions {
+    pub events: bool,
+    pub history: bool,
+}
+
+/// Execute the describe command
+pub async fn cmd_describe(
+    source: &PlcSource<'_>,
+    namespace: &str,
+    name: &str,
+    opts: &DescribeOptions,
+    format: OutputFormat,
+) -> Result<()> {
+    let plc = source.get_plc(namespace, name).await?;
+    let events = match opts.events {
+        true => Some(source.list_plc_events(namespace, name).await?),
+        false => None,
+    };
+
+    if !matches!(format, OutputFormat::Table) {
+        // The history is part of the object's status already
+        let doc = DescribeOutput {
+            computed: DescribeComputed::of(&plc),
+            object: plc,
+            events,
+        };
+        match format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&doc)?),
+            _ => println!("{}", serde_yaml::to_string(&doc)?),
+        }
+        return Ok(());
+    }
+
+    println!(
+        "{}",
+        "╔════════════════════════════════════════════════════════════╗".bright_blue()
+    );
+    println!(
+        "{}",
+        "║              Industrial PLC Resource Details               ║".bright_blue()
+    );
+    println!(
+        "{}",
+        "╚════════════════════════════════════════════════════════════╝".bright_blue()
+    );
+    println!();
+
+    // Metadata
+    println!("{}", "📋 Metadata:".bold().underline());
+    println!(
+        "  Name:        {}",
+        plc.metadata.name.as_deref().unwrap_or("N/A").cyan()
+    );
+    println!(
+        "  Namespace:   {}",
+        plc.metadata.namespace.as_deref().unwrap_or("N/A")
+    );
+    if plc.is_protected() {
+        println!("  Protected:   {}", "yes (deletion blocked)".yellow());
+    }
+    println!(
+        "  Created:     {}",
+        plc.metadata
+            .creation_timestamp
+            .as_ref()
+            .map(|t| format_timestamp(&t.0))
+            .unwrap_or_default()
+    );
+    println!();
+
+    // Spec
+    println!("{}", "⚙️  Specification:".bold().underline());
+    println!("  Device Address:  {}", plc.spec.device_address.cyan());
+    println!("  Port:            {}", plc.spec.port);
+    println!("  Target Register: {}", plc.spec.target_register);
+    println!(
+        "  Target Value:    {}",
+        plc.spec.display_value(plc.spec.target()).green()
+    );
+    for (i, t) in plc.spec.transforms.iter().enumerate() {
+        let label = if i == 0 { "Transforms:" } else { "" };
+        println!("  {:<16} {}", label, t);
+    }
+    println!("  Poll Interval:   {}s", plc.spec.poll_interval_secs);
+    if plc.spec.critical {
+        println!(
+            "  Change Watch:    every {}ms",
+            plc.spec.change_watch_interval_ms
+        );
+    }
+    if let Some(auto_correct) = plc.spec.auto_correct {
+        println!(
+            "  Auto Correct:    {} {}",
+            if auto_correct {
+                "✓ enabled".green()
+            } else {
+                "✗ disabled".red()
+            },
+            "(deprecated)".yellow()
+        );
+    }
+    println!(
+        "  Correction:      {:?}{}",
+        plc.spec.effective_correction_policy(),
+        if plc.spec.dry_run { " (dry run)" } else { "" }
+    );
+    if let Some(hooks) = &plc.spec.write_hooks {
+        for (label, url) in [
+            ("Pre-write Hook:", &hooks.pre_write),
+            ("Post-write Hook:", &hooks.post_write),
+        ] {
+            if let Some(url) = url {
+                println!("  {:<16} {}", label, url.cyan());
+            }
+        }
+    }
+    if !plc.spec.tags.is_empty() {
+        println!("  Tags:            {}", plc.spec.tags.join(", "));
+    }
+    println!();
+
+    // Status
+    if let Some(status) = &plc.status {
+        print_status_summary(status, &plc.spec, status_style(status));
+    } else {
+        println!("{}", "⚠️  No status available".yellow());
+    }
+
+    if opts.history {
+        println!();
+        println!("{}", "📜 History:".bold().underline());
+        let history = plc.status.as_ref().map(|s| s.history.as_slice());
+        let entries: Vec<_> = history.unwrap_or_default().iter().collect();
+        print_history_table(name, &entries);
+    }
+
+    if let Some(events) = events {
+        println!();
+        println!("{}", "📣 Events:".bold().underline());
+        print_event_table(&events);
+    }
+
+    Ok(())
+}
+
+/// Structured `describe` output: the full resource plus derived fields,
+/// and its Events with `--show-events`
+#[derive(serde::Serialize)]
+struct DescribeOutput {
+    object: IndustrialPLC,
+    computed: DescribeComputed,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    events: Option<Vec<PlcEvent>>,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DescribeComputed {
+    /// Current value minus desired value, when the current value is known
+    drift_delta: Option<i32>,
+    /// Seconds since the resource was created
+    age_secs: Option<i64>,
+    /// Seconds since the status was last updated
+    last_update_age_secs: Option<i64>,
+    /// Seconds the current drift has lasted
+    drift_duration_secs: Option<i64>,
+    effective_correction_policy: CorrectionPolicy,
+}
+
+impl DescribeComputed {
+    fn of(plc: &IndustrialPLC) -> Self {
+        let now = chrono::Utc::now();
+        let secs_since = |t: &str| {
+            chrono::DateTime::parse_from_rfc3339(t)
+                .ok()
+                .map(|t| (now - t.with_timezone(&chrono::Utc)).num_seconds())
+        };
+        let status = plc.status.as_ref();
+
+        Self {
+            drift_delta: status
+                .and_then(|s| s.current_value)
+                .map(|v| v as i32 - plc.spec.target() as i32),
+            age_secs: plc
+                .metadata
+                .creation_timestamp
+                .as_ref()
+                .map(|t| (now - t.0).num_seconds()),
+            last_update_age_secs: status
+                .and_then(|s| s.last_update.as_deref())
+                .and_then(secs_since),
+            drift_duration_secs: status
+                .and_then(|s| s.drift_since.as_deref())
+                .and_then(secs_since),
+            effective_correction_policy: plc.spec.effective_correction_policy(),
+        }
+    }
+}
+
+/// Execute the sync command
+pub async fn cmd_sync(client: &K8sClient, namespace: &str, name: &str, force: bool) -> Result<()> {
+    use indicatif::{ProgressBar, ProgressStyle};
+
+    // A forced sync writes the target value even when the PLC looks in sync
+    if force && confirmation_required() {
+        let plc = client.get_plc(namespace, name).await?;
+        if !confirm_plcs("Force-syncing", namespace, &[plc], "force a sync")? {
+            println!("Aborted, nothing was synced");
+            return Ok(());
+        }
+    }
+
+    println!("{}", "🔄 Triggering manual sync...".cyan());
+
+    let spinner = ProgressBar::new_spinner();
+    spinner.set_style(
+        ProgressStyle::default_spinner()
+            .template("{spinner:.cyan} {msg}")
+            .unwrap(),
+    );
+    spinner.set_message("Annotating resource...");
+
+    client.trigger_reconcile(namespace, name, force).await?;
+
+    spinner.finish_with_message(format!("{}", "✓ Sync triggered successfully!".green()));
+
+    // Show updated status
+    println!();
+    println!("{}", "Fetching updated status...".dimmed());
+    tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+
+    let opts = DescribeOptions {
+        events: false,
+        history: false,
+    };
+    let source = PlcSource::Cluster(client);
+    cmd_describe(&source, namespace, name, &opts, OutputFormat::Table).await?;
+
+    Ok(())
+}
+
+/// Execute the watch command
+/// Alerting behavior of `fabctl watch`
+pub struct WatchOptions {
+    pub bell: bool,
+    pub flash: bool,
+    pub only_problems: bool,
+}
+
+pub async fn cmd_watch(
+    client: &K8sClient,
+    namespace: &str,
+    interval_secs: u64,
+    opts: &WatchOptions,
+) -> Result<()> {
+    use std::io::stdout;
+
+    println!("{}", "👁️  Watching PLC status (Ctrl+C to exit)...".cyan());
+    println!();
+
+    let mut stdout = stdout();
+    // Phases seen on the previous refresh, to alert only on transitions
+    let mut previous: Option<HashMap<String, PLCPhase>> = None;
+
+    loop {
+        let result = client.list_plcs(namespace).await;
+
+        let mut entered_alarm = HashSet::new();
+        if let Ok(ref plcs) = result {
+            let phases: HashMap<_, _> = plcs
+                .iter()
+                .map(|p| {
+                    let phase = p
+                        .status
+                        .as_ref()
+                        .map(|s| s.phase.clone())
+                        .unwrap_or_default();
+                    (p.name_any(), phase)
+                })
+                .collect();
+            if let Some(ref previous) = previous {
+                entered_alarm = phases
+                    .iter()
+                    .filter(|(name, phase)| {
+                        is_alarm_phase(phase) && !previous.get(*name).is_some_and(is_alarm_phase)
+                    })
+                    .map(|(name, _)| name.clone())
+                    .collect();
+            }
+            previous = Some(phases);
+        }
+
+        // Clear screen using ANSI escape codes
+        print!("\x1B[2J\x1B[1;1H");
+
+        // Print header
+        println!(
+            "{}",
+            "╔════════════════════════════════════════════════════════════╗".bright_blue()
+        );
+        println!(
+            "{}",
+            "║           FabGitOps Live Dashboard (fabctl watch)          ║".bright_blue()
+        );
+        println!(
+            "{}",
+            "╚════════════════════════════════════════════════════════════╝".bright_blue()
+        );
+        println!(
+            "  Namespace: {} | Refresh: {}s | Press Ctrl+C to exit",
+            namespace.cyan(),
+            interval_secs
+        );
+        println!();
+
+        match result {
+            Ok(plcs) => {
+                let total = plcs.len();
+                let shown: Vec<_> = if opts.only_problems {
+                    plcs.into_iter()
+                        .filter(|p| !p.status.as_ref().is_some_and(|s| s.in_sync))
+                        .collect()
+                } else {
+                    plcs
+                };
+
+                if opts.only_problems && shown.is_empty() && total > 0 {
+                    println!("{}", format!("✓ All {} PLCs in sync", total).green());
+                } else if opts.flash {
+                    print_plc_table_highlighted(&shown, &entered_alarm);
+                } else {
+                    print_plc_table(&shown);
+                }
+                if opts.only_problems {
+                    println!(
+                        "{}",
+                        format!("{} healthy PLC(s) hidden", total - shown.len()).dimmed()
+                    );
+                }
+            }
+            Err(e) => println!("{} {}", "Error:".red().bold(), e),
+        }
+
+        if !entered_alarm.is_empty() {
+            let mut names: Vec<_> = entered_alarm.iter().cloned().collect();
+            names.sort();
+            println!();
+            println!(
+                "{} {}",
+                "🚨 New alarms:".red().bold(),
+                names.join(", ").red()
+            );
+            if opts.bell {
+                print!("\x07");
+            }
+        }
+
+        println!();
+        println!(
+            "{}",
+            format!("Last updated: {}", format_clock(&chrono::Utc::now())).dimmed()
+        );
+
+        // Flush stdout
+        use std::io::Write;
+        stdout.flush()?;
+
+        tokio::time::sleep(tokio::time::Duration::from_secs(interval_secs)).await;
+    }
+}
+
+fn is_alarm_phase(phase: &PLCPhase) -> bool {
+    matches!(phase, PLCPhase::DriftDetected | PLCPhase::Failed)
+}
+
+fn status_style(status: &IndustrialPLCStatus) -> StatusStyle {
+    if status.in_sync {
+        StatusStyle::Success
+    } else if status.phase == PLCPhase::DriftDetected {
+        StatusStyle::Warning
+    } else {
+        StatusStyle::Error
+    }
+}
+
+/// How far back the value trend of `fabctl watch <name>` reaches
+const WATCH_TREND_WINDOW: chrono::Duration = chrono::Duration::minutes(15);
+
+/// Phase transitions and events kept on screen by `fabctl watch <name>`
+const WATCH_RECENT: usize = 8;
+
+/// Execute the watch command for a single PLC: a detail pane with its
+/// status, value trend, phase transitions and latest events
+pub async fn cmd_watch_plc(
+    client: &K8sClient,
+    namespace: &str,
+    name: &str,
+    interval_secs: u64,
+    opts: &WatchOptions,
+) -> Result<()> {
+    use std::io::Write;
+
+    // Fail fast on a typo rather than redrawing an error forever
+    let plc = client.get_plc(namespace, name).await?;
+
+    // Seed the trend with values recorded in the status history
+    let mut points: Vec<ValuePoint> = plc
+        .status
+        .iter()
+        .flat_map(|s| s.history.iter())
+        .filter_map(|entry| {
+            let timestamp = chrono::DateTime::parse_from_rfc3339(&entry.timestamp).ok()?;
+            Some(ValuePoint {
+                timestamp: timestamp.with_timezone(&chrono::Utc),
+                value: entry.actual?,
+            })
+        })
+        .collect();
+    let mut transitions: Vec<PhaseTransition> = Vec::new();
+    let mut result = Ok(plc);
+
+    loop {
+        let now = chrono::Utc::now();
+        let mut entered_alarm = None;
+        if let Ok(plc) = &result {
+            let phase = plc
+                .status
+                .as_ref()
+                .map(|s| s.phase.clone())
+                .unwrap_or_default();
+            let previous = transitions.last().map(|t| t.to.clone());
+            if previous.as_ref() != Some(&phase) {
+                if previous.is_some() && is_alarm_phase(&phase) {
+                    entered_alarm = Some(phase.clone());
+                }
+                transitions.push(PhaseTransition {
+                    at: now,
+                    from: previous,
+                    to: phase,
+                });
+                let skip = transitions.len().saturating_sub(WATCH_RECENT);
+                transitions.drain(..skip);
+            }
+            if let Some(value) = plc.status.as_ref().and_then(|s| s.current_value) {
+                points.push(ValuePoint {
+                    timestamp: now,
+                    value,
+                });
+            }
+        }
+        let start = now - WATCH_TREND_WINDOW;
+        points.retain(|p| p.timestamp >= start);
+        let events = client.list_plc_events(namespace, name).await;
+
+        // Clear screen using ANSI escape codes
+        print!("\x1B[2J\x1B[1;1H");
+
+        println!(
+            "{}",
+            "╔════════════════════════════════════════════════════════════╗".bright_blue()
+        );
+        println!(
+            "{}",
+            "║            FabGitOps Live Detail (fabctl watch)            ║".bright_blue()
+        );
+        println!(
+            "{}",
+            "╚════════════════════════════════════════════════════════════╝".bright_blue()
+        );
+        println!(
+            "  PLC: {}/{} | Refresh: {}s | Press Ctrl+C to exit",
+            namespace.cyan(),
+            name.cyan().bold(),
+            interval_secs
+        );
+        println!();
+
+        match &result {
+            Ok(plc) => {
+                println!(
+                    "  Device: {}:{} | Register: {} | Target: {}",
+                    plc.spec.device_address,
+                    plc.spec.port,
+                    plc.spec.target_register,
+                    plc.spec.display_value(plc.spec.target())
+                );
+                println!();
+                match &plc.status {
+                    Some(status) => print_status_summary(status, &plc.spec, status_style(status)),
+                    None => println!("{}", "⚠️  No status available".yellow()),
+                }
+
+                if let Some(phase) = &entered_alarm {
+                    let alert = format!("🚨 Entered {:?}", phase).red().bold();
+                    println!("{}", if opts.flash { alert.reversed() } else { alert });
+                    if opts.bell {
+                        print!("\x07");
+                    }
+                }
+
+                println!();
+                if points.is_empty() {
+                    println!("{}", "No values read in the last 15 minutes".yellow());
+                } else {
+                    print_value_chart(
+                        "Value trend",
+                        &points,
+                        plc.spec.target(),
+                        (start, now),
+                        (60, 8),
+                    );
+                }
+            }
+            Err(e) => println!("{} {}", "Error:".red().bold(), e),
+        }
+
+        println!();
+        println!("{}", "🔀 Phase transitions:".bold().underline());
+        print_phase_transitions(&transitions);
+
+        println!();
+        println!("{}", "📋 Recent events:".bold().underline());
+        match events {
+            Ok(events) => {
+                let skip = events.len().saturating_sub(WATCH_RECENT);
+                print_event_table(&events[skip..]);
+            }
+            Err(e) => println!("{} {}", "Error:".red().bold(), e),
+        }
+
+        println!();
+        println!(
+            "{}",
+            format!("Last updated: {}", format_clock(&now)).dimmed()
+        );
+        std::io::stdout().flush()?;
+
+        tokio::select! {
+            _ = tokio::time::sleep(tokio::time::Duration::from_secs(interval_secs)) => {}
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        }
+        result = client.get_plc(namespace, name).await;
+    }
+}
+
+/// Execute the list command
+pub async fn cmd_list(client: &K8sClient, namespace: &str, output: &Output) -> Result<()> {
+    let plcs = client.list_plcs(namespace).await?;
+
+    match output {
+        Output::Format(OutputFormat::Table) | Output::Document(_) => {}
+        Output::Format(OutputFormat::Json) => {
+            println!("{}", serde_json::to_string_pretty(&plcs)?);
+            return Ok(());
+        }
+        Output::Format(OutputFormat::Yaml) => {
+            println!("{}", serde_yaml::to_string(&plcs)?);
+            return Ok(());
+        }
+        Output::Template(template) => return print_templated(&plcs, template),
+    }
+
+    println!("{}", "Managed Industrial PLCs".bold().underline());
+    println!();
+
+    for plc in plcs {
+        let name = plc.metadata.name.as_deref().unwrap_or("unknown");
+        let status_icon = if plc.status.as_ref().map(|s| s.in_sync).unwrap_or(false) {
+            "✓".green()
+        } else {
+            "✗".red()
+        };
+
+        println!(
+            "{} {} @ {}:{}",
+            status_icon,
+            name.cyan(),
+            plc.spec.device_address,
+            plc.spec.port
+        );
+    }
+
+    Ok(())
+}
+
+/// Members of a group, sorted by name, plus explicit members that don't exist
+fn group_members(group: &PLCGroup, plcs: Vec<IndustrialPLC>) -> (Vec<IndustrialPLC>, Vec<String>) {
+    let mut members: Vec<_> = plcs.into_iter().filter(|p| group.contains(p)).collect();
+    members.sort_by_key(|p| p.name_any());
+    let missing = group
+        .spec
+        .members
+        .iter()
+        .filter(|name| !members.iter().any(|p| p.name_any() == **name))
+        .cloned()
+        .collect();
+    (members, missing)
+}
+
+/// Execute the group list command
+pub async fn cmd_group_list(
+    client: &K8sClient,
+    namespace: &str,
+    format: OutputFormat,
+) -> Result<()> {
+    let groups = client.list_plc_groups(namespace).await?;
+    let plcs = client.list_plcs(namespace).await?;
+
+    let rows: Vec<_> = groups
+        .into_iter()
+        .map(|group| {
+            let (members, _) = group_members(&group, plcs.clone());
+            let health = GroupHealth::of(&members);
+            (group, health)
+        })
+        .collect();
+
+    match format {
+        OutputFormat::Table => print_group_table(&rows),
+        _ => {
+            let summary: Vec<_> = rows
+                .iter()
+                .map(|(group, health)| {
+                    serde_json::json!({
+                        "name": group.name_any(),
+                        "description": group.spec.description,
+                        "health": health,
+                    })
+                })
+                .collect();
+            match format {
+                OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&summary)?),
+                _ => println!("{}", serde_yaml::to_string(&summary)?),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Execute the group describe command
+pub async fn cmd_group_describe(
+    client: &K8sClient,
+    namespace: &str,
+    name: &str,
+    format: OutputFormat,
+) -> Result<()> {
+    let group = client.get_plc_group(namespace, name).await?;
+    let (members, missing) = group_members(&group, client.list_plcs(namespace).await?);
+    let health = GroupHealth::of(&members);
+
+    match format {
+        OutputFormat::Table => {}
+        OutputFormat::Json | OutputFormat::Yaml => {
+            let doc = serde_json::json!({
+                "name": name,
+                "spec": group.spec,
+                "health": health,
+                "members": members.iter().map(|p| p.name_any()).collect::<Vec<_>>(),
+                "missing": missing,
+            });
+            match format {
+                OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&doc)?),
+                _ => println!("{}", serde_yaml::to_string(&doc)?),
+            }
+            return Ok(());
+        }
+    }
+
+    println!("{}", format!("PLC Group: {}", name).bold().underline());
+    println!();
+    if let Some(ref description) = group.spec.description {
+        println!("  Description: {}", description);
+    }
+    if !group.spec.selector.is_empty() {
+        let selector: Vec<_> = group
+            .spec
+            .selector
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v))
+            .collect();
+        println!("  Selector:    {}", selector.join(",").cyan());
+    }
+    println!(
+        "  Health:      {} members, {} in sync, {} drifted, {} failed, {} pending",
+        health.members,
+        health.in_sync.to_string().green(),
+        health.drifted.to_string().yellow(),
+        health.failed.to_string().red(),
+        health.pending
+    );
+    for name in &missing {
+        println!(
+            "  {} Listed member '{}' does not exist",
+            "⚠".yellow(),
+            name.yellow()
+        );
+    }
+    println!();
+
+    print_plc_table(&members);
+
+    Ok(())
+}
+
+/// Execute the group sync command
+pub async fn cmd_group_sync(
+    client: &K8sClient,
+    namespace: &str,
+    name: &str,
+    force: bool,
+    format: OutputFormat,
+) -> Result<()> {
+    let group = client.get_plc_group(namespace, name).await?;
+    let (members, missing) = group_members(&group, client.list_plcs(namespace).await?);
+
+    if force
+        && confirmation_required()
+        && !confirm_plcs("Force-syncing", namespace, &members, "force a sync")?
+    {
+        println!("Aborted, nothing was synced");
+        return Ok(());
+    }
+
+    if matches!(format, OutputFormat::Table) {
+        println!(
+            "{}",
+            format!(
+                "🔄 Syncing {} member(s) of group {}...",
+                members.len(),
+                name
+            )
+            .cyan()
+        );
+    }
+
+    let mut results = Vec::new();
+    for plc in &members {
+        let plc_name = plc.name_any();
+        let result = client.trigger_reconcile(namespace, &plc_name, force).await;
+        if matches!(format, OutputFormat::Table) {
+            match &result {
+                Ok(()) => println!("  {} {}", "✓".green(), plc_name),
+                Err(e) => println!("  {} {}: {}", "✗".red(), plc_name, e),
+            }
+        }
+        results.push(serde_json::json!({
+            "name": plc_name,
+            "triggered": result.is_ok(),
+            "error": result.err().map(|e| e.to_string()),
+        }));
+    }
+    for plc_name in &missing {
+        if matches!(format, OutputFormat::Table) {
+            println!("  {} {}: not found", "✗".red(), plc_name);
+        }
+        results.push(serde_json::json!({
+            "name": plc_name,
+            "triggered": false,
+            "error": "not found",
+        }));
+    }
+
+    match format {
+        OutputFormat::Table => {}
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&results)?),
+        OutputFormat::Yaml => println!("{}", serde_yaml::to_string(&results)?),
+    }
+
+    let failed = results.iter().filter(|r| r["triggered"] == false).count();
+    if failed > 0 {
+        anyhow::bail!("sync failed for {} of {} member(s)", failed, results.len());
+    }
+    if matches!(format, OutputFormat::Table) {
+        println!(
+            "{}",
+            format!("✓ Sync triggered for {} member(s)", results.len()).green()
+        );
+    }
+
+    Ok(())
+}
+
+/// Execute the maintenance set and clear commands
+pub async fn cmd_maintenance(
+    client: &K8sClient,
+    namespace: &str,
+    name: Option<&str>,
+    window: Option<MaintenanceWindow>,
+) -> Result<()> {
+    let plcs = match name {
+        Some(name) => vec![client.get_plc(namespace, name).await?],
+        None => client.list_plcs(namespace).await?,
+    };
+    let doing = match window {
+        Some(_) => "Setting the maintenance window of",
+        None => "Clearing the maintenance windows of",
+    };
+    if confirmation_required()
+        && !confirm_plcs(doing, namespace, &plcs, "change maintenance windows")?
+    {
+        println!("Aborted, nothing was changed");
+        return Ok(());
+    }
+    let names: Vec<String> = plcs.iter().map(|p| p.name_any()).collect();
+    let windows: Vec<_> = window.into_iter().collect();
+
+    let mut failed = 0;
+    for name in &names {
+        match client
+            .set_maintenance_windows(namespace, name, &windows)
+            .await
+        {
+            Ok(()) => println!("  {} {}", "✓".green(), name),
+            Err(e) => {
+                failed += 1;
+                println!("  {} {}: {}", "✗".red(), name, e);
+            }
+        }
+    }
+
+    if failed > 0 {
+        anyhow::bail!("failed to update {} of {} PLC(s)", failed, names.len());
+    }
+
+    match windows.first() {
+        Some(w) => {
+            let days = if w.days.is_empty() {
+                "every day".to_string()
+            } else {
+                w.days
+                    .iter()
+                    .map(|d| format!("{:?}", d).to_lowercase())
+                    .collect::<Vec<_>>()
+                    .join(",")
+            };
+            println!(
+                "{}",
+                format!(
+                    "✓ Maintenance window {}-{} ({}, UTC{:+03}:{:02}) set on {} PLC(s)",
+                    w.start,
+                    w.end,
+                    days,
+                    w.utc_offset_minutes / 60,
+                    (w.utc_offset_minutes % 60).abs(),
+                    names.len()
+                )
+                .green()
+            );
+        }
+        None => println!(
+            "{}",
+            format!("✓ Maintenance windows cleared on {} PLC(s)", names.len()).green()
+        ),
+    }
+
+    Ok(())
+}
+
+/// Execute the snapshot create command
+pub async fn cmd_snapshot_create(
+    client: &K8sClient,
+    namespace: &str,
+    plc_name: &str,
+    (start, count): (u16, u16),
+    name: Option<&str>,
+    format: OutputFormat,
+) -> Result<()> {
+    let plc = client.get_plc(namespace, plc_name).await?;
+    let values = PLCClient::new(plc.spec.device_address.clone(), plc.spec.port)
+        .read_registers(start, count)
+        .await?;
+
+    let now = chrono::Utc::now();
+    let name = name
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("{}-{}", plc_name, now.format("%Y%m%d-%H%M%S")));
+    let mut snapshot = PLCSnapshot::new(
+        &name,
+        PLCSnapshotSpec {
+            plc_name: plc_name.to_string(),
+            device_address: plc.spec.device_address.clone(),
+            port: plc.spec.port,
+            start_register: start,
+            values,
+            taken_at: now.to_rfc3339(),
+        },
+    );
+    let problems = snapshot.spec.validate();
+    if !problems.is_empty() {
+        anyhow::bail!("Invalid snapshot: {}", problems.join("; "));
+    }
+    snapshot.metadata.labels = Some(
+        [(PLC_NAME_LABEL.to_string(), plc_name.to_string())]
+            .into_iter()
+            .collect(),
+    );
+
+    let created = client.create_snapshot(namespace, &snapshot).await?;
+
+    match format {
+        OutputFormat::Table => println!(
+            "{} Snapshot {} saved ({} registers, {}-{})",
+            "✓".green(),
+            name.cyan(),
+            created.spec.values.len(),
+            created.spec.start_register,
+            created.spec.end_register()
+        ),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&created)?),
+        OutputFormat::Yaml => println!("{}", serde_yaml::to_string(&created)?),
+    }
+
+    Ok(())
+}
+
+/// Execute the snapshot list command
+pub async fn cmd_snapshot_list(
+    client: &K8sClient,
+    namespace: &str,
+    plc: Option<&str>,
+    format: OutputFormat,
+) -> Result<()> {
+    let snapshots = client.list_snapshots(namespace, plc).await?;
+
+    match format {
+        OutputFormat::Table => print_snapshots(&snapshots),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&snapshots)?),
+        OutputFormat::Yaml => println!("{}", serde_yaml::to_string(&snapshots)?),
+    }
+
+    Ok(())
+}
+
+/// Execute the snapshot restore command
+pub async fn cmd_snapshot_restore(
+    client: &K8sClient,
+    namespace: &str,
+    name: &str,
+    dry_run: bool,
+    yes: bool,
+    format: OutputFormat,
+) -> Result<()> {
+    let snapshot = client.get_snapshot(namespace, name).await?;
+    let spec = &snapshot.spec;
+    let problems = spec.validate();
+    if !problems.is_empty() {
+        anyhow::bail!("Snapshot {} is invalid: {}", name, problems.join("; "));
+    }
+    let plc_client = PLCClient::new(spec.device_address.clone(), spec.port);
+    let current = plc_client
+        .read_registers(spec.start_register, spec.values.len() as u16)
+        .await?;
+    let changes = snapshot::diff(spec, &current);
+
+    match format {
+        OutputFormat::Table => {
+            if changes.is_empty() {
+                println!(
+                    "{}",
+                    format!("✓ {} already matches snapshot {}", spec.plc_name, name).green()
+                );
+                return Ok(());
+            }
+            println!(
+                "Restoring {} would change {} of {} register(s) on {} ({}:{}):",
+                name.cyan(),
+                changes.len(),
+                spec.values.len(),
+                spec.plc_name.cyan(),
+                spec.device_address,
+                spec.port
+            );
+            print_register_diff(&changes);
+        }
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&changes)?),
+        OutputFormat::Yaml => println!("{}", serde_yaml::to_string(&changes)?),
+    }
+
+    // The operator would immediately revert a restored target register that
+    // disagrees with the spec, so point that out before writing
+    if let Ok(plc) = client.get_plc(namespace, &spec.plc_name).await {
+        if let Some(change) = changes
+            .iter()
+            .find(|c| c.register == plc.spec.target_register && c.snapshot != plc.spec.target())
+        {
+            eprintln!(
+                "{} Register {} is managed by {} with targetValue {}; the operator will treat {} as drift",
+                "⚠".yellow(),
+                change.register,
+                spec.plc_name,
+                plc.spec.target(),
+                change.snapshot
+            );
+        }
+    }
+
+    if dry_run || changes.is_empty() {
+        return Ok(());
+    }
+
+    if (!yes || confirmation_required()) && !confirm("Write these values?", "write")? {
+        println!("Aborted, nothing was written");
+        return Ok(());
+    }
+
+    for (start, values) in snapshot::write_runs(&changes) {
+        plc_client.write_registers(start, &values).await?;
+    }
+
+    if matches!(format, OutputFormat::Table) {
+        println!(
+            "{}",
+            format!("✓ Restored {} register(s) from {}", changes.len(), name).green()
+        );
+    }
+
+    Ok(())
+}
+
+/// Fields overridden by `fabctl clone`
+pub struct CloneOverrides {
+    pub device: Option<String>,
+    pub port: Option<u16>,
+    pub register: Option<u16>,
+    pub value: Option<u16>,
+    pub labels: Vec<(String, String)>,
+}
+
+/// Execute the clone command
+pub async fn cmd_clone(
+    client: &K8sClient,
+    namespace: &str,
+    source: &str,
+    name: &str,
+    overrides: CloneOverrides,
+    dry_run: bool,
+    format: OutputFormat,
+) -> Result<()> {
+    let original = client.get_plc(namespace, source).await?;
+
+    let mut spec = original.spec.clone();
+    if let Some(device) = overrides.device {
+        spec.device_address = device;
+    }
+    if let Some(port) = overrides.port {
+        spec.port = port;
+    }
+    if let Some(register) = overrides.register {
+        spec.target_register = register;
+    }
+    if let Some(value) = overrides.value {
+        spec.target_value = value.into();
+    }
+
+    let problems = spec.validate();
+    if !problems.is_empty() {
+        anyhow::bail!("cloned spec is invalid: {}", problems.join("; "));
+    }
+
+    // Carry labels over so the clone lands in the same groups, but not
+    // annotations, which hold per-resource sync requests
+    let mut labels = original.metadata.labels.clone().unwrap_or_default();
+    labels.extend(overrides.labels);
+
+    let mut plc = IndustrialPLC::new(name, spec);
+    plc.metadata.namespace = Some(namespace.to_string());
+    plc.metadata.labels = (!labels.is_empty()).then_some(labels);
+
+    if dry_run {
+        let doc = serde_json::to_value(&plc)?;
+        return print_manifests(&[doc], format);
+    }
+
+    if confirmation_required()
+        && !confirm_plcs("Creating", namespace, std::slice::from_ref(&plc), "create")?
+    {
+        println!("Aborted, nothing was created");
+        return Ok(());
+    }
+
+    let created = client.create_plc(namespace, &plc).await?;
+
+    match format {
+        OutputFormat::Table => println!(
+            "{} Created {} from {} ({}:{}, register {} = {})",
+            "✓".green(),
+            name.cyan(),
+            source,
+            created.spec.device_address,
+            created.spec.port,
+            created.spec.target_register,
+            created.spec.target()
+        ),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&created)?),
+        OutputFormat::Yaml => println!("{}", serde_yaml::to_string(&created)?),
+    }
+
+    Ok(())
+}
+
+static REQUIRE_CONFIRMATION: OnceLock<bool> = OnceLock::new();
+
+/// Make every mutating command ask first for the rest of the process,
+/// under --require-confirmation or a profile that requires it
+pub fn set_require_confirmation(required: bool) {
+    let _ = REQUIRE_CONFIRMATION.set(required);
+}
+
+fn confirmation_required() -> bool {
+    REQUIRE_CONFIRMATION.get().copied().unwrap_or_default()
+}
+
+/// Ask `question` on the terminal before going on to `action`. Without a
+/// terminal there is nobody to answer, so the command is refused.
+fn confirm(question: &str, action: &str) -> Result<bool> {
+    use std::io::{IsTerminal, Write};
+
+    if !std::io::stdin().is_terminal() {
+        if confirmation_required() {
+            anyhow::bail!(
+                "refusing to {} without confirmation; confirmation is required and needs a terminal",
+                action
+            );
+        }
+        anyhow::bail!("refusing to {} without confirmation; pass --yes", action);
+    }
+    print!("{} [y/N] ", question);
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+/// List the PLCs a command is about to change, then ask to go on
+fn confirm_plcs(
+    doing: &str,
+    namespace: &str,
+    plcs: &[IndustrialPLC],
+    action: &str,
+) -> Result<bool> {
+    println!(
+        "{}",
+        format!(
+            "{} {} PLC(s) in namespace {}:",
+            doing,
+            plcs.len(),
+            namespace
+        )
+        .bold()
+    );
+    for plc in plcs {
+        let phase = plc
+            .status
+            .as_ref()
+            .map(|s| format!("{:?}", s.phase))
+            .unwrap_or_else(|| "Pending".to_string());
+        println!(
+            "  {} {}:{} register {}  targetValue {}  {}",
+            plc.name_any().cyan(),
+            plc.spec.device_address,
+            plc.spec.port,
+            plc.spec.target_register,
+            plc.spec.display_value(plc.spec.target()).green(),
+            phase.dimmed()
+        );
+    }
+    confirm("Continue?", action)
+}
+
+/// Execute the delete command
+pub async fn cmd_delete(client: &K8sClient, namespace: &str, name: &str) -> Result<()> {
+    let plc = client.get_plc(namespace, name).await?;
+    if plc.is_protected() {
+        println!(
+            "{} {} is protected; the operator holds its deletion until the annotation is removed:",
+            "⚠".yellow(),
+            name.cyan()
+        );
+        println!(
+            "  kubectl annotate industrialplc {} -n {} {}-",
+            name, namespace, PROTECTED_ANNOTATION
+        );
+        anyhow::bail!("refusing to delete protected PLC {}", name);
+    }
+
+    if confirmation_required()
+        && !confirm_plcs("Deleting", namespace, std::slice::from_ref(&plc), "delete")?
+    {
+        println!("Aborted, nothing was deleted");
+        return Ok(());
+    }
+
+    client.delete_plc(namespace, name).await?;
+    println!("{} Deleted {}", "✓".green(), name.cyan());
+    Ok(())
+}
+
+/// Execute the set command
+pub async fn cmd_set(
+    client: &K8sClient,
+    namespace: &str,
+    name: Option<&str>,
+    selector: Option<&str>,
+    value: u16,
+    dry_run: bool,
+    yes: bool,
+) -> Result<()> {
+    let mut plcs = match (name, selector) {
+        (Some(name), _) => vec![client.get_plc(namespace, name).await?],
+        (None, Some(selector)) => client.list_plcs_by_selector(namespace, selector).await?,
+        (None, None) => anyhow::bail!("specify a PLC name or --selector"),
+    };
+    plcs.sort_by_key(|p| p.name_any());
+
+    if plcs.is_empty() {
+        println!("{}", "⚠️  No PLCs match the selector".yellow());
+        return Ok(());
+    }
+
+    // Refuse the whole batch if any PLC's safety bounds reject the value
+    let violations: Vec<_> = plcs
+        .iter()
+        .filter_map(|p| {
+            p.spec
+                .check_bounds(value)
+                .err()
+                .map(|e| format!("{}: {}", p.name_any(), e))
+        })
+        .collect();
+    if !violations.is_empty() {
+        anyhow::bail!(
+            "refusing to set targetValue {}: {}",
+            value,
+            violations.join("; ")
+        );
+    }
+
+    let changes: Vec<_> = plcs.iter().filter(|p| p.spec.target() != value).collect();
+
+    println!(
+        "{}",
+        format!(
+            "targetValue → {} on {} of {} PLC(s):",
+            value,
+            changes.len(),
+            plcs.len()
+        )
+        .bold()
+    );
+    for plc in &plcs {
+        if plc.spec.target() == value {
+            println!("  {} {} (unchanged)", "=".dimmed(), plc.name_any().dimmed());
+        } else {
+            println!(
+                "  {} {}: {} → {}",
+                "~".yellow(),
+                plc.name_any().cyan(),
+                plc.spec.target().to_string().red(),
+                value.to_string().green()
+            );
+        }
+    }
+
+    if dry_run || changes.is_empty() {
+        return Ok(());
+    }
+
+    if (!yes || confirmation_required()) && !confirm("Apply?", "change setpoints")? {
+        println!("Aborted, nothing was changed");
+        return Ok(());
+    }
+
+    let mut failed = 0;
+    for plc in &changes {
+        let plc_name = plc.name_any();
+        match client.set_target_value(namespace, &plc_name, value).await {
+            Ok(()) => println!("  {} {}", "✓".green(), plc_name),
+            Err(e) => {
+                failed += 1;
+                println!("  {} {}: {}", "✗".red(), plc_name, e);
+            }
+        }
+    }
+
+    if failed > 0 {
+        anyhow::bail!("failed to update {} of {} PLC(s)", failed, changes.len());
+    }
+
+    Ok(())
+}
+
+/// Execute the hold command
+pub async fn cmd_hold(
+    client: &K8sClient,
+    namespace: &str,
+    name: &str,
+    duration: Duration,
+    reason: Option<&str>,
+    release: bool,
+) -> Result<()> {
+    let plc = client.get_plc(namespace, name).await?;
+
+    if release {
+        if !plc.annotations().contains_key(HOLD_UNTIL_ANNOTATION) {
+            println!("{}", format!("{} is not held", name).yellow());
+            return Ok(());
+        }
+        if confirmation_required()
+            && !confirm_plcs(
+                "Releasing the hold of",
+                namespace,
+                std::slice::from_ref(&plc),
+                "release the hold",
+            )?
+        {
+            println!("Aborted, nothing was changed");
+            return Ok(());
+        }
+        client.set_hold(namespace, name, None).await?;
+        println!(
+            "{}",
+            format!(
+                "✓ Released {}; drift from targetValue {} is handled again",
+                name,
+                plc.spec.display_value(plc.spec.target())
+            )
+            .green()
+        );
+        return Ok(());
+    }
+
+    if duration.is_zero() || duration.as_secs() > MAX_HOLD_SECS {
+        anyhow::bail!(
+            "--for must be between 1s and {}h (got {:?})",
+            MAX_HOLD_SECS / 3600,
+            duration
+        );
+    }
+    if confirmation_required()
+        && !confirm_plcs("Holding", namespace, std::slice::from_ref(&plc), "hold")?
+    {
+        println!("Aborted, nothing was changed");
+        return Ok(());
+    }
+    let until = chrono::Utc::now() + chrono::Duration::from_std(duration)?;
+    client
+        .set_hold(namespace, name, Some((until, reason)))
+        .await?;
+
+    let current = plc
+        .status
+        .as_ref()
+        .and_then(|s| s.current_value)
+        .map(|value| plc.spec.display_value(value))
+        .unwrap_or_else(|| "-".to_string());
+    println!(
+        "{}",
+        format!(
+            "✓ Holding {} at the value it reads until {}",
+            name,
+            format_clock(&until)
+        )
+        .green()
+    );
+    println!(
+        "  Current value: {} | targetValue: {}",
+        current,
+        plc.spec.display_value(plc.spec.target())
+    );
+    println!(
+        "{}",
+        "  Drift is neither reported nor corrected until then; release early with --release"
+            .dimmed()
+    );
+    Ok(())
+}
+
+/// Execute the validate command
+pub fn cmd_validate(files: &[PathBuf], format: OutputFormat) -> Result<()> {
+    let report = validate_paths(files)?;
+
+    match format {
+        OutputFormat::Table => print_validation_report(&report),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+        OutputFormat::Yaml => println!("{}", serde_yaml::to_string(&report)?),
+    }
+
+    if report.errors() > 0 {
+        anyhow::bail!("validation failed with {} error(s)", report.errors());
+    }
+
+    Ok(())
+}
+
+/// Execute the migrate command on manifest files
+pub fn cmd_migrate_files(files: &[PathBuf], write: bool, format: OutputFormat) -> Result<()> {
+    let report = migrate::migrate_files(files, write)?;
+    report_migrations(&report, format)
+}
+
+/// Execute the migrate command on the PLCs in a namespace
+pub async fn cmd_migrate_cluster(
+    client: &K8sClient,
+    namespace: &str,
+    write: bool,
+    format: OutputFormat,
+) -> Result<()> {
+    let mut report = migrate::MigrationReport {
+        written: write,
+        ..Default::default()
+    };
+
+    let mut pending = Vec::new();
+    for plc in client.list_plcs(namespace).await? {
+        let serde_json::Value::Object(spec) = serde_json::to_value(&plc.spec)? else {
+            continue;
+        };
+        let changes = migrate::spec_changes(&spec);
+        if !changes.is_empty() {
+            pending.push((plc, changes));
+        }
+    }
+
+    if write && !pending.is_empty() && confirmation_required() {
+        let plcs: Vec<IndustrialPLC> = pending.iter().map(|(plc, _)| plc.clone()).collect();
+        if !confirm_plcs("Migrating", namespace, &plcs, "migrate")? {
+            println!("Aborted, nothing was migrated");
+            return Ok(());
+        }
+    }
+
+    for (plc, changes) in pending {
+        let name = plc.name_any();
+        if write {
+            client
+                .patch_plc_spec(namespace, &name, migrate::spec_patch(&changes))
+                .await
+                .with_context(|| format!("Failed to migrate {}", name))?;
+        }
+        report
+            .migrations
+            .extend(changes.iter().map(|change| migrate::Migration {
+                location: format!("{}/{}", namespace, name),
+                resource: name.clone(),
+                removed: change.removed(),
+                added: change.added(),
+            }));
+    }
+
+    report_migrations(&report, format)
+}
+
+fn report_migrations(report: &migrate::MigrationReport, format: OutputFormat) -> Result<()> {
+    match format {
+        OutputFormat::Table => print_migration_report(report),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(report)?),
+        OutputFormat::Yaml => println!("{}", serde_yaml::to_string(report)?),
+    }
+    Ok(())
+}
+
+/// Execute the gen command
+pub fn cmd_gen(target: &GenTarget, namespace: &str, format: OutputFormat) -> Result<()> {
+    let docs = match target {
+        GenTarget::Crd => manifests::crds()
+            .into_iter()
+            .map(serde_json::to_value)
+            .collect::<Result<_, _>>()?,
+        GenTarget::Operator { image } => manifests::operator_manifests(&OperatorManifestOptions {
+            namespace: namespace.to_string(),
+            image: image.clone(),
+        }),
+    };
+
+    print_manifests(&docs, format)
+}
+
+/// Execute the scan command
+pub async fn cmd_scan(
+    target: &str,
+    opts: &ScanOptions,
+    manifests_path: Option<&Path>,
+    namespace: &str,
+    format: OutputFormat,
+) -> Result<()> {
+    use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+    use std::io::{IsTerminal, Write};
+
+    let targets = scan::parse_targets(target)?;
+    let scanned = targets.len();
+
+    let spinner = ProgressBar::new_spinner();
+    if matches!(format, OutputFormat::Table) {
+        spinner.set_style(
+            ProgressStyle::default_spinner()
+                .template("{spinner:.cyan} {msg}")
+                .unwrap(),
+        );
+        spinner.set_message(format!(
+            "Probing {} host(s) on port {}...",
+            scanned, opts.port
+        ));
+        spinner.enable_steady_tick(Duration::from_millis(100));
+    } else {
+        spinner.set_draw_target(ProgressDrawTarget::hidden());
+    }
+
+    let responders = scan::scan(targets, opts).await;
+    spinner.finish_and_clear();
+
+    match format {
+        OutputFormat::Table => print_scan_results(&responders, scanned, opts.register),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&responders)?),
+        OutputFormat::Yaml => println!("{}", serde_yaml::to_string(&responders)?),
+    }
+
+    if responders.is_empty() {
+        return Ok(());
+    }
+
+    let path = match manifests_path {
+        Some(path) => path.to_path_buf(),
+        None if matches!(format, OutputFormat::Table) && std::io::stdin().is_terminal() => {
+            let default = PathBuf::from("discovered-plcs.yaml");
+            print!(
+                "\nDraft IndustrialPLC manifests for {} device(s) into {}? [y/N] ",
+                responders.len(),
+                default.display()
+            );
+            std::io::stdout().flush()?;
+            let mut answer = String::new();
+            std::io::stdin().read_line(&mut answer)?;
+            if !matches!(answer.trim(), "y" | "Y" | "yes") {
+                return Ok(());
+            }
+            default
+        }
+        None => return Ok(()),
+    };
+
+    let docs = scan::draft_manifests(&responders, namespace, opts.register)
+        .into_iter()
+        .map(serde_json::to_value)
+        .collect::<Result<Vec<_>, _>>()?;
+    std::fs::write(&path, manifests_yaml(&docs)?)?;
+
+    if matches!(format, OutputFormat::Table) {
+        println!(
+            "{} Wrote {} manifest(s) to {}",
+            "✓".green(),
+            docs.len(),
+            path.display()
+        );
+        println!(
+            "  {}",
+            "Drafts use correctionPolicy: AlertOnly; review targets before enabling corrections"
+                .dimmed()
+        );
+    }
+
+    Ok(())
+}
+
+/// Execute the chaos command
+pub async fn cmd_chaos(action: &ChaosAction, target: &str, format: OutputFormat) -> Result<()> {
+    let client = ChaosClient::new(target);
+
+    let response = match action {
+        ChaosAction::Status => client.status().await?,
+        ChaosAction::Enable {
+            interval,
+            max_drift,
+        } => client.enable(*interval, *max_drift).await?,
+        ChaosAction::Disable => client.disable().await?,
+        ChaosAction::Drift { amount, register } => client.drift(*register, *amount).await?,
+        ChaosAction::Latency {
+            delay_ms,
+            jitter_ms,
+            percent,
+        } => client.latency(*delay_ms, *jitter_ms, *percent).await?,
+        ChaosAction::Connections {
+            drop_percent,
+            refuse_for,
+            refuse_every,
+        } => {
+            client
+                .connections(*drop_percent, *refuse_for, *refuse_every)
+                .await?
+        }
+        ChaosAction::Corruption { percent } => client.corruption(*percent).await?,
+        ChaosAction::Outage { secs } => client.outage(*secs).await?,
+        ChaosAction::Reboot { secs } => client.reboot(*secs).await?,
+    };
+
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&response)?),
+        OutputFormat::Yaml => println!("{}", serde_yaml::to_string(&response)?),
+        OutputFormat::Table => match action {
+            ChaosAction::Drift { .. } => println!(
+                "{} Register {} drifted: {} → {} ({:+})",
+                "🌀".yellow(),
+                response["register"],
+                response["oldValue"],
+                response["newValue"],
+                response["drift"].as_i64().unwrap_or_default()
+            ),
+            _ => {
+                let state = if response["enabled"].as_bool().unwrap_or(false) {
+                    "ENABLED".red().bold()
+                } else {
+                    "disabled".green()
+                };
+                println!("Chaos mode:  {}", state);
+                println!("  Target:    {}", target.cyan());
+                println!("  Interval:  {}s", response["intervalSecs"]);
+                println!("  Max drift: {}", response["maxDrift"]);
+                println!(
+                    "  Register:  {} = {}",
+                    response["register"], response["value"]
+                );
+                if let Some(registers) = response["registers"].as_array() {
+                    let registers: Vec<_> =
+                        registers.iter().map(serde_json::Value::to_string).collect();
+                    println!("  Drifts:    {}", registers.join(", "));
+                }
+                let latency = &response["latency"];
+                let delay_ms = latency["delayMs"].as_u64().unwrap_or_default();
+                let jitter_ms = latency["jitterMs"].as_u64().unwrap_or_default();
+                let percent = latency["percent"].as_u64().unwrap_or_default();
+                if percent > 0 && (delay_ms > 0 || jitter_ms > 0) {
+                    println!(
+                        "  Latency:   {}ms + up to {}ms jitter on {}% of responses",
+                        delay_ms, jitter_ms, percent
+                    );
+                } else {
+                    println!("  Latency:   off");
+                }
+                let connections = &response["connections"];
+                let drop_percent = connections["dropPercent"].as_u64().unwrap_or_default();
+                let refuse_for = connections["refuseForSecs"].as_u64().unwrap_or_default();
+                let refuse_every = connections["refuseEverySecs"].as_u64().unwrap_or_default();
+                if drop_percent > 0 {
+                    println!("  Drops:     {}% of requests", drop_percent);
+                } else {
+                    println!("  Drops:     off");
+                }
+                if refuse_for > 0 && refuse_every > 0 {
+                    println!("  Refusals:  {}s of every {}s", refuse_for, refuse_every);
+                } else {
+                    println!("  Refusals:  off");
+                }
+                match response["corruption"]["percent"].as_u64() {
+                    Some(percent) if percent > 0 => {
+                        println!("  Corrupt:   {}% of responses", percent)
+                    }
+                    _ => println!("  Corrupt:   off"),
+                }
+                if let Some(secs) = response["outageRemainingSecs"].as_u64() {
+                    println!(
+                        "  Outage:    {}",
+                        format!("{}s remaining", secs).red().bold()
+                    );
+                }
+                if let Some(secs) = response["rebootRemainingSecs"].as_u64() {
+                    println!(
+                        "  Reboot:    {}",
+                        format!("{}s remaining", secs).red().bold()
+                    );
+                }
+            }
+        },
+    }
+
+    Ok(())
+}
+
+/// Execute the install command
+pub async fn cmd_install(
+    client: &K8sClient,
+    namespace: &str,
+    version: &str,
+    image: Option<&str>,
+    dry_run: bool,
+    timeout_secs: u64,
+) -> Result<()> {
+    let image = image
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("{}:{}", DEFAULT_IMAGE_REPOSITORY, version));
+    let timeout = Duration::from_secs(timeout_secs);
+
+    println!(
+        "{} FabGitOps operator {} into namespace {}{}",
+        "📦 Installing".cyan(),
+        image.green(),
+        namespace.cyan(),
+        if dry_run { " (dry run)" } else { "" }
+    );
+    println!();
+
+    if client.ensure_namespace(namespace, dry_run).await? {
+        println!("  {} namespace/{} created", "✓".green(), namespace);
+    }
+
+    for crd in manifests::crds() {
+        let crd = serde_json::to_value(crd)?;
+        client.apply_manifest(&crd, dry_run).await?;
+        println!("  {} {} applied", "✓".green(), describe_manifest(&crd));
+
+        if !dry_run {
+            let crd_name = crd["metadata"]["name"].as_str().unwrap_or_default();
+            wait_until("Waiting for CRD to be established...", timeout, || {
+                client.crd_established(crd_name)
+            })
+            .await?;
+        }
+    }
+
+    let docs = manifests::operator_manifests(&OperatorManifestOptions {
+        namespace: namespace.to_string(),
+        image,
+    });
+    for doc in &docs {
+        client.apply_manifest(doc, dry_run).await?;
+        println!("  {} {} applied", "✓".green(), describe_manifest(doc));
+    }
+
+    if dry_run {
+        println!();
+        println!(
+            "{}",
+            "Dry run complete, no changes were persisted.".dimmed()
+        );
+        return Ok(());
+    }
+
+    wait_until("Waiting for operator to become ready...", timeout, || {
+        client.deployment_ready(namespace, manifests::OPERATOR_NAME)
+    })
+    .await?;
+
+    println!();
+    println!("{}", "✓ FabGitOps operator installed and ready!".green());
+
+    Ok(())
+}
+
+/// Execute the uninstall command
+pub async fn cmd_uninstall(
+    client: &K8sClient,
+    namespace: &str,
+    delete_crs: bool,
+    timeout_secs: u64,
+    yes: bool,
+) -> Result<()> {
+    let docs = manifests::operator_manifests(&OperatorManifestOptions {
+        namespace: namespace.to_string(),
+        image: String::new(),
+    });
+
+    // Deleting every PLC in the cluster always asks, unless told not to
+    if (delete_crs && !yes) || confirmation_required() {
+        println!("{}", "Removing:".bold());
+        for doc in &docs {
+            println!("  {}", describe_manifest(doc));
+        }
+        if delete_crs {
+            for crd in manifests::crds() {
+                println!("  {}", describe_manifest(&serde_json::to_value(crd)?));
+            }
+            let plcs = client.list_all_plcs().await?;
+            println!(
+                "  {} IndustrialPLC resource(s) in every namespace:",
+                plcs.len()
+            );
+            for plc in &plcs {
+                println!(
+                    "    {}/{}",
+                    plc.namespace().unwrap_or_default(),
+                    plc.name_any().cyan()
+                );
+            }
+        }
+        if !confirm("Continue?", "uninstall")? {
+            println!("Aborted, nothing was removed");
+            return Ok(());
+        }
+    }
+
+    println!(
+        "{} FabGitOps operator from namespace {}",
+        "🧹 Uninstalling".cyan(),
+        namespace.cyan()
+    );
+    println!();
+
+    // Remove CRs while the operator is still running so it can process
+    // any finalizers, then drop the CRD itself
+    if delete_crs {
+        // Their deletion would never complete once the operator is gone
+        let protected = client.protected_plcs().await?;
+        if !protected.is_empty() {
+            anyhow::bail!(
+                "{} PLC(s) are protected by the {} annotation: {}; remove it first",
+                protected.len(),
+                PROTECTED_ANNOTATION,
+                protected.join(", ")
+            );
+        }
+
+        let count = client.delete_all_plcs().await?;
+        println!(
+            "  {} deleting {} IndustrialPLC resource(s)",
+            "✓".green(),
+            count
+        );
+        wait_until(
+            "Waiting for IndustrialPLC resources to be removed...",
+            Duration::from_secs(timeout_secs),
+            || async { Ok(client.count_all_plcs().await? == 0) },
+        )
+        .await?;
+
+        for crd in manifests::crds() {
+            let crd = serde_json::to_value(crd)?;
+            print_deleted(&crd, client.delete_manifest(&crd).await?);
+        }
+    }
+
+    for doc in docs.iter().rev() {
+        print_deleted(doc, client.delete_manifest(doc).await?);
+    }
+
+    println!();
+    if delete_crs {
+        println!("{}", "✓ FabGitOps fully removed.".green());
+    } else {
+        println!("{}", "✓ FabGitOps operator removed.".green());
+        println!(
+            "{}",
+            "  The CRD and IndustrialPLC resources were kept; use --delete-crs to remove them."
+                .dimmed()
+        );
+    }
+
+    Ok(())
+}
+
+fn print_deleted(manifest: &serde_json::Value, deleted: bool) {
+    if deleted {
+        println!("  {} {} deleted", "✓".green(), describe_manifest(manifest));
+    } else {
+        println!(
+            "  {} {} not found",
+            "○".dimmed(),
+            describe_manifest(manifest)
+        );
+    }
+}
+
+/// Poll a readiness check with a spinner until it passes or times out
+async fn wait_until<F, Fut>(message: &str, timeout: Duration, mut check: F) -> Result<()>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<bool>>,
+{
+    use indicatif::{ProgressBar, ProgressStyle};
+
+    let spinner = ProgressBar::new_spinner();
+    spinner.set_style(
+        ProgressStyle::default_spinner()
+            .template("{spinner:.cyan} {msg}")
+            .unwrap(),
+    );
+    spinner.set_message(message.to_string());
+    spinner.enable_steady_tick(Duration::from_millis(100));
+
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        if check().await? {
+            spinner.finish_with_message(format!(
+                "{} {}",
+                "✓".green(),
+                message.trim_end_matches("...")
+            ));
+            return Ok(());
+        }
+        if tokio::time::Instant::now() >= deadline {
+            spinner.finish_and_clear();
+            anyhow::bail!("Timed out after {}s: {}", timeout.as_secs(), message);
+        }
+        tokio::time::sleep(Duration::from_secs(2)).await;
+    }
+}
+
+/// Execute the doctor command
+pub async fn cmd_doctor(
+    client: &K8sClient,
+    namespace: &str,
+    operator_namespace: Option<&str>,
+    format: OutputFormat,
+) -> Result<()> {
+    let checks = run_checks(client, namespace, operator_namespace.unwrap_or(namespace)).await;
+
+    match format {
+        OutputFormat::Table => print_doctor_report(&checks),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&checks)?),
+        OutputFormat::Yaml => println!("{}", serde_yaml::to_string(&checks)?),
+    }
+
+    let failed = checks
+        .iter()
+        .filter(|c| c.status == CheckStatus::Fail)
+        .count();
+    if failed > 0 {
+        anyhow::bail!("{} check(s) failed", failed);
+    }
+
+    Ok(())
+}
+
+/// Execute the summary command. PLC counts come from the resources; the
+/// operator's own totals from its metrics, left out when unreachable.
+pub async fn cmd_summary(
+    client: &K8sClient,
+    namespace: &str,
+    top: usize,
+    window: Duration,
+    operator_namespace: &str,
+    format: OutputFormat,
+) -> Result<()> {
+    let plcs = client.list_plcs(namespace).await?;
+    let mut summary = FleetSummary::new(namespace, &plcs, window, top);
+
+    let scraped = client
+        .proxy_get(
+            operator_namespace,
+            METRICS_SERVICE_NAME,
+            METRICS_PORT,
+            "/metrics",
+        )
+        .await;
+    match scraped {
+        Ok(text) => summary = summary.with_metrics(&Scrape::parse(&text)),
+        Err(e) => summary.metrics_error = Some(e.to_string()),
+    }
+
+    match format {
+        OutputFormat::Table => print_fleet_summary(&summary),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&summary)?),
+        OutputFormat::Yaml => println!("{}", serde_yaml::to_string(&summary)?),
+    }
+
+    Ok(())
+}
+
+/// Execute the top command
+pub async fn cmd_top(
+    client: &K8sClient,
+    operator_namespace: &str,
+    interval_secs: u64,
+    limit: usize,
+) -> Result<()> {
+    let mut previous: Option<(Scrape, tokio::time::Instant)> = None;
+
+    loop {
+        let scraped = client
+            .proxy_get(
+                operator_namespace,
+                METRICS_SERVICE_NAME,
+                METRICS_PORT,
+                "/metrics",
+            )
+            .await;
+        let now = tokio::time::Instant::now();
+
+        print!("\x1B[2J\x1B[1;1H");
+        println!(
+            "{}",
+            "╔════════════════════════════════════════════════════════════╗".bright_blue()
+        );
+        println!(
+            "{}",
+            "║            FabGitOps Operator Metrics (fabctl top)         ║".bright_blue()
+        );
+        println!(
+            "{}",
+            "╚════════════════════════════════════════════════════════════╝".bright_blue()
+        );
+        println!(
+            "  Operator: {} | Refresh: {}s | Press Ctrl+C to exit",
+            operator_namespace.cyan(),
+            interval_secs
+        );
+        println!();
+
+        match scraped {
+            Ok(text) => {
+                let scrape = Scrape::parse(&text);
+                print_top(
+                    &scrape,
+                    previous
+                        .as_ref()
+                        .map(|(prev, at)| (prev, now.duration_since(*at).as_secs_f64())),
+                    limit,
+                );
+                previous = Some((scrape, now));
+            }
+            Err(e) => println!("{} {}", "Error:".red().bold(), e),
+        }
+
+        use std::io::Write;
+        std::io::stdout().flush()?;
+
+        tokio::time::sleep(Duration::from_secs(interval_secs)).await;
+    }
+}
+
+/// Execute the history command
+pub async fn cmd_history(
+    client: &K8sClient,
+    namespace: &str,
+    name: &str,
+    since: Option<Duration>,
+    format: OutputFormat,
+) -> Result<()> {
+    let plc = client.get_plc(namespace, name).await?;
+    let history = plc.status.map(|s| s.history).unwrap_or_default();
+
+    let cutoff = since
+        .map(chrono::Duration::from_std)
+        .transpose()?
+        .map(|window| chrono::Utc::now() - window);
+    let entries: Vec<_> = history
+        .iter()
+        .filter(|entry| match cutoff {
+            Some(cutoff) => chrono::DateTime::parse_from_rfc3339(&entry.timestamp)
+                .map(|t| t >= cutoff)
+                .unwrap_or(true),
+            None => true,
+        })
+        .collect();
+
+    match format {
+        OutputFormat::Table => print_history_table(name, &entries),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&entries)?),
+        OutputFormat::Yaml => println!("{}", serde_yaml::to_string(&entries)?),
+    }
+
+    Ok(())
+}
+
+/// Execute the record command
+pub async fn cmd_record(
+    client: &K8sClient,
+    namespace: &str,
+    name: &str,
+    interval: Duration,
+    duration: Option<Duration>,
+    out: Option<&Path>,
+    direct: bool,
+) -> Result<()> {
+    use std::io::Write;
+
+    let plc = client.get_plc(namespace, name).await?;
+    let plc_client = direct.then(|| spec_client(&plc.spec));
+
+    let mut writer: Box<dyn Write> = match out {
+        Some(path) => Box::new(std::io::BufWriter::new(std::fs::File::create(path)?)),
+        None => Box::new(std::io::stdout()),
+    };
+    writeln!(writer, "timestamp,plc,register,value,desired,error")?;
+
+    if let Some(path) = out {
+        eprintln!(
+            "{} Recording {} every {:?} to {} (Ctrl+C to stop)",
+            "●".red(),
+            name.cyan(),
+            interval,
+            path.display()
+        );
+    }
+
+    let deadline = duration.map(|d| tokio::time::Instant::now() + d);
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let mut samples = 0usize;
+
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = tokio::signal::ctrl_c() => break,
+        }
+        if deadline.is_some_and(|d| tokio::time::Instant::now() >= d) {
+            break;
+        }
+
+        let sample = match &plc_client {
+            Some(plc_client) => plc_client
+                .read_register(plc.spec.target_register)
+                .await
+                .map(|value| (Some(value), plc.spec.target())),
+            None => client
+                .get_plc(namespace, name)
+                .await
+                .map(|p| (p.status.and_then(|s| s.current_value), p.spec.target())),
+        };
+        let (value, desired, error) = match sample {
+            Ok((value, desired)) => (value, desired, String::new()),
+            Err(e) => (None, plc.spec.target(), e.to_string()),
+        };
+
+        writeln!(
+            writer,
+            "{},{},{},{},{},{}",
+            chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            name,
+            plc.spec.target_register,
+            value.map(|v| v.to_string()).unwrap_or_default(),
+            desired,
+            csv_field(&error)
+        )?;
+        writer.flush()?;
+        samples += 1;
+    }
+
+    if let Some(path) = out {
+        eprintln!(
+            "{} Wrote {} sample(s) to {}",
+            "✓".green(),
+            samples,
+            path.display()
+        );
+    }
+
+    Ok(())
+}
+
+/// Quote a CSV field when it contains a separator, quote, or newline
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Execute the report command. Operator metrics are fetched on a best-effort
+/// basis; the report notes when they could not be.
+pub async fn cmd_report(
+    client: &K8sClient,
+    namespace: &str,
+    name: Option<&str>,
+    operator_namespace: &str,
+    output: &Output,
+) -> Result<()> {
+    let plcs = match name {
+        Some(name) => vec![client.get_plc(namespace, name).await?],
+        None => client.list_plcs(namespace).await?,
+    };
+
+    let scraped = client
+        .proxy_get(
+            operator_namespace,
+            METRICS_SERVICE_NAME,
+            METRICS_PORT,
+            "/metrics",
+        )
+        .await;
+    let (scrape, metrics_error) = match scraped {
+        Ok(text) => (Some(Scrape::parse(&text)), None),
+        Err(e) => (None, Some(e.to_string())),
+    };
+
+    let mut reports = Vec::new();
+    for plc in plcs {
+        let events = client.list_plc_events(namespace, &plc.name_any()).await?;
+        reports.push(report::PlcReport::new(plc, events, scrape.as_ref()));
+    }
+    let report = report::Report {
+        generated_at: report::timestamp(&chrono::Utc::now()),
+        namespace: namespace.to_string(),
+        plcs: reports,
+        metrics_error,
+    };
+
+    match output {
+        Output::Document(format) => print!("{}", report.render(*format)?),
+        Output::Format(OutputFormat::Json) => {
+            println!("{}", serde_json::to_string_pretty(&report)?)
+        }
+        Output::Format(OutputFormat::Yaml) => println!("{}", serde_yaml::to_string(&report)?),
+        // Markdown reads fine in a terminal too; templates are refused up front
+        Output::Format(OutputFormat::Table) | Output::Template(_) => {
+            print!("{}", report.render(DocumentFormat::Markdown)?)
+        }
+    }
+
+    Ok(())
+}
+
+/// Execute the plot command
+#[allow(clippy::too_many_arguments)]
+pub async fn cmd_plot(
+    client: &K8sClient,
+    namespace: &str,
+    name: &str,
+    since: Duration,
+    live: bool,
+    interval: Duration,
+    size: (usize, usize),
+    format: OutputFormat,
+) -> Result<()> {
+    use std::io::Write;
+
+    let window = chrono::Duration::from_std(since)?;
+    let plc = client.get_plc(namespace, name).await?;
+
+    // Seed the chart with values recorded in the status history
+    let mut points: Vec<ValuePoint> = plc
+        .status
+        .iter()
+        .flat_map(|s| s.history.iter())
+        .filter_map(|entry| {
+            let timestamp = chrono::DateTime::parse_from_rfc3339(&entry.timestamp).ok()?;
+            Some(ValuePoint {
+                timestamp: timestamp.with_timezone(&chrono::Utc),
+                value: entry.actual?,
+            })
+        })
+        .collect();
+    let mut desired = plc.spec.target();
+    let mut current = plc;
+
+    loop {
+        if let Some(value) = current.status.as_ref().and_then(|s| s.current_value) {
+            points.push(ValuePoint {
+                timestamp: chrono::Utc::now(),
+                value,
+            });
+        }
+        let end = chrono::Utc::now();
+        let start = end - window;
+        points.retain(|p| p.timestamp >= start);
+
+        match format {
+            OutputFormat::Table => {
+                if live {
+                    print!("\x1B[2J\x1B[1;1H");
+                }
+                print_value_chart(name, &points, desired, (start, end), size);
+                if live {
+                    println!(
+                        "{}",
+                        format!("Sampling every {:?} | Press Ctrl+C to exit", interval).dimmed()
+                    );
+                }
+                std::io::stdout().flush()?;
+            }
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&points)?),
+            OutputFormat::Yaml => println!("{}", serde_yaml::to_string(&points)?),
+        }
+
+        if !live {
+            return Ok(());
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {}
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        }
+        current = client.get_plc(namespace, name).await?;
+        desired = current.spec.target();
+    }
+}
+
+/// Execute the approvals list command
+pub async fn cmd_approvals_list(
+    client: &K8sClient,
+    namespace: &str,
+    all: bool,
+    format: OutputFormat,
+) -> Result<()> {
+    let requests: Vec<_> = client
+        .list_correction_requests(namespace)
+        .await?
+        .into_iter()
+        .filter(|r| all || r.state() == CorrectionRequestState::Pending)
+        .collect();
+
+    match format {
+        OutputFormat::Table => print_correction_requests(&requests),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&requests)?),
+        OutputFormat::Yaml => println!("{}", serde_yaml::to_string(&requests)?),
+    }
+
+    Ok(())
+}
+
+/// Execute the approve and reject commands
+pub async fn cmd_decide(
+    client: &K8sClient,
+    namespace: &str,
+    request_name: &str,
+    decision: Decision,
+    reason: Option<&str>,
+) -> Result<()> {
+    let request = client
+        .get_correction_request(namespace, request_name)
+        .await?;
+
+    if request.state() != CorrectionRequestState::Pending {
+        anyhow::bail!(
+            "correctionrequest/{} is {:?} and can no longer be decided",
+            request_name,
+            request.state()
+        );
+    }
+    if let Some(ref existing) = request.spec.approval {
+        anyhow::bail!(
+            "correctionrequest/{} was already {:?} by {}",
+            request_name,
+            existing.decision,
+            existing.user
+        );
+    }
+
+    if confirmation_required() {
+        let (doing, action) = match decision {
+            Decision::Approved => ("Approving", "approve"),
+            Decision::Rejected => ("Rejecting", "reject"),
+        };
+        println!(
+            "{}",
+            format!(
+                "{} correctionrequest/{}: write {} to register {} on {} (currently {})",
+                doing,
+                request_name,
+                request.spec.desired_value,
+                request.spec.register,
+                request.spec.plc_name,
+                request.spec.current_value
+            )
+            .bold()
+        );
+        if !confirm("Continue?", action)? {
+            println!("Aborted, nothing was decided");
+            return Ok(());
+        }
+    }
+
+    let user = client.current_user().await;
+    let approval = ApprovalDecision {
+        decision,
+        user: user.clone(),
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        reason: reason.map(str::to_string),
+    };
+    client
+        .decide_correction_request(namespace, &request, &approval)
+        .await?;
+
+    let verb = match decision {
+        Decision::Approved => "approved".green(),
+        Decision::Rejected => "rejected".red(),
+    };
+    println!(
+        "{} correctionrequest/{} {} by {}: write {} to register {} on {}",
+        "✓".green(),
+        request_name.cyan(),
+        verb,
+        user.bold(),
+        request.spec.desired_value,
+        request.spec.register,
+        request.spec.plc_name.cyan()
+    );
+
+    Ok(())
+}
+
+/// Parse a human duration such as `90s`, `30m`, `24h`, or `7d`
+/// Client for a PLC's device that decodes values per the spec's
+/// `encoding`, as the operator does
+pub fn spec_client(spec: &IndustrialPLCSpec) -> PLCClient {
+    let encoding = match spec.encoding {
+        ValueEncoding::Binary => Encoding::Binary,
+        ValueEncoding::Bcd => Encoding::Bcd,
+    };
+    PLCClient::new(spec.device_address.clone(), spec.port).with_encoding(encoding)
+}
+
+pub fn parse_duration(input: &str) -> std::result::Result<Duration, String> {
+    let input = input.trim();
+    let split = input
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(input.len());
+    let (amount, unit) = input.split_at(split);
+    let amount: u64 = amount
+        .parse()
+        .map_err(|_| format!("invalid duration '{}'", input))?;
+    let multiplier = match unit {
+        "ms" => return Ok(Duration::from_millis(amount)),
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        _ => {
+            return Err(format!(
+                "invalid duration unit '{}' (use ms, s, m, h, or d)",
+                unit
+            ))
+        }
+    };
+    Ok(Duration::from_secs(amount * multiplier))
+}
+
+/// Parse `-o`: a format name, or `template=TEMPLATE` (also spelled
+/// `go-template=TEMPLATE`)
+fn parse_output(value: &str) -> std::result::Result<Output, String> {
+    if let Some(source) = value
+        .strip_prefix("template=")
+        .or_else(|| value.strip_prefix("go-template="))
+    {
+        return Template::parse(source).map(Output::Template);
+    }
+    match value {
+        "markdown" | "md" => return Ok(Output::Document(DocumentFormat::Markdown)),
+        "html" => return Ok(Output::Document(DocumentFormat::Html)),
+        _ => {}
+    }
+    <OutputFormat as clap::ValueEnum>::from_str(value, false)
+        .map(Output::Format)
+        .map_err(|_| {
+            format!(
+                "invalid output '{}', expected table, json, yaml, markdown, html or template=TEMPLATE",
+                value
+            )
+        })
+}
+
+/// Parse a `key=value` label
+fn parse_label(value: &str) -> std::result::Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((k, v)) if !k.trim().is_empty() => Ok((k.trim().to_string(), v.trim().to_string())),
+        _ => Err(format!("invalid label '{}', expected key=value", value)),
+    }
+}
+
+/// Parse "HH:MM" and normalize it to two-digit hours and minutes
+fn parse_time_of_day(value: &str) -> std::result::Result<String, String> {
+    fabgitops_types::crd::parse_time_of_day(value)
+        .map(|m| format!("{:02}:{:02}", m / 60, m % 60))
+        .ok_or_else(|| format!("invalid time '{}', expected HH:MM", value))
+}
+
+/// Parse a day name such as `sat` or `saturday`
+fn parse_weekday(value: &str) -> std::result::Result<Weekday, String> {
+    value
+        .trim()
+        .parse::<chrono::Weekday>()
+        .map(Weekday::from)
+        .map_err(|_| format!("invalid day '{}', expected mon..sun", value))
+}
+
+/// Parse a UTC offset such as `+02:00`, `-0530`, or `Z` into minutes
+fn parse_utc_offset(value: &str) -> std::result::Result<i32, String> {
+    let invalid = || format!("invalid UTC offset '{}', expected e.g. +02:00", value);
+    let value = value.trim();
+    if value.eq_ignore_ascii_case("z") || value == "0" {
+        return Ok(0);
+    }
+    let (sign, rest) = match value.split_at(1) {
+        ("+", rest) => (1, rest),
+        ("-", rest) => (-1, rest),
+        _ => return Err(invalid()),
+    };
+    let digits: String = rest.chars().filter(|c| *c != ':').collect();
+    let (h, m) = match digits.len() {
+        1 | 2 => (digits.as_str(), "0"),
+        4 => digits.split_at(2),
+        _ => return Err(invalid()),
+    };
+    let (h, m): (i32, i32) = (
+        h.parse().map_err(|_| invalid())?,
+        m.parse().map_err(|_| invalid())?,
+    );
+    if h > 14 || m >= 60 {
+        return Err(invalid());
+    }
+    Ok(sign * (h * 60 + m))
+}
+
+/// Execute the plugin list command
+pub fn cmd_plugin_list(format: OutputFormat) -> Result<()> {
+    let plugins = plugins::list_plugins();
+
+    match format {
+        OutputFormat::Table => {
+            if plugins.is_empty() {
+                println!(
+                    "{}",
+                    format!(
+                        "No plugins found; add {}<name> executables to PATH",
+                        plugins::PLUGIN_PREFIX
+                    )
+                    .yellow()
+                );
+            }
+            for (name, path) in &plugins {
+                println!("  {} {}", name.cyan(), path.display().to_string().dimmed());
+            }
+        }
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&plugins)?),
+        OutputFormat::Yaml => println!("{}", serde_yaml::to_string(&plugins)?),
+    }
+
+    Ok(())
+}
+
+/// Execute the verify command
+pub async fn cmd_verify(
+    client: &K8sClient,
+    namespace: &str,
+    source: &VerifySource<'_>,
+    device_timeout: Option<Duration>,
+    format: OutputFormat,
+) -> Result<()> {
+    let checkout = verify::Checkout::clone_repo(source.repo, source.git_ref)?;
+    let root = checkout.join(source.path)?;
+    let declared = verify::load_declared(&[root], namespace)?;
+
+    let namespaces: BTreeSet<String> = declared
+        .keys()
+        .map(|(ns, _)| ns.clone())
+        .chain(std::iter::once(namespace.to_string()))
+        .collect();
+    let mut cluster = BTreeMap::new();
+    for ns in &namespaces {
+        for plc in client.list_plcs(ns).await? {
+            cluster.insert((ns.clone(), plc.name_any()), plc);
+        }
+    }
+
+    let report = verify::VerifyReport {
+        repo: source.repo.to_string(),
+        path: source.path.display().to_string(),
+        entries: verify::compare(&declared, &cluster, device_timeout).await,
+    };
+
+    match format {
+        OutputFormat::Table => print_verify_report(&report, device_timeout.is_some()),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+        OutputFormat::Yaml => println!("{}", serde_yaml::to_string(&report)?),
+    }
+
+    if report.drifted() > 0 {
+        anyhow::bail!(
+            "{} of {} PLC(s) differ from Git",
+            report.drifted(),
+            report.entries.len()
+        );
+    }
+
+    Ok(())
+}
+
+/// Compare the IndustrialPLC manifests under `files` with the live objects
+/// and show the spec changes applying them would make. Fails when anything
+/// would change, like `kubectl diff`, so CI can gate on it.
+pub async fn cmd_diff(
+    source: &PlcSource<'_>,
+    namespace: &str,
+    files: &[PathBuf],
+    format: OutputFormat,
+) -> Result<()> {
+    let declared = verify::load_declared(files, namespace)?;
+
+    let namespaces: BTreeSet<String> = declared.keys().map(|(ns, _)| ns.clone()).collect();
+    let mut live = BTreeMap::new();
+    for ns in &namespaces {
+        for plc in source.list_plcs(ns).await? {
+            live.insert((ns.clone(), plc.name_any()), plc);
+        }
+    }
+
+    let report = diff::compare(&declared, &live);
+    match format {
+        OutputFormat::Table => print_diff_report(&report),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+        OutputFormat::Yaml => println!("{}", serde_yaml::to_string(&report)?),
+    }
+
+    if report.changed() > 0 {
+        anyhow::bail!(
+            "{} of {} PLC(s) differ from the manifests",
+            report.changed(),
+            report.objects.len()
+        );
+    }
+
+    Ok(())
+}
+
+/// Where `fabctl verify` reads the source of truth from
+pub struct VerifySource<'a> {
+    pub repo: &'a str,
+    pub path: &'a Path,
+    pub git_ref: Option<&'a str>,
+}
+
+/// Execute a config subcommand. `namespace` and `context` are only set when
+/// given explicitly on the command line, and `require_confirmation` only
+/// when --require-confirmation was.
+pub fn cmd_config(
+    action: &ConfigAction,
+    namespace: Option<&str>,
+    context: Option<&str>,
+    require_confirmation: bool,
+    format: OutputFormat,
+) -> Result<()> {
+    let path = config::config_path()?;
+    let mut config = FabctlConfig::load(&path)?;
+
+    match action {
+        ConfigAction::SetProfile {
+            name,
+            no_require_confirmation,
+        } => {
+            let confirmation = match (require_confirmation, no_require_confirmation) {
+                (true, _) => Some(true),
+                (_, true) => Some(false),
+                _ => None,
+            };
+            if namespace.is_none() && context.is_none() && confirmation.is_none() {
+                anyhow::bail!(
+                    "nothing to set: pass --namespace, --context and/or --require-confirmation"
+                );
+            }
+            let profile = config.profiles.entry(name.clone()).or_default();
+            if let Some(namespace) = namespace {
+                profile.namespace = Some(namespace.to_string());
+            }
+            if let Some(context) = context {
+                profile.context = Some(context.to_string());
+            }
+            if let Some(required) = confirmation {
+                profile.require_confirmation = required;
+            }
+            let first = config.current_profile.is_none();
+            if first {
+                config.current_profile = Some(name.clone());
+            }
+            config.save(&path)?;
+
+            println!("{} Profile {} saved", "✓".green(), name.cyan());
+            if first {
+                println!("  Now the current profile");
+            }
+        }
+        ConfigAction::UseProfile { name } => {
+            if !config.profiles.contains_key(name) {
+                anyhow::bail!("Profile '{}' not found", name);
+            }
+            config.current_profile = Some(name.clone());
+            config.save(&path)?;
+            println!("{} Switched to profile {}", "✓".green(), name.cyan());
+        }
+        ConfigAction::GetProfiles => match format {
+            OutputFormat::Table => {
+                if config.profiles.is_empty() {
+                    println!(
+                        "{}",
+                        "No profiles; create one with `fabctl config set-profile`".yellow()
+                    );
+                }
+                for (name, profile) in &config.profiles {
+                    let current = config.current_profile.as_deref() == Some(name.as_str());
+                    println!(
+                        "{} {}  namespace={} context={}{}",
+                        if current { "*" } else { " " },
+                        if current {
+                            name.cyan().bold()
+                        } else {
+                            name.cyan()
+                        },
+                        profile.namespace.as_deref().unwrap_or("-"),
+                        profile.context.as_deref().unwrap_or("-"),
+                        if profile.require_confirmation {
+                            "  (confirmation required)".yellow().to_string()
+                        } else {
+                            String::new()
+                        },
+                    );
+                }
+                println!("{}", format!("Config: {}", path.display()).dimmed());
+            }
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&config)?),
+            OutputFormat::Yaml => println!("{}", serde_yaml::to_string(&config)?),
+        },
+        ConfigAction::DeleteProfile { name } => {
+            if config.profiles.remove(name).is_none() {
+                anyhow::bail!("Profile '{}' not found", name);
+            }
+            if config.current_profile.as_deref() == Some(name.as_str()) {
+                config.current_profile = None;
+            }
+            config.save(&path)?;
+            println!("{} Profile {} deleted", "✓".green(), name.cyan());
+        }
+    }
+
+    Ok(())
+}
+
+/// Run `fabctl-<name>` with the remaining arguments and exit with its code
+pub fn cmd_external(
+    args: &[String],
+    namespace: &str,
+    context: Option<&str>,
+    format: OutputFormat,
+) -> Result<()> {
+    let (name, rest) = args.split_first().context("missing plugin name")?;
+    let path = plugins::find_plugin(name).with_context(|| {
+        format!(
+            "unknown command '{}' (no {}{} found on PATH)",
+            name,
+            plugins::PLUGIN_PREFIX,
+            name
+        )
+    })?;
+
+    let output = clap::ValueEnum::to_possible_value(&format)
+        .map(|v| v.get_name().to_string())
+        .unwrap_or_default();
+    let env = plugins::PluginEnv {
+        namespace,
+        output: &output,
+        context,
+        require_confirmation: confirmation_required(),
+    };
+    let code = plugins::run_plugin(&path, rest, &env)?;
+    std::process::exit(code);
+}
 
+/// Execute the completion command
+pub fn cmd_completion(shell: Shell) -> Result<()> {
+    print!("{}", completion::script(shell));
     Ok(())
 }
 
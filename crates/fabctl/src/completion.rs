@@ -0,0 +1,347 @@
+use crate::commands::Cli;
+use crate::config::{self, FabctlConfig};
+use crate::k8s_client::K8sClient;
+use crate::retry::ClientOptions;
+use anyhow::{Context, Result};
+use clap::{Arg, CommandFactory, ValueEnum};
+use fabgitops_types::crd::{CorrectionRequest, IndustrialPLC, PLCGroup, PLCSnapshot};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+/// How long listed names are reused before the cluster is asked again
+const CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// Longest a completion waits for the cluster before giving up
+const LOOKUP_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Shells `fabctl completion` writes a script for
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+/// Where the candidates for an argument come from
+#[derive(Clone, Copy, Debug)]
+enum Source {
+    Plcs,
+    Groups,
+    Snapshots,
+    CorrectionRequests,
+    Namespaces,
+    Profiles,
+    Contexts,
+}
+
+impl Source {
+    /// The source of an argument's values, by the subcommands leading to it
+    /// and the argument's id
+    fn of(path: &[&str], arg: &str) -> Option<Self> {
+        match (path, arg) {
+            (_, "namespace" | "operator_namespace") => Some(Self::Namespaces),
+            (_, "context") => Some(Self::Contexts),
+            (_, "profile") | (["config", "use-profile" | "delete-profile"], "name") => {
+                Some(Self::Profiles)
+            }
+            (["group", "describe" | "sync"], "name") => Some(Self::Groups),
+            (["snapshot", "restore"], "snapshot") => Some(Self::Snapshots),
+            (["snapshot", "create" | "list"], "plc") | (["clone"], "source") => Some(Self::Plcs),
+            (["approve" | "reject"], "request") => Some(Self::CorrectionRequests),
+            (
+                ["get-status" | "describe" | "sync" | "delete" | "set" | "history" | "record"
+                | "plot" | "report" | "watch" | "hold"]
+                | ["maintenance", "set" | "clear"],
+                "name",
+            ) => Some(Self::Plcs),
+            _ => None,
+        }
+    }
+
+    /// Name of the cache file; namespaces are the same in every namespace
+    fn cache_key(self) -> &'static str {
+        match self {
+            Self::Plcs => "plcs",
+            Self::Groups => "groups",
+            Self::Snapshots => "snapshots",
+            Self::CorrectionRequests => "correctionrequests",
+            Self::Namespaces => "namespaces",
+            Self::Profiles => "profiles",
+            Self::Contexts => "contexts",
+        }
+    }
+}
+
+/// The shell script that hooks `fabctl __complete` into `shell`
+pub fn script(shell: Shell) -> &'static str {
+    match shell {
+        Shell::Bash => {
+            r#"# fabctl completion for bash; load with: source <(fabctl completion bash)
+_fabctl() {
+    local IFS=$'\n'
+    COMPREPLY=($(fabctl __complete -- "${COMP_WORDS[@]:1:COMP_CWORD}" 2>/dev/null))
+}
+complete -o default -F _fabctl fabctl
+"#
+        }
+        Shell::Zsh => {
+            r#"#compdef fabctl
+# fabctl completion for zsh; load with: source <(fabctl completion zsh)
+_fabctl() {
+    local -a candidates
+    candidates=("${(@f)$(fabctl __complete -- "${(@)words[2,CURRENT]}" 2>/dev/null)}")
+    candidates=(${candidates:#})
+    if (( ${#candidates} )); then
+        compadd -a candidates
+    else
+        _files
+    fi
+}
+compdef _fabctl fabctl
+"#
+        }
+        Shell::Fish => {
+            r#"# fabctl completion for fish; load with: fabctl completion fish | source
+function __fabctl_complete
+    set -l tokens (commandline -opc)
+    set -l current (commandline -ct)
+    fabctl __complete -- $tokens[2..-1] "$current" 2>/dev/null
+end
+complete -c fabctl -f -a '(__fabctl_complete)'
+"#
+        }
+    }
+}
+
+/// Flags given on the line being completed that decide where names are
+/// looked up
+#[derive(Default)]
+struct Scope {
+    namespace: Option<String>,
+    context: Option<String>,
+    profile: Option<String>,
+}
+
+impl Scope {
+    fn set(&mut self, arg: &Arg, value: &str) {
+        let value = Some(value.to_string());
+        match arg.get_id().as_str() {
+            "namespace" => self.namespace = value,
+            "context" => self.context = value,
+            "profile" => self.profile = value,
+            _ => {}
+        }
+    }
+}
+
+/// Print the candidates for the last of `words`, the arguments after
+/// `fabctl` up to the one being completed, one per line. Subcommands, flags
+/// and enum values come from the command definitions; PLC, group, snapshot,
+/// CorrectionRequest and namespace names from the cluster. Nothing is
+/// printed when nothing fits, so the shell falls back to file names.
+pub async fn complete(words: &[String]) {
+    let Some((current, before)) = words.split_last() else {
+        return;
+    };
+    let mut root = Cli::command();
+    root.build();
+
+    let mut cmd = &root;
+    let mut path: Vec<&str> = Vec::new();
+    let mut positionals = 0;
+    let mut scope = Scope::default();
+    let mut pending: Option<&Arg> = None;
+    for word in before {
+        if let Some(arg) = pending.take() {
+            scope.set(arg, word);
+        } else if let Some(flag) = word.strip_prefix("--") {
+            let (name, value) = match flag.split_once('=') {
+                Some((name, value)) => (name, Some(value)),
+                None => (flag, None),
+            };
+            if let Some(arg) = cmd.get_arguments().find(|a| a.get_long() == Some(name)) {
+                match value {
+                    Some(value) => scope.set(arg, value),
+                    None if takes_value(arg) => pending = Some(arg),
+                    None => {}
+                }
+            }
+        } else if let Some(shorts) = word.strip_prefix('-').filter(|s| !s.is_empty()) {
+            // Combined short flags; a value-taking one swallows the rest
+            for (i, short) in shorts.char_indices() {
+                let Some(arg) = cmd.get_arguments().find(|a| a.get_short() == Some(short)) else {
+                    continue;
+                };
+                if takes_value(arg) {
+                    let rest = &shorts[i + short.len_utf8()..];
+                    if rest.is_empty() {
+                        pending = Some(arg);
+                    } else {
+                        scope.set(arg, rest);
+                    }
+                    break;
+                }
+            }
+        } else if let Some(sub) = cmd.find_subcommand(word) {
+            cmd = sub;
+            path.push(sub.get_name());
+            positionals = 0;
+        } else if cmd.has_subcommands() {
+            // A plugin, which completes nothing
+            return;
+        } else {
+            positionals += 1;
+        }
+    }
+
+    let (prefix, candidates) = if let Some(arg) = pending {
+        (String::new(), values(arg, &path, &scope).await)
+    } else if let Some((flag, _)) = current
+        .strip_prefix("--")
+        .and_then(|flag| flag.split_once('='))
+    {
+        let candidates = match cmd.get_arguments().find(|a| a.get_long() == Some(flag)) {
+            Some(arg) => values(arg, &path, &scope).await,
+            None => Vec::new(),
+        };
+        (format!("--{}=", flag), candidates)
+    } else if current.starts_with('-') {
+        let flags = cmd
+            .get_arguments()
+            .filter(|a| !a.is_hide_set())
+            .filter_map(|a| a.get_long())
+            .map(|long| format!("--{}", long))
+            .collect();
+        (String::new(), flags)
+    } else if cmd.has_subcommands() {
+        let subcommands = cmd
+            .get_subcommands()
+            .filter(|sub| !sub.is_hide_set())
+            .map(|sub| sub.get_name().to_string())
+            .collect();
+        (String::new(), subcommands)
+    } else {
+        let candidates = match cmd.get_positionals().nth(positionals) {
+            Some(arg) => values(arg, &path, &scope).await,
+            None => Vec::new(),
+        };
+        (String::new(), candidates)
+    };
+
+    for candidate in candidates {
+        let candidate = format!("{}{}", prefix, candidate);
+        if candidate.starts_with(current.as_str()) {
+            println!("{}", candidate);
+        }
+    }
+}
+
+fn takes_value(arg: &Arg) -> bool {
+    arg.get_action().takes_values()
+}
+
+/// Values offered for `arg`: names from its source, else its enum values
+async fn values(arg: &Arg, path: &[&str], scope: &Scope) -> Vec<String> {
+    match Source::of(path, arg.get_id().as_str()) {
+        Some(source) => names(source, scope).await.unwrap_or_default(),
+        None => arg
+            .get_possible_values()
+            .iter()
+            .filter(|value| !value.is_hide_set())
+            .map(|value| value.get_name().to_string())
+            .collect(),
+    }
+}
+
+/// Names from `source`, in the namespace and context the line selects
+/// directly or through a profile. Cluster lookups are cached for
+/// `CACHE_TTL`; when the cluster cannot be reached in time, the last names
+/// listed are used however old they are.
+async fn names(source: Source, scope: &Scope) -> Result<Vec<String>> {
+    let config = FabctlConfig::load(&config::config_path()?).unwrap_or_default();
+    match source {
+        Source::Profiles => return Ok(config.profiles.into_keys().collect()),
+        Source::Contexts => {
+            let kubeconfig = kube::config::Kubeconfig::read()?;
+            return Ok(kubeconfig.contexts.into_iter().map(|c| c.name).collect());
+        }
+        _ => {}
+    }
+
+    let profile = config
+        .active_profile(scope.profile.as_deref())
+        .ok()
+        .flatten()
+        .map(|(_, profile)| profile.clone())
+        .unwrap_or_default();
+    let namespace = scope
+        .namespace
+        .clone()
+        .or(profile.namespace)
+        .unwrap_or_else(|| "default".to_string());
+    let context = scope.context.clone().or(profile.context);
+
+    let cache = cache_path(source, context.as_deref(), &namespace)?;
+    let cached = std::fs::read_to_string(&cache).ok();
+    let fresh = std::fs::metadata(&cache)
+        .and_then(|meta| meta.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_some_and(|age| age < CACHE_TTL);
+    if let (true, Some(cached)) = (fresh, &cached) {
+        return Ok(cached.lines().map(str::to_string).collect());
+    }
+
+    let lookup = list(source, context.as_deref(), &namespace);
+    match tokio::time::timeout(LOOKUP_TIMEOUT, lookup).await {
+        Ok(Ok(names)) => {
+            if let Some(dir) = cache.parent() {
+                std::fs::create_dir_all(dir).ok();
+            }
+            std::fs::write(&cache, names.join("\n")).ok();
+            Ok(names)
+        }
+        _ => Ok(cached
+            .map(|cached| cached.lines().map(str::to_string).collect())
+            .unwrap_or_default()),
+    }
+}
+
+async fn list(source: Source, context: Option<&str>, namespace: &str) -> Result<Vec<String>> {
+    // The lookup as a whole is bounded by LOOKUP_TIMEOUT, so retrying is moot
+    let options = ClientOptions {
+        retries: 0,
+        ..Default::default()
+    };
+    let client = K8sClient::new(context, &options).await?;
+    match source {
+        Source::Plcs => client.list_names::<IndustrialPLC>(namespace).await,
+        Source::Groups => client.list_names::<PLCGroup>(namespace).await,
+        Source::Snapshots => client.list_names::<PLCSnapshot>(namespace).await,
+        Source::CorrectionRequests => client.list_names::<CorrectionRequest>(namespace).await,
+        Source::Namespaces => client.list_namespace_names().await,
+        Source::Profiles | Source::Contexts => unreachable!("not listed from the cluster"),
+    }
+}
+
+/// `$XDG_CACHE_HOME/fabctl/completion/<context>/<namespace>/<kind>`, else
+/// the same under `~/.cache`
+fn cache_path(source: Source, context: Option<&str>, namespace: &str) -> Result<PathBuf> {
+    let cache_dir = match std::env::var_os("XDG_CACHE_HOME").filter(|p| !p.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => std::env::var_os("HOME")
+            .filter(|p| !p.is_empty())
+            .map(|home| PathBuf::from(home).join(".cache"))
+            .context("Cannot locate the cache directory: HOME is not set")?,
+    };
+    let namespace = match source {
+        Source::Namespaces => "_cluster",
+        _ => namespace,
+    };
+    Ok(cache_dir
+        .join("fabctl")
+        .join("completion")
+        .join(context.unwrap_or("_current"))
+        .join(namespace)
+        .join(source.cache_key()))
+}
@@ -0,0 +1,91 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// Overrides the location of the fabctl config file
+pub const CONFIG_ENV: &str = "FABCTL_CONFIG";
+
+/// Named defaults stored in `~/.config/fabctl/config.yaml`
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FabctlConfig {
+    /// Profile applied when `--profile` is not given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub current_profile: Option<String>,
+
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+/// Defaults applied to every command run under a profile
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Profile {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+
+    /// Kubeconfig context to connect with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
+
+    /// Make mutating commands ask before changing anything, with no way to
+    /// skip the prompt, e.g. for production namespaces
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub require_confirmation: bool,
+}
+
+/// Path of the config file: `$FABCTL_CONFIG`, else `$XDG_CONFIG_HOME/fabctl/config.yaml`,
+/// else `~/.config/fabctl/config.yaml`
+pub fn config_path() -> Result<PathBuf> {
+    if let Some(path) = std::env::var_os(CONFIG_ENV).filter(|p| !p.is_empty()) {
+        return Ok(PathBuf::from(path));
+    }
+    let config_dir = match std::env::var_os("XDG_CONFIG_HOME").filter(|p| !p.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => std::env::var_os("HOME")
+            .filter(|p| !p.is_empty())
+            .map(|home| PathBuf::from(home).join(".config"))
+            .context("Cannot locate the fabctl config: HOME is not set")?,
+    };
+    Ok(config_dir.join("fabctl").join("config.yaml"))
+}
+
+impl FabctlConfig {
+    /// Read the config file; a missing file is an empty config
+    pub fn load(path: &Path) -> Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => serde_yaml::from_str(&content)
+                .with_context(|| format!("Failed to parse {}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        }
+    }
+
+    /// Write the config file, creating its directory if needed
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        let content = serde_yaml::to_string(self)?;
+        std::fs::write(path, content).with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// The profile to apply: the one named on the command line, else the current one.
+    /// Naming a profile that does not exist is an error.
+    pub fn active_profile(&self, requested: Option<&str>) -> Result<Option<(&str, &Profile)>> {
+        match requested {
+            Some(name) => self
+                .profiles
+                .get_key_value(name)
+                .map(|(name, profile)| Some((name.as_str(), profile)))
+                .with_context(|| format!("Profile '{}' not found", name)),
+            None => Ok(self
+                .current_profile
+                .as_deref()
+                .and_then(|name| self.profiles.get_key_value(name))
+                .map(|(name, profile)| (name.as_str(), profile))),
+        }
+    }
+}
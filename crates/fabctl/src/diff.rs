@@ -0,0 +1,149 @@
+use fabgitops_types::crd::IndustrialPLC;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+/// How a single spec field changes when a manifest is applied
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum ChangeKind {
+    /// Set in the manifest but not in the cluster
+    Added,
+    /// Set in the cluster but no longer in the manifest
+    Removed,
+    Changed,
+}
+
+/// One leaf of the spec that differs, e.g. `spec.registers[0].address`
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldChange {
+    pub path: String,
+    pub kind: ChangeKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub live: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub declared: Option<Value>,
+}
+
+/// What applying a manifest does to its cluster object
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum ObjectAction {
+    Create,
+    Update,
+    Unchanged,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ObjectDiff {
+    pub namespace: String,
+    pub name: String,
+    pub action: ObjectAction,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub changes: Vec<FieldChange>,
+}
+
+/// Full report produced by `fabctl diff`
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct DiffReport {
+    pub objects: Vec<ObjectDiff>,
+}
+
+impl DiffReport {
+    /// Number of objects applying the manifests would create or update
+    pub fn changed(&self) -> usize {
+        self.objects
+            .iter()
+            .filter(|o| o.action != ObjectAction::Unchanged)
+            .count()
+    }
+}
+
+/// Compare every declared PLC with its live object. PLCs only in the cluster
+/// are left out, since applying the manifests does not touch them.
+pub fn compare(
+    declared: &BTreeMap<(String, String), IndustrialPLC>,
+    live: &BTreeMap<(String, String), IndustrialPLC>,
+) -> DiffReport {
+    let objects = declared
+        .iter()
+        .map(|((namespace, name), plc)| {
+            let declared_spec = spec_value(plc);
+            let (action, changes) = match live.get(&(namespace.clone(), name.clone())) {
+                None => (
+                    ObjectAction::Create,
+                    spec_diff(&Value::Object(Map::new()), &declared_spec),
+                ),
+                Some(current) => {
+                    let changes = spec_diff(&spec_value(current), &declared_spec);
+                    let action = if changes.is_empty() {
+                        ObjectAction::Unchanged
+                    } else {
+                        ObjectAction::Update
+                    };
+                    (action, changes)
+                }
+            };
+            ObjectDiff {
+                namespace: namespace.clone(),
+                name: name.clone(),
+                action,
+                changes,
+            }
+        })
+        .collect();
+    DiffReport { objects }
+}
+
+/// The spec as it serializes, so defaults filled in on either side compare
+/// equal
+fn spec_value(plc: &IndustrialPLC) -> Value {
+    serde_json::to_value(&plc.spec).unwrap_or(Value::Null)
+}
+
+/// Field-by-field differences from `live` to `declared`, recursing into
+/// objects and arrays. Null fields count as unset.
+pub fn spec_diff(live: &Value, declared: &Value) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+    diff_value("spec", Some(live), Some(declared), &mut changes);
+    changes
+}
+
+fn diff_value(
+    path: &str,
+    live: Option<&Value>,
+    declared: Option<&Value>,
+    changes: &mut Vec<FieldChange>,
+) {
+    let live = live.filter(|v| !v.is_null());
+    let declared = declared.filter(|v| !v.is_null());
+    match (live, declared) {
+        (None, None) => {}
+        (Some(Value::Object(a)), Some(Value::Object(b))) => {
+            let mut keys: Vec<&String> = a.keys().chain(b.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                let child = format!("{}.{}", path, key);
+                diff_value(&child, a.get(key), b.get(key), changes);
+            }
+        }
+        (Some(Value::Array(a)), Some(Value::Array(b))) => {
+            for i in 0..a.len().max(b.len()) {
+                let child = format!("{}[{}]", path, i);
+                diff_value(&child, a.get(i), b.get(i), changes);
+            }
+        }
+        (live, declared) if live == declared => {}
+        (live, declared) => changes.push(FieldChange {
+            path: path.to_string(),
+            kind: match (live, declared) {
+                (None, _) => ChangeKind::Added,
+                (_, None) => ChangeKind::Removed,
+                _ => ChangeKind::Changed,
+            },
+            live: live.cloned(),
+            declared: declared.cloned(),
+        }),
+    }
+}
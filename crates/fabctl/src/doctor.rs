@@ -0,0 +1,406 @@
+use crate::k8s_client::{deployment_is_ready, K8sClient};
+use crate::manifests::{self, METRICS_PORT, METRICS_SERVICE_NAME, OPERATOR_NAME};
+use fabgitops_types::rbac::{self, OPERATOR_RULES};
+use k8s_openapi::apiextensions_apiserver::pkg::apis::apiextensions::v1::CustomResourceDefinition;
+use serde::Serialize;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::task::JoinSet;
+
+/// How long to wait for a PLC to accept a TCP connection
+const DEVICE_CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Outcome of a single diagnostic check
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+/// A diagnostic check result with an optional suggested fix
+#[derive(Clone, Debug, Serialize)]
+pub struct Check {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fix: Option<String>,
+}
+
+impl Check {
+    fn pass(name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status: CheckStatus::Pass,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    fn warn(name: impl Into<String>, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status: CheckStatus::Warn,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+
+    fn fail(name: impl Into<String>, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status: CheckStatus::Fail,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+}
+
+/// Run every diagnostic: CRDs, operator Deployment, RBAC and the operator's
+/// own permission check, metrics endpoint, and device reachability for the PLCs in `namespace`
+pub async fn run_checks(
+    client: &K8sClient,
+    namespace: &str,
+    operator_namespace: &str,
+) -> Vec<Check> {
+    let mut checks = Vec::new();
+    for crd in manifests::crds() {
+        checks.push(check_crd(client, crd).await);
+    }
+    // Device checks need the IndustrialPLC CRD, which comes first
+    let crd_ok = checks[0].status != CheckStatus::Fail;
+
+    checks.push(check_operator(client, operator_namespace).await);
+    checks.push(check_rbac(client, operator_namespace).await);
+    checks.push(check_self_check(client, operator_namespace).await);
+    checks.push(check_metrics(client, operator_namespace).await);
+
+    if crd_ok {
+        checks.extend(check_devices(client, namespace).await);
+    }
+
+    checks
+}
+
+async fn check_crd(client: &K8sClient, expected: CustomResourceDefinition) -> Check {
+    let crd_name = expected.metadata.name.clone().unwrap_or_default();
+    let check_name = format!("CRD {}", crd_name);
+    let name = check_name.as_str();
+
+    let installed = match client.get_crd(&crd_name).await {
+        Ok(Some(crd)) => crd,
+        Ok(None) => {
+            return Check::fail(
+                name,
+                format!("{} not found", crd_name),
+                "Run `fabctl install` or `fabctl gen crd | kubectl apply -f -`",
+            )
+        }
+        Err(e) => {
+            return Check::fail(
+                name,
+                format!("Could not read CRDs: {}", e),
+                "Check your kubeconfig context and cluster connectivity",
+            )
+        }
+    };
+
+    let established = installed
+        .status
+        .as_ref()
+        .and_then(|s| s.conditions.as_ref())
+        .is_some_and(|c| {
+            c.iter()
+                .any(|c| c.type_ == "Established" && c.status == "True")
+        });
+    if !established {
+        return Check::fail(
+            name,
+            format!("{} is not Established", crd_name),
+            format!("Inspect `kubectl describe crd {}`", crd_name),
+        );
+    }
+
+    let served: Vec<_> = installed
+        .spec
+        .versions
+        .iter()
+        .filter(|v| v.served)
+        .map(|v| v.name.clone())
+        .collect();
+    let wanted: Vec<_> = expected.spec.versions.iter().map(|v| &v.name).collect();
+    if let Some(missing) = wanted.iter().find(|v| !served.contains(v)) {
+        return Check::fail(
+            name,
+            format!(
+                "Version {} is not served (served: {})",
+                missing,
+                served.join(", ")
+            ),
+            "Upgrade the CRD with `fabctl install`",
+        );
+    }
+
+    let missing_fields: Vec<_> = manifests::crd_spec_fields(&expected)
+        .difference(&manifests::crd_spec_fields(&installed))
+        .cloned()
+        .collect();
+    if !missing_fields.is_empty() {
+        return Check::warn(
+            name,
+            format!(
+                "Installed schema is older than this fabctl (missing spec fields: {})",
+                missing_fields.join(", ")
+            ),
+            "Upgrade the CRD with `fabctl install`",
+        );
+    }
+
+    Check::pass(
+        name,
+        format!("{} established, serving {}", crd_name, served.join(", ")),
+    )
+}
+
+async fn check_operator(client: &K8sClient, namespace: &str) -> Check {
+    const NAME: &str = "Operator deployment";
+    match client.get_deployment(namespace, OPERATOR_NAME).await {
+        Ok(Some(deployment)) => {
+            let status = deployment.status.clone().unwrap_or_default();
+            let detail = format!(
+                "{}/{} replicas available",
+                status.available_replicas.unwrap_or(0),
+                deployment
+                    .spec
+                    .as_ref()
+                    .and_then(|s| s.replicas)
+                    .unwrap_or(1)
+            );
+            if deployment_is_ready(&deployment) {
+                Check::pass(NAME, detail)
+            } else {
+                Check::fail(
+                    NAME,
+                    detail,
+                    format!(
+                        "Inspect `kubectl -n {} describe deployment {}` and the operator logs",
+                        namespace, OPERATOR_NAME
+                    ),
+                )
+            }
+        }
+        Ok(None) => Check::fail(
+            NAME,
+            format!("deployment/{} not found in {}", OPERATOR_NAME, namespace),
+            format!(
+                "Run `fabctl install -n {}` or pass --operator-namespace",
+                namespace
+            ),
+        ),
+        Err(e) => Check::fail(
+            NAME,
+            format!("Could not read deployment: {}", e),
+            "Check your kubeconfig context and cluster connectivity",
+        ),
+    }
+}
+
+/// Verify the operator ServiceAccount holds every permission in the
+/// generated ClusterRole
+async fn check_rbac(client: &K8sClient, namespace: &str) -> Check {
+    const NAME: &str = "RBAC permissions";
+    let required = rbac::permissions(OPERATOR_RULES);
+    let mut missing = Vec::new();
+
+    for permission in &required {
+        match client
+            .service_account_can(
+                namespace,
+                OPERATOR_NAME,
+                &permission.verb,
+                &permission.api_group,
+                &permission.resource,
+            )
+            .await
+        {
+            Ok(true) => {}
+            Ok(false) => missing.push(permission.to_string()),
+            Err(e) => {
+                return Check::warn(
+                    NAME,
+                    format!("Could not run access reviews: {}", e),
+                    "Your user needs permission to create SubjectAccessReviews",
+                )
+            }
+        }
+    }
+
+    if missing.is_empty() {
+        Check::pass(
+            NAME,
+            format!(
+                "serviceaccount/{} has all {} required permissions",
+                OPERATOR_NAME,
+                required.len()
+            ),
+        )
+    } else {
+        Check::fail(
+            NAME,
+            format!("Missing: {}", missing.join(", ")),
+            format!(
+                "Re-apply RBAC with `fabctl gen operator -n {} | kubectl apply -f -`",
+                namespace
+            ),
+        )
+    }
+}
+
+/// Report the outcome of the operator's own permission check on startup,
+/// which also spots permissions granted beyond what it needs
+async fn check_self_check(client: &K8sClient, namespace: &str) -> Check {
+    const NAME: &str = "Operator self-check";
+    let status = match client.get_operator_status(namespace, OPERATOR_NAME).await {
+        Ok(operator) => operator.and_then(|o| o.status),
+        Err(e) => {
+            return Check::warn(
+                NAME,
+                format!("Could not read fabgitopsoperator/{}: {}", OPERATOR_NAME, e),
+                "Check your kubeconfig context and cluster connectivity",
+            )
+        }
+    };
+    let Some(status) = status else {
+        return Check::warn(
+            NAME,
+            format!("fabgitopsoperator/{} has no status yet", OPERATOR_NAME),
+            "Check that the operator has started; it records its status on startup",
+        );
+    };
+
+    if !status.missing_permissions.is_empty() {
+        Check::fail(
+            NAME,
+            format!(
+                "Operator refused to start, missing: {}",
+                status.missing_permissions.join(", ")
+            ),
+            format!(
+                "Re-apply RBAC with `fabctl gen operator -n {} | kubectl apply -f -`",
+                namespace
+            ),
+        )
+    } else if !status.excessive_permissions.is_empty() {
+        Check::warn(
+            NAME,
+            format!(
+                "Permissions beyond what the operator needs: {}",
+                status.excessive_permissions.join(", ")
+            ),
+            "Remove them from the roles bound to the operator's ServiceAccount",
+        )
+    } else {
+        Check::pass(
+            NAME,
+            format!("Permissions verified by operator {}", status.version),
+        )
+    }
+}
+
+async fn check_metrics(client: &K8sClient, namespace: &str) -> Check {
+    const NAME: &str = "Metrics endpoint";
+    match client
+        .proxy_get(namespace, METRICS_SERVICE_NAME, METRICS_PORT, "/metrics")
+        .await
+    {
+        Ok(body) if body.contains("drift_events_total") => Check::pass(
+            NAME,
+            format!("service/{} serving /metrics", METRICS_SERVICE_NAME),
+        ),
+        Ok(_) => Check::warn(
+            NAME,
+            "Endpoint responded but operator metrics are missing",
+            format!(
+                "Check that service/{} selects the operator pods",
+                METRICS_SERVICE_NAME
+            ),
+        ),
+        Err(e) => Check::fail(
+            NAME,
+            format!("service/{} unreachable: {}", METRICS_SERVICE_NAME, e),
+            format!(
+                "Check `kubectl -n {} get endpoints {}`",
+                namespace, METRICS_SERVICE_NAME
+            ),
+        ),
+    }
+}
+
+/// TCP-probe each PLC's Modbus port from this workstation
+async fn check_devices(client: &K8sClient, namespace: &str) -> Vec<Check> {
+    let plcs = match client.list_plcs(namespace).await {
+        Ok(plcs) => plcs,
+        Err(e) => {
+            return vec![Check::fail(
+                "Device reachability",
+                format!("Could not list PLCs: {}", e),
+                "Check your kubeconfig context and namespace",
+            )]
+        }
+    };
+
+    if plcs.is_empty() {
+        return vec![Check::warn(
+            "Device reachability",
+            format!("No IndustrialPLC resources in {}", namespace),
+            "Pass --namespace to check another namespace",
+        )];
+    }
+
+    let mut probes = JoinSet::new();
+    for (index, plc) in plcs.into_iter().enumerate() {
+        probes.spawn(async move {
+            let name = plc.metadata.name.unwrap_or_default();
+            let target = format!("{}:{}", plc.spec.device_address, plc.spec.port);
+            let result =
+                tokio::time::timeout(DEVICE_CONNECT_TIMEOUT, TcpStream::connect(&target)).await;
+            let check_name = format!("Device {}", name);
+            let check = match result {
+                Ok(Ok(_)) => Check::pass(check_name, format!("{} accepting connections", target)),
+                Ok(Err(e)) if plc.spec.device_address.ends_with(".svc.cluster.local") => {
+                    Check::warn(
+                        check_name,
+                        format!("{} not reachable from this workstation: {}", target, e),
+                        "In-cluster address; check the operator's status for this PLC instead",
+                    )
+                }
+                Ok(Err(e)) => Check::fail(
+                    check_name,
+                    format!("{} refused or unreachable: {}", target, e),
+                    "Verify the device is powered on and the address and port are correct",
+                ),
+                Err(_) => Check::fail(
+                    check_name,
+                    format!(
+                        "{} timed out after {}s",
+                        target,
+                        DEVICE_CONNECT_TIMEOUT.as_secs()
+                    ),
+                    "Check routing and firewalls between this workstation and the OT network",
+                ),
+            };
+            (index, check)
+        });
+    }
+
+    let mut results = Vec::new();
+    while let Some(result) = probes.join_next().await {
+        if let Ok(result) = result {
+            results.push(result);
+        }
+    }
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, check)| check).collect()
+}
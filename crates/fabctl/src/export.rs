@@ -0,0 +1,162 @@
+use crate::k8s_client::{K8sClient, PlcEvent};
+use anyhow::{Context, Result};
+use fabgitops_types::crd::IndustrialPLC;
+use k8s_openapi::api::core::v1::Event;
+use kube::ResourceExt;
+use serde::Deserialize;
+use serde_yaml::Value;
+use std::path::{Path, PathBuf};
+
+/// A fleet exported to a file, as written by `fabctl get-status -o yaml` or
+/// `kubectl get industrialplcs,events -o yaml`. Documents may be single
+/// objects, lists of objects, or `List` objects with `items`.
+pub struct Export {
+    path: PathBuf,
+    plcs: Vec<IndustrialPLC>,
+    events: Vec<Event>,
+}
+
+impl Export {
+    pub fn load(path: &Path) -> Result<Self> {
+        let file = path.display().to_string();
+        let content =
+            std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", file))?;
+
+        let mut export = Self {
+            path: path.to_path_buf(),
+            plcs: Vec::new(),
+            events: Vec::new(),
+        };
+        for (index, document) in serde_yaml::Deserializer::from_str(&content).enumerate() {
+            let value = Value::deserialize(document)
+                .with_context(|| format!("Invalid YAML in {}#{}", file, index + 1))?;
+            let objects = match value {
+                Value::Null => continue,
+                Value::Sequence(items) => items,
+                Value::Mapping(ref map) => match map.get("items") {
+                    Some(Value::Sequence(items)) => items.clone(),
+                    _ => vec![value],
+                },
+                _ => anyhow::bail!("{}#{} is not a Kubernetes object", file, index + 1),
+            };
+            for object in objects {
+                // Items without a kind are taken to be IndustrialPLCs
+                match object.get("kind").and_then(Value::as_str) {
+                    None | Some("IndustrialPLC") => {
+                        export
+                            .plcs
+                            .push(serde_yaml::from_value(object).with_context(|| {
+                                format!("Invalid IndustrialPLC in {}#{}", file, index + 1)
+                            })?)
+                    }
+                    Some("Event") => export.events.push(
+                        serde_yaml::from_value(object)
+                            .with_context(|| format!("Invalid Event in {}#{}", file, index + 1))?,
+                    ),
+                    Some(_) => {}
+                }
+            }
+        }
+        Ok(export)
+    }
+
+    /// Whether an exported object belongs to `namespace`; objects exported
+    /// without one belong to any
+    fn in_namespace(object_namespace: Option<String>, namespace: &str) -> bool {
+        object_namespace.is_none_or(|ns| ns == namespace)
+    }
+}
+
+/// Where read-only commands get PLCs from: the cluster, or an export for
+/// analysts without cluster access. Both feed the same rendering.
+pub enum PlcSource<'a> {
+    Cluster(&'a K8sClient),
+    Export(&'a Export),
+}
+
+impl PlcSource<'_> {
+    pub async fn list_plcs(&self, namespace: &str) -> Result<Vec<IndustrialPLC>> {
+        match self {
+            Self::Cluster(client) => client.list_plcs(namespace).await,
+            Self::Export(export) => Ok(export
+                .plcs
+                .iter()
+                .filter(|plc| Export::in_namespace(plc.namespace(), namespace))
+                .cloned()
+                .collect()),
+        }
+    }
+
+    /// One page of PLCs; an export pages by position, with the offset of
+    /// the next page as the continue token
+    pub async fn list_plcs_page(
+        &self,
+        namespace: &str,
+        limit: u32,
+        continue_token: Option<&str>,
+    ) -> Result<(Vec<IndustrialPLC>, Option<String>)> {
+        match self {
+            Self::Cluster(client) => {
+                client
+                    .list_plcs_page(namespace, limit, continue_token)
+                    .await
+            }
+            Self::Export(_) => {
+                let offset: usize = match continue_token {
+                    Some(token) => token
+                        .parse()
+                        .with_context(|| format!("Invalid continue token '{}'", token))?,
+                    None => 0,
+                };
+                let plcs = self.list_plcs(namespace).await?;
+                let end = plcs.len().min(offset + limit as usize);
+                let next = (end < plcs.len()).then(|| end.to_string());
+                Ok((plcs.get(offset..end).unwrap_or_default().to_vec(), next))
+            }
+        }
+    }
+
+    pub async fn get_plc(&self, namespace: &str, name: &str) -> Result<IndustrialPLC> {
+        match self {
+            Self::Cluster(client) => client.get_plc(namespace, name).await,
+            Self::Export(export) => export
+                .plcs
+                .iter()
+                .find(|plc| {
+                    plc.name_any() == name && Export::in_namespace(plc.namespace(), namespace)
+                })
+                .cloned()
+                .with_context(|| {
+                    format!(
+                        "IndustrialPLC {}/{} not found in {}",
+                        namespace,
+                        name,
+                        export.path.display()
+                    )
+                }),
+        }
+    }
+
+    /// Events about a PLC, oldest first; empty for exports without Events
+    pub async fn list_plc_events(&self, namespace: &str, name: &str) -> Result<Vec<PlcEvent>> {
+        match self {
+            Self::Cluster(client) => client.list_plc_events(namespace, name).await,
+            Self::Export(export) => {
+                let mut events: Vec<PlcEvent> = export
+                    .events
+                    .iter()
+                    .filter(|event| {
+                        let involved = &event.involved_object;
+                        involved.kind.as_deref() == Some("IndustrialPLC")
+                            && involved.name.as_deref() == Some(name)
+                            && Export::in_namespace(event.namespace(), namespace)
+                    })
+                    .cloned()
+                    .map(PlcEvent::from)
+                    .collect();
+                events.sort_by_key(|event| event.last_seen);
+                Ok(events)
+            }
+        }
+    }
+}
@@ -1,29 +1,133 @@
+use crate::retry::{ClientOptions, RetryLayer};
 use anyhow::{Context, Result};
+use fabgitops_types::crd::{
+    ApprovalDecision, CorrectionRequest, FabGitOpsOperator, IndustrialPLC, MaintenanceWindow,
+    PLCGroup, PLCSnapshot, PLC_NAME_LABEL,
+};
+use fabgitops_types::{
+    FORCE_SYNC_ANNOTATION, HOLD_REASON_ANNOTATION, HOLD_UNTIL_ANNOTATION,
+    LAST_SYNC_REQUEST_ANNOTATION,
+};
+use k8s_openapi::api::apps::v1::Deployment;
+use k8s_openapi::api::authentication::v1::SelfSubjectReview;
+use k8s_openapi::api::authorization::v1::{
+    ResourceAttributes, SubjectAccessReview, SubjectAccessReviewSpec,
+};
+use k8s_openapi::api::core::v1::{Event, Namespace};
+use k8s_openapi::apiextensions_apiserver::pkg::apis::apiextensions::v1::CustomResourceDefinition;
 use k8s_openapi::apimachinery::pkg::apis::meta::v1::Time;
-use kube::api::{ListParams, Patch, PatchParams};
-use kube::{Api, Client};
-use operator::crd::IndustrialPLC;
+use k8s_openapi::NamespaceResourceScope;
+use kube::api::{
+    ApiResource, DeleteParams, DynamicObject, GroupVersionKind, ListParams, Patch, PatchParams,
+    PostParams,
+};
+use kube::client::ClientBuilder;
+use kube::config::KubeConfigOptions;
+use kube::{Api, Client, Config, Resource, ResourceExt};
+use serde::de::DeserializeOwned;
 use std::collections::BTreeMap;
 
+/// Field manager recorded for server-side applies made by fabctl
+const FIELD_MANAGER: &str = "fabctl";
+
+/// Page size used when listing every IndustrialPLC in a namespace
+const LIST_PAGE_SIZE: u32 = 250;
+
+/// A Kubernetes Event about a PLC, as `describe --show-events` shows it
+#[derive(Clone, Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlcEvent {
+    /// `Normal` or `Warning`
+    #[serde(rename = "type")]
+    pub type_: String,
+    pub reason: String,
+    pub message: String,
+    /// Occurrences folded into this Event
+    pub count: i32,
+    /// When it last occurred
+    pub last_seen: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl From<Event> for PlcEvent {
+    fn from(event: Event) -> Self {
+        // Events written through events.k8s.io only carry eventTime
+        let last_seen = event
+            .last_timestamp
+            .map(|t| t.0)
+            .or(event.event_time.map(|t| t.0))
+            .or(event.metadata.creation_timestamp.map(|t| t.0));
+        Self {
+            type_: event.type_.unwrap_or_default(),
+            reason: event.reason.unwrap_or_default(),
+            message: event.message.unwrap_or_default(),
+            count: event.count.unwrap_or(1),
+            last_seen,
+        }
+    }
+}
+
 /// Kubernetes client wrapper for FabGitOps operations
 pub struct K8sClient {
     client: Client,
 }
 
 impl K8sClient {
-    /// Create a new K8sClient from default configuration
-    pub async fn new() -> Result<Self> {
-        let client = Client::try_default()
-            .await
-            .context("Failed to create Kubernetes client")?;
+    /// Create a new K8sClient from default configuration, or from the named
+    /// kubeconfig context, sending requests as `options` says
+    pub async fn new(context: Option<&str>, options: &ClientOptions) -> Result<Self> {
+        let config = match context {
+            Some(context) => {
+                let options = KubeConfigOptions {
+                    context: Some(context.to_string()),
+                    ..Default::default()
+                };
+                Config::from_kubeconfig(&options)
+                    .await
+                    .with_context(|| format!("Failed to load kubeconfig context '{}'", context))?
+            }
+            None => Config::infer()
+                .await
+                .context("Failed to create Kubernetes client")?,
+        };
+        let client = ClientBuilder::try_from(config)
+            .context("Failed to create Kubernetes client")?
+            .with_layer(&RetryLayer::new(options))
+            .build();
         Ok(Self { client })
     }
 
-    /// List all IndustrialPLC resources in a namespace
+    /// List all IndustrialPLC resources in a namespace, fetching page by page
     pub async fn list_plcs(&self, namespace: &str) -> Result<Vec<IndustrialPLC>> {
+        let mut plcs = Vec::new();
+        let mut token = None;
+        loop {
+            let (page, next) = self
+                .list_plcs_page(namespace, LIST_PAGE_SIZE, token.as_deref())
+                .await?;
+            plcs.extend(page);
+            match next {
+                Some(next) => token = Some(next),
+                None => return Ok(plcs),
+            }
+        }
+    }
+
+    /// List one page of IndustrialPLC resources, returning the continue
+    /// token for the next page if there is one
+    pub async fn list_plcs_page(
+        &self,
+        namespace: &str,
+        limit: u32,
+        continue_token: Option<&str>,
+    ) -> Result<(Vec<IndustrialPLC>, Option<String>)> {
         let api: Api<IndustrialPLC> = Api::namespaced(self.client.clone(), namespace);
-        let plcs = api.list(&ListParams::default()).await?;
-        Ok(plcs.items)
+        let mut params = ListParams::default().limit(limit);
+        if let Some(token) = continue_token {
+            params = params.continue_token(token);
+        }
+        let list = api.list(&params).await?;
+        let next = list.metadata.continue_.filter(|t| !t.is_empty());
+        Ok((list.items, next))
     }
 
     /// Get a specific IndustrialPLC resource
@@ -33,18 +137,144 @@ impl K8sClient {
         Ok(plc)
     }
 
+    /// Events recorded for an IndustrialPLC, oldest first
+    pub async fn list_plc_events(&self, namespace: &str, name: &str) -> Result<Vec<PlcEvent>> {
+        let api: Api<Event> = Api::namespaced(self.client.clone(), namespace);
+        let params = ListParams::default().fields(&format!(
+            "involvedObject.kind=IndustrialPLC,involvedObject.name={}",
+            name
+        ));
+        let mut events: Vec<PlcEvent> = api
+            .list(&params)
+            .await?
+            .items
+            .into_iter()
+            .map(PlcEvent::from)
+            .collect();
+        events.sort_by_key(|event| event.last_seen);
+        Ok(events)
+    }
+
+    /// List IndustrialPLC resources matching a label selector
+    pub async fn list_plcs_by_selector(
+        &self,
+        namespace: &str,
+        selector: &str,
+    ) -> Result<Vec<IndustrialPLC>> {
+        let api: Api<IndustrialPLC> = Api::namespaced(self.client.clone(), namespace);
+        let plcs = api.list(&ListParams::default().labels(selector)).await?;
+        Ok(plcs.items)
+    }
+
+    /// Change the desired value of an IndustrialPLC
+    pub async fn set_target_value(&self, namespace: &str, name: &str, value: u16) -> Result<()> {
+        let api: Api<IndustrialPLC> = Api::namespaced(self.client.clone(), namespace);
+        let patch = Patch::Merge(serde_json::json!({
+            "spec": { "targetValue": value }
+        }));
+        api.patch(name, &PatchParams::default(), &patch).await?;
+        Ok(())
+    }
+
+    /// Hold an IndustrialPLC at the value it reads until `until`, or end
+    /// the hold when None
+    pub async fn set_hold(
+        &self,
+        namespace: &str,
+        name: &str,
+        hold: Option<(chrono::DateTime<chrono::Utc>, Option<&str>)>,
+    ) -> Result<()> {
+        let api: Api<IndustrialPLC> = Api::namespaced(self.client.clone(), namespace);
+        // Null removes an annotation in a merge patch
+        let (until, reason) = match hold {
+            Some((until, reason)) => (Some(until.to_rfc3339()), reason),
+            None => (None, None),
+        };
+        let patch = Patch::Merge(serde_json::json!({
+            "metadata": {
+                "annotations": {
+                    HOLD_UNTIL_ANNOTATION: until,
+                    HOLD_REASON_ANNOTATION: reason,
+                }
+            }
+        }));
+        api.patch(name, &PatchParams::default(), &patch).await?;
+        Ok(())
+    }
+
+    /// Apply a merge patch to the spec of an IndustrialPLC
+    pub async fn patch_plc_spec(
+        &self,
+        namespace: &str,
+        name: &str,
+        patch: serde_json::Value,
+    ) -> Result<()> {
+        let api: Api<IndustrialPLC> = Api::namespaced(self.client.clone(), namespace);
+        api.patch(name, &PatchParams::default(), &Patch::Merge(patch))
+            .await?;
+        Ok(())
+    }
+
+    /// Create a new IndustrialPLC
+    pub async fn create_plc(&self, namespace: &str, plc: &IndustrialPLC) -> Result<IndustrialPLC> {
+        let api: Api<IndustrialPLC> = Api::namespaced(self.client.clone(), namespace);
+        Ok(api.create(&PostParams::default(), plc).await?)
+    }
+
+    /// Delete an IndustrialPLC
+    pub async fn delete_plc(&self, namespace: &str, name: &str) -> Result<()> {
+        let api: Api<IndustrialPLC> = Api::namespaced(self.client.clone(), namespace);
+        api.delete(name, &DeleteParams::default()).await?;
+        Ok(())
+    }
+
+    /// List all PLCGroups in a namespace
+    pub async fn list_plc_groups(&self, namespace: &str) -> Result<Vec<PLCGroup>> {
+        let api: Api<PLCGroup> = Api::namespaced(self.client.clone(), namespace);
+        let groups = api.list(&ListParams::default()).await?;
+        Ok(groups.items)
+    }
+
+    /// Names of the `K` objects in a namespace, listed as metadata only so
+    /// completion stays quick in large fleets
+    pub async fn list_names<K>(&self, namespace: &str) -> Result<Vec<String>>
+    where
+        K: Resource<Scope = NamespaceResourceScope, DynamicType = ()>
+            + Clone
+            + DeserializeOwned
+            + std::fmt::Debug,
+    {
+        let api: Api<K> = Api::namespaced(self.client.clone(), namespace);
+        let list = api.list_metadata(&ListParams::default()).await?;
+        Ok(list.items.iter().map(|object| object.name_any()).collect())
+    }
+
+    /// Names of every namespace
+    pub async fn list_namespace_names(&self) -> Result<Vec<String>> {
+        let api: Api<Namespace> = Api::all(self.client.clone());
+        let list = api.list_metadata(&ListParams::default()).await?;
+        Ok(list.items.iter().map(|object| object.name_any()).collect())
+    }
+
+    /// Get a specific PLCGroup
+    pub async fn get_plc_group(&self, namespace: &str, name: &str) -> Result<PLCGroup> {
+        let api: Api<PLCGroup> = Api::namespaced(self.client.clone(), namespace);
+        let group = api.get(name).await?;
+        Ok(group)
+    }
+
     /// Trigger a reconciliation by annotating the resource
     pub async fn trigger_reconcile(&self, namespace: &str, name: &str, force: bool) -> Result<()> {
         let api: Api<IndustrialPLC> = Api::namespaced(self.client.clone(), namespace);
 
         let mut annotations: BTreeMap<String, String> = BTreeMap::new();
         annotations.insert(
-            "fabgitops.io/last-sync-request".to_string(),
+            LAST_SYNC_REQUEST_ANNOTATION.to_string(),
             Time(chrono::Utc::now()).0.to_rfc3339(),
         );
 
         if force {
-            annotations.insert("fabgitops.io/force-sync".to_string(), "true".to_string());
+            annotations.insert(FORCE_SYNC_ANNOTATION.to_string(), "true".to_string());
         }
 
         let patch = Patch::Merge(serde_json::json!({
@@ -57,4 +287,383 @@ impl K8sClient {
 
         Ok(())
     }
+
+    /// Replace the maintenance windows of an IndustrialPLC; empty clears them
+    pub async fn set_maintenance_windows(
+        &self,
+        namespace: &str,
+        name: &str,
+        windows: &[MaintenanceWindow],
+    ) -> Result<()> {
+        let api: Api<IndustrialPLC> = Api::namespaced(self.client.clone(), namespace);
+        let windows = if windows.is_empty() {
+            serde_json::Value::Null
+        } else {
+            serde_json::to_value(windows)?
+        };
+        let patch = Patch::Merge(serde_json::json!({
+            "spec": { "maintenanceWindows": windows }
+        }));
+        api.patch(name, &PatchParams::default(), &patch).await?;
+        Ok(())
+    }
+
+    /// Store a new PLCSnapshot
+    pub async fn create_snapshot(
+        &self,
+        namespace: &str,
+        snapshot: &PLCSnapshot,
+    ) -> Result<PLCSnapshot> {
+        let api: Api<PLCSnapshot> = Api::namespaced(self.client.clone(), namespace);
+        Ok(api.create(&PostParams::default(), snapshot).await?)
+    }
+
+    /// List PLCSnapshots in a namespace, optionally for one PLC, oldest first
+    pub async fn list_snapshots(
+        &self,
+        namespace: &str,
+        plc: Option<&str>,
+    ) -> Result<Vec<PLCSnapshot>> {
+        let api: Api<PLCSnapshot> = Api::namespaced(self.client.clone(), namespace);
+        let mut params = ListParams::default();
+        if let Some(plc) = plc {
+            params = params.labels(&format!("{}={}", PLC_NAME_LABEL, plc));
+        }
+        let mut snapshots = api.list(&params).await?.items;
+        snapshots.sort_by(|a, b| a.spec.taken_at.cmp(&b.spec.taken_at));
+        Ok(snapshots)
+    }
+
+    /// Get a specific PLCSnapshot
+    pub async fn get_snapshot(&self, namespace: &str, name: &str) -> Result<PLCSnapshot> {
+        let api: Api<PLCSnapshot> = Api::namespaced(self.client.clone(), namespace);
+        Ok(api.get(name).await?)
+    }
+
+    /// List CorrectionRequests in a namespace, oldest first
+    pub async fn list_correction_requests(
+        &self,
+        namespace: &str,
+    ) -> Result<Vec<CorrectionRequest>> {
+        let api: Api<CorrectionRequest> = Api::namespaced(self.client.clone(), namespace);
+        let mut requests = api.list(&ListParams::default()).await?.items;
+        requests.sort_by(|a, b| {
+            a.metadata
+                .creation_timestamp
+                .cmp(&b.metadata.creation_timestamp)
+        });
+        Ok(requests)
+    }
+
+    /// Get a specific CorrectionRequest
+    pub async fn get_correction_request(
+        &self,
+        namespace: &str,
+        name: &str,
+    ) -> Result<CorrectionRequest> {
+        let api: Api<CorrectionRequest> = Api::namespaced(self.client.clone(), namespace);
+        Ok(api.get(name).await?)
+    }
+
+    /// Record an approval decision on a CorrectionRequest, provided it has
+    /// not changed since `request` was read, so a concurrent decision is
+    /// never overwritten
+    pub async fn decide_correction_request(
+        &self,
+        namespace: &str,
+        request: &CorrectionRequest,
+        decision: &ApprovalDecision,
+    ) -> Result<()> {
+        let name = request.name_any();
+        let api: Api<CorrectionRequest> = Api::namespaced(self.client.clone(), namespace);
+        let patch = Patch::Merge(serde_json::json!({
+            "metadata": {
+                "resourceVersion": request.resource_version()
+            },
+            "spec": {
+                "approval": decision
+            }
+        }));
+        match api.patch(&name, &PatchParams::default(), &patch).await {
+            Ok(_) => Ok(()),
+            Err(kube::Error::Api(e)) if e.code == 409 => {
+                match api.get(&name).await?.spec.approval {
+                    Some(existing) => anyhow::bail!(
+                        "correctionrequest/{} was already {:?} by {}",
+                        name,
+                        existing.decision,
+                        existing.user
+                    ),
+                    None => {
+                        anyhow::bail!("correctionrequest/{} changed meanwhile; try again", name)
+                    }
+                }
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Username the API server authenticates this client as, falling back
+    /// to the local user on clusters without SelfSubjectReview
+    pub async fn current_user(&self) -> String {
+        let api: Api<SelfSubjectReview> = Api::all(self.client.clone());
+        let review = SelfSubjectReview::default();
+        match api.create(&PostParams::default(), &review).await {
+            Ok(review) => review
+                .status
+                .and_then(|s| s.user_info)
+                .and_then(|u| u.username)
+                .unwrap_or_else(local_user),
+            Err(_) => local_user(),
+        }
+    }
+
+    /// Server-side apply an arbitrary manifest (as produced by `manifests`)
+    pub async fn apply_manifest(&self, manifest: &serde_json::Value, dry_run: bool) -> Result<()> {
+        let (api, name) = self.dynamic_api(manifest)?;
+        let mut params = PatchParams::apply(FIELD_MANAGER).force();
+        if dry_run {
+            params = params.dry_run();
+        }
+        api.patch(&name, &params, &Patch::Apply(manifest))
+            .await
+            .with_context(|| format!("Failed to apply {}", describe_manifest(manifest)))?;
+        Ok(())
+    }
+
+    /// Delete the object described by a manifest. Returns false if it was
+    /// already absent.
+    pub async fn delete_manifest(&self, manifest: &serde_json::Value) -> Result<bool> {
+        let (api, name) = self.dynamic_api(manifest)?;
+        match api.delete(&name, &DeleteParams::background()).await {
+            Ok(_) => Ok(true),
+            Err(kube::Error::Api(e)) if e.code == 404 => Ok(false),
+            Err(e) => {
+                Err(e).with_context(|| format!("Failed to delete {}", describe_manifest(manifest)))
+            }
+        }
+    }
+
+    /// Create the namespace if it does not exist yet
+    pub async fn ensure_namespace(&self, namespace: &str, dry_run: bool) -> Result<bool> {
+        let api: Api<Namespace> = Api::all(self.client.clone());
+        if api.get_opt(namespace).await?.is_some() {
+            return Ok(false);
+        }
+
+        let ns: Namespace = serde_json::from_value(serde_json::json!({
+            "apiVersion": "v1",
+            "kind": "Namespace",
+            "metadata": { "name": namespace }
+        }))?;
+        let params = PostParams {
+            dry_run,
+            field_manager: Some(FIELD_MANAGER.to_string()),
+        };
+        api.create(&params, &ns).await?;
+        Ok(true)
+    }
+
+    /// Get a CRD by name, if installed
+    pub async fn get_crd(&self, name: &str) -> Result<Option<CustomResourceDefinition>> {
+        let api: Api<CustomResourceDefinition> = Api::all(self.client.clone());
+        Ok(api.get_opt(name).await?)
+    }
+
+    /// Whether a CRD exists and reports the Established condition
+    pub async fn crd_established(&self, name: &str) -> Result<bool> {
+        Ok(self
+            .get_crd(name)
+            .await?
+            .and_then(|crd| crd.status)
+            .and_then(|s| s.conditions)
+            .unwrap_or_default()
+            .iter()
+            .any(|c| c.type_ == "Established" && c.status == "True"))
+    }
+
+    /// Get a Deployment by name, if it exists
+    pub async fn get_deployment(&self, namespace: &str, name: &str) -> Result<Option<Deployment>> {
+        let api: Api<Deployment> = Api::namespaced(self.client.clone(), namespace);
+        Ok(api.get_opt(name).await?)
+    }
+
+    /// Get the state the operator recorded about itself, if any
+    pub async fn get_operator_status(
+        &self,
+        namespace: &str,
+        name: &str,
+    ) -> Result<Option<FabGitOpsOperator>> {
+        let api: Api<FabGitOpsOperator> = Api::namespaced(self.client.clone(), namespace);
+        Ok(api.get_opt(name).await?)
+    }
+
+    /// Whether a Deployment has all desired replicas updated and available
+    pub async fn deployment_ready(&self, namespace: &str, name: &str) -> Result<bool> {
+        Ok(self
+            .get_deployment(namespace, name)
+            .await?
+            .is_some_and(|d| deployment_is_ready(&d)))
+    }
+
+    /// Ask the API server whether a ServiceAccount may perform an action
+    pub async fn service_account_can(
+        &self,
+        namespace: &str,
+        service_account: &str,
+        verb: &str,
+        group: &str,
+        resource: &str,
+    ) -> Result<bool> {
+        let (resource, subresource) = match resource.split_once('/') {
+            Some((r, sub)) => (r, Some(sub.to_string())),
+            None => (resource, None),
+        };
+        let review = SubjectAccessReview {
+            spec: SubjectAccessReviewSpec {
+                user: Some(format!(
+                    "system:serviceaccount:{}:{}",
+                    namespace, service_account
+                )),
+                resource_attributes: Some(ResourceAttributes {
+                    verb: Some(verb.to_string()),
+                    group: Some(group.to_string()),
+                    resource: Some(resource.to_string()),
+                    subresource,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let api: Api<SubjectAccessReview> = Api::all(self.client.clone());
+        let result = api.create(&PostParams::default(), &review).await?;
+        Ok(result.status.is_some_and(|s| s.allowed))
+    }
+
+    /// GET a path on a Service through the API server proxy
+    pub async fn proxy_get(
+        &self,
+        namespace: &str,
+        service: &str,
+        port: u16,
+        path: &str,
+    ) -> Result<String> {
+        let uri = format!(
+            "/api/v1/namespaces/{}/services/{}:{}/proxy{}",
+            namespace, service, port, path
+        );
+        let request = http::Request::get(uri).body(Vec::new())?;
+        Ok(self.client.request_text(request).await?)
+    }
+
+    /// Every IndustrialPLC in the cluster (none if the CRD is not installed)
+    pub async fn list_all_plcs(&self) -> Result<Vec<IndustrialPLC>> {
+        let api: Api<IndustrialPLC> = Api::all(self.client.clone());
+        match api.list(&ListParams::default()).await {
+            Ok(list) => Ok(list.items),
+            Err(kube::Error::Api(e)) if e.code == 404 => Ok(Vec::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// `namespace/name` of every IndustrialPLC protected from deletion
+    pub async fn protected_plcs(&self) -> Result<Vec<String>> {
+        Ok(self
+            .list_all_plcs()
+            .await?
+            .iter()
+            .filter(|plc| plc.is_protected())
+            .map(|plc| format!("{}/{}", plc.namespace().unwrap_or_default(), plc.name_any()))
+            .collect())
+    }
+
+    /// Delete every IndustrialPLC in the cluster. Returns how many were found.
+    pub async fn delete_all_plcs(&self) -> Result<usize> {
+        let api: Api<IndustrialPLC> = Api::all(self.client.clone());
+        let plcs = match api.list(&ListParams::default()).await {
+            Ok(list) => list.items,
+            // CRD not installed, nothing to remove
+            Err(kube::Error::Api(e)) if e.code == 404 => return Ok(0),
+            Err(e) => return Err(e.into()),
+        };
+
+        for plc in &plcs {
+            let (Some(namespace), Some(name)) = (&plc.metadata.namespace, &plc.metadata.name)
+            else {
+                continue;
+            };
+            let api: Api<IndustrialPLC> = Api::namespaced(self.client.clone(), namespace);
+            match api.delete(name, &DeleteParams::default()).await {
+                Ok(_) => {}
+                Err(kube::Error::Api(e)) if e.code == 404 => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Ok(plcs.len())
+    }
+
+    /// Count IndustrialPLC resources left in the cluster (0 if the CRD is gone)
+    pub async fn count_all_plcs(&self) -> Result<usize> {
+        let api: Api<IndustrialPLC> = Api::all(self.client.clone());
+        match api.list(&ListParams::default()).await {
+            Ok(list) => Ok(list.items.len()),
+            Err(kube::Error::Api(e)) if e.code == 404 => Ok(0),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Build a dynamic API handle for the object a manifest describes
+    fn dynamic_api(&self, manifest: &serde_json::Value) -> Result<(Api<DynamicObject>, String)> {
+        let api_version = manifest["apiVersion"]
+            .as_str()
+            .context("Manifest is missing apiVersion")?;
+        let kind = manifest["kind"]
+            .as_str()
+            .context("Manifest is missing kind")?;
+        let name = manifest["metadata"]["name"]
+            .as_str()
+            .context("Manifest is missing metadata.name")?;
+
+        let (group, version) = api_version.rsplit_once('/').unwrap_or(("", api_version));
+        let resource = ApiResource::from_gvk(&GroupVersionKind::gvk(group, version, kind));
+
+        let api = match manifest["metadata"]["namespace"].as_str() {
+            Some(namespace) => Api::namespaced_with(self.client.clone(), namespace, &resource),
+            None => Api::all_with(self.client.clone(), &resource),
+        };
+        Ok((api, name.to_string()))
+    }
+}
+
+fn local_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+/// Whether a Deployment has all desired replicas updated and available
+pub fn deployment_is_ready(deployment: &Deployment) -> bool {
+    let desired = deployment
+        .spec
+        .as_ref()
+        .and_then(|s| s.replicas)
+        .unwrap_or(1);
+    let status = deployment.status.clone().unwrap_or_default();
+    status.updated_replicas.unwrap_or(0) >= desired
+        && status.available_replicas.unwrap_or(0) >= desired
+}
+
+/// Short `kind/name` label for a manifest, used in messages
+pub fn describe_manifest(manifest: &serde_json::Value) -> String {
+    format!(
+        "{}/{}",
+        manifest["kind"]
+            .as_str()
+            .unwrap_or("Unknown")
+            .to_lowercase(),
+        manifest["metadata"]["name"].as_str().unwrap_or("unknown")
+    )
 }
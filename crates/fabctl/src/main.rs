@@ -1,11 +1,33 @@
+mod chaos;
 mod commands;
+mod completion;
+mod config;
+mod diff;
+mod doctor;
+mod export;
 mod k8s_client;
+mod manifests;
+mod migrate;
 mod output;
+mod plugins;
+mod prom;
+mod report;
+mod retry;
+mod scan;
+mod snapshot;
+mod summary;
+mod template;
+mod validate;
+mod verify;
 
 use crate::commands::*;
+use crate::config::FabctlConfig;
+use crate::export::{Export, PlcSource};
 use crate::k8s_client::K8sClient;
-use clap::Parser;
+use clap::parser::ValueSource;
+use clap::{ArgMatches, CommandFactory, FromArgMatches};
 use colored::*;
+use fabgitops_types::crd::{Decision, MaintenanceWindow};
 use tracing::{error, Level};
 use tracing_subscriber::FmtSubscriber;
 
@@ -18,24 +40,140 @@ async fn main() -> anyhow::Result<()> {
     tracing::subscriber::set_global_default(subscriber)?;
 
     // Parse CLI arguments
-    let cli = Cli::parse();
+    let matches = Cli::command().get_matches();
+    let mut cli = match Cli::from_arg_matches(&matches) {
+        Ok(cli) => cli,
+        Err(e) => e.exit(),
+    };
+    let explicit_namespace = explicit(&matches, "namespace").then(|| cli.namespace.clone());
+
+    // Profiles fill in flags that were not given explicitly
+    if !matches!(
+        cli.command,
+        Commands::Config { .. } | Commands::Completion { .. } | Commands::Complete { .. }
+    ) {
+        if let Err(e) = apply_profile(&mut cli, &matches) {
+            eprintln!("{} {}", "Error:".red().bold(), e);
+            std::process::exit(1);
+        }
+    }
 
-    // Print banner
-    print_banner();
+    output::set_time_display(cli.time_display());
+    set_require_confirmation(cli.require_confirmation);
 
-    // Create K8s client
-    let client = K8sClient::new().await?;
+    // Required confirmation cannot be skipped
+    if cli.require_confirmation && skips_confirmation(&cli.command) {
+        eprintln!(
+            "{} --yes is not allowed while confirmation is required",
+            "Error:".red().bold()
+        );
+        std::process::exit(1);
+    }
+
+    // Only the PLC listings render templates
+    if matches!(cli.output, Output::Template(_))
+        && !matches!(cli.command, Commands::GetStatus { .. } | Commands::List)
+    {
+        eprintln!(
+            "{} -o template is only supported by get-status and list",
+            "Error:".red().bold()
+        );
+        std::process::exit(1);
+    }
+
+    // Only reports are rendered as documents
+    if matches!(cli.output, Output::Document(_)) && !matches!(cli.command, Commands::Report { .. })
+    {
+        eprintln!(
+            "{} -o markdown and -o html are only supported by report",
+            "Error:".red().bold()
+        );
+        std::process::exit(1);
+    }
+
+    // Print banner, keeping machine-readable output clean
+    if matches!(cli.output, Output::Format(OutputFormat::Table))
+        && !matches!(
+            cli.command,
+            Commands::Gen { .. }
+                | Commands::Report { .. }
+                | Commands::Completion { .. }
+                | Commands::Complete { .. }
+                | Commands::Record { .. }
+                | Commands::Clone { dry_run: true, .. }
+                | Commands::External(_)
+        )
+    {
+        print_banner();
+    }
 
     // Execute command
     let result = match &cli.command {
-        Commands::GetStatus { name } => {
-            cmd_get_status(&client, &cli.namespace, name.as_deref(), cli.output).await
+        // Offline commands never touch the cluster
+        Commands::Validate { files } => cmd_validate(files, cli.format()),
+        Commands::Migrate { files, write } if !files.is_empty() => {
+            cmd_migrate_files(files, *write, cli.format())
+        }
+        Commands::Gen { target } => cmd_gen(target, &cli.namespace, cli.format()),
+        Commands::Scan {
+            target,
+            port,
+            register,
+            timeout_ms,
+            concurrency,
+            manifests,
+        } => {
+            let opts = scan::ScanOptions {
+                port: *port,
+                timeout: std::time::Duration::from_millis(*timeout_ms),
+                concurrency: *concurrency,
+                register: *register,
+            };
+            cmd_scan(
+                target,
+                &opts,
+                manifests.as_deref(),
+                &cli.namespace,
+                cli.format(),
+            )
+            .await
+        }
+        Commands::Chaos { action, target } => cmd_chaos(action, target, cli.format()).await,
+        Commands::Plugin {
+            action: PluginAction::List,
+        } => cmd_plugin_list(cli.format()),
+        Commands::Config { action } => cmd_config(
+            action,
+            explicit_namespace.as_deref(),
+            cli.context.as_deref(),
+            cli.require_confirmation,
+            cli.format(),
+        ),
+        Commands::External(args) => {
+            cmd_external(args, &cli.namespace, cli.context.as_deref(), cli.format())
         }
-        Commands::Describe { name } => cmd_describe(&client, &cli.namespace, name).await,
-        Commands::Sync { name, force } => cmd_sync(&client, &cli.namespace, name, *force).await,
-        Commands::Watch { interval } => cmd_watch(&client, &cli.namespace, *interval).await,
-        Commands::List => cmd_list(&client, &cli.namespace).await,
         Commands::Version => cmd_version().await,
+        Commands::Completion { shell } => cmd_completion(*shell),
+        Commands::Complete { words } => {
+            completion::complete(words).await;
+            Ok(())
+        }
+        Commands::GetStatus {
+            from_file: Some(path),
+            ..
+        }
+        | Commands::Describe {
+            from_file: Some(path),
+            ..
+        }
+        | Commands::Diff {
+            from_file: Some(path),
+            ..
+        } => match Export::load(path) {
+            Ok(export) => run_plc_query(&cli, &PlcSource::Export(&export)).await,
+            Err(e) => Err(e),
+        },
+        _ => run_cluster_command(&cli).await,
     };
 
     if let Err(ref e) = result {
@@ -47,6 +185,402 @@ async fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Whether a flag was passed on the command line rather than defaulted
+fn explicit(matches: &ArgMatches, id: &str) -> bool {
+    matches!(matches.value_source(id), Some(ValueSource::CommandLine))
+}
+
+/// Fill in namespace and context from the selected or current profile
+fn apply_profile(cli: &mut Cli, matches: &ArgMatches) -> anyhow::Result<()> {
+    let config = FabctlConfig::load(&config::config_path()?)?;
+    let Some((_, profile)) = config.active_profile(cli.profile.as_deref())? else {
+        return Ok(());
+    };
+
+    if !explicit(matches, "namespace") {
+        if let Some(namespace) = &profile.namespace {
+            cli.namespace = namespace.clone();
+        }
+    }
+    if cli.context.is_none() {
+        cli.context = profile.context.clone();
+    }
+    // A profile's policy cannot be switched off from the command line
+    cli.require_confirmation |= profile.require_confirmation;
+    Ok(())
+}
+
+/// Whether a command was told to go ahead without asking
+fn skips_confirmation(command: &Commands) -> bool {
+    matches!(
+        command,
+        Commands::Set { yes: true, .. }
+            | Commands::Snapshot {
+                action: SnapshotAction::Restore { yes: true, .. }
+            }
+            | Commands::Uninstall { yes: true, .. }
+    )
+}
+
+/// Execute a read-only PLC command against the cluster or an export
+async fn run_plc_query(cli: &Cli, source: &PlcSource<'_>) -> anyhow::Result<()> {
+    match &cli.command {
+        Commands::GetStatus {
+            name,
+            sort_by,
+            limit,
+            continue_token,
+            ..
+        } => {
+            let page = limit.map(|limit| (limit, continue_token.as_deref()));
+            cmd_get_status(
+                source,
+                &cli.namespace,
+                name.as_deref(),
+                *sort_by,
+                page,
+                &cli.output,
+            )
+            .await
+        }
+        Commands::Describe {
+            name,
+            show_events,
+            show_history,
+            ..
+        } => {
+            let opts = DescribeOptions {
+                events: *show_events,
+                history: *show_history,
+            };
+            cmd_describe(source, &cli.namespace, name, &opts, cli.format()).await
+        }
+        Commands::Diff { files, .. } => cmd_diff(source, &cli.namespace, files, cli.format()).await,
+        _ => unreachable!("not a PLC query"),
+    }
+}
+
+/// Execute a command that needs a Kubernetes connection
+async fn run_cluster_command(cli: &Cli) -> anyhow::Result<()> {
+    let client = K8sClient::new(cli.context.as_deref(), &cli.client_options()).await?;
+
+    match &cli.command {
+        Commands::GetStatus { .. } | Commands::Describe { .. } | Commands::Diff { .. } => {
+            run_plc_query(cli, &PlcSource::Cluster(&client)).await
+        }
+        Commands::Sync { name, force } => cmd_sync(&client, &cli.namespace, name, *force).await,
+        Commands::Watch {
+            name,
+            interval,
+            bell,
+            flash,
+            only_problems,
+        } => {
+            let opts = WatchOptions {
+                bell: *bell,
+                flash: *flash,
+                only_problems: *only_problems,
+            };
+            match name {
+                Some(name) => cmd_watch_plc(&client, &cli.namespace, name, *interval, &opts).await,
+                None => cmd_watch(&client, &cli.namespace, *interval, &opts).await,
+            }
+        }
+        Commands::List => cmd_list(&client, &cli.namespace, &cli.output).await,
+        Commands::Install {
+            version,
+            image,
+            dry_run,
+            timeout,
+        } => {
+            cmd_install(
+                &client,
+                &cli.namespace,
+                version,
+                image.as_deref(),
+                *dry_run,
+                *timeout,
+            )
+            .await
+        }
+        Commands::Doctor { operator_namespace } => {
+            cmd_doctor(
+                &client,
+                &cli.namespace,
+                operator_namespace.as_deref(),
+                cli.format(),
+            )
+            .await
+        }
+        Commands::Summary {
+            top,
+            window,
+            operator_namespace,
+        } => {
+            cmd_summary(
+                &client,
+                &cli.namespace,
+                *top,
+                *window,
+                operator_namespace.as_deref().unwrap_or(&cli.namespace),
+                cli.format(),
+            )
+            .await
+        }
+        Commands::Top {
+            operator_namespace,
+            interval,
+            limit,
+        } => {
+            cmd_top(
+                &client,
+                operator_namespace.as_deref().unwrap_or(&cli.namespace),
+                *interval,
+                *limit,
+            )
+            .await
+        }
+        Commands::History { name, since } => {
+            cmd_history(&client, &cli.namespace, name, *since, cli.format()).await
+        }
+        Commands::Record {
+            name,
+            interval,
+            duration,
+            out,
+            direct,
+        } => {
+            cmd_record(
+                &client,
+                &cli.namespace,
+                name,
+                *interval,
+                *duration,
+                out.as_deref(),
+                *direct,
+            )
+            .await
+        }
+        Commands::Report {
+            name,
+            all: _,
+            operator_namespace,
+        } => {
+            cmd_report(
+                &client,
+                &cli.namespace,
+                name.as_deref(),
+                operator_namespace.as_deref().unwrap_or(&cli.namespace),
+                &cli.output,
+            )
+            .await
+        }
+        Commands::Plot {
+            name,
+            since,
+            live,
+            interval,
+            width,
+            height,
+        } => {
+            cmd_plot(
+                &client,
+                &cli.namespace,
+                name,
+                *since,
+                *live,
+                *interval,
+                (*width, *height),
+                cli.format(),
+            )
+            .await
+        }
+        Commands::Group { action } => match action {
+            GroupAction::List => cmd_group_list(&client, &cli.namespace, cli.format()).await,
+            GroupAction::Describe { name } => {
+                cmd_group_describe(&client, &cli.namespace, name, cli.format()).await
+            }
+            GroupAction::Sync { name, force } => {
+                cmd_group_sync(&client, &cli.namespace, name, *force, cli.format()).await
+            }
+        },
+        Commands::Maintenance { action } => match action {
+            MaintenanceAction::Set {
+                name,
+                from,
+                to,
+                days,
+                utc_offset,
+                ..
+            } => {
+                let window = MaintenanceWindow {
+                    start: from.clone(),
+                    end: to.clone(),
+                    days: days.clone(),
+                    utc_offset_minutes: utc_offset
+                        .unwrap_or_else(|| chrono::Local::now().offset().local_minus_utc() / 60),
+                };
+                cmd_maintenance(&client, &cli.namespace, name.as_deref(), Some(window)).await
+            }
+            MaintenanceAction::Clear { name, .. } => {
+                cmd_maintenance(&client, &cli.namespace, name.as_deref(), None).await
+            }
+        },
+        Commands::Snapshot { action } => match action {
+            SnapshotAction::Create { plc, range, name } => {
+                cmd_snapshot_create(
+                    &client,
+                    &cli.namespace,
+                    plc,
+                    *range,
+                    name.as_deref(),
+                    cli.format(),
+                )
+                .await
+            }
+            SnapshotAction::List { plc } => {
+                cmd_snapshot_list(&client, &cli.namespace, plc.as_deref(), cli.format()).await
+            }
+            SnapshotAction::Restore {
+                snapshot,
+                dry_run,
+                yes,
+            } => {
+                cmd_snapshot_restore(
+                    &client,
+                    &cli.namespace,
+                    snapshot,
+                    *dry_run,
+                    *yes,
+                    cli.format(),
+                )
+                .await
+            }
+        },
+        Commands::Clone {
+            source,
+            name,
+            device,
+            port,
+            register,
+            value,
+            label,
+            dry_run,
+        } => {
+            let overrides = CloneOverrides {
+                device: device.clone(),
+                port: *port,
+                register: *register,
+                value: *value,
+                labels: label.clone(),
+            };
+            cmd_clone(
+                &client,
+                &cli.namespace,
+                source,
+                name,
+                overrides,
+                *dry_run,
+                cli.format(),
+            )
+            .await
+        }
+        Commands::Delete { name } => cmd_delete(&client, &cli.namespace, name).await,
+        Commands::Set {
+            name,
+            selector,
+            value,
+            dry_run,
+            yes,
+        } => {
+            cmd_set(
+                &client,
+                &cli.namespace,
+                name.as_deref(),
+                selector.as_deref(),
+                *value,
+                *dry_run,
+                *yes,
+            )
+            .await
+        }
+        Commands::Hold {
+            name,
+            duration,
+            reason,
+            release,
+        } => {
+            cmd_hold(
+                &client,
+                &cli.namespace,
+                name,
+                *duration,
+                reason.as_deref(),
+                *release,
+            )
+            .await
+        }
+        Commands::Approvals {
+            action: ApprovalsAction::List { all },
+        } => cmd_approvals_list(&client, &cli.namespace, *all, cli.format()).await,
+        Commands::Approve { request, reason } => {
+            cmd_decide(
+                &client,
+                &cli.namespace,
+                request,
+                Decision::Approved,
+                reason.as_deref(),
+            )
+            .await
+        }
+        Commands::Reject { request, reason } => {
+            cmd_decide(
+                &client,
+                &cli.namespace,
+                request,
+                Decision::Rejected,
+                Some(reason),
+            )
+            .await
+        }
+        Commands::Verify {
+            git_repo,
+            path,
+            git_ref,
+            no_device,
+            timeout_ms,
+        } => {
+            let source = VerifySource {
+                repo: git_repo,
+                path,
+                git_ref: git_ref.as_deref(),
+            };
+            let timeout = (!no_device).then(|| std::time::Duration::from_millis(*timeout_ms));
+            cmd_verify(&client, &cli.namespace, &source, timeout, cli.format()).await
+        }
+        Commands::Migrate { write, .. } => {
+            cmd_migrate_cluster(&client, &cli.namespace, *write, cli.format()).await
+        }
+        Commands::Uninstall {
+            delete_crs,
+            timeout,
+            yes,
+        } => cmd_uninstall(&client, &cli.namespace, *delete_crs, *timeout, *yes).await,
+        Commands::Validate { .. }
+        | Commands::Gen { .. }
+        | Commands::Scan { .. }
+        | Commands::Chaos { .. }
+        | Commands::Plugin { .. }
+        | Commands::Config { .. }
+        | Commands::External(_)
+        | Commands::Version
+        | Commands::Completion { .. }
+        | Commands::Complete { .. } => {
+            unreachable!("handled offline")
+        }
+    }
+}
+
 fn print_banner() {
     println!(
         "{}",
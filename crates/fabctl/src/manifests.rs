@@ -0,0 +1,240 @@
+use fabgitops_types::crd::{
+    CorrectionRequest, FabGitOpsOperator, IndustrialPLC, PLCGroup, PLCSnapshot, ProductionCalendar,
+};
+use fabgitops_types::rbac::OPERATOR_RULES;
+use k8s_openapi::apiextensions_apiserver::pkg::apis::apiextensions::v1::{
+    CustomResourceDefinition, ValidationRule,
+};
+use kube::CustomResourceExt;
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+
+/// Name shared by the operator's ServiceAccount, RBAC objects, and Deployment
+pub const OPERATOR_NAME: &str = "fabgitops-operator";
+
+/// Default operator image repository
+pub const DEFAULT_IMAGE_REPOSITORY: &str = "ghcr.io/yourusername/fabgitops-operator";
+
+/// Service exposing the operator's metrics endpoint
+pub const METRICS_SERVICE_NAME: &str = "fabgitops-operator-metrics";
+
+/// Port serving /metrics, /health and /ready
+pub const METRICS_PORT: u16 = 8080;
+
+/// Options for rendering the operator's runtime manifests
+pub struct OperatorManifestOptions {
+    pub namespace: String,
+    pub image: String,
+}
+
+/// CEL rules on the IndustrialPLC spec, with their messages, that span
+/// several fields and so cannot be expressed in the generated schema. The
+/// API server rejects PLCs breaking them.
+const PLC_SPEC_RULES: &[(&str, &str)] = &[(
+    "!has(self.minValue) || !has(self.maxValue) || self.minValue <= self.maxValue",
+    "minValue must not be greater than maxValue",
+)];
+
+/// CEL rules on the PLCSnapshot spec, with their messages
+const SNAPSHOT_SPEC_RULES: &[(&str, &str)] = &[(
+    "self.startRegister + size(self.values) <= 65536",
+    "values must not run past register 65535",
+)];
+
+/// The IndustrialPLC CRD generated from the Rust types, with its CEL rules
+pub fn crd() -> CustomResourceDefinition {
+    with_spec_rules(IndustrialPLC::crd(), PLC_SPEC_RULES)
+}
+
+/// Attach `rules` to the spec schema of every version of `crd`
+fn with_spec_rules(
+    mut crd: CustomResourceDefinition,
+    rules: &[(&str, &str)],
+) -> CustomResourceDefinition {
+    let rules: Vec<ValidationRule> = rules
+        .iter()
+        .map(|(rule, message)| ValidationRule {
+            rule: rule.to_string(),
+            message: Some(message.to_string()),
+            ..Default::default()
+        })
+        .collect();
+    let specs = crd
+        .spec
+        .versions
+        .iter_mut()
+        .filter_map(|v| v.schema.as_mut()?.open_api_v3_schema.as_mut())
+        .filter_map(|s| s.properties.as_mut()?.get_mut("spec"));
+    for spec in specs {
+        spec.x_kubernetes_validations = Some(rules.clone());
+    }
+    crd
+}
+
+/// Every CRD FabGitOps installs, IndustrialPLC first
+pub fn crds() -> Vec<CustomResourceDefinition> {
+    vec![
+        crd(),
+        CorrectionRequest::crd(),
+        PLCGroup::crd(),
+        with_spec_rules(PLCSnapshot::crd(), SNAPSHOT_SPEC_RULES),
+        ProductionCalendar::crd(),
+        FabGitOpsOperator::crd(),
+    ]
+}
+
+/// Spec field names declared in a CRD's OpenAPI schema
+pub fn crd_spec_fields(crd: &CustomResourceDefinition) -> BTreeSet<String> {
+    crd.spec
+        .versions
+        .iter()
+        .filter_map(|v| v.schema.as_ref()?.open_api_v3_schema.as_ref())
+        .filter_map(|s| s.properties.as_ref()?.get("spec")?.properties.as_ref())
+        .flat_map(|props| props.keys().cloned())
+        .collect()
+}
+
+/// ServiceAccount, RBAC, Deployment, and metrics Service for the operator,
+/// in the order they should be applied
+pub fn operator_manifests(opts: &OperatorManifestOptions) -> Vec<Value> {
+    vec![
+        service_account(opts),
+        cluster_role(),
+        cluster_role_binding(opts),
+        deployment(opts),
+        service(opts),
+    ]
+}
+
+fn service_account(opts: &OperatorManifestOptions) -> Value {
+    json!({
+        "apiVersion": "v1",
+        "kind": "ServiceAccount",
+        "metadata": {
+            "name": OPERATOR_NAME,
+            "namespace": opts.namespace,
+        }
+    })
+}
+
+/// ClusterRole granting the operator exactly what it needs at runtime
+pub fn cluster_role() -> Value {
+    let rules: Vec<Value> = OPERATOR_RULES
+        .iter()
+        .map(|rule| {
+            json!({
+                "apiGroups": [rule.api_group],
+                "resources": rule.resources,
+                "verbs": rule.verbs
+            })
+        })
+        .collect();
+    json!({
+        "apiVersion": "rbac.authorization.k8s.io/v1",
+        "kind": "ClusterRole",
+        "metadata": { "name": OPERATOR_NAME },
+        "rules": rules
+    })
+}
+
+fn cluster_role_binding(opts: &OperatorManifestOptions) -> Value {
+    json!({
+        "apiVersion": "rbac.authorization.k8s.io/v1",
+        "kind": "ClusterRoleBinding",
+        "metadata": { "name": OPERATOR_NAME },
+        "roleRef": {
+            "apiGroup": "rbac.authorization.k8s.io",
+            "kind": "ClusterRole",
+            "name": OPERATOR_NAME
+        },
+        "subjects": [{
+            "kind": "ServiceAccount",
+            "name": OPERATOR_NAME,
+            "namespace": opts.namespace
+        }]
+    })
+}
+
+fn deployment(opts: &OperatorManifestOptions) -> Value {
+    json!({
+        "apiVersion": "apps/v1",
+        "kind": "Deployment",
+        "metadata": {
+            "name": OPERATOR_NAME,
+            "namespace": opts.namespace,
+            "labels": { "app": OPERATOR_NAME }
+        },
+        "spec": {
+            "replicas": 1,
+            "selector": { "matchLabels": { "app": OPERATOR_NAME } },
+            "template": {
+                "metadata": { "labels": { "app": OPERATOR_NAME } },
+                "spec": {
+                    "serviceAccountName": OPERATOR_NAME,
+                    "containers": [{
+                        "name": "operator",
+                        "image": opts.image,
+                        "imagePullPolicy": "IfNotPresent",
+                        "ports": [{
+                            "name": "metrics",
+                            "containerPort": METRICS_PORT,
+                            "protocol": "TCP"
+                        }],
+                        "livenessProbe": {
+                            "httpGet": { "path": "/health", "port": "metrics" },
+                            "initialDelaySeconds": 10,
+                            "periodSeconds": 10
+                        },
+                        "readinessProbe": {
+                            "httpGet": { "path": "/ready", "port": "metrics" },
+                            "initialDelaySeconds": 5,
+                            "periodSeconds": 5
+                        },
+                        "env": [
+                            { "name": "RUST_LOG", "value": "info" },
+                            {
+                                "name": "POD_NAME",
+                                "valueFrom": { "fieldRef": { "fieldPath": "metadata.name" } }
+                            },
+                            {
+                                "name": "HOSTNAME",
+                                "valueFrom": { "fieldRef": { "fieldPath": "spec.nodeName" } }
+                            }
+                        ],
+                        "resources": {
+                            "limits": { "cpu": "500m", "memory": "256Mi" },
+                            "requests": { "cpu": "100m", "memory": "128Mi" }
+                        }
+                    }]
+                }
+            }
+        }
+    })
+}
+
+fn service(opts: &OperatorManifestOptions) -> Value {
+    json!({
+        "apiVersion": "v1",
+        "kind": "Service",
+        "metadata": {
+            "name": METRICS_SERVICE_NAME,
+            "namespace": opts.namespace,
+            "labels": { "app": OPERATOR_NAME },
+            "annotations": {
+                "prometheus.io/scrape": "true",
+                "prometheus.io/port": METRICS_PORT.to_string(),
+                "prometheus.io/path": "/metrics"
+            }
+        },
+        "spec": {
+            "type": "ClusterIP",
+            "ports": [{
+                "port": METRICS_PORT,
+                "targetPort": "metrics",
+                "protocol": "TCP",
+                "name": "metrics"
+            }],
+            "selector": { "app": OPERATOR_NAME }
+        }
+    })
+}
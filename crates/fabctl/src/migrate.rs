@@ -0,0 +1,211 @@
+use crate::validate::collect_manifest_files;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::path::PathBuf;
+
+/// A deprecated field to drop from a spec, and what to set instead
+#[derive(Clone, Debug)]
+pub struct SpecChange {
+    pub field: &'static str,
+    pub old: Value,
+    /// Field and value written in its place; none when the field can go
+    pub replacement: Option<(&'static str, Value)>,
+}
+
+impl SpecChange {
+    /// `field: value` as it appears in a manifest
+    pub fn removed(&self) -> String {
+        format!("{}: {}", self.field, render(&self.old))
+    }
+
+    pub fn added(&self) -> Option<String> {
+        self.replacement
+            .as_ref()
+            .map(|(field, value)| format!("{}: {}", field, render(value)))
+    }
+}
+
+/// Changes bringing an IndustrialPLC spec up to the current schema
+pub fn spec_changes(spec: &Map<String, Value>) -> Vec<SpecChange> {
+    let mut changes = Vec::new();
+
+    // A non-boolean autoCorrect is left for `fabctl validate` to report
+    if let Some(auto_correct) = spec.get("autoCorrect").filter(|v| v.is_boolean()) {
+        let policy = if auto_correct.as_bool() == Some(true) {
+            "Auto"
+        } else {
+            "AlertOnly"
+        };
+        changes.push(SpecChange {
+            field: "autoCorrect",
+            old: auto_correct.clone(),
+            // correctionPolicy already overrides autoCorrect, which can just go
+            replacement: (!spec.contains_key("correctionPolicy"))
+                .then(|| ("correctionPolicy", Value::from(policy))),
+        });
+    }
+
+    changes
+}
+
+/// Merge patch applying `changes` to a spec
+pub fn spec_patch(changes: &[SpecChange]) -> Value {
+    let mut spec = Map::new();
+    for change in changes {
+        spec.insert(change.field.to_string(), Value::Null);
+        if let Some((field, value)) = &change.replacement {
+            spec.insert(field.to_string(), value.clone());
+        }
+    }
+    serde_json::json!({ "spec": spec })
+}
+
+/// One field migrated in a manifest or resource
+#[derive(Clone, Debug, Serialize)]
+pub struct Migration {
+    /// `file#document` for manifests, `namespace/name` for resources
+    pub location: String,
+    pub resource: String,
+    /// The deprecated field, e.g. `autoCorrect: true`
+    pub removed: String,
+    /// The field replacing it, if any
+    pub added: Option<String>,
+}
+
+/// Result of migrating manifests or resources
+#[derive(Debug, Default, Serialize)]
+pub struct MigrationReport {
+    pub migrations: Vec<Migration>,
+    /// Deprecated fields that have to be migrated by hand, with where
+    pub manual: Vec<String>,
+    /// Whether the changes were applied rather than only previewed
+    pub written: bool,
+}
+
+impl MigrationReport {
+    /// Number of resources with at least one migration
+    pub fn resources(&self) -> usize {
+        let mut locations: Vec<&str> = self
+            .migrations
+            .iter()
+            .map(|m| m.location.as_str())
+            .collect();
+        locations.dedup();
+        locations.len()
+    }
+}
+
+/// Migrate the IndustrialPLC manifests under `paths`, rewriting the files
+/// when `write` is set. Only the lines of deprecated fields are touched, so
+/// comments and formatting are kept. Fields written in flow style or across
+/// several lines are reported for migration by hand.
+pub fn migrate_files(paths: &[PathBuf], write: bool) -> Result<MigrationReport> {
+    let mut report = MigrationReport {
+        written: write,
+        ..Default::default()
+    };
+
+    for path in collect_manifest_files(paths)? {
+        let file = path.display().to_string();
+        let content =
+            std::fs::read_to_string(&path).with_context(|| format!("Failed to read {}", file))?;
+        let mut lines: Vec<String> = content.split_inclusive('\n').map(str::to_string).collect();
+        let mut changed = false;
+
+        let mut document = 0;
+        for range in document_ranges(&lines) {
+            let text = lines[range.clone()].concat();
+            let value: serde_yaml::Value = match serde_yaml::from_str(&text) {
+                Ok(serde_yaml::Value::Null) => continue,
+                Ok(value) => value,
+                // Broken documents are for `fabctl validate` to report
+                Err(_) => {
+                    document += 1;
+                    continue;
+                }
+            };
+            document += 1;
+            let Ok(value) = serde_json::to_value(&value) else {
+                continue;
+            };
+            if value.get("kind").and_then(Value::as_str) != Some("IndustrialPLC") {
+                continue;
+            }
+            let Some(spec) = value.get("spec").and_then(Value::as_object) else {
+                continue;
+            };
+            let name = value
+                .pointer("/metadata/name")
+                .and_then(Value::as_str)
+                .unwrap_or("<unnamed>")
+                .to_string();
+            let location = format!("{}#{}", file, document);
+
+            for change in spec_changes(spec) {
+                let prefix = format!("{}:", change.field);
+                let line = range.clone().find(|&i| {
+                    let line = &lines[i];
+                    let trimmed = line.trim_start();
+                    trimmed.len() < line.len() && trimmed.starts_with(&prefix)
+                });
+                let Some(line) = line else {
+                    report.manual.push(format!(
+                        "{} ({}): rewrite {} by hand",
+                        location,
+                        name,
+                        change.removed()
+                    ));
+                    continue;
+                };
+
+                let ending = if lines[line].ends_with('\n') {
+                    "\n"
+                } else {
+                    ""
+                };
+                let indent_len = lines[line].len() - lines[line].trim_start().len();
+                let indent = lines[line][..indent_len].to_string();
+                lines[line] = match change.added() {
+                    Some(added) => format!("{}{}{}", indent, added, ending),
+                    None => String::new(),
+                };
+                changed = true;
+                report.migrations.push(Migration {
+                    location: location.clone(),
+                    resource: name.clone(),
+                    removed: change.removed(),
+                    added: change.added(),
+                });
+            }
+        }
+
+        if write && changed {
+            std::fs::write(&path, lines.concat())
+                .with_context(|| format!("Failed to write {}", file))?;
+        }
+    }
+
+    Ok(report)
+}
+
+/// Line ranges of the YAML documents in a file, split at `---` lines
+fn document_ranges(lines: &[String]) -> Vec<std::ops::Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    for (i, line) in lines.iter().enumerate() {
+        if line.trim_end() == "---" || line.starts_with("--- ") {
+            ranges.push(start..i);
+            start = i + 1;
+        }
+    }
+    ranges.push(start..lines.len());
+    ranges
+}
+
+fn render(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        value => value.to_string(),
+    }
+}
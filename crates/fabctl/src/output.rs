@@ -1,6 +1,25 @@
+use crate::commands::OutputFormat;
+use crate::diff::{ChangeKind, DiffReport, ObjectAction};
+use crate::doctor::{Check, CheckStatus};
+use crate::k8s_client::PlcEvent;
+use crate::migrate::MigrationReport;
+use crate::prom::Scrape;
+use crate::scan::Responder;
+use crate::snapshot::RegisterChange;
+use crate::summary::FleetSummary;
+use crate::template::Template;
+use crate::validate::{Severity, ValidationReport};
+use crate::verify::{Verdict, VerifyReport};
 use colored::*;
-use comfy_table::{modifiers::UTF8_ROUND_CORNERS, presets::UTF8_FULL, Cell, Color, Table};
-use operator::crd::{IndustrialPLC, PLCPhase};
+use comfy_table::{
+    modifiers::UTF8_ROUND_CORNERS, presets::UTF8_FULL, Attribute, Cell, Color, Table,
+};
+use fabgitops_types::crd::{
+    CorrectionRequest, CorrectionRequestState, HistoryEntry, HistoryEvent, IndustrialPLC, PLCGroup,
+    PLCPhase, PLCSnapshot, StepState, ValueQuality, CLOCK_SKEW,
+};
+use std::collections::HashSet;
+use std::sync::OnceLock;
 
 #[allow(dead_code)]
 pub enum StatusStyle {
@@ -10,8 +29,87 @@ pub enum StatusStyle {
     Neutral,
 }
 
+/// How timestamps are shown in tables
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TimeDisplay {
+    /// Age relative to now, e.g. `3m ago`
+    #[default]
+    Relative,
+    /// Absolute time in the local time zone
+    Local,
+    /// Absolute time in UTC
+    Utc,
+}
+
+static TIME_DISPLAY: OnceLock<TimeDisplay> = OnceLock::new();
+
+/// Choose how timestamps are shown for the rest of the process
+pub fn set_time_display(display: TimeDisplay) {
+    let _ = TIME_DISPLAY.set(display);
+}
+
+fn time_display() -> TimeDisplay {
+    TIME_DISPLAY.get().copied().unwrap_or_default()
+}
+
+/// Format a timestamp for a table cell according to `--utc`/`--local`
+pub fn format_timestamp(time: &chrono::DateTime<chrono::Utc>) -> String {
+    match time_display() {
+        TimeDisplay::Relative => format_age(chrono::Utc::now().signed_duration_since(*time)),
+        TimeDisplay::Local => time
+            .with_timezone(&chrono::Local)
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string(),
+        TimeDisplay::Utc => time.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+    }
+}
+
+/// Format an RFC3339 timestamp string, leaving unparseable values as they are
+pub fn format_timestamp_str(time: &str) -> String {
+    chrono::DateTime::parse_from_rfc3339(time)
+        .map(|t| format_timestamp(&t.with_timezone(&chrono::Utc)))
+        .unwrap_or_else(|_| time.to_string())
+}
+
+/// Format a wall-clock time of day, in UTC with `--utc` and local time otherwise
+pub fn format_clock(time: &chrono::DateTime<chrono::Utc>) -> String {
+    match time_display() {
+        TimeDisplay::Utc => time.format("%H:%M:%SZ").to_string(),
+        _ => time
+            .with_timezone(&chrono::Local)
+            .format("%H:%M:%S")
+            .to_string(),
+    }
+}
+
+/// Format an age as `just now`, `45s ago`, `3m ago`, `2h ago`, or `4d ago`
+fn format_age(age: chrono::Duration) -> String {
+    let (secs, suffix) = if age.num_seconds() < 0 {
+        (-age.num_seconds(), false)
+    } else {
+        (age.num_seconds(), true)
+    };
+    let amount = match secs {
+        0..=4 => return "just now".to_string(),
+        5..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m", secs / 60),
+        3600..=86399 => format!("{}h", secs / 3600),
+        _ => format!("{}d", secs / 86400),
+    };
+    if suffix {
+        format!("{} ago", amount)
+    } else {
+        format!("in {}", amount)
+    }
+}
+
 /// Print a beautiful ASCII table of PLC status
 pub fn print_plc_table(plcs: &[IndustrialPLC]) {
+    print_plc_table_highlighted(plcs, &HashSet::new());
+}
+
+/// Print the PLC status table, flashing the rows of the named PLCs
+pub fn print_plc_table_highlighted(plcs: &[IndustrialPLC], highlight: &HashSet<String>) {
     if plcs.is_empty() {
         println!("{}", "⚠️  No IndustrialPLC resources found".yellow());
         return;
@@ -30,24 +128,36 @@ pub fn print_plc_table(plcs: &[IndustrialPLC]) {
             Cell::new("Status").fg(Color::Cyan),
             Cell::new("Phase").fg(Color::Cyan),
             Cell::new("Drifts").fg(Color::Cyan),
+            Cell::new("Updated").fg(Color::Cyan),
         ]);
 
     for plc in plcs {
         let name = plc.metadata.name.as_deref().unwrap_or("unknown");
         let device = format!("{}:{}", plc.spec.device_address, plc.spec.port);
         let register = plc.spec.target_register.to_string();
-        let desired = plc.spec.target_value.to_string();
+        let desired = plc.spec.display_value(plc.spec.target());
+        let updated = plc
+            .status
+            .as_ref()
+            .and_then(|s| s.last_update.as_deref())
+            .map(format_timestamp_str)
+            .unwrap_or_else(|| "-".to_string());
 
         let (actual, status, phase, drifts) = if let Some(ref s) = plc.status {
-            let actual_str = s
-                .current_value
-                .map(|v: u16| v.to_string())
-                .unwrap_or_else(|| "-".to_string());
+            let actual_str = match s.current_value {
+                Some(v) if s.value_quality == ValueQuality::Stale => {
+                    format!("{} (stale)", plc.spec.display_value(v))
+                }
+                Some(v) => plc.spec.display_value(v),
+                None => "-".to_string(),
+            };
 
             let status_str = if s.in_sync {
                 "✓ SYNCED".to_string()
             } else if s.phase == PLCPhase::DriftDetected {
                 "⚠ DRIFT".to_string()
+            } else if s.phase == PLCPhase::Unscheduled {
+                "⏸ UNSCHEDULED".to_string()
             } else {
                 "✗ UNKNOWN".to_string()
             };
@@ -71,6 +181,7 @@ pub fn print_plc_table(plcs: &[IndustrialPLC]) {
         let status_cell = match status.as_str() {
             "✓ SYNCED" => Cell::new(status).fg(Color::Green),
             "⚠ DRIFT" => Cell::new(status).fg(Color::Yellow),
+            "⏸ UNSCHEDULED" => Cell::new(status).fg(Color::Grey),
             _ => Cell::new(status).fg(Color::Red),
         };
 
@@ -83,7 +194,7 @@ pub fn print_plc_table(plcs: &[IndustrialPLC]) {
             _ => Cell::new(phase).fg(Color::Grey),
         };
 
-        table.add_row(vec![
+        let mut row = vec![
             Cell::new(name),
             Cell::new(device),
             Cell::new(register),
@@ -92,14 +203,29 @@ pub fn print_plc_table(plcs: &[IndustrialPLC]) {
             status_cell,
             phase_cell,
             Cell::new(drifts),
-        ]);
+            Cell::new(updated).fg(Color::Grey),
+        ];
+        if highlight.contains(name) {
+            row = row
+                .into_iter()
+                .map(|cell| {
+                    cell.add_attribute(Attribute::SlowBlink)
+                        .add_attribute(Attribute::Reverse)
+                })
+                .collect();
+        }
+        table.add_row(row);
     }
 
     println!("{}", table);
 }
 
 /// Print a status summary box
-pub fn print_status_summary(status: &operator::crd::IndustrialPLCStatus, style: StatusStyle) {
+pub fn print_status_summary(
+    status: &fabgitops_types::crd::IndustrialPLCStatus,
+    spec: &fabgitops_types::crd::IndustrialPLCSpec,
+    style: StatusStyle,
+) {
     let border_color = match style {
         StatusStyle::Success => Color::Green,
         StatusStyle::Warning => Color::Yellow,
@@ -138,9 +264,26 @@ pub fn print_status_summary(status: &operator::crd::IndustrialPLCStatus, style:
     ]);
 
     if let Some(value) = status.current_value {
+        let value = spec.display_value(value);
+        let cell = if status.value_quality == ValueQuality::Stale {
+            Cell::new(format!("{} (stale)", value)).fg(Color::Yellow)
+        } else {
+            Cell::new(value)
+        };
+        table.add_row(vec![Cell::new("Current Value:"), cell]);
+    }
+
+    if let Some(ref until) = status.hold_until {
+        table.add_row(vec![
+            Cell::new("Held Until:"),
+            Cell::new(format_timestamp_str(until)).fg(Color::Yellow),
+        ]);
+    }
+
+    if let Some(ref read) = status.last_good_read_time {
         table.add_row(vec![
-            Cell::new("Current Value:"),
-            Cell::new(value.to_string()),
+            Cell::new("Last Good Read:"),
+            Cell::new(format_timestamp_str(read)),
         ]);
     }
 
@@ -161,12 +304,80 @@ pub fn print_status_summary(status: &operator::crd::IndustrialPLCStatus, style:
         ]);
     }
 
+    if let Some(ref reason) = status.reason {
+        table.add_row(vec![Cell::new("Reason:"), Cell::new(reason).fg(Color::Red)]);
+    }
+
+    for block in &status.blocks {
+        let text = block
+            .actual_text
+            .as_ref()
+            .map(|text| format!("{:?} ", text))
+            .unwrap_or_default();
+        let state = if block.in_sync {
+            Cell::new(format!("{}in sync", text)).fg(Color::Green)
+        } else {
+            let offsets: Vec<String> = block.drifted_offsets.iter().map(u16::to_string).collect();
+            Cell::new(format!("{}drift at offset(s) {}", text, offsets.join(", ")))
+                .fg(Color::Yellow)
+        };
+        table.add_row(vec![Cell::new(format!("Block {}:", block.name)), state]);
+    }
+
+    for step in &status.correction_steps {
+        let (state, color) = match step.state {
+            StepState::Applied => ("applied", Color::Green),
+            StepState::Failed => ("failed", Color::Red),
+            StepState::Skipped => ("skipped", Color::Grey),
+        };
+        let state = match step.message {
+            Some(ref message) => format!("{}: {}", state, message),
+            None => state.to_string(),
+        };
+        table.add_row(vec![
+            Cell::new(format!("Step {} (order {}):", step.name, step.order)),
+            Cell::new(state).fg(color),
+        ]);
+    }
+
+    if let Some(ref config_map) = status.snapshot_config_map {
+        table.add_row(vec![
+            Cell::new("Snapshot:"),
+            Cell::new(format!("configmap/{}", config_map)),
+        ]);
+    }
+
+    if let Some(skew) = status.clock_skew_secs {
+        let skewed = status
+            .conditions
+            .iter()
+            .any(|c| c.type_ == CLOCK_SKEW && c.status == "True");
+        table.add_row(vec![
+            Cell::new("Clock Skew:"),
+            Cell::new(format!("{:+}s", skew)).fg(if skewed { Color::Red } else { Color::Green }),
+        ]);
+    }
+
+    if let Some(ref pending) = status.pending_correction {
+        table.add_row(vec![
+            Cell::new("Would Write:"),
+            Cell::new(format!(
+                "register {}: {} -> {} ({})",
+                pending.register,
+                spec.display_value(pending.old_value),
+                spec.display_value(pending.new_value),
+                format_timestamp_str(&pending.timestamp)
+            ))
+            .fg(Color::Yellow),
+        ]);
+    }
+
     table.add_row(vec![Cell::new("Message:"), Cell::new(&status.message)]);
 
     if let Some(ref updated) = status.last_update {
         table.add_row(vec![
             Cell::new("Last Update:"),
-            Cell::new(updated).fg(Color::Grey),
+            Cell::new(format_timestamp_str(updated)).fg(Color::Grey),
         ]);
     }
 
@@ -197,3 +408,1122 @@ pub fn print_status_line(plc: &IndustrialPLC) {
         println!("○ {}: {}", name, "PENDING".dimmed());
     }
 }
+
+/// Print the findings of `fabctl validate`
+pub fn print_migration_report(report: &MigrationReport) {
+    let mut location = None;
+    for migration in &report.migrations {
+        if location != Some(&migration.location) {
+            location = Some(&migration.location);
+            println!(
+                "{} ({})",
+                migration.location.dimmed(),
+                migration.resource.cyan()
+            );
+        }
+        println!("  {}", format!("- {}", migration.removed).red());
+        if let Some(added) = &migration.added {
+            println!("  {}", format!("+ {}", added).green());
+        }
+    }
+    for manual in &report.manual {
+        println!("{} {}", "⚠".yellow(), manual);
+    }
+
+    if !report.migrations.is_empty() || !report.manual.is_empty() {
+        println!();
+    }
+
+    let count = report.migrations.len();
+    let resources = report.resources();
+    if count == 0 {
+        println!("{} {}", "✓".green(), "Nothing to migrate".green());
+    } else if report.written {
+        println!(
+            "{} Migrated {} field(s) in {} resource(s)",
+            "✓".green(),
+            count,
+            resources
+        );
+    } else {
+        println!(
+            "{}",
+            format!(
+                "{} field(s) in {} resource(s) to migrate; run again with --write to apply",
+                count, resources
+            )
+            .yellow()
+        );
+    }
+}
+
+pub fn print_validation_report(report: &ValidationReport) {
+    for finding in &report.findings {
+        let (icon, label) = match finding.severity {
+            Severity::Error => ("✗".red(), "error".red().bold()),
+            Severity::Warning => ("⚠".yellow(), "warning".yellow().bold()),
+        };
+        let location = format!("{}#{}", finding.file, finding.document);
+        match finding.resource {
+            Some(ref name) => println!(
+                "{} {} {} ({}): {}",
+                icon,
+                label,
+                location.dimmed(),
+                name.cyan(),
+                finding.message
+            ),
+            None => println!(
+                "{} {} {}: {}",
+                icon,
+                label,
+                location.dimmed(),
+                finding.message
+            ),
+        }
+    }
+
+    if !report.findings.is_empty() {
+        println!();
+    }
+
+    let summary = format!(
+        "Checked {} IndustrialPLC resource(s) in {} file(s): {} error(s), {} warning(s)",
+        report.resources,
+        report.files,
+        report.errors(),
+        report.warnings()
+    );
+    if report.errors() > 0 {
+        println!("{}", summary.red());
+    } else if report.warnings() > 0 {
+        println!("{}", summary.yellow());
+    } else {
+        println!("{} {}", "✓".green(), summary.green());
+    }
+}
+
+/// Print Kubernetes manifests as a multi-document YAML stream, or as a
+/// `v1/List` for JSON output, ready to pipe into `kubectl apply -f -`
+pub fn print_manifests(docs: &[serde_json::Value], format: OutputFormat) -> anyhow::Result<()> {
+    match format {
+        OutputFormat::Json => {
+            let list = serde_json::json!({
+                "apiVersion": "v1",
+                "kind": "List",
+                "items": docs,
+            });
+            println!("{}", serde_json::to_string_pretty(&list)?);
+        }
+        OutputFormat::Table | OutputFormat::Yaml => print!("{}", manifests_yaml(docs)?),
+    }
+    Ok(())
+}
+
+/// Render resources as a multi-document YAML stream
+pub fn manifests_yaml(docs: &[serde_json::Value]) -> anyhow::Result<String> {
+    let mut stream = String::new();
+    for doc in docs {
+        stream.push_str("---\n");
+        stream.push_str(&serde_yaml::to_string(doc)?);
+    }
+    Ok(stream)
+}
+
+/// Print `fabctl doctor` results with suggested fixes
+pub fn print_doctor_report(checks: &[Check]) {
+    for check in checks {
+        let label = match check.status {
+            CheckStatus::Pass => "[PASS]".green().bold(),
+            CheckStatus::Warn => "[WARN]".yellow().bold(),
+            CheckStatus::Fail => "[FAIL]".red().bold(),
+        };
+        println!("{} {}: {}", label, check.name.bold(), check.detail);
+        if let Some(ref fix) = check.fix {
+            println!("       {} {}", "→".dimmed(), fix.dimmed());
+        }
+    }
+
+    let count = |status| checks.iter().filter(|c| c.status == status).count();
+    println!();
+    let summary = format!(
+        "{} passed, {} warning(s), {} failed",
+        count(CheckStatus::Pass),
+        count(CheckStatus::Warn),
+        count(CheckStatus::Fail)
+    );
+    if count(CheckStatus::Fail) > 0 {
+        println!("{}", summary.red());
+    } else if count(CheckStatus::Warn) > 0 {
+        println!("{}", summary.yellow());
+    } else {
+        println!("{} {}", "✓".green(), summary.green());
+    }
+}
+
+/// Print operator and fleet metrics for `fabctl top`. Rates are computed
+/// against the previous scrape when one is available.
+/// Print the fleet overview of `fabctl summary`
+pub fn print_fleet_summary(summary: &FleetSummary) {
+    println!(
+        "{} {} PLC(s) in {}, {} in sync",
+        "Fleet:".bold(),
+        summary.total,
+        summary.namespace.cyan(),
+        summary.in_sync
+    );
+    let mut phases = Table::new();
+    phases
+        .load_preset(UTF8_FULL)
+        .apply_modifier(UTF8_ROUND_CORNERS)
+        .set_header(vec![
+            Cell::new("Phase").fg(Color::Cyan),
+            Cell::new("PLCs").fg(Color::Cyan),
+        ]);
+    for phase in &summary.phases {
+        let color = match phase.phase {
+            PLCPhase::Failed => Color::Red,
+            PLCPhase::DriftDetected | PLCPhase::Correcting => Color::Yellow,
+            PLCPhase::Connected => Color::Green,
+            _ => Color::Grey,
+        };
+        phases.add_row(vec![
+            Cell::new(format!("{:?}", phase.phase)).fg(color),
+            Cell::new(phase.count),
+        ]);
+    }
+    if !summary.phases.is_empty() {
+        println!("{}", phases);
+    }
+    println!();
+
+    println!("{}", "Operator".bold().underline());
+    match (&summary.operator, &summary.metrics_error) {
+        (Some(operator), _) => {
+            println!(
+                "  {} reconciliation(s), {} failed",
+                operator.reconciliations,
+                if operator.reconcile_errors > 0.0 {
+                    operator.reconcile_errors.to_string().red()
+                } else {
+                    operator.reconcile_errors.to_string().green()
+                }
+            );
+            if operator.stalled {
+                println!("  {} {}", "✗".red(), "Reconcile loop stalled".red().bold());
+            }
+        }
+        (None, Some(error)) => println!("  {}", format!("Metrics unavailable: {}", error).dimmed()),
+        (None, None) => {}
+    }
+    println!();
+
+    let corrections = &summary.corrections;
+    println!(
+        "{}",
+        format!(
+            "Corrections (last {})",
+            format_duration_secs(corrections.window_secs as i64)
+        )
+        .bold()
+        .underline()
+    );
+    println!(
+        "  {} corrected ({:.1}/h), {} failed, {} drift(s) detected",
+        corrections.corrected.to_string().green(),
+        corrections.per_hour(),
+        if corrections.failed > 0 {
+            corrections.failed.to_string().red()
+        } else {
+            corrections.failed.to_string().normal()
+        },
+        corrections.drifts_detected
+    );
+    println!();
+
+    println!("{}", "Top drifting PLCs".bold().underline());
+    if summary.top_drifting.is_empty() {
+        println!("{}", "No drift recorded".green());
+    } else {
+        let mut table = Table::new();
+        table
+            .load_preset(UTF8_FULL)
+            .apply_modifier(UTF8_ROUND_CORNERS)
+            .set_header(vec![
+                Cell::new("PLC").fg(Color::Cyan),
+                Cell::new("Drifts").fg(Color::Cyan),
+                Cell::new("Phase").fg(Color::Cyan),
+                Cell::new("Drifting For").fg(Color::Cyan),
+            ]);
+        for plc in &summary.top_drifting {
+            table.add_row(vec![
+                Cell::new(&plc.name),
+                Cell::new(plc.drift_events).fg(Color::Yellow),
+                Cell::new(format!("{:?}", plc.phase)),
+                Cell::new(
+                    plc.drift_age_secs
+                        .map(format_duration_secs)
+                        .unwrap_or_else(|| "-".to_string()),
+                ),
+            ]);
+        }
+        println!("{}", table);
+    }
+    println!();
+
+    println!("{}", "Unreachable devices".bold().underline());
+    if summary.unreachable.is_empty() {
+        println!("{}", "All devices answered on their last poll".green());
+        return;
+    }
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_FULL)
+        .apply_modifier(UTF8_ROUND_CORNERS)
+        .set_header(vec![
+            Cell::new("Device").fg(Color::Cyan),
+            Cell::new("PLCs").fg(Color::Cyan),
+            Cell::new("Reason").fg(Color::Cyan),
+            Cell::new("Last Good Read").fg(Color::Cyan),
+        ]);
+    for device in &summary.unreachable {
+        table.add_row(vec![
+            Cell::new(&device.device).fg(Color::Red),
+            Cell::new(device.plcs.join(", ")),
+            Cell::new(&device.reason),
+            Cell::new(
+                device
+                    .last_good_read_time
+                    .as_deref()
+                    .map(format_timestamp_str)
+                    .unwrap_or_else(|| "never".to_string()),
+            )
+            .fg(Color::Grey),
+        ]);
+    }
+    println!("{}", table);
+}
+
+pub fn print_top(current: &Scrape, previous: Option<(&Scrape, f64)>, limit: usize) {
+    let rate = |total: &dyn Fn(&Scrape) -> f64| -> String {
+        match previous {
+            Some((prev, elapsed)) if elapsed > 0.0 => {
+                let delta = total(current) - total(prev);
+                // Counters reset when the operator restarts
+                if delta < 0.0 {
+                    "-".to_string()
+                } else {
+                    format!("{:.2}/s", delta / elapsed)
+                }
+            }
+            _ => "-".to_string(),
+        }
+    };
+    let errors = |s: &Scrape| {
+        s.with_label("reconciliations_total", "result", "failed")
+            + s.with_label("reconciliations_total", "result", "error")
+    };
+
+    let mut fleet = Table::new();
+    fleet
+        .load_preset(UTF8_FULL)
+        .apply_modifier(UTF8_ROUND_CORNERS)
+        .set_header(vec![
+            Cell::new("Metric").fg(Color::Cyan),
+            Cell::new("Total").fg(Color::Cyan),
+            Cell::new("Rate").fg(Color::Cyan),
+        ]);
+
+    fleet.add_row(vec![
+        Cell::new("Managed PLCs"),
+        Cell::new(current.total("managed_plcs")),
+        Cell::new("-"),
+    ]);
+    fleet.add_row(vec![
+        Cell::new("Reconciliations"),
+        Cell::new(current.total("reconciliations_total")),
+        Cell::new(rate(&|s| s.total("reconciliations_total"))),
+    ]);
+
+    let error_total = errors(current);
+    let error_color = if error_total > 0.0 {
+        Color::Red
+    } else {
+        Color::Green
+    };
+    fleet.add_row(vec![
+        Cell::new("Errors"),
+        Cell::new(error_total).fg(error_color),
+        Cell::new(rate(&errors)).fg(error_color),
+    ]);
+    fleet.add_row(vec![
+        Cell::new("Drift events"),
+        Cell::new(current.total("drift_events_total")).fg(Color::Yellow),
+        Cell::new(rate(&|s| s.total("drift_events_total"))),
+    ]);
+    fleet.add_row(vec![
+        Cell::new("Corrections"),
+        Cell::new(current.total("corrections_total")).fg(Color::Green),
+        Cell::new(rate(&|s| s.total("corrections_total"))),
+    ]);
+
+    println!("{}", "Fleet".bold().underline());
+    println!("{}", fleet);
+    println!();
+
+    let mut devices: Vec<_> = current.series("plc_reconcile_duration_seconds").collect();
+    devices.sort_by(|a, b| b.value.total_cmp(&a.value));
+
+    println!("{}", "Slowest devices".bold().underline());
+    if devices.is_empty() {
+        println!("{}", "No per-device metrics reported yet".dimmed());
+        return;
+    }
+
+    let mut slowest = Table::new();
+    slowest
+        .load_preset(UTF8_FULL)
+        .apply_modifier(UTF8_ROUND_CORNERS)
+        .set_header(vec![
+            Cell::new("PLC").fg(Color::Cyan),
+            Cell::new("Last Reconcile").fg(Color::Cyan),
+            Cell::new("Drifts").fg(Color::Cyan),
+        ]);
+
+    for sample in devices.into_iter().take(limit) {
+        let namespace = sample.labels.get("namespace").cloned().unwrap_or_default();
+        let name = sample.labels.get("name").cloned().unwrap_or_default();
+        let drifts = current
+            .series("plc_drift_events_total")
+            .find(|s| {
+                s.labels.get("namespace") == Some(&namespace) && s.labels.get("name") == Some(&name)
+            })
+            .map(|s| s.value)
+            .unwrap_or(0.0);
+        let duration_color = if sample.value >= 1.0 {
+            Color::Red
+        } else if sample.value >= 0.25 {
+            Color::Yellow
+        } else {
+            Color::Green
+        };
+
+        slowest.add_row(vec![
+            Cell::new(format!("{}/{}", namespace, name)),
+            Cell::new(format!("{:.0} ms", sample.value * 1000.0)).fg(duration_color),
+            Cell::new(drifts),
+        ]);
+    }
+
+    println!("{}", slowest);
+}
+
+/// Print each resource through an `-o template=` template, one per line
+/// unless the template ends its own lines
+pub fn print_templated<T: serde::Serialize>(
+    items: &[T],
+    template: &Template,
+) -> anyhow::Result<()> {
+    for item in items {
+        let rendered = template.render(&serde_json::to_value(item)?);
+        if rendered.ends_with('\n') {
+            print!("{}", rendered);
+        } else {
+            println!("{}", rendered);
+        }
+    }
+    Ok(())
+}
+
+/// Print the Kubernetes Events recorded for a PLC
+pub fn print_event_table(events: &[PlcEvent]) {
+    if events.is_empty() {
+        println!(
+            "{}",
+            "No events recorded (Events expire after an hour)".yellow()
+        );
+        return;
+    }
+
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_FULL)
+        .apply_modifier(UTF8_ROUND_CORNERS)
+        .set_header(vec![
+            Cell::new("Last Seen").fg(Color::Cyan),
+            Cell::new("Type").fg(Color::Cyan),
+            Cell::new("Reason").fg(Color::Cyan),
+            Cell::new("Count").fg(Color::Cyan),
+            Cell::new("Message").fg(Color::Cyan),
+        ]);
+
+    for event in events {
+        let type_cell = match event.type_.as_str() {
+            "Warning" => Cell::new(&event.type_).fg(Color::Yellow),
+            _ => Cell::new(&event.type_),
+        };
+        table.add_row(vec![
+            Cell::new(
+                event
+                    .last_seen
+                    .as_ref()
+                    .map(format_timestamp)
+                    .unwrap_or_else(|| "-".to_string()),
+            )
+            .fg(Color::Grey),
+            type_cell,
+            Cell::new(&event.reason),
+            Cell::new(event.count),
+            Cell::new(&event.message),
+        ]);
+    }
+
+    println!("{}", table);
+}
+
+/// Print a timeline of drift and correction events
+pub fn print_history_table(name: &str, entries: &[&HistoryEntry]) {
+    if entries.is_empty() {
+        println!("{}", format!("No history recorded for {}", name).yellow());
+        return;
+    }
+
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_FULL)
+        .apply_modifier(UTF8_ROUND_CORNERS)
+        .set_header(vec![
+            Cell::new("Time").fg(Color::Cyan),
+            Cell::new("Event").fg(Color::Cyan),
+            Cell::new("Desired").fg(Color::Cyan),
+            Cell::new("Actual").fg(Color::Cyan),
+            Cell::new("Duration").fg(Color::Cyan),
+            Cell::new("Detail").fg(Color::Cyan),
+        ]);
+
+    for entry in entries {
+        let event_cell = match entry.event {
+            HistoryEvent::DriftDetected => Cell::new("⚠ DriftDetected").fg(Color::Yellow),
+            HistoryEvent::Corrected => Cell::new("✓ Corrected").fg(Color::Green),
+            HistoryEvent::CorrectionFailed => Cell::new("✗ CorrectionFailed").fg(Color::Red),
+            HistoryEvent::Resolved => Cell::new("○ Resolved").fg(Color::Blue),
+        };
+
+        table.add_row(vec![
+            Cell::new(format_timestamp_str(&entry.timestamp)).fg(Color::Grey),
+            event_cell,
+            Cell::new(entry.desired).fg(Color::Green),
+            Cell::new(
+                entry
+                    .actual
+                    .map(|v| v.to_string())
+                    .unwrap_or_else(|| "-".to_string()),
+            ),
+            Cell::new(
+                entry
+                    .duration_secs
+                    .map(format_duration_secs)
+                    .unwrap_or_else(|| "-".to_string()),
+            ),
+            Cell::new(history_detail(entry)),
+        ]);
+    }
+
+    println!("{}", table);
+}
+
+/// The message of a history entry followed by the diagnostic registers read
+/// with it
+pub fn history_detail(entry: &HistoryEntry) -> String {
+    let diagnostics = entry.diagnostics.iter().map(|d| d.to_string());
+    let parts: Vec<String> = entry
+        .message
+        .clone()
+        .into_iter()
+        .chain(diagnostics)
+        .collect();
+    parts.join("; ")
+}
+
+/// Format a number of seconds as a compact duration, e.g. `1h 02m` or `45s`
+pub fn format_duration_secs(secs: i64) -> String {
+    let secs = secs.max(0);
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m {:02}s", secs / 60, secs % 60),
+        _ => format!("{}h {:02}m", secs / 3600, (secs % 3600) / 60),
+    }
+}
+
+/// Print a table of CorrectionRequests
+pub fn print_correction_requests(requests: &[CorrectionRequest]) {
+    if requests.is_empty() {
+        println!("{}", "✓ No correction requests awaiting approval".green());
+        return;
+    }
+
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_FULL)
+        .apply_modifier(UTF8_ROUND_CORNERS)
+        .set_header(vec![
+            Cell::new("Request").fg(Color::Cyan),
+            Cell::new("PLC").fg(Color::Cyan),
+            Cell::new("Register").fg(Color::Cyan),
+            Cell::new("Current").fg(Color::Cyan),
+            Cell::new("Desired").fg(Color::Cyan),
+            Cell::new("State").fg(Color::Cyan),
+            Cell::new("Decided By").fg(Color::Cyan),
+            Cell::new("Requested").fg(Color::Cyan),
+        ]);
+
+    for request in requests {
+        let state = request.state();
+        let state_cell = match state {
+            CorrectionRequestState::Pending => Cell::new("Pending").fg(Color::Yellow),
+            CorrectionRequestState::Applied => Cell::new("Applied").fg(Color::Green),
+            CorrectionRequestState::Rejected => Cell::new("Rejected").fg(Color::Red),
+            CorrectionRequestState::Failed => Cell::new("Failed").fg(Color::Red),
+            CorrectionRequestState::Superseded => Cell::new("Superseded").fg(Color::Grey),
+        };
+        let decided_by = request
+            .spec
+            .approval
+            .as_ref()
+            .map(|a| format!("{} ({:?})", a.user, a.decision))
+            .unwrap_or_else(|| "-".to_string());
+
+        table.add_row(vec![
+            Cell::new(request.metadata.name.as_deref().unwrap_or("unknown")),
+            Cell::new(&request.spec.plc_name),
+            Cell::new(request.spec.register),
+            Cell::new(request.spec.current_value).fg(Color::Yellow),
+            Cell::new(request.spec.desired_value).fg(Color::Green),
+            state_cell,
+            Cell::new(decided_by),
+            Cell::new(
+                request
+                    .metadata
+                    .creation_timestamp
+                    .as_ref()
+                    .map(|t| format_timestamp(&t.0))
+                    .unwrap_or_default(),
+            )
+            .fg(Color::Grey),
+        ]);
+    }
+
+    println!("{}", table);
+}
+
+/// Print the devices found by `fabctl scan`
+pub fn print_scan_results(responders: &[Responder], scanned: usize, register: Option<u16>) {
+    if responders.is_empty() {
+        println!(
+            "{}",
+            format!("No Modbus devices answered ({} hosts probed)", scanned).yellow()
+        );
+        return;
+    }
+
+    let mut header = vec![
+        Cell::new("Address").fg(Color::Cyan),
+        Cell::new("Port").fg(Color::Cyan),
+        Cell::new("Vendor").fg(Color::Cyan),
+        Cell::new("Product").fg(Color::Cyan),
+        Cell::new("Revision").fg(Color::Cyan),
+    ];
+    if let Some(register) = register {
+        header.push(Cell::new(format!("Register {}", register)).fg(Color::Cyan));
+    }
+
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_FULL)
+        .apply_modifier(UTF8_ROUND_CORNERS)
+        .set_header(header);
+
+    for responder in responders {
+        let id = responder.identification.as_ref();
+        let mut row = vec![
+            Cell::new(responder.address),
+            Cell::new(responder.port),
+            Cell::new(id.and_then(|i| i.vendor_name.as_deref()).unwrap_or("-")),
+            Cell::new(id.and_then(|i| i.product_code.as_deref()).unwrap_or("-")),
+            Cell::new(id.and_then(|i| i.revision.as_deref()).unwrap_or("-")),
+        ];
+        if register.is_some() {
+            row.push(match responder.register_value {
+                Some(value) => Cell::new(value).fg(Color::Green),
+                None => Cell::new("unreadable").fg(Color::Red),
+            });
+        }
+        table.add_row(row);
+    }
+
+    println!("{}", table);
+    println!(
+        "\n{} {} device(s) found across {} hosts",
+        "✓".green(),
+        responders.len(),
+        scanned
+    );
+}
+
+/// A sampled register value
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct ValuePoint {
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub value: u16,
+}
+
+/// Print a step chart of values over a time window with the desired value
+/// drawn as a reference line
+pub fn print_value_chart(
+    name: &str,
+    points: &[ValuePoint],
+    desired: u16,
+    window: (chrono::DateTime<chrono::Utc>, chrono::DateTime<chrono::Utc>),
+    (width, height): (usize, usize),
+) {
+    let (start, end) = window;
+    let (width, height) = (width.max(10), height.max(3));
+
+    println!("{} {}", "📈".cyan(), name.bold());
+    if points.is_empty() {
+        println!(
+            "{}",
+            "No values recorded in this window; try --live or a longer --since".yellow()
+        );
+        return;
+    }
+
+    // Hold the last known value across each column (step interpolation)
+    let span = (end - start).num_milliseconds().max(1);
+    let columns: Vec<Option<u16>> = (0..width)
+        .map(|c| {
+            let at = start + chrono::Duration::milliseconds(span * (c as i64 + 1) / width as i64);
+            points
+                .iter()
+                .filter(|p| p.timestamp <= at)
+                .max_by_key(|p| p.timestamp)
+                .map(|p| p.value)
+        })
+        .collect();
+
+    let values = columns.iter().flatten().copied().chain([desired]);
+    let lo = values.clone().min().unwrap_or(desired) as f64;
+    let hi = (values.max().unwrap_or(desired) as f64).max(lo + 1.0);
+    let row_of = |v: u16| (((v as f64 - lo) / (hi - lo)) * (height - 1) as f64).round() as usize;
+    let desired_row = row_of(desired);
+    let label_width = format!("{}", hi as u16)
+        .len()
+        .max(desired.to_string().len());
+
+    for row in (0..height).rev() {
+        let label = if row == height - 1 {
+            format!("{:>w$}", hi as u16, w = label_width)
+        } else if row == 0 {
+            format!("{:>w$}", lo as u16, w = label_width)
+        } else if row == desired_row {
+            format!("{:>w$}", desired, w = label_width)
+        } else {
+            " ".repeat(label_width)
+        };
+
+        let mut line = String::new();
+        let mut previous: Option<usize> = None;
+        for value in &columns {
+            let cell = match value.map(row_of) {
+                Some(r) if r == row => {
+                    let glyph = "●";
+                    if value == &Some(desired) {
+                        glyph.green().to_string()
+                    } else {
+                        glyph.yellow().to_string()
+                    }
+                }
+                Some(r) if previous.is_some_and(|p| (row > p.min(r)) && (row < p.max(r))) => {
+                    "│".yellow().to_string()
+                }
+                _ if row == desired_row => "─".green().dimmed().to_string(),
+                _ => " ".to_string(),
+            };
+            line.push_str(&cell);
+            if let Some(v) = value {
+                previous = Some(row_of(*v));
+            }
+        }
+        println!("{} ┤{}", label.dimmed(), line);
+    }
+
+    let start_label = format_clock(&start);
+    let end_label = format_clock(&end);
+    println!(
+        "{} └{}",
+        " ".repeat(label_width),
+        "─".repeat(width).dimmed()
+    );
+    println!(
+        "{}  {}{:>pad$}",
+        " ".repeat(label_width),
+        start_label.dimmed(),
+        end_label.dimmed(),
+        pad = width.saturating_sub(start_label.len())
+    );
+    println!(
+        "{}  {} actual   {} desired ({})",
+        " ".repeat(label_width),
+        "●".yellow(),
+        "─".green(),
+        desired
+    );
+}
+
+/// A phase change seen while watching a single PLC
+#[derive(Clone, Debug)]
+pub struct PhaseTransition {
+    pub at: chrono::DateTime<chrono::Utc>,
+    /// None for the phase the PLC was in when the watch started
+    pub from: Option<PLCPhase>,
+    pub to: PLCPhase,
+}
+
+/// Print the phase changes seen so far, oldest first
+pub fn print_phase_transitions(transitions: &[PhaseTransition]) {
+    for transition in transitions {
+        let to = format!("{:?}", transition.to);
+        let to = match transition.to {
+            PLCPhase::Connected => to.green(),
+            PLCPhase::DriftDetected | PLCPhase::Correcting => to.yellow(),
+            PLCPhase::Failed => to.red(),
+            _ => to.normal(),
+        };
+        match &transition.from {
+            Some(from) => println!(
+                "  {}  {:?} → {}",
+                format_clock(&transition.at).dimmed(),
+                from,
+                to
+            ),
+            None => println!(
+                "  {}  {} (watch started)",
+                format_clock(&transition.at).dimmed(),
+                to
+            ),
+        }
+    }
+}
+
+/// Aggregated health of the members of a PLCGroup
+#[derive(Clone, Debug, Default, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupHealth {
+    pub members: usize,
+    pub in_sync: usize,
+    pub drifted: usize,
+    pub failed: usize,
+    pub pending: usize,
+}
+
+impl GroupHealth {
+    pub fn of(plcs: &[IndustrialPLC]) -> Self {
+        let mut health = Self {
+            members: plcs.len(),
+            ..Self::default()
+        };
+        for plc in plcs {
+            match plc.status.as_ref() {
+                Some(s) if s.phase == PLCPhase::Failed => health.failed += 1,
+                Some(s) if s.in_sync => health.in_sync += 1,
+                Some(s) if matches!(s.phase, PLCPhase::DriftDetected | PLCPhase::Correcting) => {
+                    health.drifted += 1
+                }
+                _ => health.pending += 1,
+            }
+        }
+        health
+    }
+
+    fn cell(&self) -> Cell {
+        if self.failed > 0 {
+            Cell::new("✗ DEGRADED").fg(Color::Red)
+        } else if self.drifted > 0 {
+            Cell::new("⚠ DRIFT").fg(Color::Yellow)
+        } else if self.members > 0 && self.in_sync == self.members {
+            Cell::new("✓ HEALTHY").fg(Color::Green)
+        } else {
+            Cell::new("○ PENDING").fg(Color::Grey)
+        }
+    }
+}
+
+/// Print PLCGroups with aggregated member health
+pub fn print_group_table(groups: &[(PLCGroup, GroupHealth)]) {
+    if groups.is_empty() {
+        println!("{}", "⚠️  No PLCGroup resources found".yellow());
+        return;
+    }
+
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_FULL)
+        .apply_modifier(UTF8_ROUND_CORNERS)
+        .set_header(vec![
+            Cell::new("Group").fg(Color::Cyan),
+            Cell::new("Description").fg(Color::Cyan),
+            Cell::new("Members").fg(Color::Cyan),
+            Cell::new("In Sync").fg(Color::Cyan),
+            Cell::new("Drifted").fg(Color::Cyan),
+            Cell::new("Failed").fg(Color::Cyan),
+            Cell::new("Health").fg(Color::Cyan),
+        ]);
+
+    for (group, health) in groups {
+        table.add_row(vec![
+            Cell::new(group.metadata.name.as_deref().unwrap_or("unknown")),
+            Cell::new(group.spec.description.as_deref().unwrap_or("-")),
+            Cell::new(health.members),
+            Cell::new(health.in_sync).fg(Color::Green),
+            Cell::new(health.drifted).fg(Color::Yellow),
+            Cell::new(health.failed).fg(Color::Red),
+            health.cell(),
+        ]);
+    }
+
+    println!("{}", table);
+}
+
+/// Print stored register snapshots
+pub fn print_snapshots(snapshots: &[PLCSnapshot]) {
+    if snapshots.is_empty() {
+        println!("{}", "⚠️  No PLCSnapshot resources found".yellow());
+        return;
+    }
+
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_FULL)
+        .apply_modifier(UTF8_ROUND_CORNERS)
+        .set_header(vec![
+            Cell::new("Snapshot").fg(Color::Cyan),
+            Cell::new("PLC").fg(Color::Cyan),
+            Cell::new("Registers").fg(Color::Cyan),
+            Cell::new("Count").fg(Color::Cyan),
+            Cell::new("Taken").fg(Color::Cyan),
+        ]);
+
+    for snapshot in snapshots {
+        table.add_row(vec![
+            Cell::new(snapshot.metadata.name.as_deref().unwrap_or("unknown")),
+            Cell::new(&snapshot.spec.plc_name),
+            Cell::new(format!(
+                "{}-{}",
+                snapshot.spec.start_register,
+                snapshot.spec.end_register()
+            )),
+            Cell::new(snapshot.spec.values.len()),
+            Cell::new(format_timestamp_str(&snapshot.spec.taken_at)).fg(Color::Grey),
+        ]);
+    }
+
+    println!("{}", table);
+}
+
+/// Print the registers a snapshot restore would change
+pub fn print_register_diff(changes: &[RegisterChange]) {
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_FULL)
+        .apply_modifier(UTF8_ROUND_CORNERS)
+        .set_header(vec![
+            Cell::new("Register").fg(Color::Cyan),
+            Cell::new("Current").fg(Color::Cyan),
+            Cell::new("Snapshot").fg(Color::Cyan),
+            Cell::new("Delta").fg(Color::Cyan),
+        ]);
+
+    for change in changes {
+        table.add_row(vec![
+            Cell::new(change.register),
+            Cell::new(change.current).fg(Color::Red),
+            Cell::new(change.snapshot).fg(Color::Green),
+            Cell::new(format!(
+                "{:+}",
+                change.snapshot as i32 - change.current as i32
+            )),
+        ]);
+    }
+
+    println!("{}", table);
+}
+
+/// Print what applying the manifests would change, kubectl-diff style:
+/// `+` added, `-` removed, `~` changed
+pub fn print_diff_report(report: &DiffReport) {
+    if report.objects.is_empty() {
+        println!("{}", "No IndustrialPLC manifests found".yellow());
+        return;
+    }
+
+    let render = |value: &Option<serde_json::Value>| {
+        value.as_ref().map(|v| v.to_string()).unwrap_or_default()
+    };
+    for object in &report.objects {
+        let id = format!("{}/{}", object.namespace, object.name);
+        match object.action {
+            ObjectAction::Unchanged => continue,
+            ObjectAction::Create => {
+                println!("{} {} {}", "+".green(), id.cyan(), "(created)".green())
+            }
+            ObjectAction::Update => println!("{} {}", "~".yellow(), id.cyan()),
+        }
+        for change in &object.changes {
+            let line = match change.kind {
+                ChangeKind::Added => {
+                    format!("+ {}: {}", change.path, render(&change.declared)).green()
+                }
+                ChangeKind::Removed => format!("- {}: {}", change.path, render(&change.live)).red(),
+                ChangeKind::Changed => format!(
+                    "~ {}: {} → {}",
+                    change.path,
+                    render(&change.live),
+                    render(&change.declared)
+                )
+                .yellow(),
+            };
+            println!("    {}", line);
+        }
+    }
+
+    let changed = report.changed();
+    if changed == 0 {
+        println!(
+            "{} {}",
+            "✓".green(),
+            "Cluster matches the manifests".green()
+        );
+        return;
+    }
+    let created = report
+        .objects
+        .iter()
+        .filter(|o| o.action == ObjectAction::Create)
+        .count();
+    println!();
+    println!(
+        "{}",
+        format!(
+            "{} of {} PLC(s) would change ({} to create)",
+            changed,
+            report.objects.len(),
+            created
+        )
+        .yellow()
+    );
+}
+
+/// Print the three-way Git / cluster / device report of `fabctl verify`
+pub fn print_verify_report(report: &VerifyReport, read_devices: bool) {
+    if report.entries.is_empty() {
+        println!(
+            "{}",
+            format!(
+                "No IndustrialPLC resources in {} ({}) or the cluster",
+                report.repo, report.path
+            )
+            .yellow()
+        );
+        return;
+    }
+
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_FULL)
+        .apply_modifier(UTF8_ROUND_CORNERS)
+        .set_header(vec![
+            Cell::new("Name").fg(Color::Cyan),
+            Cell::new("Git").fg(Color::Cyan),
+            Cell::new("Cluster").fg(Color::Cyan),
+            Cell::new("Device").fg(Color::Cyan),
+            Cell::new("Result").fg(Color::Cyan),
+        ]);
+
+    let value = |v: Option<u16>| v.map(|v| v.to_string()).unwrap_or_else(|| "-".to_string());
+    for entry in &report.entries {
+        let cluster_color = if entry.cluster_value == entry.git_value {
+            Color::Green
+        } else {
+            Color::Yellow
+        };
+        let device_cell = match (&entry.device_error, entry.device_value) {
+            (Some(_), _) => Cell::new("unreachable").fg(Color::Red),
+            (None, Some(v)) if Some(v) == entry.git_value => Cell::new(v).fg(Color::Green),
+            (None, Some(v)) => Cell::new(v).fg(Color::Red),
+            (None, None) if !read_devices => Cell::new("skipped").fg(Color::DarkGrey),
+            (None, None) => Cell::new("-"),
+        };
+        let result = entry
+            .verdicts
+            .iter()
+            .map(|v| match v {
+                Verdict::InSync => "In sync",
+                Verdict::ClusterDrift => "Cluster differs",
+                Verdict::DeviceDrift => "Device differs",
+                Verdict::MissingInCluster => "Missing in cluster",
+                Verdict::NotInGit => "Not in Git",
+                Verdict::Unreachable => "Device unreachable",
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        let result_color = if entry.in_sync() {
+            Color::Green
+        } else {
+            Color::Red
+        };
+
+        table.add_row(vec![
+            Cell::new(format!("{}/{}", entry.namespace, entry.name)),
+            Cell::new(value(entry.git_value)).fg(Color::Green),
+            Cell::new(value(entry.cluster_value)).fg(cluster_color),
+            device_cell,
+            Cell::new(result).fg(result_color),
+        ]);
+    }
+
+    println!("{}", table);
+
+    for entry in &report.entries {
+        if !entry.differing_fields.is_empty() {
+            println!(
+                "  {} {}: cluster differs from Git in spec.{}",
+                "⚠".yellow(),
+                entry.name.cyan(),
+                entry.differing_fields.join(", spec.")
+            );
+        }
+        if let Some(ref error) = entry.device_error {
+            println!(
+                "  {} {}: device read failed: {}",
+                "✗".red(),
+                entry.name.cyan(),
+                error
+            );
+        }
+    }
+
+    let drifted = report.drifted();
+    let summary = format!(
+        "{} of {} PLC(s) match Git ({} at {})",
+        report.entries.len() - drifted,
+        report.entries.len(),
+        report.repo,
+        report.path
+    );
+    println!();
+    if drifted == 0 {
+        println!("{} {}", "✓".green(), summary.green());
+    } else {
+        println!("{}", summary.red());
+    }
+}
@@ -0,0 +1,95 @@
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Executables named `fabctl-<name>` on PATH extend fabctl with `fabctl <name>`
+pub const PLUGIN_PREFIX: &str = "fabctl-";
+
+/// Settings handed to a plugin through its environment
+pub struct PluginEnv<'a> {
+    pub namespace: &'a str,
+    pub output: &'a str,
+    /// Kubeconfig context selected by --context or the active profile
+    pub context: Option<&'a str>,
+    /// Whether mutating commands must ask before changing anything
+    pub require_confirmation: bool,
+}
+
+/// Find the executable for `fabctl <name>`, searching PATH in order
+pub fn find_plugin(name: &str) -> Option<PathBuf> {
+    let file = format!("{}{}", PLUGIN_PREFIX, name);
+    search_path()
+        .map(|dir| dir.join(&file))
+        .find(|path| is_executable(path))
+}
+
+/// All plugins on PATH by name; earlier PATH entries shadow later ones
+pub fn list_plugins() -> BTreeMap<String, PathBuf> {
+    let mut plugins = BTreeMap::new();
+    for dir in search_path() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let file = entry.file_name().to_string_lossy().to_string();
+            if let Some(name) = file.strip_prefix(PLUGIN_PREFIX) {
+                let path = entry.path();
+                if !name.is_empty() && is_executable(&path) {
+                    plugins.entry(name.to_string()).or_insert(path);
+                }
+            }
+        }
+    }
+    plugins
+}
+
+/// Run a plugin with the remaining arguments and return its exit code
+pub fn run_plugin(path: &Path, args: &[String], env: &PluginEnv) -> Result<i32> {
+    let mut command = Command::new(path);
+    command
+        .args(args)
+        .env("FABCTL_NAMESPACE", env.namespace)
+        .env("FABCTL_OUTPUT", env.output);
+
+    // Best effort: plugins can still find the config through KUBECONFIG
+    let context = env.context.map(str::to_string).or_else(|| {
+        kube::config::Kubeconfig::read()
+            .ok()
+            .and_then(|config| config.current_context)
+    });
+    if let Some(context) = context {
+        command.env("FABCTL_KUBE_CONTEXT", context);
+    }
+    if env.require_confirmation {
+        command.env("FABCTL_REQUIRE_CONFIRMATION", "1");
+    }
+    if let Ok(exe) = std::env::current_exe() {
+        command.env("FABCTL_BIN", exe);
+    }
+
+    let status = command
+        .status()
+        .with_context(|| format!("Failed to run plugin {}", path.display()))?;
+    Ok(status.code().unwrap_or(1))
+}
+
+fn search_path() -> impl Iterator<Item = PathBuf> {
+    std::env::var_os("PATH")
+        .map(|paths| std::env::split_paths(&paths).collect::<Vec<_>>())
+        .unwrap_or_default()
+        .into_iter()
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
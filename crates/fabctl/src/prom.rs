@@ -0,0 +1,106 @@
+use std::collections::BTreeMap;
+
+/// One sample from a Prometheus text exposition
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sample {
+    pub name: String,
+    pub labels: BTreeMap<String, String>,
+    pub value: f64,
+}
+
+/// A parsed scrape of the operator's /metrics endpoint
+#[derive(Clone, Debug, Default)]
+pub struct Scrape {
+    pub samples: Vec<Sample>,
+}
+
+impl Scrape {
+    /// Parse the Prometheus text format, skipping comments and malformed lines
+    pub fn parse(text: &str) -> Self {
+        let samples = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(parse_line)
+            .collect();
+        Self { samples }
+    }
+
+    /// Sum of every sample of a metric, across all label sets
+    pub fn total(&self, name: &str) -> f64 {
+        self.samples
+            .iter()
+            .filter(|s| s.name == name)
+            .map(|s| s.value)
+            .sum()
+    }
+
+    /// Value of the first sample matching a metric name and label pair
+    pub fn with_label(&self, name: &str, label: &str, value: &str) -> f64 {
+        self.samples
+            .iter()
+            .filter(|s| s.name == name && s.labels.get(label).map(String::as_str) == Some(value))
+            .map(|s| s.value)
+            .sum()
+    }
+
+    /// All samples of a metric
+    pub fn series(&self, name: &str) -> impl Iterator<Item = &Sample> {
+        let name = name.to_string();
+        self.samples.iter().filter(move |s| s.name == name)
+    }
+}
+
+fn parse_line(line: &str) -> Option<Sample> {
+    let (head, rest) = match line.find('{') {
+        Some(open) => {
+            let close = line[open..].find('}')? + open;
+            (&line[..close + 1], &line[close + 1..])
+        }
+        None => line.split_at(line.find(char::is_whitespace)?),
+    };
+
+    let value = rest.split_whitespace().next()?.parse().ok()?;
+
+    let (name, labels) = match head.find('{') {
+        Some(open) => (&head[..open], parse_labels(&head[open + 1..head.len() - 1])),
+        None => (head, BTreeMap::new()),
+    };
+
+    Some(Sample {
+        name: name.to_string(),
+        labels,
+        value,
+    })
+}
+
+fn parse_labels(body: &str) -> BTreeMap<String, String> {
+    let mut labels = BTreeMap::new();
+    let mut chars = body.chars().peekable();
+
+    loop {
+        while chars.peek().is_some_and(|c| *c == ',' || c.is_whitespace()) {
+            chars.next();
+        }
+        let key: String = chars.by_ref().take_while(|c| *c != '=').collect();
+        if key.is_empty() || chars.next() != Some('"') {
+            break;
+        }
+
+        let mut value = String::new();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some('n') => value.push('\n'),
+                    Some(other) => value.push(other),
+                    None => break,
+                },
+                '"' => break,
+                other => value.push(other),
+            }
+        }
+        labels.insert(key.trim().to_string(), value);
+    }
+
+    labels
+}
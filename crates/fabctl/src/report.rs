@@ -0,0 +1,369 @@
+use crate::k8s_client::PlcEvent;
+use crate::output::{format_duration_secs, history_detail};
+use crate::prom::Scrape;
+use anyhow::Result;
+use fabgitops_types::crd::{IndustrialPLC, IndustrialPLCSpec, IndustrialPLCStatus};
+use kube::ResourceExt;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Most recent history entries kept per PLC
+const RECENT_HISTORY: usize = 20;
+
+/// Document formats `fabctl report` renders for tickets
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DocumentFormat {
+    Markdown,
+    Html,
+}
+
+/// A point-in-time status report of one or more PLCs. Timestamps are
+/// absolute, in UTC, so the report still reads right when opened later.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Report {
+    pub generated_at: String,
+    pub namespace: String,
+    pub plcs: Vec<PlcReport>,
+    /// Why the operator metrics could not be included
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metrics_error: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlcReport {
+    pub name: String,
+    pub spec: IndustrialPLCSpec,
+    /// Status with the history cut down to the most recent entries
+    pub status: Option<IndustrialPLCStatus>,
+    pub events: Vec<PlcEvent>,
+    pub metrics: Vec<MetricSample>,
+}
+
+/// A sample of a per-PLC or per-device operator metric
+#[derive(Clone, Debug, Serialize)]
+pub struct MetricSample {
+    pub metric: String,
+    /// Labels besides the ones naming the PLC or device
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+    pub value: f64,
+}
+
+impl PlcReport {
+    pub fn new(plc: IndustrialPLC, events: Vec<PlcEvent>, scrape: Option<&Scrape>) -> Self {
+        let metrics = scrape
+            .map(|scrape| metric_excerpt(scrape, &plc))
+            .unwrap_or_default();
+        let name = plc.name_any();
+        let mut status = plc.status;
+        if let Some(status) = &mut status {
+            let skip = status.history.len().saturating_sub(RECENT_HISTORY);
+            status.history.drain(..skip);
+        }
+        Self {
+            name,
+            spec: plc.spec,
+            status,
+            events,
+            metrics,
+        }
+    }
+}
+
+/// Samples labelled with the PLC's namespace and name, or with its device
+/// address. Histogram buckets are left out; their sum and count stay.
+fn metric_excerpt(scrape: &Scrape, plc: &IndustrialPLC) -> Vec<MetricSample> {
+    let namespace = plc.namespace().unwrap_or_default();
+    let name = plc.name_any();
+    let device = format!("{}:{}", plc.spec.device_address, plc.spec.port);
+    let label = |sample: &crate::prom::Sample, key: &str| sample.labels.get(key).cloned();
+
+    scrape
+        .samples
+        .iter()
+        .filter(|s| !s.name.ends_with("_bucket"))
+        .filter(|s| {
+            (label(s, "namespace").as_ref() == Some(&namespace)
+                && label(s, "name").as_ref() == Some(&name))
+                || label(s, "device").as_ref() == Some(&device)
+        })
+        .map(|s| MetricSample {
+            metric: s.name.clone(),
+            labels: s
+                .labels
+                .iter()
+                .filter(|(key, _)| !matches!(key.as_str(), "namespace" | "name" | "device"))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+            value: s.value,
+        })
+        .collect()
+}
+
+/// Building blocks shared by the Markdown and HTML renderings
+enum Block {
+    Heading(u8, String),
+    Paragraph(String),
+    /// Header and rows of plain-text cells
+    Table(Vec<&'static str>, Vec<Vec<String>>),
+    Code(String),
+}
+
+impl Report {
+    pub fn render(&self, format: DocumentFormat) -> Result<String> {
+        let blocks = self.blocks()?;
+        Ok(match format {
+            DocumentFormat::Markdown => markdown(&blocks),
+            DocumentFormat::Html => html(&blocks),
+        })
+    }
+
+    fn blocks(&self) -> Result<Vec<Block>> {
+        let mut blocks = vec![
+            Block::Heading(1, "FabGitOps status report".to_string()),
+            Block::Paragraph(format!(
+                "Generated {} for {} PLC(s) in namespace {}.",
+                self.generated_at,
+                self.plcs.len(),
+                self.namespace
+            )),
+        ];
+        if let Some(error) = &self.metrics_error {
+            blocks.push(Block::Paragraph(format!("Metrics unavailable: {}", error)));
+        }
+
+        for plc in &self.plcs {
+            blocks.push(Block::Heading(2, plc.name.clone()));
+
+            blocks.push(Block::Heading(3, "Status".to_string()));
+            match &plc.status {
+                Some(status) => blocks.push(Block::Table(
+                    vec!["Field", "Value"],
+                    status_rows(&plc.spec, status),
+                )),
+                None => blocks.push(Block::Paragraph("Not reconciled yet.".to_string())),
+            }
+
+            blocks.push(Block::Heading(3, "Spec".to_string()));
+            blocks.push(Block::Code(serde_yaml::to_string(&plc.spec)?));
+
+            blocks.push(Block::Heading(3, "Recent events".to_string()));
+            if plc.events.is_empty() {
+                blocks.push(Block::Paragraph("No events recorded.".to_string()));
+            } else {
+                let rows = plc
+                    .events
+                    .iter()
+                    .map(|event| {
+                        vec![
+                            event.last_seen.as_ref().map(timestamp).unwrap_or_default(),
+                            event.type_.clone(),
+                            event.reason.clone(),
+                            event.count.to_string(),
+                            event.message.clone(),
+                        ]
+                    })
+                    .collect();
+                blocks.push(Block::Table(
+                    vec!["Last seen", "Type", "Reason", "Count", "Message"],
+                    rows,
+                ));
+            }
+
+            blocks.push(Block::Heading(3, "Drift history".to_string()));
+            let history = plc.status.as_ref().map(|s| &s.history[..]).unwrap_or(&[]);
+            if history.is_empty() {
+                blocks.push(Block::Paragraph("No drift recorded.".to_string()));
+            } else {
+                let rows = history
+                    .iter()
+                    .map(|entry| {
+                        vec![
+                            timestamp_str(&entry.timestamp),
+                            format!("{:?}", entry.event),
+                            entry.desired.to_string(),
+                            optional(entry.actual),
+                            entry
+                                .duration_secs
+                                .map(format_duration_secs)
+                                .unwrap_or_else(|| "-".to_string()),
+                            history_detail(entry),
+                        ]
+                    })
+                    .collect();
+                blocks.push(Block::Table(
+                    vec!["Time", "Event", "Desired", "Actual", "Duration", "Detail"],
+                    rows,
+                ));
+            }
+
+            if self.metrics_error.is_none() {
+                blocks.push(Block::Heading(3, "Metrics".to_string()));
+                if plc.metrics.is_empty() {
+                    blocks.push(Block::Paragraph(
+                        "No metrics reported for this PLC.".to_string(),
+                    ));
+                } else {
+                    let rows = plc
+                        .metrics
+                        .iter()
+                        .map(|sample| {
+                            let labels: Vec<String> = sample
+                                .labels
+                                .iter()
+                                .map(|(key, value)| format!("{}={}", key, value))
+                                .collect();
+                            vec![
+                                sample.metric.clone(),
+                                labels.join(", "),
+                                sample.value.to_string(),
+                            ]
+                        })
+                        .collect();
+                    blocks.push(Block::Table(vec!["Metric", "Labels", "Value"], rows));
+                }
+            }
+        }
+        Ok(blocks)
+    }
+}
+
+fn status_rows(spec: &IndustrialPLCSpec, status: &IndustrialPLCStatus) -> Vec<Vec<String>> {
+    let mut rows = vec![
+        ("Phase", format!("{:?}", status.phase)),
+        (
+            "In sync",
+            if status.in_sync { "yes" } else { "no" }.to_string(),
+        ),
+        ("Target value", spec.display_value(spec.target())),
+        (
+            "Current value",
+            status
+                .current_value
+                .map(|value| spec.display_value(value))
+                .unwrap_or_else(|| "-".to_string()),
+        ),
+        ("Value quality", format!("{:?}", status.value_quality)),
+        ("Drift events", status.drift_events.to_string()),
+        (
+            "Corrections applied",
+            status.corrections_applied.to_string(),
+        ),
+    ];
+    if let Some(since) = &status.drift_since {
+        rows.push(("Drifting since", timestamp_str(since)));
+    }
+    if let Some(until) = &status.hold_until {
+        rows.push(("Held until", timestamp_str(until)));
+    }
+    if let Some(reason) = &status.reason {
+        rows.push(("Reason", reason.clone()));
+    }
+    if let Some(error) = &status.last_error {
+        rows.push(("Last error", error.clone()));
+    }
+    if let Some(update) = &status.last_update {
+        rows.push(("Last update", timestamp_str(update)));
+    }
+    rows.push(("Message", status.message.clone()));
+    rows.into_iter()
+        .map(|(field, value)| vec![field.to_string(), value])
+        .collect()
+}
+
+fn optional(value: Option<u16>) -> String {
+    value
+        .map(|v| v.to_string())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+pub fn timestamp(time: &chrono::DateTime<chrono::Utc>) -> String {
+    time.format("%Y-%m-%d %H:%M:%S UTC").to_string()
+}
+
+fn timestamp_str(time: &str) -> String {
+    chrono::DateTime::parse_from_rfc3339(time)
+        .map(|t| timestamp(&t.with_timezone(&chrono::Utc)))
+        .unwrap_or_else(|_| time.to_string())
+}
+
+fn markdown(blocks: &[Block]) -> String {
+    let cell = |text: &str| text.replace('|', "\\|").replace('\n', "<br>");
+    let mut out = String::new();
+    for block in blocks {
+        match block {
+            Block::Heading(level, text) => {
+                out.push_str(&format!("{} {}\n\n", "#".repeat(*level as usize), text))
+            }
+            Block::Paragraph(text) => out.push_str(&format!("{}\n\n", text)),
+            Block::Table(header, rows) => {
+                out.push_str(&format!("| {} |\n", header.join(" | ")));
+                out.push_str(&format!("|{}\n", "---|".repeat(header.len())));
+                for row in rows {
+                    let cells: Vec<String> = row.iter().map(|c| cell(c)).collect();
+                    out.push_str(&format!("| {} |\n", cells.join(" | ")));
+                }
+                out.push('\n');
+            }
+            Block::Code(code) => out.push_str(&format!("```yaml\n{}```\n\n", code)),
+        }
+    }
+    out
+}
+
+fn html(blocks: &[Block]) -> String {
+    let mut body = String::new();
+    for block in blocks {
+        match block {
+            Block::Heading(level, text) => {
+                body.push_str(&format!("<h{0}>{1}</h{0}>\n", level, escape(text)))
+            }
+            Block::Paragraph(text) => body.push_str(&format!("<p>{}</p>\n", escape(text))),
+            Block::Table(header, rows) => {
+                body.push_str("<table>\n<tr>");
+                for title in header {
+                    body.push_str(&format!("<th>{}</th>", escape(title)));
+                }
+                body.push_str("</tr>\n");
+                for row in rows {
+                    body.push_str("<tr>");
+                    for cell in row {
+                        body.push_str(&format!("<td>{}</td>", escape(cell)));
+                    }
+                    body.push_str("</tr>\n");
+                }
+                body.push_str("</table>\n");
+            }
+            Block::Code(code) => body.push_str(&format!("<pre>{}</pre>\n", escape(code))),
+        }
+    }
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>FabGitOps status report</title>
+<style>
+body {{ font-family: sans-serif; margin: 2em; }}
+table {{ border-collapse: collapse; margin-bottom: 1em; }}
+th, td {{ border: 1px solid #ccc; padding: 4px 8px; text-align: left; vertical-align: top; }}
+th {{ background: #f3f3f3; }}
+pre {{ background: #f6f8fa; padding: 8px; }}
+</style>
+</head>
+<body>
+{}</body>
+</html>
+"#,
+        body
+    )
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,271 @@
+use http::{Method, Request, Response, StatusCode};
+use hyper::Body;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::time::Instant;
+use tower::{BoxError, Layer, Service};
+use tracing::warn;
+
+/// First wait between attempts; doubled after each retry
+const INITIAL_BACKOFF: Duration = Duration::from_millis(250);
+
+/// Longest wait between attempts, also the cap on a server's Retry-After
+const MAX_BACKOFF: Duration = Duration::from_secs(8);
+
+/// How requests to the API server cope with a flaky link
+#[derive(Clone, Debug)]
+pub struct ClientOptions {
+    /// Time allowed for each attempt to get a response; none waits forever
+    pub timeout: Option<Duration>,
+    /// Extra attempts after a transient failure
+    pub retries: u32,
+    /// Sustained requests per second; zero turns rate limiting off
+    pub qps: f64,
+    /// Requests allowed in a burst above `qps`
+    pub burst: u32,
+}
+
+impl Default for ClientOptions {
+    fn default() -> Self {
+        Self {
+            timeout: Some(Duration::from_secs(30)),
+            retries: 3,
+            qps: 20.0,
+            burst: 40,
+        }
+    }
+}
+
+/// Wraps the Kubernetes client's HTTP stack so every request is rate
+/// limited, given up on after the timeout, and retried with exponential
+/// backoff when the failure is transient.
+///
+/// A request is only repeated when doing so cannot apply a change twice:
+/// any request is retried when the connection could not be made or the
+/// server answered 429 or 503, but GET and HEAD alone after a timeout, a
+/// dropped connection, or a 500, 502 or 504.
+pub struct RetryLayer {
+    options: ClientOptions,
+    limiter: Arc<RateLimiter>,
+}
+
+impl RetryLayer {
+    pub fn new(options: &ClientOptions) -> Self {
+        Self {
+            options: options.clone(),
+            limiter: Arc::new(RateLimiter::new(options.qps, options.burst)),
+        }
+    }
+}
+
+impl<S> Layer<S> for RetryLayer {
+    type Service = RetryService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RetryService {
+            inner: Arc::new(tokio::sync::Mutex::new(inner)),
+            options: self.options.clone(),
+            limiter: self.limiter.clone(),
+        }
+    }
+}
+
+pub struct RetryService<S> {
+    /// Shared so a request can be sent again after its first attempt
+    inner: Arc<tokio::sync::Mutex<S>>,
+    options: ClientOptions,
+    limiter: Arc<RateLimiter>,
+}
+
+impl<S, B> Service<Request<Body>> for RetryService<S>
+where
+    S: Service<Request<Body>, Response = Response<B>> + Send + 'static,
+    S::Future: Send,
+    S::Error: Into<BoxError>,
+    B: Send + 'static,
+{
+    type Response = Response<B>;
+    type Error = BoxError;
+    type Future = Pin<Box<dyn Future<Output = Result<Response<B>, BoxError>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), BoxError>> {
+        // Readiness of the inner service is awaited per attempt
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: Request<Body>) -> Self::Future {
+        let inner = self.inner.clone();
+        let options = self.options.clone();
+        let limiter = self.limiter.clone();
+        Box::pin(async move {
+            let (parts, body) = request.into_parts();
+            // Buffered so it can be sent more than once
+            let body = hyper::body::to_bytes(body).await?;
+            let safe = parts.method == Method::GET || parts.method == Method::HEAD;
+
+            let mut backoff = INITIAL_BACKOFF;
+            let mut attempt = 0;
+            loop {
+                let mut request = Request::new(Body::from(body.clone()));
+                *request.method_mut() = parts.method.clone();
+                *request.uri_mut() = parts.uri.clone();
+                *request.version_mut() = parts.version;
+                *request.headers_mut() = parts.headers.clone();
+
+                limiter.acquire().await;
+                let result = send(&inner, request, options.timeout).await;
+                let (failure, retry_after) = match &result {
+                    Ok(response) => match retryable_status(response.status(), safe) {
+                        true => (response.status().to_string(), retry_after(response)),
+                        false => return result,
+                    },
+                    Err(error) => match retryable_error(error, safe) {
+                        true => (error.to_string(), None),
+                        false => return result,
+                    },
+                };
+                if attempt >= options.retries {
+                    return result;
+                }
+
+                let wait = retry_after.unwrap_or(backoff).min(MAX_BACKOFF);
+                warn!(
+                    "{} {} failed ({}); retrying in {}ms ({}/{})",
+                    parts.method,
+                    parts.uri.path(),
+                    failure,
+                    wait.as_millis(),
+                    attempt + 1,
+                    options.retries
+                );
+                tokio::time::sleep(wait).await;
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+                attempt += 1;
+            }
+        })
+    }
+}
+
+/// Send one attempt, waiting at most `timeout` for the response head
+async fn send<S, B>(
+    inner: &tokio::sync::Mutex<S>,
+    request: Request<Body>,
+    timeout: Option<Duration>,
+) -> Result<Response<B>, BoxError>
+where
+    S: Service<Request<Body>, Response = Response<B>>,
+    S::Error: Into<BoxError>,
+{
+    let response = async {
+        let future = {
+            let mut service = inner.lock().await;
+            std::future::poll_fn(|cx| service.poll_ready(cx))
+                .await
+                .map_err(Into::into)?;
+            service.call(request)
+        };
+        future.await.map_err(Into::into)
+    };
+    match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, response)
+            .await
+            .map_err(|_| TimedOut(timeout))?,
+        None => response.await,
+    }
+}
+
+/// An attempt that got no response within the request timeout
+#[derive(Debug)]
+struct TimedOut(Duration);
+
+impl std::fmt::Display for TimedOut {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "request timed out after {:?}", self.0)
+    }
+}
+
+impl std::error::Error for TimedOut {}
+
+fn retryable_status(status: StatusCode, safe: bool) -> bool {
+    match status {
+        // The server turned the request away without acting on it
+        StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE => true,
+        StatusCode::INTERNAL_SERVER_ERROR
+        | StatusCode::BAD_GATEWAY
+        | StatusCode::GATEWAY_TIMEOUT => safe,
+        _ => false,
+    }
+}
+
+fn retryable_error(error: &BoxError, safe: bool) -> bool {
+    // Nothing reached the server when the connection could not be made
+    let mut source: Option<&(dyn std::error::Error + 'static)> = Some(error.as_ref());
+    while let Some(error) = source {
+        if error
+            .downcast_ref::<hyper::Error>()
+            .is_some_and(hyper::Error::is_connect)
+        {
+            return true;
+        }
+        source = error.source();
+    }
+    safe
+}
+
+/// The server's Retry-After, in seconds
+fn retry_after<B>(response: &Response<B>) -> Option<Duration> {
+    response
+        .headers()
+        .get(http::header::RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .map(Duration::from_secs)
+}
+
+/// Token bucket shared by every request of a client
+struct RateLimiter {
+    qps: f64,
+    burst: f64,
+    /// Tokens available and when they were last topped up
+    bucket: Mutex<(f64, Instant)>,
+}
+
+impl RateLimiter {
+    fn new(qps: f64, burst: u32) -> Self {
+        let burst = f64::from(burst.max(1));
+        Self {
+            qps,
+            burst,
+            bucket: Mutex::new((burst, Instant::now())),
+        }
+    }
+
+    /// Wait until a request may be sent
+    async fn acquire(&self) {
+        if self.qps <= 0.0 {
+            return;
+        }
+        loop {
+            let wait = {
+                let mut bucket = self.bucket.lock().unwrap();
+                let (tokens, refilled) = &mut *bucket;
+                let now = Instant::now();
+                *tokens = (*tokens + now.duration_since(*refilled).as_secs_f64() * self.qps)
+                    .min(self.burst);
+                *refilled = now;
+                if *tokens >= 1.0 {
+                    *tokens -= 1.0;
+                    return;
+                }
+                Duration::from_secs_f64((1.0 - *tokens) / self.qps)
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
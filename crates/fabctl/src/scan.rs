@@ -0,0 +1,183 @@
+use anyhow::{Context, Result};
+use fabgitops_modbus::{DeviceIdentification, PLCClient};
+use fabgitops_types::crd::{CorrectionPolicy, IndustrialPLC, IndustrialPLCSpec};
+use serde::Serialize;
+use std::net::Ipv4Addr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// Smallest prefix length accepted, to keep accidental /8 sweeps off the OT network
+const MIN_PREFIX_LEN: u8 = 16;
+
+/// How a subnet sweep is performed
+pub struct ScanOptions {
+    pub port: u16,
+    pub timeout: Duration,
+    pub concurrency: usize,
+    /// Register to read from each responder, used as the manifest target
+    pub register: Option<u16>,
+}
+
+/// A host that accepted a Modbus TCP connection
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Responder {
+    pub address: Ipv4Addr,
+    pub port: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub identification: Option<DeviceIdentification>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub register_value: Option<u16>,
+}
+
+/// Expand `10.20.0.0/24` or a single address into the hosts to probe.
+/// Network and broadcast addresses are skipped for prefixes up to /30.
+pub fn parse_targets(spec: &str) -> Result<Vec<Ipv4Addr>> {
+    let (addr, prefix) = match spec.split_once('/') {
+        Some((addr, prefix)) => (
+            addr,
+            prefix
+                .parse::<u8>()
+                .with_context(|| format!("Invalid prefix length in '{}'", spec))?,
+        ),
+        None => (spec, 32),
+    };
+    let addr: Ipv4Addr = addr
+        .parse()
+        .with_context(|| format!("Invalid IPv4 address in '{}'", spec))?;
+
+    if prefix > 32 {
+        anyhow::bail!("Invalid prefix length /{}", prefix);
+    }
+    if prefix < MIN_PREFIX_LEN {
+        anyhow::bail!(
+            "Refusing to scan /{}: the smallest allowed prefix is /{}",
+            prefix,
+            MIN_PREFIX_LEN
+        );
+    }
+
+    let mask = if prefix == 0 {
+        0
+    } else {
+        u32::MAX << (32 - prefix)
+    };
+    let network = u32::from(addr) & mask;
+    let broadcast = network | !mask;
+
+    let hosts = if prefix <= 30 {
+        (network + 1..broadcast).map(Ipv4Addr::from).collect()
+    } else {
+        (network..=broadcast).map(Ipv4Addr::from).collect()
+    };
+    Ok(hosts)
+}
+
+/// Probe every target and return the hosts that answered, sorted by address
+pub async fn scan(targets: Vec<Ipv4Addr>, opts: &ScanOptions) -> Vec<Responder> {
+    let permits = Arc::new(Semaphore::new(opts.concurrency.max(1)));
+    let mut probes = JoinSet::new();
+
+    for address in targets {
+        let permits = permits.clone();
+        let (port, timeout, register) = (opts.port, opts.timeout, opts.register);
+        probes.spawn(async move {
+            let _permit = permits.acquire_owned().await.ok()?;
+            probe(address, port, timeout, register).await
+        });
+    }
+
+    let mut responders = Vec::new();
+    while let Some(result) = probes.join_next().await {
+        if let Ok(Some(responder)) = result {
+            responders.push(responder);
+        }
+    }
+    responders.sort_by_key(|r| r.address);
+    responders
+}
+
+async fn probe(
+    address: Ipv4Addr,
+    port: u16,
+    timeout: Duration,
+    register: Option<u16>,
+) -> Option<Responder> {
+    let target = (address, port);
+    tokio::time::timeout(timeout, TcpStream::connect(target))
+        .await
+        .ok()?
+        .ok()?;
+
+    let client = PLCClient::new(address.to_string(), port);
+    let identification = tokio::time::timeout(timeout, client.read_device_identification())
+        .await
+        .ok()
+        .and_then(Result::ok);
+    let register_value = match register {
+        Some(register) => tokio::time::timeout(timeout, client.read_register(register))
+            .await
+            .ok()
+            .and_then(Result::ok),
+        None => None,
+    };
+
+    Some(Responder {
+        address,
+        port,
+        identification,
+        register_value,
+    })
+}
+
+/// Draft IndustrialPLC resources for discovered devices. They start in
+/// AlertOnly mode with the value currently in the register as the target,
+/// so applying them never writes to a device before someone reviews them.
+pub fn draft_manifests(
+    responders: &[Responder],
+    namespace: &str,
+    register: Option<u16>,
+) -> Vec<IndustrialPLC> {
+    responders
+        .iter()
+        .map(|r| {
+            let name = format!("plc-{}", r.address.to_string().replace('.', "-"));
+            let mut plc = IndustrialPLC::new(
+                &name,
+                IndustrialPLCSpec {
+                    device_address: r.address.to_string(),
+                    port: r.port,
+                    target_register: register.unwrap_or(0),
+                    target_value: r.register_value.unwrap_or(0).into(),
+                    value_names: Default::default(),
+                    target_order: 0,
+                    encoding: Default::default(),
+                    transforms: Vec::new(),
+                    poll_interval_secs: 5,
+                    critical: false,
+                    change_watch_interval_ms: 250,
+                    auto_correct: None,
+                    correction_policy: Some(CorrectionPolicy::AlertOnly),
+                    dry_run: false,
+                    min_value: None,
+                    max_value: None,
+                    tags: vec!["discovered".to_string()],
+                    maintenance_windows: Vec::new(),
+                    correction_conditions: Vec::new(),
+                    production_calendar: None,
+                    write_blocks: Vec::new(),
+                    read_snapshot: None,
+                    write_hooks: None,
+                    clock_check: None,
+                    auth_sequence: None,
+                    drift_diagnostics: Vec::new(),
+                },
+            );
+            plc.metadata.namespace = Some(namespace.to_string());
+            plc
+        })
+        .collect()
+}
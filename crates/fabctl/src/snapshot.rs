@@ -0,0 +1,73 @@
+use fabgitops_types::crd::PLCSnapshotSpec;
+use serde::Serialize;
+
+/// Largest block a snapshot may cover
+const MAX_SNAPSHOT_REGISTERS: u32 = 2000;
+
+/// A register whose value on the device differs from the snapshot
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegisterChange {
+    pub register: u16,
+    pub current: u16,
+    pub snapshot: u16,
+}
+
+/// Parse an inclusive register range like `4000-4050` (or a single register)
+/// into the first register and the number of registers
+pub fn parse_register_range(value: &str) -> Result<(u16, u16), String> {
+    let invalid = || {
+        format!(
+            "invalid register range '{}', expected e.g. 4000-4050",
+            value
+        )
+    };
+    let (start, end) = match value.split_once('-') {
+        Some((start, end)) => (start.trim(), end.trim()),
+        None => (value.trim(), value.trim()),
+    };
+    let start: u16 = start.parse().map_err(|_| invalid())?;
+    let end: u16 = end.parse().map_err(|_| invalid())?;
+    if end < start {
+        return Err(invalid());
+    }
+
+    let count = end as u32 - start as u32 + 1;
+    if count > MAX_SNAPSHOT_REGISTERS {
+        return Err(format!(
+            "register range covers {} registers, the maximum is {}",
+            count, MAX_SNAPSHOT_REGISTERS
+        ));
+    }
+    Ok((start, count as u16))
+}
+
+/// Registers where the device's current values differ from the snapshot
+pub fn diff(snapshot: &PLCSnapshotSpec, current: &[u16]) -> Vec<RegisterChange> {
+    snapshot
+        .values
+        .iter()
+        .zip(current)
+        .enumerate()
+        .filter(|(_, (snap, cur))| snap != cur)
+        .map(|(i, (snap, cur))| RegisterChange {
+            register: snapshot.start_register + i as u16,
+            current: *cur,
+            snapshot: *snap,
+        })
+        .collect()
+}
+
+/// Group changes into contiguous runs so each can be written in one request
+pub fn write_runs(changes: &[RegisterChange]) -> Vec<(u16, Vec<u16>)> {
+    let mut runs: Vec<(u16, Vec<u16>)> = Vec::new();
+    for change in changes {
+        match runs.last_mut() {
+            Some((start, values)) if *start as usize + values.len() == change.register as usize => {
+                values.push(change.snapshot)
+            }
+            _ => runs.push((change.register, vec![change.snapshot])),
+        }
+    }
+    runs
+}
@@ -0,0 +1,222 @@
+use crate::prom::Scrape;
+use fabgitops_types::crd::{HistoryEvent, IndustrialPLC, PLCPhase, ValueQuality};
+use kube::ResourceExt;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// `status.reason` values meaning the device could not be reached
+const UNREACHABLE_REASONS: [&str; 2] = ["ConnectTimeout", "Unreachable"];
+
+/// Phases in the order `fabctl summary` lists them, worst first
+const PHASES: [PLCPhase; 7] = [
+    PLCPhase::Failed,
+    PLCPhase::DriftDetected,
+    PLCPhase::Correcting,
+    PLCPhase::Connecting,
+    PLCPhase::Pending,
+    PLCPhase::Unscheduled,
+    PLCPhase::Connected,
+];
+
+/// The fleet at a glance, for the first look during an on-call shift
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FleetSummary {
+    pub namespace: String,
+    pub total: usize,
+    pub in_sync: usize,
+    /// PLC count per phase, worst phase first; phases without PLCs are left out
+    pub phases: Vec<PhaseCount>,
+    /// PLCs with the most drift events, most first
+    pub top_drifting: Vec<DriftingPlc>,
+    /// Devices whose last read failed to connect, grouped by address
+    pub unreachable: Vec<UnreachableDevice>,
+    pub corrections: CorrectionRate,
+    /// Totals from the operator's metrics, when they could be fetched
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operator: Option<OperatorTotals>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metrics_error: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct PhaseCount {
+    pub phase: PLCPhase,
+    pub count: usize,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DriftingPlc {
+    pub name: String,
+    pub drift_events: u32,
+    pub phase: PLCPhase,
+    /// How long the current drift has lasted, if the PLC is drifting
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub drift_age_secs: Option<i64>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnreachableDevice {
+    /// `address:port`
+    pub device: String,
+    pub plcs: Vec<String>,
+    pub reason: String,
+    /// When a register of the device was last read successfully (RFC3339)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_good_read_time: Option<String>,
+}
+
+/// Corrections recorded in the PLCs' status history within the window
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CorrectionRate {
+    pub window_secs: u64,
+    pub corrected: usize,
+    pub failed: usize,
+    pub drifts_detected: usize,
+}
+
+impl CorrectionRate {
+    /// Successful corrections per hour over the window
+    pub fn per_hour(&self) -> f64 {
+        self.corrected as f64 * 3600.0 / self.window_secs.max(1) as f64
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OperatorTotals {
+    pub reconciliations: f64,
+    pub reconcile_errors: f64,
+    /// Whether the operator's reconcile loop is stalled
+    pub stalled: bool,
+}
+
+impl FleetSummary {
+    pub fn new(namespace: &str, plcs: &[IndustrialPLC], window: Duration, top: usize) -> Self {
+        // PLCs without a status have not been picked up yet
+        let phase_of = |plc: &IndustrialPLC| {
+            plc.status
+                .as_ref()
+                .map_or(PLCPhase::Pending, |s| s.phase.clone())
+        };
+        let phases = PHASES
+            .iter()
+            .map(|phase| PhaseCount {
+                phase: phase.clone(),
+                count: plcs.iter().filter(|plc| phase_of(plc) == *phase).count(),
+            })
+            .filter(|phase| phase.count > 0)
+            .collect();
+
+        let mut top_drifting: Vec<DriftingPlc> = plcs
+            .iter()
+            .filter_map(|plc| {
+                let status = plc.status.as_ref()?;
+                (status.drift_events > 0).then(|| DriftingPlc {
+                    name: plc.name_any(),
+                    drift_events: status.drift_events,
+                    phase: status.phase.clone(),
+                    drift_age_secs: status.drift_age_secs,
+                })
+            })
+            .collect();
+        top_drifting.sort_by(|a, b| {
+            b.drift_events
+                .cmp(&a.drift_events)
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        top_drifting.truncate(top);
+
+        let mut devices: BTreeMap<String, UnreachableDevice> = BTreeMap::new();
+        for plc in plcs {
+            let Some(status) = &plc.status else {
+                continue;
+            };
+            let Some(reason) = status
+                .reason
+                .as_deref()
+                .filter(|r| UNREACHABLE_REASONS.contains(r))
+            else {
+                continue;
+            };
+            if status.value_quality == ValueQuality::Good {
+                continue;
+            }
+            let device = format!("{}:{}", plc.spec.device_address, plc.spec.port);
+            let entry = devices
+                .entry(device.clone())
+                .or_insert_with(|| UnreachableDevice {
+                    device,
+                    plcs: Vec::new(),
+                    reason: reason.to_string(),
+                    last_good_read_time: None,
+                });
+            entry.plcs.push(plc.name_any());
+            // RFC3339 timestamps in UTC order as strings
+            if status.last_good_read_time > entry.last_good_read_time {
+                entry.last_good_read_time = status.last_good_read_time.clone();
+            }
+        }
+
+        let cutoff = chrono::Duration::from_std(window)
+            .ok()
+            .map(|window| chrono::Utc::now() - window);
+        let mut corrections = CorrectionRate {
+            window_secs: window.as_secs(),
+            corrected: 0,
+            failed: 0,
+            drifts_detected: 0,
+        };
+        let recent = plcs
+            .iter()
+            .filter_map(|plc| plc.status.as_ref())
+            .flat_map(|status| &status.history)
+            .filter(|entry| {
+                match (
+                    cutoff,
+                    chrono::DateTime::parse_from_rfc3339(&entry.timestamp),
+                ) {
+                    (Some(cutoff), Ok(time)) => time >= cutoff,
+                    _ => false,
+                }
+            });
+        for entry in recent {
+            match entry.event {
+                HistoryEvent::Corrected => corrections.corrected += 1,
+                HistoryEvent::CorrectionFailed => corrections.failed += 1,
+                HistoryEvent::DriftDetected => corrections.drifts_detected += 1,
+                HistoryEvent::Resolved => {}
+            }
+        }
+
+        Self {
+            namespace: namespace.to_string(),
+            total: plcs.len(),
+            in_sync: plcs
+                .iter()
+                .filter(|plc| plc.status.as_ref().is_some_and(|s| s.in_sync))
+                .count(),
+            phases,
+            top_drifting,
+            unreachable: devices.into_values().collect(),
+            corrections,
+            operator: None,
+            metrics_error: None,
+        }
+    }
+
+    /// Add the operator's own totals from a scrape of its metrics
+    pub fn with_metrics(mut self, scrape: &Scrape) -> Self {
+        self.operator = Some(OperatorTotals {
+            reconciliations: scrape.total("reconciliations_total"),
+            reconcile_errors: scrape.with_label("reconciliations_total", "result", "failed")
+                + scrape.with_label("reconciliations_total", "result", "error"),
+            stalled: scrape.total("controller_stalled") > 0.0,
+        });
+        self
+    }
+}
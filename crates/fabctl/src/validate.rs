@@ -0,0 +1,324 @@
+use crate::manifests;
+use anyhow::{Context, Result};
+use fabgitops_types::crd::IndustrialPLC;
+use fabgitops_types::API_VERSION;
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+/// How serious a validation finding is
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// A single problem found in a manifest
+#[derive(Clone, Debug, Serialize)]
+pub struct Finding {
+    pub file: String,
+    /// 1-based index of the YAML document within the file
+    pub document: usize,
+    pub resource: Option<String>,
+    pub severity: Severity,
+    pub message: String,
+}
+
+/// Result of validating a set of manifest files
+#[derive(Debug, Default, Serialize)]
+pub struct ValidationReport {
+    pub files: usize,
+    pub resources: usize,
+    pub findings: Vec<Finding>,
+}
+
+impl ValidationReport {
+    pub fn errors(&self) -> usize {
+        self.count(Severity::Error)
+    }
+
+    pub fn warnings(&self) -> usize {
+        self.count(Severity::Warning)
+    }
+
+    fn count(&self, severity: Severity) -> usize {
+        self.findings
+            .iter()
+            .filter(|f| f.severity == severity)
+            .count()
+    }
+}
+
+/// Where a validated resource was declared, used for cross-file checks
+struct Declared {
+    file: String,
+    document: usize,
+    name: String,
+    target_value: u16,
+}
+
+/// Validate every IndustrialPLC manifest found under the given paths.
+/// Directories are searched recursively for `.yaml`/`.yml` files; documents
+/// of other kinds are skipped so a whole GitOps repo can be checked at once.
+pub fn validate_paths(paths: &[PathBuf]) -> Result<ValidationReport> {
+    let files = collect_manifest_files(paths)?;
+    let known_fields = manifests::crd_spec_fields(&manifests::crd());
+
+    let mut report = ValidationReport {
+        files: files.len(),
+        ..Default::default()
+    };
+    let mut findings = Vec::new();
+    let mut names: BTreeMap<(String, String), Vec<(String, usize)>> = BTreeMap::new();
+    let mut registers: BTreeMap<(String, u16, u16), Vec<Declared>> = BTreeMap::new();
+
+    for path in &files {
+        let file = path.display().to_string();
+        let content =
+            std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", file))?;
+
+        for (index, document) in serde_yaml::Deserializer::from_str(&content).enumerate() {
+            let document_no = index + 1;
+            let mut finding = |resource: Option<&str>, severity, message: String| {
+                findings.push(Finding {
+                    file: file.clone(),
+                    document: document_no,
+                    resource: resource.map(str::to_string),
+                    severity,
+                    message,
+                })
+            };
+
+            let value = match Value::deserialize(document) {
+                Ok(Value::Null) => continue,
+                Ok(value) => value,
+                Err(e) => {
+                    finding(None, Severity::Error, format!("Invalid YAML: {}", e));
+                    // The parser cannot resume after a syntax error
+                    break;
+                }
+            };
+
+            if value.get("kind").and_then(Value::as_str) != Some("IndustrialPLC") {
+                continue;
+            }
+            report.resources += 1;
+
+            let name = value
+                .get("metadata")
+                .and_then(|m| m.get("name"))
+                .and_then(Value::as_str)
+                .map(str::to_string);
+            let namespace = value
+                .get("metadata")
+                .and_then(|m| m.get("namespace"))
+                .and_then(Value::as_str)
+                .unwrap_or("default")
+                .to_string();
+
+            let api_version = value.get("apiVersion").and_then(Value::as_str);
+            if api_version != Some(API_VERSION) {
+                finding(
+                    name.as_deref(),
+                    Severity::Error,
+                    format!(
+                        "Unsupported apiVersion '{}', expected '{}'",
+                        api_version.unwrap_or(""),
+                        API_VERSION
+                    ),
+                );
+                continue;
+            }
+
+            let Some(name) = name else {
+                finding(
+                    None,
+                    Severity::Error,
+                    "metadata.name is required".to_string(),
+                );
+                continue;
+            };
+
+            let Some(spec) = value.get("spec").and_then(Value::as_mapping) else {
+                finding(Some(&name), Severity::Error, "spec is required".to_string());
+                continue;
+            };
+
+            for key in spec.keys().filter_map(Value::as_str) {
+                if !known_fields.contains(key) {
+                    finding(
+                        Some(&name),
+                        Severity::Warning,
+                        format!(
+                            "Unknown field spec.{} will be pruned by the API server",
+                            key
+                        ),
+                    );
+                }
+            }
+
+            let mut out_of_range = false;
+            for field in ["port", "targetRegister", "targetValue"] {
+                let Some(raw) = spec.get(field) else { continue };
+                // A name from enum, checked against it below
+                if field == "targetValue" && raw.is_string() {
+                    continue;
+                }
+                if raw.as_u64().is_none_or(|v| v > u16::MAX as u64) {
+                    finding(
+                        Some(&name),
+                        Severity::Error,
+                        format!(
+                            "spec.{} must be an integer in range 0-65535 (got {})",
+                            field,
+                            render(raw)
+                        ),
+                    );
+                    out_of_range = true;
+                }
+            }
+            if out_of_range {
+                continue;
+            }
+
+            let plc: IndustrialPLC = match serde_yaml::from_value(value.clone()) {
+                Ok(plc) => plc,
+                Err(e) => {
+                    finding(Some(&name), Severity::Error, format!("Schema error: {}", e));
+                    continue;
+                }
+            };
+
+            for problem in plc.spec.validate() {
+                finding(Some(&name), Severity::Error, problem);
+            }
+            for deprecation in plc.spec.deprecations() {
+                finding(Some(&name), Severity::Warning, deprecation);
+            }
+
+            names
+                .entry((namespace, name.clone()))
+                .or_default()
+                .push((file.clone(), document_no));
+            registers
+                .entry((
+                    plc.spec.device_address.clone(),
+                    plc.spec.port,
+                    plc.spec.target_register,
+                ))
+                .or_default()
+                .push(Declared {
+                    file: file.clone(),
+                    document: document_no,
+                    name,
+                    target_value: plc.spec.target(),
+                });
+        }
+    }
+
+    report.findings = findings;
+
+    for ((namespace, name), places) in names {
+        if places.len() < 2 {
+            continue;
+        }
+        let (file, document) = places[0].clone();
+        report.findings.push(Finding {
+            file,
+            document,
+            resource: Some(name.clone()),
+            severity: Severity::Error,
+            message: format!(
+                "{}/{} is declared {} times: {}",
+                namespace,
+                name,
+                places.len(),
+                places
+                    .iter()
+                    .map(|(f, d)| format!("{}#{}", f, d))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        });
+    }
+
+    for ((address, port, register), declared) in registers {
+        if declared.len() < 2 {
+            continue;
+        }
+        let values: BTreeSet<u16> = declared.iter().map(|d| d.target_value).collect();
+        let owners = declared
+            .iter()
+            .map(|d| format!("{} ({}#{})", d.name, d.file, d.document))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let (severity, message) = if values.len() > 1 {
+            (
+                Severity::Error,
+                format!(
+                    "Register {} on {}:{} has conflicting target values {:?} across: {}",
+                    register, address, port, values, owners
+                ),
+            )
+        } else {
+            (
+                Severity::Warning,
+                format!(
+                    "Register {} on {}:{} is managed by multiple resources: {}",
+                    register, address, port, owners
+                ),
+            )
+        };
+        report.findings.push(Finding {
+            file: declared[0].file.clone(),
+            document: declared[0].document,
+            resource: Some(declared[0].name.clone()),
+            severity,
+            message,
+        });
+    }
+
+    Ok(report)
+}
+
+/// Expand the given paths into a sorted list of manifest files
+pub fn collect_manifest_files(paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            walk_dir(path, &mut files)?;
+        } else if path.exists() {
+            files.push(path.clone());
+        } else {
+            anyhow::bail!("{} does not exist", path.display());
+        }
+    }
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
+fn walk_dir(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    let entries =
+        std::fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))?;
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_dir() {
+            walk_dir(&path, files)?;
+        } else if matches!(
+            path.extension().and_then(|e| e.to_str()),
+            Some("yaml") | Some("yml")
+        ) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn render(value: &Value) -> String {
+    serde_yaml::to_string(value)
+        .map(|s| s.trim().to_string())
+        .unwrap_or_default()
+}
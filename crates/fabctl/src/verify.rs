@@ -0,0 +1,238 @@
+use crate::commands::spec_client;
+use crate::validate::collect_manifest_files;
+use anyhow::{Context, Result};
+use fabgitops_types::crd::{IndustrialPLC, IndustrialPLCSpec};
+use kube::ResourceExt;
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+/// A shallow clone of the Git source of truth, removed when dropped
+pub struct Checkout {
+    dir: PathBuf,
+}
+
+impl Checkout {
+    /// Clone `repo` (a URL or local path) at `git_ref`, or the default branch
+    pub fn clone_repo(repo: &str, git_ref: Option<&str>) -> Result<Self> {
+        let dir = std::env::temp_dir().join(format!(
+            "fabctl-verify-{}-{}",
+            std::process::id(),
+            chrono::Utc::now().timestamp_millis()
+        ));
+        // Created first so the directory is cleaned up even if the clone fails
+        let checkout = Self { dir };
+
+        let mut command = Command::new("git");
+        command.args(["clone", "--quiet", "--depth", "1"]);
+        if let Some(git_ref) = git_ref {
+            // One argument, so a ref can never be read as an option
+            command.arg(format!("--branch={}", git_ref));
+        }
+        let status = command
+            .arg("--")
+            .arg(repo)
+            .arg(&checkout.dir)
+            .status()
+            .context("Failed to run git; is it installed?")?;
+        if !status.success() {
+            anyhow::bail!("git clone of {} failed", repo);
+        }
+        Ok(checkout)
+    }
+
+    /// Resolve a path inside the repository, refusing to escape it
+    pub fn join(&self, path: &Path) -> Result<PathBuf> {
+        if path.is_absolute()
+            || path
+                .components()
+                .any(|c| matches!(c, std::path::Component::ParentDir))
+        {
+            anyhow::bail!("--path must be relative to the repository root");
+        }
+        Ok(self.dir.join(path))
+    }
+}
+
+impl Drop for Checkout {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Load every IndustrialPLC declared under `paths`, keyed by (namespace, name).
+/// Manifests without a namespace land in `default_namespace`.
+pub fn load_declared(
+    paths: &[PathBuf],
+    default_namespace: &str,
+) -> Result<BTreeMap<(String, String), IndustrialPLC>> {
+    let mut declared = BTreeMap::new();
+    for path in collect_manifest_files(paths)? {
+        let file = path.display().to_string();
+        let content =
+            std::fs::read_to_string(&path).with_context(|| format!("Failed to read {}", file))?;
+
+        for (index, document) in serde_yaml::Deserializer::from_str(&content).enumerate() {
+            let value = Value::deserialize(document)
+                .with_context(|| format!("Invalid YAML in {}#{}", file, index + 1))?;
+            if value.get("kind").and_then(Value::as_str) != Some("IndustrialPLC") {
+                continue;
+            }
+            let plc: IndustrialPLC = serde_yaml::from_value(value).with_context(|| {
+                format!(
+                    "Invalid IndustrialPLC in {}#{}; run `fabctl validate` for details",
+                    file,
+                    index + 1
+                )
+            })?;
+            let namespace = plc
+                .namespace()
+                .unwrap_or_else(|| default_namespace.to_string());
+            declared.insert((namespace, plc.name_any()), plc);
+        }
+    }
+    Ok(declared)
+}
+
+/// Outcome of comparing one PLC across Git, the cluster, and the device
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum Verdict {
+    InSync,
+    /// The cluster object differs from Git (pending sync or a manual edit)
+    ClusterDrift,
+    /// The device register differs from the value in Git
+    DeviceDrift,
+    MissingInCluster,
+    /// In the cluster but not declared in Git
+    NotInGit,
+    /// The device could not be read
+    Unreachable,
+}
+
+/// One row of the three-way report
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyEntry {
+    pub namespace: String,
+    pub name: String,
+    pub git_value: Option<u16>,
+    pub cluster_value: Option<u16>,
+    pub device_value: Option<u16>,
+    /// Spec fields whose values differ between Git and the cluster
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub differing_fields: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device_error: Option<String>,
+    pub verdicts: Vec<Verdict>,
+}
+
+impl VerifyEntry {
+    pub fn in_sync(&self) -> bool {
+        self.verdicts == [Verdict::InSync]
+    }
+}
+
+/// Full report produced by `fabctl verify`
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyReport {
+    pub repo: String,
+    pub path: String,
+    pub entries: Vec<VerifyEntry>,
+}
+
+impl VerifyReport {
+    pub fn drifted(&self) -> usize {
+        self.entries.iter().filter(|e| !e.in_sync()).count()
+    }
+}
+
+/// Names of the spec fields that differ between two specs
+pub fn spec_differences(git: &IndustrialPLCSpec, cluster: &IndustrialPLCSpec) -> Vec<String> {
+    let (Ok(git), Ok(cluster)) = (serde_json::to_value(git), serde_json::to_value(cluster)) else {
+        return Vec::new();
+    };
+    let empty = serde_json::Map::new();
+    let git = git.as_object().unwrap_or(&empty);
+    let cluster = cluster.as_object().unwrap_or(&empty);
+
+    git.keys()
+        .chain(cluster.keys())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .filter(|key| {
+            let null = serde_json::Value::Null;
+            git.get(*key).unwrap_or(&null) != cluster.get(*key).unwrap_or(&null)
+        })
+        .cloned()
+        .collect()
+}
+
+/// Compare the declared, cluster, and (optionally) device state of every PLC
+pub async fn compare(
+    declared: &BTreeMap<(String, String), IndustrialPLC>,
+    cluster: &BTreeMap<(String, String), IndustrialPLC>,
+    device_timeout: Option<Duration>,
+) -> Vec<VerifyEntry> {
+    let keys: BTreeSet<_> = declared.keys().chain(cluster.keys()).collect();
+    let mut entries = Vec::new();
+
+    for key in keys {
+        let git = declared.get(key);
+        let live = cluster.get(key);
+        let differing_fields = match (git, live) {
+            (Some(git), Some(live)) => spec_differences(&git.spec, &live.spec),
+            _ => Vec::new(),
+        };
+
+        // Read the register the cluster is managing, falling back to Git's
+        let target = live.or(git).map(|plc| &plc.spec);
+        let (device_value, device_error) = match (device_timeout, target) {
+            (Some(timeout), Some(spec)) => {
+                let client = spec_client(spec);
+                match tokio::time::timeout(timeout, client.read_register(spec.target_register))
+                    .await
+                {
+                    Ok(Ok(value)) => (Some(value), None),
+                    Ok(Err(e)) => (None, Some(e.to_string())),
+                    Err(_) => (None, Some("timed out".to_string())),
+                }
+            }
+            _ => (None, None),
+        };
+
+        let mut verdicts = Vec::new();
+        match (git, live) {
+            (None, _) => verdicts.push(Verdict::NotInGit),
+            (_, None) => verdicts.push(Verdict::MissingInCluster),
+            _ if !differing_fields.is_empty() => verdicts.push(Verdict::ClusterDrift),
+            _ => {}
+        }
+        if device_error.is_some() {
+            verdicts.push(Verdict::Unreachable);
+        } else if let (Some(git), Some(value)) = (git, device_value) {
+            if value != git.spec.target() {
+                verdicts.push(Verdict::DeviceDrift);
+            }
+        }
+        if verdicts.is_empty() {
+            verdicts.push(Verdict::InSync);
+        }
+
+        entries.push(VerifyEntry {
+            namespace: key.0.clone(),
+            name: key.1.clone(),
+            git_value: git.map(|p| p.spec.target()),
+            cluster_value: live.map(|p| p.spec.target()),
+            device_value,
+            differing_fields,
+            device_error,
+            verdicts,
+        });
+    }
+
+    entries
+}
@@ -0,0 +1,56 @@
+use crate::AuthFailed;
+use anyhow::Result;
+use std::time::Duration;
+use tokio_modbus::prelude::*;
+
+/// One step of the login sequence some gateways require on every new
+/// connection before they accept writes. Values are written as given,
+/// whatever encoding the client uses.
+#[derive(Clone, PartialEq, Eq)]
+pub enum AuthStep {
+    /// Write consecutive holding registers starting at `register`
+    Write { register: u16, values: Vec<u16> },
+    /// Read `register` and stop unless it holds `value`, such as a login
+    /// status the gateway sets once it has checked the credentials
+    Expect { register: u16, value: u16 },
+    /// Wait before the next step, for gateways that process a login slowly
+    Pause(Duration),
+}
+
+/// Run `steps` in order on a freshly opened connection. Errors never
+/// include written values, which are usually credentials.
+pub(crate) async fn login(ctx: &mut client::Context, steps: &[AuthStep]) -> Result<()> {
+    for (i, step) in steps.iter().enumerate() {
+        let failed = |reason: String| AuthFailed {
+            step: i + 1,
+            reason,
+        };
+        match step {
+            AuthStep::Write { register, values } => {
+                let result = match values.as_slice() {
+                    [value] => ctx.write_single_register(*register, *value).await,
+                    values => ctx.write_multiple_registers(*register, values).await,
+                };
+                result.map_err(|e| failed(format!("writing register {}: {}", register, e)))?;
+            }
+            AuthStep::Expect { register, value } => {
+                let response = ctx
+                    .read_holding_registers(*register, 1)
+                    .await
+                    .map_err(|e| failed(format!("reading register {}: {}", register, e)))?;
+                let actual = response.first().copied();
+                if actual != Some(*value) {
+                    let reason = match actual {
+                        Some(actual) => {
+                            format!("register {} holds {}, expected {}", register, actual, value)
+                        }
+                        None => format!("empty response for register {}", register),
+                    };
+                    return Err(failed(reason).into());
+                }
+            }
+            AuthStep::Pause(delay) => tokio::time::sleep(*delay).await,
+        }
+    }
+    Ok(())
+}
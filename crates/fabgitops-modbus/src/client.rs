@@ -0,0 +1,247 @@
+use crate::auth::login;
+use crate::observer::OpenConnection;
+use crate::{
+    AuthStep, ConnectOutcome, ConnectTimeout, ConnectionObserver, Encoding, PlcProtocol, PlcSession,
+};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::borrow::Cow;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::time::{timeout, Instant};
+use tokio_modbus::prelude::*;
+
+/// Modbus function code for encapsulated interface transport (MEI)
+const FC_ENCAPSULATED_INTERFACE: u8 = 0x2B;
+
+/// MEI type for Read Device Identification
+const MEI_READ_DEVICE_ID: u8 = 0x0E;
+
+/// Identification strings reported via Read Device Identification (0x2B/0x0E)
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceIdentification {
+    pub vendor_name: Option<String>,
+    pub product_code: Option<String>,
+    pub revision: Option<String>,
+}
+
+/// How long to wait for a PLC to accept a TCP connection
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Client for communicating with Modbus TCP devices
+#[derive(Clone)]
+pub struct PLCClient {
+    address: String,
+    port: u16,
+    encoding: Encoding,
+    observer: Option<Arc<dyn ConnectionObserver>>,
+    auth: Arc<[AuthStep]>,
+}
+
+impl PLCClient {
+    pub fn new(address: impl Into<String>, port: u16) -> Self {
+        Self {
+            address: address.into(),
+            port,
+            encoding: Encoding::Binary,
+            observer: None,
+            auth: Arc::new([]),
+        }
+    }
+
+    /// Convert register values with `encoding`, so callers read and write
+    /// plain numbers whatever the device stores
+    pub fn with_encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Report every connection the client opens to `observer`
+    pub fn with_observer(mut self, observer: Arc<dyn ConnectionObserver>) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    /// Run `steps` on every connection the client opens, before any other
+    /// request
+    pub fn with_auth(mut self, steps: Vec<AuthStep>) -> Self {
+        self.auth = steps.into();
+        self
+    }
+
+    /// Resolve the address (supports both IPs and hostnames via DNS)
+    fn addr_str(&self) -> String {
+        format!("{}:{}", self.address, self.port)
+    }
+
+    /// Open a TCP connection, giving up after [`CONNECT_TIMEOUT`], and
+    /// report the attempt to the observer
+    async fn open_stream(&self) -> Result<(TcpStream, OpenConnection)> {
+        let addr = self.addr_str();
+        let started = Instant::now();
+        let result = timeout(CONNECT_TIMEOUT, TcpStream::connect(&addr)).await;
+        if let Some(observer) = &self.observer {
+            let outcome = match &result {
+                Ok(Ok(_)) => ConnectOutcome::Connected,
+                Ok(Err(_)) => ConnectOutcome::Failed,
+                Err(_) => ConnectOutcome::TimedOut,
+            };
+            observer.connect_attempt(&addr, outcome, started.elapsed());
+        }
+        let stream = result
+            .map_err(|_| ConnectTimeout { addr: addr.clone() })?
+            .context("Failed to connect to PLC")?;
+        Ok((stream, OpenConnection::new(addr, self.observer.clone())))
+    }
+
+    /// Open a Modbus TCP connection and log in if the gateway needs it; it
+    /// counts as open until the returned guard is dropped
+    async fn connect(&self) -> Result<(client::Context, OpenConnection)> {
+        let (stream, open) = self.open_stream().await?;
+        let mut ctx = tcp::attach(stream);
+        login(&mut ctx, &self.auth).await?;
+        Ok((ctx, open))
+    }
+
+    /// Open a connection for several requests in a row
+    pub async fn session(&self) -> Result<PlcSession> {
+        let (ctx, open) = self.connect().await?;
+        Ok(PlcSession::new(ctx, open, self.encoding))
+    }
+
+    /// Run `request` on a connection of its own; callers making several
+    /// requests in a row should share a `session` instead
+    async fn once<T, F>(&self, request: impl FnOnce(PlcSession) -> F) -> Result<T>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        let session = self.session().await?;
+        let result = request(session.clone()).await;
+        session.close().await;
+        result
+    }
+
+    /// Read a holding register from the PLC
+    pub async fn read_register(&self, register: u16) -> Result<u16> {
+        self.once(|s| async move { s.read_register(register).await })
+            .await
+    }
+
+    /// Write a value to a holding register
+    pub async fn write_register(&self, register: u16, value: u16) -> Result<()> {
+        self.once(|s| async move { s.write_register(register, value).await })
+            .await
+    }
+
+    /// Read a contiguous block of holding registers, split into requests
+    /// of at most 125 registers as the Modbus spec requires
+    pub async fn read_registers(&self, start: u16, count: u16) -> Result<Vec<u16>> {
+        self.once(|s| async move { s.read_registers(start, count).await })
+            .await
+    }
+
+    /// Write a contiguous block of holding registers, split into requests
+    /// of at most 123 registers as the Modbus spec requires
+    pub async fn write_registers(&self, start: u16, values: &[u16]) -> Result<()> {
+        self.once(|s| async move { s.write_registers(start, values).await })
+            .await
+    }
+
+    /// Read the basic device identification objects (vendor, product code,
+    /// revision). Many older devices do not implement this function.
+    pub async fn read_device_identification(&self) -> Result<DeviceIdentification> {
+        let (mut ctx, _open) = self.connect().await?;
+
+        // Basic identification stream (0x01), starting at object 0
+        let request = Request::Custom(
+            FC_ENCAPSULATED_INTERFACE,
+            Cow::Borrowed(&[MEI_READ_DEVICE_ID, 0x01, 0x00]),
+        );
+        let response = ctx
+            .call(request)
+            .await
+            .context("Failed to read device identification")?;
+
+        ctx.disconnect().await.ok();
+
+        match response {
+            Response::Custom(FC_ENCAPSULATED_INTERFACE, data) => parse_device_identification(&data),
+            _ => anyhow::bail!("Device identification not supported"),
+        }
+    }
+
+    /// Check if the PLC is reachable: `false` when it refuses the
+    /// connection, an error when it does not answer in time
+    pub async fn health_check(&self) -> Result<bool> {
+        match self.open_stream().await {
+            Ok(_) => Ok(true),
+            Err(e) if e.is::<ConnectTimeout>() => Err(e),
+            Err(_) => Ok(false),
+        }
+    }
+}
+
+impl PlcProtocol for PLCClient {
+    fn read_register(&self, register: u16) -> impl Future<Output = Result<u16>> + Send {
+        PLCClient::read_register(self, register)
+    }
+
+    fn write_register(&self, register: u16, value: u16) -> impl Future<Output = Result<()>> + Send {
+        PLCClient::write_register(self, register, value)
+    }
+
+    fn read_registers(
+        &self,
+        start: u16,
+        count: u16,
+    ) -> impl Future<Output = Result<Vec<u16>>> + Send {
+        PLCClient::read_registers(self, start, count)
+    }
+
+    fn write_registers(
+        &self,
+        start: u16,
+        values: &[u16],
+    ) -> impl Future<Output = Result<()>> + Send {
+        PLCClient::write_registers(self, start, values)
+    }
+
+    fn health_check(&self) -> impl Future<Output = Result<bool>> + Send {
+        PLCClient::health_check(self)
+    }
+}
+
+/// Parse a Read Device Identification response body: MEI type, read code,
+/// conformity level, more-follows, next object id, object count, then
+/// `(id, length, value)` triples.
+fn parse_device_identification(data: &[u8]) -> Result<DeviceIdentification> {
+    if data.len() < 6 || data[0] != MEI_READ_DEVICE_ID {
+        anyhow::bail!("Malformed device identification response");
+    }
+
+    let mut ident = DeviceIdentification::default();
+    let count = data[5] as usize;
+    let mut pos = 6;
+
+    for _ in 0..count {
+        let (Some(&id), Some(&len)) = (data.get(pos), data.get(pos + 1)) else {
+            break;
+        };
+        let Some(value) = data.get(pos + 2..pos + 2 + len as usize) else {
+            break;
+        };
+        let value = Some(String::from_utf8_lossy(value).trim().to_string());
+        match id {
+            0x00 => ident.vendor_name = value,
+            0x01 => ident.product_code = value,
+            0x02 => ident.revision = value,
+            _ => {}
+        }
+        pos += 2 + len as usize;
+    }
+
+    Ok(ident)
+}
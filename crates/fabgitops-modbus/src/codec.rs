@@ -0,0 +1,147 @@
+use crate::InvalidValue;
+use anyhow::Result;
+
+/// How numbers are stored in holding registers
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Encoding {
+    /// Plain unsigned 16-bit binary
+    #[default]
+    Binary,
+    /// Binary-coded decimal, four decimal digits per register (0-9999)
+    Bcd,
+}
+
+impl Encoding {
+    /// The register value that stores `value`
+    pub fn encode(self, value: u16) -> Result<u16> {
+        match self {
+            Encoding::Binary => Ok(value),
+            Encoding::Bcd => {
+                if value > 9999 {
+                    anyhow::bail!("{} does not fit in a BCD register (0-9999)", value);
+                }
+                Ok((0..4).fold(0, |bcd, digit| {
+                    bcd | ((value / 10u16.pow(digit)) % 10) << (4 * digit)
+                }))
+            }
+        }
+    }
+
+    /// The value stored in `register`
+    pub fn decode(self, register: u16) -> Result<u16> {
+        match self {
+            Encoding::Binary => Ok(register),
+            Encoding::Bcd => (0..4).rev().try_fold(0, |value, digit| {
+                let nibble = (register >> (4 * digit)) & 0xF;
+                if nibble > 9 {
+                    return Err(InvalidValue {
+                        value: register,
+                        encoding: "BCD",
+                    }
+                    .into());
+                }
+                Ok(value * 10 + nibble)
+            }),
+        }
+    }
+}
+
+/// Pack ASCII text into registers, two characters per register with the
+/// first in the high byte, NUL-padded to `length` characters
+pub fn encode_ascii(text: &str, length: u16) -> Vec<u16> {
+    let mut bytes: Vec<u8> = text.bytes().take(length as usize).collect();
+    bytes.resize(length as usize + length as usize % 2, 0);
+    bytes
+        .chunks(2)
+        .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+        .collect()
+}
+
+/// Unpack text packed by [`encode_ascii`]. Trailing NUL and space padding
+/// is dropped; bytes outside ASCII decode as U+FFFD.
+pub fn decode_ascii(registers: &[u16]) -> String {
+    let text: String = registers
+        .iter()
+        .flat_map(|register| register.to_be_bytes())
+        .map(|byte| {
+            if byte.is_ascii() {
+                char::from(byte)
+            } else {
+                char::REPLACEMENT_CHARACTER
+            }
+        })
+        .collect();
+    trim_padding(&text).to_string()
+}
+
+/// `text` without trailing NUL or space padding, as compared after decoding
+pub fn trim_padding(text: &str) -> &str {
+    text.trim_end_matches(['\0', ' '])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bcd_round_trips() {
+        for value in [0, 7, 42, 1234, 9999] {
+            let register = Encoding::Bcd.encode(value).unwrap();
+            assert_eq!(Encoding::Bcd.decode(register).unwrap(), value);
+        }
+        assert_eq!(Encoding::Bcd.encode(1234).unwrap(), 0x1234);
+        assert_eq!(Encoding::Bcd.decode(0x0950).unwrap(), 950);
+    }
+
+    #[test]
+    fn bcd_rejects_values_above_9999() {
+        assert!(Encoding::Bcd.encode(10000).is_err());
+        assert!(Encoding::Bcd.encode(u16::MAX).is_err());
+    }
+
+    #[test]
+    fn bcd_rejects_invalid_nibbles() {
+        for register in [0x000A, 0x00F0, 0x1A00, 0xF999] {
+            let error = Encoding::Bcd.decode(register).unwrap_err();
+            let invalid = error.downcast_ref::<InvalidValue>().unwrap();
+            assert_eq!(invalid.value, register);
+        }
+    }
+
+    #[test]
+    fn binary_passes_values_through() {
+        assert_eq!(Encoding::Binary.encode(u16::MAX).unwrap(), u16::MAX);
+        assert_eq!(Encoding::Binary.decode(0xABCD).unwrap(), 0xABCD);
+    }
+
+    #[test]
+    fn ascii_is_padded_to_length() {
+        assert_eq!(encode_ascii("AB", 6), vec![0x4142, 0x0000, 0x0000]);
+        // An odd length still fills whole registers
+        assert_eq!(encode_ascii("ABC", 3), vec![0x4142, 0x4300]);
+    }
+
+    #[test]
+    fn ascii_is_truncated_at_length() {
+        assert_eq!(encode_ascii("RECIPE-42", 4), vec![0x5245, 0x4349]);
+        assert_eq!(decode_ascii(&encode_ascii("RECIPE-42", 4)), "RECI");
+    }
+
+    #[test]
+    fn ascii_round_trips_without_padding() {
+        let registers = encode_ascii("LOT 7", 8);
+        assert_eq!(decode_ascii(&registers), "LOT 7");
+        assert_eq!(decode_ascii(&[0x4120, 0x2000]), "A");
+    }
+
+    #[test]
+    fn non_ascii_bytes_decode_as_replacement() {
+        assert_eq!(decode_ascii(&[0x41FF]), "A\u{FFFD}");
+    }
+
+    #[test]
+    fn trim_padding_drops_trailing_nul_and_space() {
+        assert_eq!(trim_padding("AB \0\0 "), "AB");
+        assert_eq!(trim_padding(" AB"), " AB");
+    }
+}
@@ -0,0 +1,99 @@
+use std::io;
+
+/// Modbus exception codes and the descriptions tokio-modbus reports them by
+const EXCEPTIONS: [(u8, &str); 9] = [
+    (0x01, "Illegal function"),
+    (0x02, "Illegal data address"),
+    (0x03, "Illegal data value"),
+    (0x04, "Server device failure"),
+    (0x05, "Acknowledge"),
+    (0x06, "Server device busy"),
+    (0x08, "Memory parity error"),
+    (0x0A, "Gateway path unavailable"),
+    (0x0B, "Gateway target device failed to respond"),
+];
+
+/// The PLC did not accept a TCP connection within the connect timeout
+#[derive(Debug, thiserror::Error)]
+#[error("Timed out connecting to {addr}")]
+pub struct ConnectTimeout {
+    pub addr: String,
+}
+
+/// A gateway rejected the login sequence run after connecting
+#[derive(Debug, thiserror::Error)]
+#[error("Gateway login failed at step {step}: {reason}")]
+pub struct AuthFailed {
+    /// Step of the sequence that failed, counted from 1
+    pub step: usize,
+    pub reason: String,
+}
+
+/// A register held a value its encoding cannot represent, such as a BCD
+/// digit above 9
+#[derive(Debug, thiserror::Error)]
+#[error("Register value {value:#06x} is not valid {encoding}")]
+pub struct InvalidValue {
+    pub value: u16,
+    pub encoding: &'static str,
+}
+
+/// Why a PLC request failed, so callers can react differently to a slow
+/// network and to a device that rejected the request
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlcErrorKind {
+    /// No connection within the connect timeout
+    ConnectTimeout,
+    /// The device answered with a Modbus exception response
+    Exception { code: u8 },
+    /// A register value does not match the configured encoding
+    InvalidValue,
+    /// The gateway rejected the login sequence
+    AuthFailed,
+    /// Anything else: refused or dropped connections, malformed responses
+    Other,
+}
+
+impl PlcErrorKind {
+    /// Classify an error returned by [`PLCClient`](crate::PLCClient)
+    pub fn of(error: &anyhow::Error) -> Self {
+        for cause in error.chain() {
+            if cause.is::<ConnectTimeout>() {
+                return Self::ConnectTimeout;
+            }
+            if cause.is::<InvalidValue>() {
+                return Self::InvalidValue;
+            }
+            if cause.is::<AuthFailed>() {
+                return Self::AuthFailed;
+            }
+            // tokio-modbus reports exceptions as I/O errors wrapping a
+            // private type, "Modbus function <fc>: <description>"
+            let description = cause
+                .downcast_ref::<io::Error>()
+                .and_then(|e| e.get_ref())
+                .map(|inner| inner.to_string());
+            if let Some(code) = description.as_deref().and_then(exception_code) {
+                return Self::Exception { code };
+            }
+        }
+        Self::Other
+    }
+}
+
+/// Name of a Modbus exception code
+pub fn exception_name(code: u8) -> &'static str {
+    EXCEPTIONS
+        .iter()
+        .find(|(c, _)| *c == code)
+        .map(|(_, name)| *name)
+        .unwrap_or("Unknown exception")
+}
+
+fn exception_code(description: &str) -> Option<u8> {
+    let (_, name) = description.rsplit_once(": ")?;
+    EXCEPTIONS
+        .iter()
+        .find(|(_, n)| *n == name)
+        .map(|(code, _)| *code)
+}
@@ -0,0 +1,329 @@
+use crate::{PlcErrorKind, PlcProtocol};
+use std::time::Duration;
+use tokio::time::{sleep, timeout};
+
+/// How long a guarded write or its confirming read may take
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Writes attempted before a confirmed-unchanged register is given up on
+const MAX_WRITE_ATTEMPTS: u32 = 3;
+
+/// Pause before retrying, multiplied by the attempt number
+const RETRY_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Result of [`write_register_guarded`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WriteOutcome {
+    /// The PLC acknowledged the write, or a re-read shows the new value
+    Applied,
+    /// The register is confirmed to still hold its old value; `kind`
+    /// classifies the last failure
+    NotApplied { reason: String, kind: PlcErrorKind },
+    /// The write may have been applied; re-sending it is not known to be safe
+    Unknown { reason: String },
+}
+
+/// Write a holding register without ever sending it twice blindly. When a
+/// write fails or times out, the register is re-read: the new value means
+/// it was applied, and the old value means it was not, so it is retried.
+/// Anything else, or a failed re-read, leaves the outcome unknown. A Modbus
+/// exception is the device refusing the write, so it is never retried.
+pub async fn write_register_guarded(
+    plc: &impl PlcProtocol,
+    register: u16,
+    value: u16,
+) -> WriteOutcome {
+    let previous = match timeout(REQUEST_TIMEOUT, plc.read_register(register)).await {
+        Ok(Ok(previous)) => previous,
+        Ok(Err(e)) => {
+            return WriteOutcome::NotApplied {
+                reason: format!("{:#}", e),
+                kind: PlcErrorKind::of(&e),
+            }
+        }
+        Err(_) => {
+            return WriteOutcome::NotApplied {
+                reason: "Timed out reading register before writing".to_string(),
+                kind: PlcErrorKind::Other,
+            }
+        }
+    };
+    if previous == value {
+        return WriteOutcome::Applied;
+    }
+
+    let mut attempt = 1;
+    loop {
+        let (error, kind) =
+            match timeout(REQUEST_TIMEOUT, plc.write_register(register, value)).await {
+                Ok(Ok(())) => return WriteOutcome::Applied,
+                Ok(Err(e)) => (format!("{:#}", e), PlcErrorKind::of(&e)),
+                Err(_) => (
+                    "Timed out writing register".to_string(),
+                    PlcErrorKind::Other,
+                ),
+            };
+        if let PlcErrorKind::Exception { .. } = kind {
+            return WriteOutcome::NotApplied {
+                reason: error,
+                kind,
+            };
+        }
+
+        match timeout(REQUEST_TIMEOUT, plc.read_register(register)).await {
+            Ok(Ok(current)) if current == value => return WriteOutcome::Applied,
+            Ok(Ok(current)) if current == previous => {
+                if attempt == MAX_WRITE_ATTEMPTS {
+                    return WriteOutcome::NotApplied {
+                        reason: format!("{} (after {} attempts)", error, attempt),
+                        kind,
+                    };
+                }
+            }
+            Ok(Ok(current)) => {
+                return WriteOutcome::Unknown {
+                    reason: format!(
+                        "{}; register {} changed from {} to {} meanwhile",
+                        error, register, previous, current
+                    ),
+                }
+            }
+            Ok(Err(e)) => {
+                return WriteOutcome::Unknown {
+                    reason: format!("{}; confirming read failed: {:#}", error, e),
+                }
+            }
+            Err(_) => {
+                return WriteOutcome::Unknown {
+                    reason: format!("{}; confirming read timed out", error),
+                }
+            }
+        }
+
+        sleep(RETRY_BACKOFF * attempt).await;
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use std::collections::VecDeque;
+    use std::future::Future;
+    use std::io;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Mutex;
+
+    const REGISTER: u16 = 40001;
+    const OLD: u16 = 100;
+    const NEW: u16 = 250;
+
+    /// What the fake PLC does with one write
+    enum Write {
+        /// Lost on the way; the register keeps its value and no reply comes
+        Lost,
+        /// Applied, but the reply is lost
+        Unacknowledged,
+        /// Another writer sets the register to this value; no reply comes
+        Overwritten(u16),
+        /// Refused with an Illegal data value exception
+        Exception,
+    }
+
+    /// One holding register that handles writes as scripted
+    struct FakePlc {
+        value: Mutex<u16>,
+        writes: Mutex<VecDeque<Write>>,
+        /// Every read after the first fails
+        fail_rereads: bool,
+        reads: AtomicU32,
+        writes_sent: AtomicU32,
+    }
+
+    impl FakePlc {
+        fn new(writes: impl IntoIterator<Item = Write>) -> Self {
+            Self {
+                value: Mutex::new(OLD),
+                writes: Mutex::new(writes.into_iter().collect()),
+                fail_rereads: false,
+                reads: AtomicU32::new(0),
+                writes_sent: AtomicU32::new(0),
+            }
+        }
+
+        fn writes_sent(&self) -> u32 {
+            self.writes_sent.load(Ordering::SeqCst)
+        }
+    }
+
+    /// Longer than any request timeout
+    async fn no_reply() {
+        sleep(REQUEST_TIMEOUT * 10).await;
+    }
+
+    impl PlcProtocol for FakePlc {
+        fn read_register(&self, _register: u16) -> impl Future<Output = Result<u16>> + Send {
+            let first = self.reads.fetch_add(1, Ordering::SeqCst) == 0;
+            let result = if self.fail_rereads && !first {
+                Err(anyhow::anyhow!("Connection reset by peer"))
+            } else {
+                Ok(*self.value.lock().unwrap())
+            };
+            std::future::ready(result)
+        }
+
+        fn write_register(
+            &self,
+            _register: u16,
+            value: u16,
+        ) -> impl Future<Output = Result<()>> + Send {
+            self.writes_sent.fetch_add(1, Ordering::SeqCst);
+            let write = self.writes.lock().unwrap().pop_front();
+            let mut current = self.value.lock().unwrap();
+            let reply = match write {
+                None => {
+                    *current = value;
+                    Some(Ok(()))
+                }
+                Some(Write::Lost) => None,
+                Some(Write::Unacknowledged) => {
+                    *current = value;
+                    None
+                }
+                Some(Write::Overwritten(other)) => {
+                    *current = other;
+                    None
+                }
+                Some(Write::Exception) => Some(Err(io::Error::other(
+                    "Modbus function 6: Illegal data value",
+                )
+                .into())),
+            };
+            async move {
+                match reply {
+                    Some(reply) => reply,
+                    None => {
+                        no_reply().await;
+                        Ok(())
+                    }
+                }
+            }
+        }
+
+        fn read_registers(
+            &self,
+            _start: u16,
+            _count: u16,
+        ) -> impl Future<Output = Result<Vec<u16>>> + Send {
+            std::future::ready(Err(anyhow::anyhow!("not used")))
+        }
+
+        fn write_registers(
+            &self,
+            _start: u16,
+            _values: &[u16],
+        ) -> impl Future<Output = Result<()>> + Send {
+            std::future::ready(Err(anyhow::anyhow!("not used")))
+        }
+
+        fn health_check(&self) -> impl Future<Output = Result<bool>> + Send {
+            std::future::ready(Ok(true))
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn acknowledged_write_is_applied() {
+        let plc = FakePlc::new([]);
+        assert_eq!(
+            write_register_guarded(&plc, REGISTER, NEW).await,
+            WriteOutcome::Applied
+        );
+        assert_eq!(plc.writes_sent(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn register_already_holding_the_value_is_not_written() {
+        let plc = FakePlc::new([]);
+        assert_eq!(
+            write_register_guarded(&plc, REGISTER, OLD).await,
+            WriteOutcome::Applied
+        );
+        assert_eq!(plc.writes_sent(), 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn timeout_with_value_unchanged_is_retried() {
+        let plc = FakePlc::new([Write::Lost]);
+        assert_eq!(
+            write_register_guarded(&plc, REGISTER, NEW).await,
+            WriteOutcome::Applied
+        );
+        assert_eq!(plc.writes_sent(), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn timeout_with_value_unchanged_gives_up_after_every_attempt() {
+        let plc = FakePlc::new((0..MAX_WRITE_ATTEMPTS).map(|_| Write::Lost));
+        match write_register_guarded(&plc, REGISTER, NEW).await {
+            WriteOutcome::NotApplied { reason, kind } => {
+                assert_eq!(kind, PlcErrorKind::Other);
+                assert!(reason.contains("after 3 attempts"), "{}", reason);
+            }
+            outcome => panic!("expected NotApplied, got {:?}", outcome),
+        }
+        assert_eq!(plc.writes_sent(), MAX_WRITE_ATTEMPTS);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn timeout_with_value_written_is_applied() {
+        let plc = FakePlc::new([Write::Unacknowledged]);
+        assert_eq!(
+            write_register_guarded(&plc, REGISTER, NEW).await,
+            WriteOutcome::Applied
+        );
+        assert_eq!(plc.writes_sent(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn timeout_with_value_changed_otherwise_is_unknown() {
+        let plc = FakePlc::new([Write::Overwritten(175)]);
+        match write_register_guarded(&plc, REGISTER, NEW).await {
+            WriteOutcome::Unknown { reason } => {
+                assert!(reason.contains("changed from 100 to 175"), "{}", reason)
+            }
+            outcome => panic!("expected Unknown, got {:?}", outcome),
+        }
+        assert_eq!(plc.writes_sent(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn exception_is_not_applied_without_retrying() {
+        let plc = FakePlc::new([Write::Exception]);
+        assert_eq!(
+            write_register_guarded(&plc, REGISTER, NEW).await,
+            WriteOutcome::NotApplied {
+                reason: "Modbus function 6: Illegal data value".to_string(),
+                kind: PlcErrorKind::Exception { code: 0x03 },
+            }
+        );
+        assert_eq!(plc.writes_sent(), 1);
+        // Refused outright, so not even re-read
+        assert_eq!(plc.reads.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn failed_reread_is_unknown() {
+        let plc = FakePlc {
+            fail_rereads: true,
+            ..FakePlc::new([Write::Lost])
+        };
+        match write_register_guarded(&plc, REGISTER, NEW).await {
+            WriteOutcome::Unknown { reason } => {
+                assert!(reason.contains("confirming read failed"), "{}", reason)
+            }
+            outcome => panic!("expected Unknown, got {:?}", outcome),
+        }
+        assert_eq!(plc.writes_sent(), 1);
+    }
+}
@@ -0,0 +1,19 @@
+//! Modbus TCP client shared by the operator, `fabctl` and mock-plc tests.
+
+mod auth;
+mod client;
+mod codec;
+mod error;
+mod guarded;
+mod observer;
+mod protocol;
+mod session;
+
+pub use auth::AuthStep;
+pub use client::{DeviceIdentification, PLCClient};
+pub use codec::{decode_ascii, encode_ascii, trim_padding, Encoding};
+pub use error::{exception_name, AuthFailed, ConnectTimeout, InvalidValue, PlcErrorKind};
+pub use guarded::{write_register_guarded, WriteOutcome};
+pub use observer::{ConnectOutcome, ConnectionObserver};
+pub use protocol::PlcProtocol;
+pub use session::PlcSession;
@@ -0,0 +1,54 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How a TCP connection attempt ended
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConnectOutcome {
+    Connected,
+    /// No answer within the connect timeout
+    TimedOut,
+    /// Refused, unroutable or otherwise failed
+    Failed,
+}
+
+impl ConnectOutcome {
+    /// Lowercase name, as used for metric labels
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConnectOutcome::Connected => "connected",
+            ConnectOutcome::TimedOut => "timeout",
+            ConnectOutcome::Failed => "failed",
+        }
+    }
+}
+
+/// Notified of the connections a [`PLCClient`](crate::PLCClient) opens, for
+/// example to export per-device connection metrics. `device` is the
+/// `address:port` connected to.
+pub trait ConnectionObserver: Send + Sync {
+    /// A connection attempt ended after `latency`
+    fn connect_attempt(&self, device: &str, outcome: ConnectOutcome, latency: Duration);
+
+    /// A connection reported as [`ConnectOutcome::Connected`] was closed
+    fn connection_closed(&self, device: &str);
+}
+
+/// Reports the connection closed when dropped, so early returns are counted
+pub(crate) struct OpenConnection {
+    device: String,
+    observer: Option<Arc<dyn ConnectionObserver>>,
+}
+
+impl OpenConnection {
+    pub(crate) fn new(device: String, observer: Option<Arc<dyn ConnectionObserver>>) -> Self {
+        Self { device, observer }
+    }
+}
+
+impl Drop for OpenConnection {
+    fn drop(&mut self) {
+        if let Some(observer) = &self.observer {
+            observer.connection_closed(&self.device);
+        }
+    }
+}
@@ -0,0 +1,30 @@
+use anyhow::Result;
+use std::future::Future;
+
+/// Holding register access to a PLC, independent of the wire protocol.
+/// The operator reconciles through this trait so other transports can be
+/// added beside Modbus TCP.
+pub trait PlcProtocol: Sync {
+    /// Read one holding register
+    fn read_register(&self, register: u16) -> impl Future<Output = Result<u16>> + Send;
+
+    /// Write one holding register
+    fn write_register(&self, register: u16, value: u16) -> impl Future<Output = Result<()>> + Send;
+
+    /// Read a contiguous block of holding registers
+    fn read_registers(
+        &self,
+        start: u16,
+        count: u16,
+    ) -> impl Future<Output = Result<Vec<u16>>> + Send;
+
+    /// Write a contiguous block of holding registers
+    fn write_registers(
+        &self,
+        start: u16,
+        values: &[u16],
+    ) -> impl Future<Output = Result<()>> + Send;
+
+    /// Whether the device accepts connections
+    fn health_check(&self) -> impl Future<Output = Result<bool>> + Send;
+}
@@ -0,0 +1,167 @@
+use crate::observer::OpenConnection;
+use crate::{Encoding, PlcProtocol};
+use anyhow::{Context, Result};
+use std::future::Future;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tokio_modbus::prelude::*;
+
+/// Largest block a single Read Holding Registers request may return
+const MAX_READ_REGISTERS: u16 = 125;
+
+/// Largest block a single Write Multiple Registers request may carry
+const MAX_WRITE_REGISTERS: usize = 123;
+
+/// Address `offset` registers past `start`, failing past register 65535
+fn register_at(start: u16, offset: usize) -> Result<u16> {
+    u16::try_from(offset)
+        .ok()
+        .and_then(|offset| start.checked_add(offset))
+        .with_context(|| format!("Register {}+{} is past register 65535", start, offset))
+}
+
+/// One Modbus TCP connection used for several requests in a row, such as
+/// every correction of a reconcile, opened with
+/// [`PLCClient::session`](crate::PLCClient::session). Clones share the
+/// connection and requests on it run one at a time.
+#[derive(Clone)]
+pub struct PlcSession {
+    ctx: Arc<Mutex<client::Context>>,
+    encoding: Encoding,
+    _open: Arc<OpenConnection>,
+}
+
+impl PlcSession {
+    pub(crate) fn new(ctx: client::Context, open: OpenConnection, encoding: Encoding) -> Self {
+        Self {
+            ctx: Arc::new(Mutex::new(ctx)),
+            encoding,
+            _open: Arc::new(open),
+        }
+    }
+
+    /// Convert register values with `encoding` on this handle; other clones
+    /// keep theirs
+    pub fn with_encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Read a holding register
+    pub async fn read_register(&self, register: u16) -> Result<u16> {
+        let response = self
+            .ctx
+            .lock()
+            .await
+            .read_holding_registers(register, 1)
+            .await
+            .context("Failed to read register")?;
+        let register = response
+            .first()
+            .copied()
+            .context("Empty response from PLC")?;
+        self.encoding.decode(register)
+    }
+
+    /// Write a value to a holding register
+    pub async fn write_register(&self, register: u16, value: u16) -> Result<()> {
+        let value = self.encoding.encode(value)?;
+        self.ctx
+            .lock()
+            .await
+            .write_single_register(register, value)
+            .await
+            .context("Failed to write register")?;
+        Ok(())
+    }
+
+    /// Read a contiguous block of holding registers, split into requests
+    /// of at most 125 registers as the Modbus spec requires
+    pub async fn read_registers(&self, start: u16, count: u16) -> Result<Vec<u16>> {
+        if count > 0 {
+            register_at(start, count as usize - 1)?;
+        }
+        let mut ctx = self.ctx.lock().await;
+        let mut values = Vec::with_capacity(count as usize);
+
+        while values.len() < count as usize {
+            let address = register_at(start, values.len())?;
+            let chunk = (count - values.len() as u16).min(MAX_READ_REGISTERS);
+            let response = ctx
+                .read_holding_registers(address, chunk)
+                .await
+                .with_context(|| format!("Failed to read registers {}+{}", address, chunk))?;
+            if response.len() != chunk as usize {
+                anyhow::bail!("Short response from PLC at register {}", address);
+            }
+            values.extend(response);
+        }
+
+        values
+            .into_iter()
+            .map(|register| self.encoding.decode(register))
+            .collect()
+    }
+
+    /// Write a contiguous block of holding registers, split into requests
+    /// of at most 123 registers as the Modbus spec requires
+    pub async fn write_registers(&self, start: u16, values: &[u16]) -> Result<()> {
+        let values = values
+            .iter()
+            .map(|&value| self.encoding.encode(value))
+            .collect::<Result<Vec<_>>>()?;
+        // Checked up front so a block running off the end is not half written
+        if !values.is_empty() {
+            register_at(start, values.len() - 1)?;
+        }
+        let mut ctx = self.ctx.lock().await;
+
+        for (i, chunk) in values.chunks(MAX_WRITE_REGISTERS).enumerate() {
+            let address = register_at(start, i * MAX_WRITE_REGISTERS)?;
+            ctx.write_multiple_registers(address, chunk)
+                .await
+                .with_context(|| format!("Failed to write registers at {}", address))?;
+        }
+
+        Ok(())
+    }
+
+    /// Disconnect cleanly. The connection stays open while clones exist,
+    /// and is dropped without a clean disconnect on early returns.
+    pub async fn close(self) {
+        if let Ok(ctx) = Arc::try_unwrap(self.ctx) {
+            ctx.into_inner().disconnect().await.ok();
+        }
+    }
+}
+
+impl PlcProtocol for PlcSession {
+    fn read_register(&self, register: u16) -> impl Future<Output = Result<u16>> + Send {
+        PlcSession::read_register(self, register)
+    }
+
+    fn write_register(&self, register: u16, value: u16) -> impl Future<Output = Result<()>> + Send {
+        PlcSession::write_register(self, register, value)
+    }
+
+    fn read_registers(
+        &self,
+        start: u16,
+        count: u16,
+    ) -> impl Future<Output = Result<Vec<u16>>> + Send {
+        PlcSession::read_registers(self, start, count)
+    }
+
+    fn write_registers(
+        &self,
+        start: u16,
+        values: &[u16],
+    ) -> impl Future<Output = Result<()>> + Send {
+        PlcSession::write_registers(self, start, values)
+    }
+
+    /// Always true: the session holds an open connection
+    fn health_check(&self) -> impl Future<Output = Result<bool>> + Send {
+        std::future::ready(Ok(true))
+    }
+}
@@ -0,0 +1,2071 @@
+use crate::transform::{self, Transform};
+use kube::{CustomResource, ResourceExt};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// IndustrialPLC is the Custom Resource Definition for managing
+/// industrial PLCs via GitOps principles.
+#[derive(CustomResource, Clone, Debug, Deserialize, Serialize, JsonSchema)]
+#[kube(
+    group = "fabgitops.io",
+    version = "v1",
+    kind = "IndustrialPLC",
+    plural = "industrialplcs",
+    shortname = "plc",
+    namespaced,
+    status = "IndustrialPLCStatus"
+)]
+#[serde(rename_all = "camelCase")]
+pub struct IndustrialPLCSpec {
+    /// IP address or hostname of the PLC device
+    pub device_address: String,
+
+    /// Port for Modbus TCP communication (default: 502)
+    #[serde(default = "default_port")]
+    #[schemars(range(min = 1, max = 65535))]
+    pub port: u16,
+
+    /// The Modbus register address to monitor/control
+    pub target_register: u16,
+
+    /// The desired value for the target register: a number, or a name
+    /// from `enum`
+    #[schemars(schema_with = "int_or_string")]
+    pub target_value: TargetValue,
+
+    /// Names of the target register's values, such as `0: Stopped`, usable
+    /// as `targetValue` and shown next to values in status and `fabctl`
+    #[serde(rename = "enum", default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub value_names: BTreeMap<u16, String>,
+
+    /// Position of the target register's correction among the writes of one
+    /// reconcile; lower runs first, ties keep declaration order with the
+    /// target register first (default: 0)
+    #[serde(default)]
+    pub target_order: i32,
+
+    /// How the device stores numbers in the target register and `uint16`
+    /// write blocks (default: binary). Values in the spec stay decimal.
+    #[serde(default, skip_serializing_if = "ValueEncoding::is_binary")]
+    pub encoding: ValueEncoding,
+
+    /// Pipeline turning raw values of the target register into the values
+    /// compared for drift and displayed. Drift is only reported when the
+    /// transformed actual and target values differ.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub transforms: Vec<Transform>,
+
+    /// Polling interval in seconds (default: 5)
+    #[serde(default = "default_interval")]
+    #[schemars(range(min = 1))]
+    pub poll_interval_secs: u64,
+
+    /// Also read the target register every `changeWatchIntervalMs` between
+    /// reconciles, and report a deviation from `targetValue` as soon as it
+    /// is seen
+    #[serde(default)]
+    pub critical: bool,
+
+    /// How often the target register of a `critical` PLC is read, in
+    /// milliseconds (default: 250)
+    #[serde(default = "default_change_watch_interval")]
+    pub change_watch_interval_ms: u64,
+
+    /// Deprecated: use `correctionPolicy`. Correct drift automatically when
+    /// no policy is set (default: true).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_correct: Option<bool>,
+
+    /// How drift is handled; overrides `autoCorrect` when set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correction_policy: Option<CorrectionPolicy>,
+
+    /// Never write, whatever the correction policy; the correction of the
+    /// target register that would be made is recorded in
+    /// `status.pendingCorrection` instead
+    #[serde(default)]
+    pub dry_run: bool,
+
+    /// Lowest value that may ever be written to the target register
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_value: Option<u16>,
+
+    /// Highest value that may ever be written to the target register
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_value: Option<u16>,
+
+    /// Tags for categorization
+    #[serde(default)]
+    pub tags: Vec<String>,
+
+    /// Recurring windows during which drift is reported but never corrected
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub maintenance_windows: Vec<MaintenanceWindow>,
+
+    /// Objects in the PLC's namespace that must all be in the stated state
+    /// for drift to be corrected; drift is still detected and reported
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub correction_conditions: Vec<CorrectionCondition>,
+
+    /// ProductionCalendar in the PLC's namespace deciding when drift may be
+    /// corrected
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub production_calendar: Option<String>,
+
+    /// Contiguous register blocks, such as recipe parameters, that are
+    /// drift-checked as a whole and corrected with a single write
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub write_blocks: Vec<WriteBlock>,
+
+    /// Register range published to a ConfigMap after every poll, so other
+    /// workloads can consume PLC data without Modbus access
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub read_snapshot: Option<ReadSnapshot>,
+
+    /// HTTP hooks called around every correction
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub write_hooks: Option<WriteHooks>,
+
+    /// Registers holding the PLC's real-time clock, compared to cluster time
+    /// on every poll
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clock_check: Option<ClockCheck>,
+
+    /// Login handshake the gateway requires before it accepts writes, run
+    /// on every new connection with credentials from a Secret
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth_sequence: Option<AuthSequence>,
+
+    /// Registers read in the same poll whenever drift is seen, such as an
+    /// alarm word, the mode register or the local/remote switch, and
+    /// attached to the drift event and history entry to tell an operator
+    /// override from drift caused by the PLC program
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub drift_diagnostics: Vec<DiagnosticRegister>,
+}
+
+impl IndustrialPLC {
+    /// Whether the `fabgitops.io/protected: "true"` annotation blocks deletion
+    pub fn is_protected(&self) -> bool {
+        self.annotations()
+            .get(crate::PROTECTED_ANNOTATION)
+            .is_some_and(|v| v == "true")
+    }
+
+    /// End of the hold set by `fabctl hold`, if one is in force at `now`.
+    /// A hold ending more than `MAX_HOLD_SECS` from now, as only a hand-set
+    /// annotation can, is ignored.
+    pub fn hold_until(
+        &self,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Option<chrono::DateTime<chrono::Utc>> {
+        let until = self.annotations().get(crate::HOLD_UNTIL_ANNOTATION)?;
+        let until = chrono::DateTime::parse_from_rfc3339(until).ok()?;
+        let until = until.with_timezone(&chrono::Utc);
+        let longest = chrono::Duration::seconds(crate::MAX_HOLD_SECS as i64);
+        (until > now && until <= now + longest).then_some(until)
+    }
+}
+
+/// Longest a write hook may be given to answer, in seconds
+pub const MAX_HOOK_TIMEOUT_SECS: u64 = 60;
+
+/// HTTP endpoints the operator POSTs the proposed change to before, and its
+/// outcome to after, each correction, so an external system such as an MES
+/// can gate writes
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct WriteHooks {
+    /// Called before writing; anything but a 2xx answer vetoes the write
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pre_write: Option<String>,
+
+    /// Called after writing; its answer is ignored
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_write: Option<String>,
+
+    /// Seconds to wait for a hook to answer (default: 5). A pre-write hook
+    /// that does not answer in time vetoes the write.
+    #[serde(default = "default_hook_timeout")]
+    pub timeout_secs: u64,
+}
+
+fn default_hook_timeout() -> u64 {
+    5
+}
+
+/// Most registers one Write Multiple Registers (FC16) request may carry
+pub const MAX_BLOCK_REGISTERS: usize = 123;
+
+/// A run of holding registers written together in one FC16 request, so the
+/// device never sees half of the block updated
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct WriteBlock {
+    /// Name reported in status and events, e.g. "recipe"
+    pub name: String,
+
+    /// First register of the block
+    pub start_register: u16,
+
+    /// How the registers are interpreted (default: uint16)
+    #[serde(default, skip_serializing_if = "DataType::is_uint16")]
+    pub data_type: DataType,
+
+    /// Desired values of a `uint16` block, one per register from
+    /// `startRegister` on
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub values: Vec<u16>,
+
+    /// Desired text of a `string` block
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+
+    /// Characters a `string` block holds, two per register
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub length: Option<u16>,
+
+    /// Position of this block's correction among the writes of one
+    /// reconcile, as for `targetOrder` (default: 0)
+    #[serde(default)]
+    pub order: i32,
+}
+
+/// Where a PLC keeps its real-time clock, and how far it may drift from
+/// cluster time before the `ClockSkew` condition is raised
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ClockCheck {
+    /// First register of the clock
+    pub start_register: u16,
+
+    /// How the clock is stored (default: unixSeconds)
+    #[serde(default)]
+    pub format: ClockFormat,
+
+    /// Offset of the PLC's local time from UTC, in minutes, for the
+    /// `dateTime` format
+    #[serde(default)]
+    pub utc_offset_minutes: i32,
+
+    /// Largest difference from cluster time tolerated, in seconds (default: 5)
+    #[serde(default = "default_max_skew")]
+    pub max_skew_secs: u64,
+}
+
+fn default_max_skew() -> u64 {
+    5
+}
+
+/// Most registers `driftDiagnostics` may list
+pub const MAX_DRIFT_DIAGNOSTICS: usize = 16;
+
+/// A register read for context when drift is seen
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticRegister {
+    /// Name shown with the reading, e.g. "mode"
+    pub name: String,
+
+    pub register: u16,
+
+    /// Names of the register's values, such as `1: Local`, shown next to
+    /// the value read
+    #[serde(rename = "enum", default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub value_names: BTreeMap<u16, String>,
+}
+
+/// Longest pause allowed after a login step, in milliseconds
+pub const MAX_AUTH_DELAY_MS: u64 = 10_000;
+
+/// A target value given as a number, or as a name from `enum`
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum TargetValue {
+    Number(u16),
+    Name(String),
+}
+
+impl From<u16> for TargetValue {
+    fn from(value: u16) -> Self {
+        TargetValue::Number(value)
+    }
+}
+
+impl std::fmt::Display for TargetValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TargetValue::Number(value) => write!(f, "{}", value),
+            TargetValue::Name(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// Schema of a number-or-name field, in the form structural CRD schemas
+/// allow
+fn int_or_string(_: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+    schemars::schema::SchemaObject {
+        extensions: [(
+            "x-kubernetes-int-or-string".to_string(),
+            serde_json::Value::Bool(true),
+        )]
+        .into_iter()
+        .collect(),
+        ..Default::default()
+    }
+    .into()
+}
+
+/// Vendor-specific login of a Modbus gateway: registers written, and
+/// checked, in order right after connecting. The operator reads the Secret
+/// on every reconcile, so rotated credentials apply from the next poll.
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthSequence {
+    /// Secret in the PLC's namespace holding the credentials
+    pub secret_name: String,
+
+    pub steps: Vec<AuthSequenceStep>,
+}
+
+/// One step of a login sequence. Exactly one of `value`, `secretKey` and
+/// `expect` is set.
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthSequenceStep {
+    /// Register written, or read with `expect`
+    pub register: u16,
+
+    /// Write this value
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<u16>,
+
+    /// Write the value of this key of the Secret: a number as one register,
+    /// anything else as ASCII text, two characters per register
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret_key: Option<String>,
+
+    /// Registers the text of `secretKey` fills, NUL-padded; defaults to
+    /// just enough for the text
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub length: Option<u16>,
+
+    /// Read the register and stop unless it holds this value, such as a
+    /// login status set by the gateway
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expect: Option<u16>,
+
+    /// Wait this long after the step, in milliseconds (default: 0)
+    #[serde(default)]
+    pub delay_ms: u64,
+}
+
+/// Register layout of a PLC clock
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ClockFormat {
+    /// Seconds since the Unix epoch, UTC, in two registers, high word first
+    #[default]
+    UnixSeconds,
+    /// Year, month, day, hour, minute and second, one register each, in the
+    /// PLC's local time
+    DateTime,
+}
+
+impl ClockCheck {
+    /// Registers the clock occupies
+    pub fn count(&self) -> u16 {
+        match self.format {
+            ClockFormat::UnixSeconds => 2,
+            ClockFormat::DateTime => 6,
+        }
+    }
+
+    /// The time held in the clock registers `raw`
+    pub fn decode(&self, raw: &[u16]) -> Result<chrono::DateTime<chrono::Utc>, String> {
+        if raw.len() != self.count() as usize {
+            return Err(format!(
+                "expected {} clock registers, got {}",
+                self.count(),
+                raw.len()
+            ));
+        }
+        match self.format {
+            ClockFormat::UnixSeconds => {
+                let secs = (i64::from(raw[0]) << 16) | i64::from(raw[1]);
+                chrono::DateTime::from_timestamp(secs, 0)
+                    .ok_or_else(|| format!("{} is not a valid Unix time", secs))
+            }
+            ClockFormat::DateTime => {
+                let local = chrono::NaiveDate::from_ymd_opt(
+                    i32::from(raw[0]),
+                    u32::from(raw[1]),
+                    u32::from(raw[2]),
+                )
+                .and_then(|date| {
+                    date.and_hms_opt(u32::from(raw[3]), u32::from(raw[4]), u32::from(raw[5]))
+                })
+                .ok_or_else(|| {
+                    format!(
+                        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} is not a valid date and time",
+                        raw[0], raw[1], raw[2], raw[3], raw[4], raw[5]
+                    )
+                })?;
+                let utc = local - chrono::Duration::minutes(i64::from(self.utc_offset_minutes));
+                Ok(utc.and_utc())
+            }
+        }
+    }
+}
+
+/// Most registers one Read Holding Registers (FC3) request may return
+pub const MAX_READ_REGISTERS: u16 = 125;
+
+/// A run of holding registers read on every poll and published, raw, to the
+/// ConfigMap `<plc>-registers` owned by the IndustrialPLC
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadSnapshot {
+    /// First register of the range
+    pub start_register: u16,
+
+    /// Number of registers to read (1-125)
+    pub count: u16,
+}
+
+impl ReadSnapshot {
+    /// Name of the ConfigMap holding the snapshot of PLC `plc_name`
+    pub fn config_map_name(plc_name: &str) -> String {
+        format!("{}-registers", plc_name)
+    }
+}
+
+/// How the registers of a write block are interpreted
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DataType {
+    /// One unsigned 16-bit value per register
+    #[default]
+    Uint16,
+    /// ASCII text of up to `length` characters, two per register with the
+    /// first in the high byte, NUL-padded on write
+    String,
+}
+
+impl DataType {
+    pub fn is_uint16(&self) -> bool {
+        *self == DataType::Uint16
+    }
+}
+
+impl WriteBlock {
+    /// Number of registers the block spans
+    pub fn register_count(&self) -> usize {
+        match self.data_type {
+            DataType::Uint16 => self.values.len(),
+            DataType::String => (self.length.unwrap_or_default() as usize).div_ceil(2),
+        }
+    }
+
+    /// Registers covered by the block
+    pub fn registers(&self) -> std::ops::Range<u32> {
+        let start = u32::from(self.start_register);
+        start..start + self.register_count() as u32
+    }
+}
+
+/// A recurring time-of-day window, e.g. 22:00-02:00 on weekends.
+/// A window whose end is before its start runs past midnight into the next day.
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct MaintenanceWindow {
+    /// Opening time of day, "HH:MM"
+    pub start: String,
+
+    /// Closing time of day, "HH:MM"
+    pub end: String,
+
+    /// Days the window opens on; every day when empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub days: Vec<Weekday>,
+
+    /// Offset of the site's local time from UTC, in minutes
+    #[serde(default)]
+    pub utc_offset_minutes: i32,
+}
+
+/// Cluster state a correction waits for, such as a Deployment with ready
+/// replicas or a ConfigMap flag
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CorrectionCondition {
+    pub kind: ConditionKind,
+
+    /// Name of the object in the PLC's namespace
+    pub name: String,
+
+    /// Deployment: fewest ready replicas that meet the condition (default: 1)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_ready_replicas: Option<i32>,
+
+    /// ConfigMap: key whose value is compared with `value`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+
+    /// ConfigMap: value `key` must hold
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+}
+
+/// Kind of object a correction condition refers to
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, JsonSchema, PartialEq, Eq)]
+pub enum ConditionKind {
+    Deployment,
+    ConfigMap,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Weekday {
+    Mon,
+    Tue,
+    Wed,
+    Thu,
+    Fri,
+    Sat,
+    Sun,
+}
+
+impl From<chrono::Weekday> for Weekday {
+    fn from(day: chrono::Weekday) -> Self {
+        match day {
+            chrono::Weekday::Mon => Weekday::Mon,
+            chrono::Weekday::Tue => Weekday::Tue,
+            chrono::Weekday::Wed => Weekday::Wed,
+            chrono::Weekday::Thu => Weekday::Thu,
+            chrono::Weekday::Fri => Weekday::Fri,
+            chrono::Weekday::Sat => Weekday::Sat,
+            chrono::Weekday::Sun => Weekday::Sun,
+        }
+    }
+}
+
+/// Parse "HH:MM" into minutes after midnight
+pub fn parse_time_of_day(value: &str) -> Option<u32> {
+    let (h, m) = value.trim().split_once(':')?;
+    let (h, m): (u32, u32) = (h.parse().ok()?, m.parse().ok()?);
+    (h < 24 && m < 60).then_some(h * 60 + m)
+}
+
+impl MaintenanceWindow {
+    /// Whether the window is open at `now`. Malformed windows are never open.
+    pub fn is_open(&self, now: chrono::DateTime<chrono::Utc>) -> bool {
+        window_open(
+            &self.start,
+            &self.end,
+            &self.days,
+            self.utc_offset_minutes,
+            now,
+        )
+    }
+}
+
+/// Whether a recurring "HH:MM" window on `days` (every day when empty) is
+/// open at `now`. One ending before it starts runs past midnight.
+fn window_open(
+    start: &str,
+    end: &str,
+    days: &[Weekday],
+    utc_offset_minutes: i32,
+    now: chrono::DateTime<chrono::Utc>,
+) -> bool {
+    use chrono::{Datelike, Timelike};
+
+    let (Some(start), Some(end)) = (parse_time_of_day(start), parse_time_of_day(end)) else {
+        return false;
+    };
+    let local = now + chrono::Duration::minutes(utc_offset_minutes as i64);
+    let minute = local.hour() * 60 + local.minute();
+    let opens_on = |day: chrono::Weekday| days.is_empty() || days.contains(&day.into());
+
+    if start <= end {
+        opens_on(local.weekday()) && minute >= start && minute < end
+    } else {
+        (opens_on(local.weekday()) && minute >= start)
+            || (opens_on(local.weekday().pred()) && minute < end)
+    }
+}
+
+/// How a device stores numbers in its registers
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ValueEncoding {
+    /// Plain unsigned 16-bit binary
+    #[default]
+    Binary,
+    /// Binary-coded decimal, four digits per register, so values are 0-9999
+    Bcd,
+}
+
+impl ValueEncoding {
+    pub fn is_binary(&self) -> bool {
+        *self == ValueEncoding::Binary
+    }
+
+    /// Largest value the encoding can store
+    pub fn max_value(&self) -> u16 {
+        match self {
+            ValueEncoding::Binary => u16::MAX,
+            ValueEncoding::Bcd => 9999,
+        }
+    }
+}
+
+/// How the operator responds to detected drift
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, JsonSchema, PartialEq, Eq)]
+pub enum CorrectionPolicy {
+    /// Write the desired value back immediately
+    Auto,
+    /// Only report drift, never write
+    AlertOnly,
+    /// Open a CorrectionRequest and write only once it is approved
+    ManualApproval,
+}
+
+/// Fastest polling interval the operator accepts, in seconds
+pub const MIN_POLL_INTERVAL_SECS: u64 = 1;
+
+/// Shortest `changeWatchIntervalMs` accepted
+pub const MIN_CHANGE_WATCH_INTERVAL_MS: u64 = 100;
+
+impl IndustrialPLCSpec {
+    /// The policy in effect, falling back to the `autoCorrect` flag
+    pub fn effective_correction_policy(&self) -> CorrectionPolicy {
+        match self.correction_policy {
+            Some(policy) => policy,
+            None if self.auto_correct.unwrap_or(true) => CorrectionPolicy::Auto,
+            None => CorrectionPolicy::AlertOnly,
+        }
+    }
+
+    /// Deprecated fields in use, each with how to migrate away from it.
+    /// They keep working until removed from the CRD.
+    pub fn deprecations(&self) -> Vec<String> {
+        let mut deprecations = Vec::new();
+        match (self.auto_correct, self.correction_policy) {
+            (None, _) => {}
+            (Some(_), Some(_)) => deprecations.push(
+                "autoCorrect is deprecated and ignored since correctionPolicy is set; remove it"
+                    .to_string(),
+            ),
+            (Some(auto_correct), None) => deprecations.push(format!(
+                "autoCorrect is deprecated; replace autoCorrect: {} with correctionPolicy: {:?}",
+                auto_correct,
+                self.effective_correction_policy()
+            )),
+        }
+        deprecations
+    }
+
+    /// Whether drift is only reported, so corrections are previewed in
+    /// `status.pendingCorrection` rather than written
+    pub fn previews_corrections(&self) -> bool {
+        self.dry_run || self.effective_correction_policy() == CorrectionPolicy::AlertOnly
+    }
+
+    /// `targetValue` as a number. A name missing from `enum`, which
+    /// `validate` reports, gives 0.
+    pub fn target(&self) -> u16 {
+        match &self.target_value {
+            TargetValue::Number(value) => *value,
+            TargetValue::Name(name) => self.resolve(name).unwrap_or_default(),
+        }
+    }
+
+    /// A value given by its name in `enum` or as a number
+    pub fn resolve(&self, value: &str) -> Option<u16> {
+        self.value_of(value).or_else(|| value.parse().ok())
+    }
+
+    /// The value `enum` gives `name`
+    pub fn value_of(&self, name: &str) -> Option<u16> {
+        self.value_names
+            .iter()
+            .find(|(_, n)| n.as_str() == name)
+            .map(|(&value, _)| value)
+    }
+
+    /// The name `enum` gives `value`
+    pub fn value_name(&self, value: u16) -> Option<&str> {
+        self.value_names.get(&value).map(String::as_str)
+    }
+
+    /// `value` for display, with its name from `enum` when it has one,
+    /// e.g. `Running (1)`
+    pub fn describe_value(&self, value: u16) -> String {
+        match self.value_name(value) {
+            Some(name) => format!("{} ({})", name, value),
+            None => value.to_string(),
+        }
+    }
+
+    /// A raw value of the target register for display: named by `enum`,
+    /// otherwise as `transforms` display it
+    pub fn display_value(&self, raw: u16) -> String {
+        match self.value_name(raw) {
+            Some(_) => self.describe_value(raw),
+            None => transform::display(&self.transforms, raw),
+        }
+    }
+
+    /// Whether a raw value of the target register matches `targetValue`
+    /// once both have gone through `transforms`
+    pub fn matches_target(&self, raw: u16) -> bool {
+        let target = self.target();
+        raw == target
+            || transform::apply(&self.transforms, raw) == transform::apply(&self.transforms, target)
+    }
+
+    /// Check a value against the `minValue`/`maxValue` safety bounds
+    pub fn check_bounds(&self, value: u16) -> Result<(), String> {
+        match (self.min_value, self.max_value) {
+            (Some(min), _) if value < min => Err(format!(
+                "value {} is below the safety minimum {}",
+                value, min
+            )),
+            (_, Some(max)) if value > max => Err(format!(
+                "value {} is above the safety maximum {}",
+                value, max
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// The maintenance window open at `now`, if any
+    pub fn active_maintenance_window(
+        &self,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Option<&MaintenanceWindow> {
+        self.maintenance_windows.iter().find(|w| w.is_open(now))
+    }
+
+    /// Check the semantic rules the OpenAPI schema cannot express.
+    /// Returns one message per violated rule; empty means valid.
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        if self.device_address.trim().is_empty() {
+            problems.push("deviceAddress must not be empty".to_string());
+        } else if self.device_address.chars().any(char::is_whitespace) {
+            problems.push(format!(
+                "deviceAddress '{}' must not contain whitespace",
+                self.device_address
+            ));
+        }
+
+        if self.port == 0 {
+            problems.push("port must be in range 1-65535".to_string());
+        }
+
+        if self.poll_interval_secs < MIN_POLL_INTERVAL_SECS {
+            problems.push(format!(
+                "pollIntervalSecs must be at least {} (got {})",
+                MIN_POLL_INTERVAL_SECS, self.poll_interval_secs
+            ));
+        }
+
+        if self.critical && self.change_watch_interval_ms < MIN_CHANGE_WATCH_INTERVAL_MS {
+            problems.push(format!(
+                "changeWatchIntervalMs must be at least {} (got {})",
+                MIN_CHANGE_WATCH_INTERVAL_MS, self.change_watch_interval_ms
+            ));
+        }
+
+        if let (Some(min), Some(max)) = (self.min_value, self.max_value) {
+            if min > max {
+                problems.push(format!(
+                    "minValue {} must not be greater than maxValue {}",
+                    min, max
+                ));
+            }
+        }
+        let mut names = std::collections::BTreeSet::new();
+        for name in self.value_names.values() {
+            if name.trim().is_empty() {
+                problems.push("enum names must not be empty".to_string());
+            } else if name.parse::<u16>().is_ok() {
+                problems.push(format!("enum name '{}' must not be a number", name));
+            } else if !names.insert(name.as_str()) {
+                problems.push(format!("enum name '{}' is used twice", name));
+            }
+        }
+        if let TargetValue::Name(name) = &self.target_value {
+            if self.resolve(name).is_none() {
+                problems.push(format!("targetValue '{}' is not a name in enum", name));
+            }
+        }
+
+        if let Err(e) = self.check_bounds(self.target()) {
+            problems.push(format!("targetValue: {}", e));
+        }
+
+        let max = self.encoding.max_value();
+        let encoded = [
+            ("targetValue", Some(self.target())),
+            ("minValue", self.min_value),
+            ("maxValue", self.max_value),
+        ];
+        for (field, value) in encoded {
+            if let Some(value) = value.filter(|&v| v > max) {
+                problems.push(format!(
+                    "{} {} does not fit the {:?} encoding (0-{})",
+                    field, value, self.encoding, max
+                ));
+            }
+        }
+
+        problems.extend(transform::validate(&self.transforms));
+
+        let mut names = std::collections::BTreeSet::new();
+        for (i, block) in self.write_blocks.iter().enumerate() {
+            if block.name.trim().is_empty() {
+                problems.push(format!("writeBlocks[{}] name must not be empty", i));
+            } else if !names.insert(block.name.as_str()) {
+                problems.push(format!("writeBlocks name '{}' is used twice", block.name));
+            }
+            match block.data_type {
+                DataType::Uint16 => {
+                    if block.values.is_empty() || block.values.len() > MAX_BLOCK_REGISTERS {
+                        problems.push(format!(
+                            "writeBlocks '{}' must hold 1-{} values (got {})",
+                            block.name,
+                            MAX_BLOCK_REGISTERS,
+                            block.values.len()
+                        ));
+                    }
+                    if let Some(value) = block.values.iter().find(|&&v| v > max) {
+                        problems.push(format!(
+                            "writeBlocks '{}' value {} does not fit the {:?} encoding (0-{})",
+                            block.name, value, self.encoding, max
+                        ));
+                    }
+                    if block.text.is_some() || block.length.is_some() {
+                        problems.push(format!(
+                            "writeBlocks '{}' sets text or length but its dataType is uint16",
+                            block.name
+                        ));
+                    }
+                }
+                DataType::String => {
+                    let length = block.length.unwrap_or_default();
+                    if length == 0 || length as usize > 2 * MAX_BLOCK_REGISTERS {
+                        problems.push(format!(
+                            "writeBlocks '{}' string length must be 1-{} (got {})",
+                            block.name,
+                            2 * MAX_BLOCK_REGISTERS,
+                            length
+                        ));
+                    }
+                    match &block.text {
+                        None => problems.push(format!(
+                            "writeBlocks '{}' of dataType string needs text",
+                            block.name
+                        )),
+                        Some(text) if !text.is_ascii() => problems
+                            .push(format!("writeBlocks '{}' text must be ASCII", block.name)),
+                        Some(text) if text.len() > length as usize => problems.push(format!(
+                            "writeBlocks '{}' text is longer than {} characters",
+                            block.name, length
+                        )),
+                        Some(_) => {}
+                    }
+                    if !block.values.is_empty() {
+                        problems.push(format!(
+                            "writeBlocks '{}' sets values but its dataType is string",
+                            block.name
+                        ));
+                    }
+                }
+            }
+            if block.registers().end > 0x1_0000 {
+                problems.push(format!(
+                    "writeBlocks '{}' runs past register 65535",
+                    block.name
+                ));
+            }
+            if block.registers().contains(&u32::from(self.target_register)) {
+                problems.push(format!(
+                    "writeBlocks '{}' must not include targetRegister {}",
+                    block.name, self.target_register
+                ));
+            }
+            for other in &self.write_blocks[..i] {
+                let (a, b) = (block.registers(), other.registers());
+                if a.start < b.end && b.start < a.end {
+                    problems.push(format!(
+                        "writeBlocks '{}' overlaps '{}'",
+                        block.name, other.name
+                    ));
+                }
+            }
+        }
+
+        if let Some(snapshot) = &self.read_snapshot {
+            if snapshot.count == 0 || snapshot.count > MAX_READ_REGISTERS {
+                problems.push(format!(
+                    "readSnapshot count must be 1-{} (got {})",
+                    MAX_READ_REGISTERS, snapshot.count
+                ));
+            }
+            if u32::from(snapshot.start_register) + u32::from(snapshot.count) > 0x1_0000 {
+                problems.push("readSnapshot runs past register 65535".to_string());
+            }
+        }
+
+        if let Some(clock) = &self.clock_check {
+            if u32::from(clock.start_register) + u32::from(clock.count()) > 0x1_0000 {
+                problems.push("clockCheck runs past register 65535".to_string());
+            }
+            if clock.utc_offset_minutes.abs() > 14 * 60 {
+                problems.push(format!(
+                    "clockCheck utcOffsetMinutes {} is out of range",
+                    clock.utc_offset_minutes
+                ));
+            }
+            if clock.max_skew_secs == 0 {
+                problems.push("clockCheck maxSkewSecs must be at least 1".to_string());
+            }
+        }
+
+        if let Some(hooks) = &self.write_hooks {
+            for (field, url) in [
+                ("preWrite", &hooks.pre_write),
+                ("postWrite", &hooks.post_write),
+            ] {
+                if let Some(url) = url.as_deref() {
+                    if !url.starts_with("http://") && !url.starts_with("https://") {
+                        problems.push(format!(
+                            "writeHooks {} '{}' must be an http:// or https:// URL",
+                            field, url
+                        ));
+                    }
+                }
+            }
+            if hooks.timeout_secs == 0 || hooks.timeout_secs > MAX_HOOK_TIMEOUT_SECS {
+                problems.push(format!(
+                    "writeHooks timeoutSecs must be 1-{} (got {})",
+                    MAX_HOOK_TIMEOUT_SECS, hooks.timeout_secs
+                ));
+            }
+        }
+
+        if let Some(auth) = &self.auth_sequence {
+            if auth.secret_name.trim().is_empty() {
+                problems.push("authSequence secretName must not be empty".to_string());
+            }
+            if auth.steps.is_empty() {
+                problems.push("authSequence needs at least one step".to_string());
+            }
+            for (i, step) in auth.steps.iter().enumerate() {
+                let actions = [
+                    step.value.is_some(),
+                    step.secret_key.is_some(),
+                    step.expect.is_some(),
+                ];
+                if actions.iter().filter(|&&set| set).count() != 1 {
+                    problems.push(format!(
+                        "authSequence step {} needs exactly one of value, secretKey and expect",
+                        i + 1
+                    ));
+                }
+                if step.length.is_some() && step.secret_key.is_none() {
+                    problems.push(format!(
+                        "authSequence step {} sets length without secretKey",
+                        i + 1
+                    ));
+                }
+                if step.length == Some(0) {
+                    problems.push(format!(
+                        "authSequence step {} length must be at least 1",
+                        i + 1
+                    ));
+                }
+                if step.delay_ms > MAX_AUTH_DELAY_MS {
+                    problems.push(format!(
+                        "authSequence step {} delayMs must be at most {} (got {})",
+                        i + 1,
+                        MAX_AUTH_DELAY_MS,
+                        step.delay_ms
+                    ));
+                }
+            }
+        }
+
+        if self.drift_diagnostics.len() > MAX_DRIFT_DIAGNOSTICS {
+            problems.push(format!(
+                "driftDiagnostics must list at most {} registers (got {})",
+                MAX_DRIFT_DIAGNOSTICS,
+                self.drift_diagnostics.len()
+            ));
+        }
+        let mut names = std::collections::BTreeSet::new();
+        for (i, diagnostic) in self.drift_diagnostics.iter().enumerate() {
+            if diagnostic.name.trim().is_empty() {
+                problems.push(format!("driftDiagnostics[{}] name must not be empty", i));
+            } else if !names.insert(diagnostic.name.as_str()) {
+                problems.push(format!(
+                    "driftDiagnostics name '{}' is used twice",
+                    diagnostic.name
+                ));
+            }
+        }
+
+        for condition in &self.correction_conditions {
+            let label = format!(
+                "correctionConditions {:?} '{}'",
+                condition.kind, condition.name
+            );
+            if condition.name.trim().is_empty() {
+                problems.push("correctionConditions name must not be empty".to_string());
+            }
+            match condition.kind {
+                ConditionKind::Deployment => {
+                    if condition.key.is_some() || condition.value.is_some() {
+                        problems.push(format!("{} must not set key or value", label));
+                    }
+                    if condition.min_ready_replicas.is_some_and(|min| min < 0) {
+                        problems.push(format!("{} minReadyReplicas must not be negative", label));
+                    }
+                }
+                ConditionKind::ConfigMap => {
+                    if condition.key.is_none() || condition.value.is_none() {
+                        problems.push(format!("{} needs key and value", label));
+                    }
+                    if condition.min_ready_replicas.is_some() {
+                        problems.push(format!("{} must not set minReadyReplicas", label));
+                    }
+                }
+            }
+        }
+
+        for window in &self.maintenance_windows {
+            for time in [&window.start, &window.end] {
+                if parse_time_of_day(time).is_none() {
+                    problems.push(format!(
+                        "maintenanceWindows time '{}' must be HH:MM (00:00-23:59)",
+                        time
+                    ));
+                }
+            }
+            if window.utc_offset_minutes.abs() > 14 * 60 {
+                problems.push(format!(
+                    "maintenanceWindows utcOffsetMinutes {} is out of range",
+                    window.utc_offset_minutes
+                ));
+            }
+        }
+
+        problems
+    }
+}
+
+fn default_port() -> u16 {
+    502
+}
+
+fn default_interval() -> u64 {
+    5
+}
+
+fn default_change_watch_interval() -> u64 {
+    250
+}
+
+/// Status subresource for IndustrialPLC
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct IndustrialPLCStatus {
+    /// Current state of the PLC
+    pub phase: PLCPhase,
+
+    /// Last time the status was updated
+    pub last_update: Option<String>,
+
+    /// Current value read from the PLC
+    pub current_value: Option<u16>,
+
+    /// Name `enum` gives `currentValue`, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub current_value_name: Option<String>,
+
+    /// Whether `currentValue` reflects the latest poll or is left over from
+    /// an earlier successful read
+    #[serde(default)]
+    pub value_quality: ValueQuality,
+
+    /// When the target register was last read successfully (RFC3339)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_good_read_time: Option<String>,
+
+    /// Whether the PLC matches desired state
+    pub in_sync: bool,
+
+    /// Number of drift events detected
+    pub drift_events: u32,
+
+    /// Number of successful corrections
+    pub corrections_applied: u32,
+
+    /// Last error message (if any)
+    pub last_error: Option<String>,
+
+    /// Machine-readable cause of the last failure, such as `ConnectTimeout`
+    /// or `ProtocolException`, cleared once the PLC is back in sync
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+
+    /// Human-readable message
+    pub message: String,
+
+    /// When the current drift was first detected (unset while in sync)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub drift_since: Option<String>,
+
+    /// Seconds the current drift had lasted at the last status update
+    /// (unset while in sync)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub drift_age_secs: Option<i64>,
+
+    /// Most recent drift and correction events, oldest first
+    #[serde(default)]
+    pub history: Vec<HistoryEntry>,
+
+    /// State of each write block as of the last poll
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub blocks: Vec<BlockStatus>,
+
+    /// ConfigMap the `readSnapshot` range is published to, removed again
+    /// once `readSnapshot` is dropped from the spec. Serialized as null when
+    /// unset so the status merge patch clears it.
+    #[serde(default)]
+    pub snapshot_config_map: Option<String>,
+
+    /// Write to the target register that auto-correct would have made after
+    /// the last successful read, under `dryRun` or the AlertOnly policy. Serialized
+    /// as null when unset so the status merge patch clears it.
+    #[serde(default)]
+    pub pending_correction: Option<PendingCorrection>,
+
+    /// Each write of the last correction plan, in the order they ran
+    #[serde(default)]
+    pub correction_steps: Vec<CorrectionStep>,
+
+    /// PLC clock minus cluster time in seconds, as of the last `clockCheck`.
+    /// Serialized as null when unset so the status merge patch clears it.
+    #[serde(default)]
+    pub clock_skew_secs: Option<i64>,
+
+    /// When the hold set by `fabctl hold` ends (RFC3339), while one is in
+    /// force. Serialized as null when unset so the status merge patch
+    /// clears it.
+    #[serde(default)]
+    pub hold_until: Option<String>,
+
+    /// Conditions such as `ClockSkew`
+    #[serde(default)]
+    pub conditions: Vec<PLCCondition>,
+}
+
+/// Condition types of an IndustrialPLC
+pub const CLOCK_SKEW: &str = "ClockSkew";
+pub const DEPRECATED_SPEC: &str = "DeprecatedSpec";
+
+/// One aspect of a PLC's state
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PLCCondition {
+    /// `ClockSkew` or `DeprecatedSpec`
+    #[serde(rename = "type")]
+    pub type_: String,
+
+    /// "True", "False" or "Unknown"
+    pub status: String,
+
+    /// Machine-readable cause, e.g. `SkewExceeded`
+    pub reason: String,
+
+    /// Human-readable message
+    pub message: String,
+
+    /// When `status` last changed (RFC3339)
+    pub last_transition_time: String,
+}
+
+/// A correction the operator held back from writing
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingCorrection {
+    /// Register that would be written
+    pub register: u16,
+
+    /// Value read from the register
+    pub old_value: u16,
+
+    /// Value that would be written
+    pub new_value: u16,
+
+    /// When the write would have been made (RFC3339)
+    pub timestamp: String,
+}
+
+/// One write of a correction plan
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CorrectionStep {
+    /// `targetRegister`, or the name of the write block
+    pub name: String,
+
+    /// First register written
+    pub register: u16,
+
+    /// `targetOrder` or the block's `order`
+    pub order: i32,
+
+    pub state: StepState,
+
+    /// Why the step failed or was skipped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+
+    /// When the step ran (RFC3339); unset for skipped steps
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<String>,
+}
+
+/// Outcome of a correction step
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, JsonSchema, PartialEq, Eq)]
+pub enum StepState {
+    /// The write was applied
+    Applied,
+    /// The write failed, was vetoed, or was refused
+    Failed,
+    /// Not attempted because an earlier step failed
+    Skipped,
+}
+
+/// Drift state of one write block
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockStatus {
+    pub name: String,
+
+    /// Whether every register of the block holds its desired value
+    pub in_sync: bool,
+
+    /// Offsets from `startRegister` that differ from the desired values
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub drifted_offsets: Vec<u16>,
+
+    /// Values read at each of `driftedOffsets` of a `uint16` block
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub drifted_values: Vec<u16>,
+
+    /// Text read from a `string` block
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub actual_text: Option<String>,
+}
+
+/// Maximum number of entries kept in `status.history`
+pub const MAX_HISTORY_ENTRIES: usize = 50;
+
+/// A drift or correction event recorded in the status history
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEntry {
+    /// When the event happened (RFC3339)
+    pub timestamp: String,
+
+    /// What happened
+    pub event: HistoryEvent,
+
+    /// Desired register value at the time
+    pub desired: u16,
+
+    /// Value read from the PLC, if known
+    pub actual: Option<u16>,
+
+    /// Seconds between drift detection and this event, for events ending a drift
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_secs: Option<i64>,
+
+    /// Extra detail, such as the error for a failed correction
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+
+    /// `driftDiagnostics` registers as read when the drift was detected
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub diagnostics: Vec<DiagnosticReading>,
+}
+
+/// The value of a `driftDiagnostics` register at one poll
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticReading {
+    pub name: String,
+
+    pub register: u16,
+
+    /// Value read; unset when the read failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<u16>,
+
+    /// Name `enum` gives the value, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value_name: Option<String>,
+}
+
+impl std::fmt::Display for DiagnosticReading {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.value, &self.value_name) {
+            (Some(value), Some(name)) => write!(f, "{}={} ({})", self.name, value, name),
+            (Some(value), None) => write!(f, "{}={}", self.name, value),
+            (None, _) => write!(f, "{}=unreadable", self.name),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub enum HistoryEvent {
+    /// The PLC value moved away from the desired value
+    DriftDetected,
+    /// The operator wrote the desired value back
+    Corrected,
+    /// The operator tried and failed to write the desired value
+    CorrectionFailed,
+    /// The drift went away without a correction by the operator
+    Resolved,
+}
+
+/// Freshness of `status.currentValue`
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, JsonSchema, Default, PartialEq, Eq)]
+#[serde(rename_all = "PascalCase")]
+pub enum ValueQuality {
+    /// The register has never been read
+    #[default]
+    Unknown,
+    /// Read on the latest poll
+    Good,
+    /// The latest read failed; the value is the last one read successfully
+    Stale,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, Default, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub enum PLCPhase {
+    #[default]
+    Pending,
+    Connecting,
+    Connected,
+    DriftDetected,
+    Correcting,
+    Failed,
+    /// Beyond the operator's cap on managed PLCs, so not polled
+    Unscheduled,
+}
+
+impl IndustrialPLCStatus {
+    pub fn new() -> Self {
+        Self {
+            phase: PLCPhase::Pending,
+            last_update: None,
+            current_value: None,
+            current_value_name: None,
+            value_quality: ValueQuality::Unknown,
+            last_good_read_time: None,
+            in_sync: false,
+            drift_events: 0,
+            corrections_applied: 0,
+            last_error: None,
+            reason: None,
+            message: "Initializing...".to_string(),
+            drift_since: None,
+            drift_age_secs: None,
+            history: Vec::new(),
+            blocks: Vec::new(),
+            snapshot_config_map: None,
+            pending_correction: None,
+            correction_steps: Vec::new(),
+            clock_skew_secs: None,
+            hold_until: None,
+            conditions: Vec::new(),
+        }
+    }
+
+    /// Record the target register reading `value`, in sync with `desired`
+    pub fn set_synced(&mut self, desired: u16, value: u16) {
+        if self.drift_since.is_some() {
+            self.record(HistoryEvent::Resolved, desired, Some(value), None);
+        }
+        self.phase = PLCPhase::Connected;
+        self.current_value = Some(value);
+        self.in_sync = true;
+        self.last_error = None;
+        self.reason = None;
+        self.message = format!("PLC in sync. Current value: {}", value);
+        self.update_timestamp();
+    }
+
+    /// Record drift of the target register; `diagnostics` go on the
+    /// history entry of a drift that starts now
+    pub fn set_drift(&mut self, desired: u16, actual: u16, diagnostics: Vec<DiagnosticReading>) {
+        self.phase = PLCPhase::DriftDetected;
+        self.current_value = Some(actual);
+        self.in_sync = false;
+        self.message = format!("DRIFT DETECTED! Desired: {}, Actual: {}", desired, actual);
+        self.update_timestamp();
+        // Count a drift once when it starts, not on every poll while it lasts
+        if self.drift_since.is_none() {
+            self.drift_events += 1;
+            self.drift_since = self.last_update.clone();
+            self.drift_age_secs = Some(0);
+            self.record(HistoryEvent::DriftDetected, desired, Some(actual), None);
+            if let Some(entry) = self.history.last_mut() {
+                entry.diagnostics = diagnostics;
+            }
+        }
+    }
+
+    /// Accept the value read as the desired one while a hold is in force.
+    /// A drift in progress ends, as the held value no longer drifts.
+    pub fn set_held(
+        &mut self,
+        spec: &IndustrialPLCSpec,
+        value: u16,
+        until: chrono::DateTime<chrono::Utc>,
+    ) {
+        self.set_synced(spec.target(), value);
+        self.hold_until = Some(until.to_rfc3339());
+        self.message = format!(
+            "Held at actual value {} until {}",
+            spec.display_value(value),
+            until.format("%Y-%m-%d %H:%M:%S UTC")
+        );
+    }
+
+    /// Note a successful read of the target register
+    pub fn set_value_read(&mut self) {
+        self.value_quality = ValueQuality::Good;
+        self.last_good_read_time = Some(chrono::Utc::now().to_rfc3339());
+    }
+
+    /// Note that the target register could not be read. The last value is
+    /// kept but marked stale.
+    pub fn set_value_stale(&mut self) {
+        if self.current_value.is_some() {
+            self.value_quality = ValueQuality::Stale;
+        }
+    }
+
+    /// Seconds since the last successful read, if there ever was one
+    pub fn value_age_secs(&self, now: chrono::DateTime<chrono::Utc>) -> Option<i64> {
+        let read = self.last_good_read_time.as_deref()?;
+        let read = chrono::DateTime::parse_from_rfc3339(read).ok()?;
+        Some((now - read.with_timezone(&chrono::Utc)).num_seconds())
+    }
+
+    /// Seconds since the current drift was detected, if there is one
+    pub fn drift_secs(&self, now: chrono::DateTime<chrono::Utc>) -> Option<i64> {
+        let since = self.drift_since.as_deref()?;
+        let since = chrono::DateTime::parse_from_rfc3339(since).ok()?;
+        Some((now - since.with_timezone(&chrono::Utc)).num_seconds())
+    }
+
+    /// Record the write auto-correct would make instead of making it
+    pub fn set_pending_correction(&mut self, register: u16, old_value: u16, new_value: u16) {
+        self.pending_correction = Some(PendingCorrection {
+            register,
+            old_value,
+            new_value,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        });
+    }
+
+    pub fn set_correcting(&mut self) {
+        self.phase = PLCPhase::Correcting;
+        self.message = "Applying correction...".to_string();
+        self.update_timestamp();
+    }
+
+    pub fn set_corrected(&mut self, value: u16) {
+        self.corrections_applied += 1;
+        self.record(HistoryEvent::Corrected, value, Some(value), None);
+        self.set_synced(value, value);
+    }
+
+    pub fn set_correction_failed(&mut self, desired: u16, error: String) {
+        self.record(
+            HistoryEvent::CorrectionFailed,
+            desired,
+            self.current_value,
+            Some(error.clone()),
+        );
+        self.set_error(format!("Failed to correct: {}", error));
+    }
+
+    /// Record the state of the write blocks. A drifted block takes the PLC
+    /// out of sync even when the target register matches.
+    pub fn set_blocks(&mut self, blocks: Vec<BlockStatus>) {
+        let drifted: Vec<&str> = blocks
+            .iter()
+            .filter(|b| !b.in_sync)
+            .map(|b| b.name.as_str())
+            .collect();
+        if !drifted.is_empty() && self.phase != PLCPhase::Failed {
+            self.phase = PLCPhase::DriftDetected;
+            self.in_sync = false;
+            self.message = format!("{} Block drift: {}", self.message, drifted.join(", "));
+            self.update_timestamp();
+        }
+        self.blocks = blocks;
+    }
+
+    /// Set the condition `type_`, keeping its transition time while its
+    /// status stays the same. Returns whether the status changed.
+    pub fn set_condition(
+        &mut self,
+        type_: &str,
+        status: Option<bool>,
+        reason: &str,
+        message: String,
+    ) -> bool {
+        let status = match status {
+            Some(true) => "True",
+            Some(false) => "False",
+            None => "Unknown",
+        };
+        let now = chrono::Utc::now().to_rfc3339();
+        match self.conditions.iter_mut().find(|c| c.type_ == type_) {
+            Some(condition) => {
+                let changed = condition.status != status;
+                if changed {
+                    condition.status = status.to_string();
+                    condition.last_transition_time = now;
+                }
+                condition.reason = reason.to_string();
+                condition.message = message;
+                changed
+            }
+            None => {
+                self.conditions.push(PLCCondition {
+                    type_: type_.to_string(),
+                    status: status.to_string(),
+                    reason: reason.to_string(),
+                    message,
+                    last_transition_time: now,
+                });
+                true
+            }
+        }
+    }
+
+    pub fn remove_condition(&mut self, type_: &str) {
+        self.conditions.retain(|c| c.type_ != type_);
+    }
+
+    /// Stop being managed, as a PLC beyond the operator's cap. The last
+    /// value read stays, marked stale.
+    pub fn set_unscheduled(&mut self, reason: &str, message: String) {
+        self.phase = PLCPhase::Unscheduled;
+        self.in_sync = false;
+        self.set_value_stale();
+        self.reason = Some(reason.to_string());
+        self.message = message;
+        self.update_timestamp();
+    }
+
+    /// Enter the Failed phase with a machine-readable reason
+    pub fn set_failed(&mut self, reason: &str, error: String) {
+        self.set_error(error);
+        self.reason = Some(reason.to_string());
+    }
+
+    pub fn set_error(&mut self, error: String) {
+        self.phase = PLCPhase::Failed;
+        self.last_error = Some(error.clone());
+        self.message = error;
+        self.update_timestamp();
+    }
+
+    fn update_timestamp(&mut self) {
+        let now = chrono::Utc::now();
+        self.last_update = Some(now.to_rfc3339());
+        self.drift_age_secs = self.drift_secs(now);
+    }
+
+    /// Append a history entry, dropping the oldest beyond the ring size.
+    /// Events other than drift detection and failed corrections close the
+    /// current drift and carry its duration.
+    fn record(
+        &mut self,
+        event: HistoryEvent,
+        desired: u16,
+        actual: Option<u16>,
+        message: Option<String>,
+    ) {
+        let now = chrono::Utc::now();
+        let closes_drift = matches!(event, HistoryEvent::Corrected | HistoryEvent::Resolved);
+        let duration_secs = if closes_drift {
+            self.drift_since
+                .take()
+                .and_then(|since| chrono::DateTime::parse_from_rfc3339(&since).ok())
+                .map(|since| (now - since.with_timezone(&chrono::Utc)).num_seconds())
+        } else {
+            None
+        };
+
+        self.history.push(HistoryEntry {
+            timestamp: now.to_rfc3339(),
+            event,
+            desired,
+            actual,
+            duration_secs,
+            message,
+            diagnostics: Vec::new(),
+        });
+        if self.history.len() > MAX_HISTORY_ENTRIES {
+            let excess = self.history.len() - MAX_HISTORY_ENTRIES;
+            self.history.drain(..excess);
+        }
+    }
+}
+
+/// Label linking a CorrectionRequest or snapshot ConfigMap to its IndustrialPLC
+pub const PLC_NAME_LABEL: &str = "fabgitops.io/plc";
+
+/// CorrectionRequest asks a human to approve a write the operator wants to
+/// make to a PLC running under the ManualApproval correction policy.
+#[derive(CustomResource, Clone, Debug, Deserialize, Serialize, JsonSchema)]
+#[kube(
+    group = "fabgitops.io",
+    version = "v1",
+    kind = "CorrectionRequest",
+    plural = "correctionrequests",
+    shortname = "plccr",
+    namespaced,
+    status = "CorrectionRequestStatus"
+)]
+#[serde(rename_all = "camelCase")]
+pub struct CorrectionRequestSpec {
+    /// Name of the IndustrialPLC to correct
+    pub plc_name: String,
+
+    /// Register the correction writes
+    pub register: u16,
+
+    /// Value read from the PLC when the drift was detected
+    pub current_value: u16,
+
+    /// Value the correction will write
+    pub desired_value: u16,
+
+    /// Start of the drift this request belongs to (from `status.driftSince`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub drift_since: Option<String>,
+
+    /// Decision recorded by an approver
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approval: Option<ApprovalDecision>,
+}
+
+/// An approver's verdict on a CorrectionRequest
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ApprovalDecision {
+    pub decision: Decision,
+
+    /// User who made the decision
+    pub user: String,
+
+    /// When the decision was made (RFC3339)
+    pub timestamp: String,
+
+    /// Why the request was approved or rejected
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, JsonSchema, PartialEq, Eq)]
+pub enum Decision {
+    Approved,
+    Rejected,
+}
+
+/// Status subresource for CorrectionRequest
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CorrectionRequestStatus {
+    pub state: CorrectionRequestState,
+
+    /// Last time the status was updated
+    pub last_update: Option<String>,
+
+    /// Human-readable message
+    pub message: String,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, JsonSchema, Default, PartialEq, Eq)]
+pub enum CorrectionRequestState {
+    /// Waiting for a decision
+    #[default]
+    Pending,
+    /// Approved and written to the PLC
+    Applied,
+    /// Rejected by an approver
+    Rejected,
+    /// Approved but the write failed
+    Failed,
+    /// The drift ended or changed before a decision was made
+    Superseded,
+}
+
+impl CorrectionRequest {
+    /// Current state, treating a missing status as Pending
+    pub fn state(&self) -> CorrectionRequestState {
+        self.status.as_ref().map(|s| s.state).unwrap_or_default()
+    }
+}
+
+impl CorrectionRequestStatus {
+    pub fn new(state: CorrectionRequestState, message: impl Into<String>) -> Self {
+        Self {
+            state,
+            last_update: Some(chrono::Utc::now().to_rfc3339()),
+            message: message.into(),
+        }
+    }
+}
+
+/// PLCGroup collects IndustrialPLCs, by label selector and/or by name, so a
+/// line or cell can be inspected and synced as one unit.
+#[derive(CustomResource, Clone, Debug, Deserialize, Serialize, JsonSchema)]
+#[kube(
+    group = "fabgitops.io",
+    version = "v1",
+    kind = "PLCGroup",
+    plural = "plcgroups",
+    shortname = "plcg",
+    namespaced
+)]
+#[serde(rename_all = "camelCase")]
+pub struct PLCGroupSpec {
+    /// What the group represents (e.g. "Etch line 3")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// Labels an IndustrialPLC must carry to be a member
+    #[serde(default)]
+    pub selector: BTreeMap<String, String>,
+
+    /// IndustrialPLCs included by name, in addition to the selector
+    #[serde(default)]
+    pub members: Vec<String>,
+}
+
+impl PLCGroup {
+    /// Whether a PLC in the same namespace belongs to this group
+    pub fn contains(&self, plc: &IndustrialPLC) -> bool {
+        if self.spec.members.iter().any(|m| *m == plc.name_any()) {
+            return true;
+        }
+        !self.spec.selector.is_empty()
+            && self
+                .spec
+                .selector
+                .iter()
+                .all(|(k, v)| plc.labels().get(k) == Some(v))
+    }
+}
+
+/// ProductionCalendar says when a line runs, so the IndustrialPLCs that
+/// reference it are corrected only during a shift, and never on a holiday
+/// or in planned downtime. One calendar serves every PLC of a line.
+#[derive(CustomResource, Clone, Debug, Deserialize, Serialize, JsonSchema)]
+#[kube(
+    group = "fabgitops.io",
+    version = "v1",
+    kind = "ProductionCalendar",
+    plural = "productioncalendars",
+    shortname = "pcal",
+    namespaced
+)]
+#[serde(rename_all = "camelCase")]
+pub struct ProductionCalendarSpec {
+    /// Offset of the site's local time from UTC, in minutes; shifts and
+    /// holidays are in local time
+    #[serde(default)]
+    pub utc_offset_minutes: i32,
+
+    /// Shifts during which corrections are allowed; any time when empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub shifts: Vec<Shift>,
+
+    /// Local dates, "YYYY-MM-DD", on which nothing is corrected
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub holidays: Vec<String>,
+
+    /// Planned downtime during which nothing is corrected
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub downtime: Vec<Downtime>,
+}
+
+/// A recurring shift, e.g. 22:00-06:00 on weekdays. A shift whose end is
+/// before its start runs past midnight into the next day.
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Shift {
+    pub name: String,
+
+    /// Starting time of day, "HH:MM"
+    pub start: String,
+
+    /// Ending time of day, "HH:MM"
+    pub end: String,
+
+    /// Days the shift starts on; every day when empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub days: Vec<Weekday>,
+}
+
+/// A one-off stretch of planned downtime
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Downtime {
+    /// Start (RFC3339)
+    pub start: String,
+
+    /// End (RFC3339)
+    pub end: String,
+
+    /// Why, shown while the downtime holds corrections back
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl ProductionCalendarSpec {
+    /// Why corrections are not allowed at `now`, or none when they are
+    pub fn closed(&self, now: chrono::DateTime<chrono::Utc>) -> Option<String> {
+        for downtime in &self.downtime {
+            let (Ok(start), Ok(end)) = (
+                chrono::DateTime::parse_from_rfc3339(&downtime.start),
+                chrono::DateTime::parse_from_rfc3339(&downtime.end),
+            ) else {
+                continue;
+            };
+            if start <= now && now < end {
+                return Some(match &downtime.reason {
+                    Some(reason) => format!("planned downtime until {} ({})", downtime.end, reason),
+                    None => format!("planned downtime until {}", downtime.end),
+                });
+            }
+        }
+
+        let today = (now + chrono::Duration::minutes(self.utc_offset_minutes as i64))
+            .format("%Y-%m-%d")
+            .to_string();
+        if self.holidays.iter().any(|day| day.trim() == today) {
+            return Some(format!("holiday {}", today));
+        }
+
+        let on_shift = self.shifts.is_empty()
+            || self.shifts.iter().any(|shift| {
+                window_open(
+                    &shift.start,
+                    &shift.end,
+                    &shift.days,
+                    self.utc_offset_minutes,
+                    now,
+                )
+            });
+        (!on_shift).then(|| "outside every shift".to_string())
+    }
+
+    /// Check the rules the OpenAPI schema cannot express; empty means valid
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.utc_offset_minutes.abs() > 14 * 60 {
+            problems.push(format!(
+                "utcOffsetMinutes {} is out of range",
+                self.utc_offset_minutes
+            ));
+        }
+        for shift in &self.shifts {
+            for time in [&shift.start, &shift.end] {
+                if parse_time_of_day(time).is_none() {
+                    problems.push(format!(
+                        "shift '{}' time '{}' must be HH:MM (00:00-23:59)",
+                        shift.name, time
+                    ));
+                }
+            }
+        }
+        for day in &self.holidays {
+            if chrono::NaiveDate::parse_from_str(day.trim(), "%Y-%m-%d").is_err() {
+                problems.push(format!("holiday '{}' must be YYYY-MM-DD", day));
+            }
+        }
+        for downtime in &self.downtime {
+            match (
+                chrono::DateTime::parse_from_rfc3339(&downtime.start),
+                chrono::DateTime::parse_from_rfc3339(&downtime.end),
+            ) {
+                (Ok(start), Ok(end)) if start >= end => problems.push(format!(
+                    "downtime starting {} must end after it starts",
+                    downtime.start
+                )),
+                (Ok(_), Ok(_)) => {}
+                _ => problems.push(format!(
+                    "downtime {} - {} must be RFC3339 timestamps",
+                    downtime.start, downtime.end
+                )),
+            }
+        }
+        problems
+    }
+}
+
+/// PLCSnapshot records the values of a block of holding registers so they
+/// can be compared against or restored to the device later.
+#[derive(CustomResource, Clone, Debug, Deserialize, Serialize, JsonSchema)]
+#[kube(
+    group = "fabgitops.io",
+    version = "v1",
+    kind = "PLCSnapshot",
+    plural = "plcsnapshots",
+    shortname = "plcsnap",
+    namespaced
+)]
+#[serde(rename_all = "camelCase")]
+pub struct PLCSnapshotSpec {
+    /// IndustrialPLC the snapshot was taken from
+    pub plc_name: String,
+
+    /// Device address at the time of the snapshot
+    pub device_address: String,
+
+    /// Modbus TCP port at the time of the snapshot
+    pub port: u16,
+
+    /// First register in the block
+    pub start_register: u16,
+
+    /// Register values, starting at `startRegister`
+    pub values: Vec<u16>,
+
+    /// When the values were read (RFC3339)
+    pub taken_at: String,
+}
+
+impl PLCSnapshotSpec {
+    /// Last register in the block, capped at register 65535
+    pub fn end_register(&self) -> u16 {
+        let end = self.start_register as usize + self.values.len().saturating_sub(1);
+        end.min(u16::MAX as usize) as u16
+    }
+
+    /// Check the rules the OpenAPI schema cannot express; empty means valid
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.start_register as usize + self.values.len() > 1 << 16 {
+            problems.push(format!(
+                "{} values from register {} run past register 65535",
+                self.values.len(),
+                self.start_register
+            ));
+        }
+        problems
+    }
+}
+
+/// FabGitOpsOperator records the state of the operator itself, such as the
+/// outcome of its RBAC self-check. The operator writes a single one, named
+/// after itself, in its own namespace.
+#[derive(CustomResource, Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
+#[kube(
+    group = "fabgitops.io",
+    version = "v1",
+    kind = "FabGitOpsOperator",
+    plural = "fabgitopsoperators",
+    shortname = "fgo",
+    namespaced,
+    status = "FabGitOpsOperatorStatus"
+)]
+#[serde(rename_all = "camelCase")]
+pub struct FabGitOpsOperatorSpec {}
+
+/// Status subresource for FabGitOpsOperator
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct FabGitOpsOperatorStatus {
+    /// Version of the operator that wrote the status
+    pub version: String,
+
+    /// Pod that wrote the status
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+
+    /// When the ServiceAccount's permissions were last checked (RFC3339)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub permissions_checked_at: Option<String>,
+
+    /// Permissions the operator needs but was not granted, e.g.
+    /// "create events.k8s.io/events"
+    #[serde(default)]
+    pub missing_permissions: Vec<String>,
+
+    /// Permissions granted beyond what the operator needs
+    #[serde(default)]
+    pub excessive_permissions: Vec<String>,
+
+    #[serde(default)]
+    pub conditions: Vec<OperatorCondition>,
+}
+
+/// Condition types of a FabGitOpsOperator
+pub const PERMISSIONS_SUFFICIENT: &str = "PermissionsSufficient";
+pub const PERMISSIONS_MINIMAL: &str = "PermissionsMinimal";
+
+/// One aspect of the operator's state
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct OperatorCondition {
+    /// `PermissionsSufficient` or `PermissionsMinimal`
+    #[serde(rename = "type")]
+    pub type_: String,
+
+    /// "True", "False" or "Unknown"
+    pub status: String,
+
+    /// Machine-readable cause, e.g. `MissingPermissions`
+    pub reason: String,
+
+    /// Human-readable message
+    pub message: String,
+}
+
+impl OperatorCondition {
+    pub fn new(type_: &str, status: Option<bool>, reason: &str, message: String) -> Self {
+        let status = match status {
+            Some(true) => "True",
+            Some(false) => "False",
+            None => "Unknown",
+        };
+        Self {
+            type_: type_.to_string(),
+            status: status.to_string(),
+            reason: reason.to_string(),
+            message,
+        }
+    }
+}
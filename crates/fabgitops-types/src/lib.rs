@@ -0,0 +1,45 @@
+//! Custom resources and shared constants of FabGitOps, used by the operator,
+//! `fabctl` and tests without pulling in either binary's dependencies.
+
+pub mod crd;
+pub mod rbac;
+pub mod transform;
+
+/// API group of every FabGitOps custom resource
+pub const API_GROUP: &str = "fabgitops.io";
+
+/// `apiVersion` of FabGitOps manifests
+pub const API_VERSION: &str = "fabgitops.io/v1";
+
+/// Annotation set by `fabctl sync` to request an immediate reconciliation
+pub const LAST_SYNC_REQUEST_ANNOTATION: &str = "fabgitops.io/last-sync-request";
+
+/// Annotation set by `fabctl sync --force` to correct drift without waiting
+pub const FORCE_SYNC_ANNOTATION: &str = "fabgitops.io/force-sync";
+
+/// Annotation set by `fabctl hold` to when the hold ends (RFC3339). Until
+/// then the operator takes the value it reads as the desired value.
+pub const HOLD_UNTIL_ANNOTATION: &str = "fabgitops.io/hold-until";
+
+/// Annotation set by `fabctl hold --reason` to why the value is held
+pub const HOLD_REASON_ANNOTATION: &str = "fabgitops.io/hold-reason";
+
+/// Longest `fabctl hold` accepts, so a forgotten hold cannot outlive a shift
+pub const MAX_HOLD_SECS: u64 = 12 * 3600;
+
+/// Annotation that, set to "true", blocks deletion of an IndustrialPLC until
+/// it is removed
+pub const PROTECTED_ANNOTATION: &str = "fabgitops.io/protected";
+
+/// Finalizer the operator keeps on protected IndustrialPLCs
+pub const PROTECTION_FINALIZER: &str = "fabgitops.io/deletion-protection";
+
+/// Annotation the operator sets to when it last corrected drift (RFC3339)
+pub const LAST_CORRECTED_AT_ANNOTATION: &str = "fabgitops.io/last-corrected-at";
+
+/// Annotation the operator sets to what its last correction overwrote,
+/// e.g. `register 4001: 1200`
+pub const LAST_CORRECTED_FROM_ANNOTATION: &str = "fabgitops.io/last-corrected-from";
+
+/// Annotation the operator sets to the instance that last corrected drift
+pub const LAST_CORRECTED_BY_ANNOTATION: &str = "fabgitops.io/last-corrected-by-operator-instance";
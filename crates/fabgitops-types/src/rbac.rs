@@ -0,0 +1,157 @@
+use std::fmt;
+
+/// Verbs granted on resources of one API group, in the shape of an RBAC
+/// policy rule
+#[derive(Clone, Copy, Debug)]
+pub struct Rule {
+    /// API group; empty for the core group
+    pub api_group: &'static str,
+    /// Resources, with subresources written as `resource/subresource`
+    pub resources: &'static [&'static str],
+    pub verbs: &'static [&'static str],
+}
+
+/// Everything the operator does against the Kubernetes API, and nothing
+/// more. The operator checks its ServiceAccount against these rules on
+/// startup; the ClusterRoles in the Helm chart, `k8s/rbac.yaml` and
+/// `fabctl install` grant exactly these.
+pub const OPERATOR_RULES: &[Rule] = &[
+    // Watch PLCs and report their state; keep the deletion protection finalizer
+    Rule {
+        api_group: crate::API_GROUP,
+        resources: &["industrialplcs"],
+        verbs: &["list", "watch", "patch"],
+    },
+    // Open CorrectionRequests under the ManualApproval policy
+    Rule {
+        api_group: crate::API_GROUP,
+        resources: &["correctionrequests"],
+        verbs: &["list", "watch", "create"],
+    },
+    // Record the operator's own state
+    Rule {
+        api_group: crate::API_GROUP,
+        resources: &["fabgitopsoperators"],
+        verbs: &["create", "patch"],
+    },
+    Rule {
+        api_group: crate::API_GROUP,
+        resources: &[
+            "industrialplcs/status",
+            "correctionrequests/status",
+            "fabgitopsoperators/status",
+        ],
+        verbs: &["patch"],
+    },
+    // Re-read a PLC's status after a conflicting update
+    Rule {
+        api_group: crate::API_GROUP,
+        resources: &["industrialplcs/status"],
+        verbs: &["get"],
+    },
+    // Owner references that block deletion of their PLC
+    Rule {
+        api_group: crate::API_GROUP,
+        resources: &["industrialplcs/finalizers"],
+        verbs: &["update"],
+    },
+    Rule {
+        api_group: "events.k8s.io",
+        resources: &["events"],
+        verbs: &["create"],
+    },
+    // Publish read snapshots
+    Rule {
+        api_group: "",
+        resources: &["configmaps"],
+        verbs: &["create", "patch", "delete"],
+    },
+    // Calendars deciding when corrections are allowed
+    Rule {
+        api_group: crate::API_GROUP,
+        resources: &["productioncalendars"],
+        verbs: &["get", "list", "watch"],
+    },
+    // Objects gating corrections through correctionConditions
+    Rule {
+        api_group: "apps",
+        resources: &["deployments"],
+        verbs: &["get", "list", "watch"],
+    },
+    Rule {
+        api_group: "",
+        resources: &["configmaps"],
+        verbs: &["get", "list", "watch"],
+    },
+    // Read gateway credentials for authSequence
+    Rule {
+        api_group: "",
+        resources: &["secrets"],
+        verbs: &["get"],
+    },
+    // Shard membership of operator replicas
+    Rule {
+        api_group: "coordination.k8s.io",
+        resources: &["leases"],
+        verbs: &["list", "create", "patch", "delete"],
+    },
+    // The permission self-check itself
+    Rule {
+        api_group: "authorization.k8s.io",
+        resources: &["selfsubjectaccessreviews", "selfsubjectrulesreviews"],
+        verbs: &["create"],
+    },
+];
+
+/// A single verb on a single resource
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Permission {
+    pub api_group: String,
+    /// Resource, with a subresource written as `resource/subresource`
+    pub resource: String,
+    pub verb: String,
+}
+
+impl Permission {
+    pub fn new(api_group: &str, resource: &str, verb: &str) -> Self {
+        Self {
+            api_group: api_group.to_string(),
+            resource: resource.to_string(),
+            verb: verb.to_string(),
+        }
+    }
+
+    /// The resource and subresource parts of `resource`
+    pub fn split_resource(&self) -> (&str, Option<&str>) {
+        match self.resource.split_once('/') {
+            Some((resource, subresource)) => (resource, Some(subresource)),
+            None => (&self.resource, None),
+        }
+    }
+}
+
+/// `verb resource` for the core group, `verb group/resource` otherwise,
+/// e.g. `create events.k8s.io/events`
+impl fmt::Display for Permission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.api_group.is_empty() {
+            write!(f, "{} {}", self.verb, self.resource)
+        } else {
+            write!(f, "{} {}/{}", self.verb, self.api_group, self.resource)
+        }
+    }
+}
+
+/// Every permission granted by `rules`, one per verb and resource
+pub fn permissions(rules: &[Rule]) -> Vec<Permission> {
+    rules
+        .iter()
+        .flat_map(|rule| {
+            rule.resources.iter().flat_map(move |resource| {
+                rule.verbs
+                    .iter()
+                    .map(move |verb| Permission::new(rule.api_group, resource, verb))
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,180 @@
+//! Transformation pipeline between raw register values and the values the
+//! operator compares and `fabctl` displays, such as a temperature in tenths
+//! of a degree scaled to degrees.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// One stage of a `transforms` pipeline. Only the fields of its `type` may
+/// be set.
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Transform {
+    /// What the stage does
+    #[serde(rename = "type")]
+    pub kind: TransformKind,
+
+    /// `scale`: multiplier applied before `offset` (default: 1)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub factor: Option<f64>,
+
+    /// `scale`: added after multiplying (default: 0)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offset: Option<f64>,
+
+    /// `clamp`: lower limit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+
+    /// `clamp`: upper limit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+
+    /// `lookup`: values mapped to other values; anything not listed passes
+    /// through unchanged
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub table: Vec<LookupEntry>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TransformKind {
+    /// `value * factor + offset`
+    Scale,
+    /// Limit the value to `min`..=`max`
+    Clamp,
+    /// Replace values listed in `table`
+    Lookup,
+}
+
+/// A `lookup` table row
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, JsonSchema, PartialEq)]
+pub struct LookupEntry {
+    pub from: f64,
+    pub to: f64,
+}
+
+impl Transform {
+    fn apply(&self, value: f64) -> f64 {
+        match self.kind {
+            TransformKind::Scale => {
+                value * self.factor.unwrap_or(1.0) + self.offset.unwrap_or_default()
+            }
+            TransformKind::Clamp => {
+                let value = self.min.map_or(value, |min| value.max(min));
+                self.max.map_or(value, |max| value.min(max))
+            }
+            TransformKind::Lookup => self
+                .table
+                .iter()
+                .find(|entry| entry.from == value)
+                .map_or(value, |entry| entry.to),
+        }
+    }
+}
+
+impl fmt::Display for Transform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            TransformKind::Scale => write!(
+                f,
+                "scale x{} {:+}",
+                self.factor.unwrap_or(1.0),
+                self.offset.unwrap_or_default()
+            ),
+            TransformKind::Clamp => {
+                let bound = |b: Option<f64>| b.map(|b| b.to_string()).unwrap_or_default();
+                write!(f, "clamp {}..{}", bound(self.min), bound(self.max))
+            }
+            TransformKind::Lookup => {
+                let entries: Vec<String> = self
+                    .table
+                    .iter()
+                    .map(|e| format!("{}->{}", e.from, e.to))
+                    .collect();
+                write!(f, "lookup {}", entries.join(", "))
+            }
+        }
+    }
+}
+
+/// Run a raw register value through every stage in order
+pub fn apply(transforms: &[Transform], raw: u16) -> f64 {
+    transforms
+        .iter()
+        .fold(f64::from(raw), |value, transform| transform.apply(value))
+}
+
+/// A raw value for display: unchanged without transforms, otherwise the
+/// transformed value followed by the raw one
+pub fn display(transforms: &[Transform], raw: u16) -> String {
+    if transforms.is_empty() {
+        raw.to_string()
+    } else {
+        format!("{} (raw {})", apply(transforms, raw), raw)
+    }
+}
+
+/// Check a pipeline; returns one message per problem, empty means valid
+pub fn validate(transforms: &[Transform]) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    for (i, t) in transforms.iter().enumerate() {
+        let field = format!("transforms[{}]", i);
+        let mut numbers = [t.factor, t.offset, t.min, t.max]
+            .into_iter()
+            .flatten()
+            .chain(t.table.iter().flat_map(|e| [e.from, e.to]));
+        if numbers.any(|n| !n.is_finite()) {
+            problems.push(format!("{} must only hold finite numbers", field));
+        }
+
+        let (uses_scale, uses_clamp) = (
+            t.factor.is_some() || t.offset.is_some(),
+            t.min.is_some() || t.max.is_some(),
+        );
+        let foreign = match t.kind {
+            TransformKind::Scale => uses_clamp || !t.table.is_empty(),
+            TransformKind::Clamp => uses_scale || !t.table.is_empty(),
+            TransformKind::Lookup => uses_scale || uses_clamp,
+        };
+        if foreign {
+            problems.push(format!(
+                "{} sets fields that do not belong to type {:?}",
+                field, t.kind
+            ));
+        }
+
+        match t.kind {
+            TransformKind::Scale => {
+                if !uses_scale {
+                    problems.push(format!("{} of type scale needs factor or offset", field));
+                }
+                if t.factor == Some(0.0) {
+                    problems.push(format!("{} factor must not be 0", field));
+                }
+            }
+            TransformKind::Clamp => match (t.min, t.max) {
+                (None, None) => problems.push(format!("{} of type clamp needs min or max", field)),
+                (Some(min), Some(max)) if min > max => problems.push(format!(
+                    "{} min {} must not be greater than max {}",
+                    field, min, max
+                )),
+                _ => {}
+            },
+            TransformKind::Lookup => {
+                if t.table.is_empty() {
+                    problems.push(format!("{} of type lookup needs a table", field));
+                }
+                for (j, entry) in t.table.iter().enumerate() {
+                    if t.table[..j].iter().any(|e| e.from == entry.from) {
+                        problems.push(format!("{} maps {} twice", field, entry.from));
+                    }
+                }
+            }
+        }
+    }
+
+    problems
+}
@@ -0,0 +1,51 @@
+#!/bin/bash
+#
+# Generate certificates for testing Modbus/TCP Security against mock-plc:
+#   ca.pem                     Test CA (pass to clients as the trusted root)
+#   server.pem / server-key.pem     Valid server certificate signed by the CA
+#   expired.pem / expired-key.pem   Server certificate signed by the CA that expired in 2020
+#   self-signed.pem / self-signed-key.pem   Server certificate no CA vouches for
+#   client.pem / client-key.pem     Client certificate for --tls-client-ca ca.pem
+#
+# Usage: gen-test-certs.sh [output-dir]   (default: ./certs; needs OpenSSL 3.4+)
+#
+
+set -e
+
+OUT="${1:-certs}"
+SAN="subjectAltName=DNS:localhost,DNS:mock-plc,IP:127.0.0.1"
+mkdir -p "$OUT"
+cd "$OUT"
+
+key() {
+    openssl genpkey -algorithm EC -pkeyopt ec_paramgen_curve:P-256 -out "$1" 2>/dev/null
+}
+
+# Sign a certificate for key $1 with subject $2 into $3, with extra x509 options
+sign() {
+    local key="$1" subject="$2" out="$3"
+    shift 3
+    openssl req -new -key "$key" -subj "/CN=$subject" \
+        | openssl x509 -req -CA ca.pem -CAkey ca-key.pem -CAcreateserial -out "$out" "$@" 2>/dev/null
+}
+
+key ca-key.pem
+openssl req -x509 -new -key ca-key.pem -subj "/CN=FabGitOps Test CA" -days 3650 -out ca.pem
+
+key server-key.pem
+sign server-key.pem mock-plc server.pem -days 825 -extfile <(echo "$SAN")
+
+key expired-key.pem
+sign expired-key.pem mock-plc expired.pem \
+    -not_before 20200101000000Z -not_after 20200102000000Z -extfile <(echo "$SAN")
+
+key self-signed-key.pem
+openssl req -x509 -new -key self-signed-key.pem -subj "/CN=mock-plc" -days 825 \
+    -addext "$SAN" -out self-signed.pem
+
+key client-key.pem
+sign client-key.pem fabgitops-operator client.pem -days 825 \
+    -extfile <(echo "extendedKeyUsage=clientAuth")
+
+rm -f ca.srl
+echo "Test certificates written to $(pwd)"
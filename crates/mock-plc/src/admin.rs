@@ -0,0 +1,536 @@
+use crate::chaos::{ChaosConfig, ChaosEngine, ConnectionChaos, Corruption, Drift, Latency};
+use crate::map_reload;
+use crate::register_map::RegisterMap;
+use crate::server::{Fault, PLCState, RegisterFaultMode, RegisterKind};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    routing::{get, post, put},
+    Json, Router,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tracing::{info, warn};
+
+/// Most values a single admin read or write may cover
+const MAX_ADMIN_VALUES: u16 = 2000;
+
+/// Shared handles behind the admin routes
+#[derive(Clone)]
+struct AdminState {
+    chaos: ChaosEngine,
+    plc: Arc<Mutex<PLCState>>,
+}
+
+type ApiError = (StatusCode, String);
+
+/// Chaos settings and the register they act on, as reported by `GET /chaos`
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChaosStatus {
+    #[serde(flatten)]
+    pub config: ChaosConfig,
+    pub register: u16,
+    pub value: u16,
+    /// Seed of the chaos random number generator, for `--chaos-seed`
+    pub seed: u64,
+    /// Seconds left in an outage started with `POST /chaos/outage`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub outage_remaining_secs: Option<u64>,
+    /// Seconds left in a reboot started with `POST /chaos/reboot`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reboot_remaining_secs: Option<u64>,
+}
+
+/// Body of `POST /chaos/outage` and `POST /chaos/reboot`
+#[derive(Deserialize)]
+pub struct OutageRequest {
+    pub secs: u64,
+}
+
+/// Optional overrides for `POST /chaos/enable`
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnableRequest {
+    pub interval_secs: Option<u64>,
+    pub max_drift: Option<u16>,
+}
+
+/// Body of `POST /chaos/drift`; a random amount and chaos register are used
+/// for whatever is omitted
+#[derive(Default, Deserialize)]
+pub struct DriftRequest {
+    pub register: Option<u16>,
+    pub amount: Option<i32>,
+}
+
+/// Query of the register routes; `unit` defaults to the lowest unit ID
+#[derive(Deserialize)]
+pub struct RegisterQuery {
+    pub unit: Option<u8>,
+    pub count: Option<u16>,
+}
+
+/// Body of `PUT /registers/:kind/:address`: one value or consecutive values
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum RegisterWrite {
+    One { value: u16 },
+    Many { values: Vec<u16> },
+}
+
+/// Query of the register fault routes; `unit` defaults to the lowest unit ID
+#[derive(Deserialize)]
+pub struct UnitQuery {
+    pub unit: Option<u8>,
+}
+
+/// Body of `PUT /faults/registers/:address`
+#[derive(Deserialize)]
+pub struct RegisterFaultRequest {
+    pub mode: RegisterFaultMode,
+}
+
+/// A misbehaving holding register
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegisterFault {
+    pub unit: u8,
+    pub address: u16,
+    pub mode: RegisterFaultMode,
+}
+
+/// Values of consecutive registers; coils and discrete inputs read as 0 or 1
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegisterValues {
+    pub unit: u8,
+    pub kind: RegisterKind,
+    pub address: u16,
+    pub values: Vec<u16>,
+}
+
+/// Non-zero values of one unit, as reported by `GET /state`
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnitDump {
+    pub holding_registers: BTreeMap<u16, u16>,
+    pub input_registers: BTreeMap<u16, u16>,
+    pub coils: BTreeMap<u16, u16>,
+    pub discrete_inputs: BTreeMap<u16, u16>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub register_faults: BTreeMap<u16, RegisterFaultMode>,
+}
+
+/// Full device state
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StateDump {
+    pub chaos: ChaosStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fault: Option<Fault>,
+    pub units: BTreeMap<u8, UnitDump>,
+}
+
+/// Start the HTTP admin API used to control the mock at runtime
+pub async fn start_admin_server(
+    bind_addr: &str,
+    port: u16,
+    chaos: ChaosEngine,
+    plc: Arc<Mutex<PLCState>>,
+) -> anyhow::Result<()> {
+    let socket_addr: SocketAddr = format!("{}:{}", bind_addr, port).parse()?;
+
+    info!("Starting admin API on http://{}", socket_addr);
+
+    let listener = TcpListener::bind(socket_addr).await?;
+    serve_admin(listener, chaos, plc).await
+}
+
+/// Serve the admin API on a bound listener until it fails
+pub async fn serve_admin(
+    listener: TcpListener,
+    chaos: ChaosEngine,
+    plc: Arc<Mutex<PLCState>>,
+) -> anyhow::Result<()> {
+    let app = Router::new()
+        .route("/chaos", get(status))
+        .route("/chaos/enable", post(enable))
+        .route("/chaos/disable", post(disable))
+        .route("/chaos/drift", post(drift))
+        .route("/chaos/latency", post(latency))
+        .route("/chaos/connections", post(connections))
+        .route("/chaos/outage", post(outage))
+        .route("/chaos/reboot", post(reboot))
+        .route("/chaos/corruption", post(corruption))
+        .route(
+            "/registers/:kind/:address",
+            get(read_registers).put(write_registers),
+        )
+        .route(
+            "/faults",
+            get(get_fault).post(set_fault).delete(clear_fault),
+        )
+        .route("/faults/registers", get(list_register_faults))
+        .route(
+            "/faults/registers/:address",
+            put(set_register_fault).delete(clear_register_fault),
+        )
+        .route("/state", get(dump_state))
+        .route("/map", put(reload_map))
+        .route("/health", get(|| async { "OK" }))
+        .route("/healthz", get(|| async { "ok" }))
+        .route("/readyz", get(ready))
+        .with_state(AdminState { chaos, plc });
+
+    axum::serve(listener, app).await?;
+
+    Ok(())
+}
+
+fn status_of(chaos: &ChaosEngine) -> ChaosStatus {
+    let (register, value) = chaos.register();
+    ChaosStatus {
+        config: chaos.config(),
+        register,
+        value,
+        seed: chaos.seed(),
+        outage_remaining_secs: chaos
+            .outage_remaining()
+            .map(|d| d.as_secs_f64().ceil() as u64),
+        reboot_remaining_secs: chaos
+            .reboot_remaining()
+            .map(|d| d.as_secs_f64().ceil() as u64),
+    }
+}
+
+fn lock(plc: &Mutex<PLCState>) -> Result<std::sync::MutexGuard<'_, PLCState>, ApiError> {
+    plc.lock().map_err(|_| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "PLC state is poisoned".to_string(),
+        )
+    })
+}
+
+async fn status(State(admin): State<AdminState>) -> Json<ChaosStatus> {
+    Json(status_of(&admin.chaos))
+}
+
+async fn enable(
+    State(admin): State<AdminState>,
+    body: Option<Json<EnableRequest>>,
+) -> Json<ChaosStatus> {
+    let Json(req) = body.unwrap_or_default();
+    admin.chaos.enable(req.interval_secs, req.max_drift);
+    Json(status_of(&admin.chaos))
+}
+
+async fn disable(State(admin): State<AdminState>) -> Json<ChaosStatus> {
+    admin.chaos.disable();
+    Json(status_of(&admin.chaos))
+}
+
+async fn drift(State(admin): State<AdminState>, body: Option<Json<DriftRequest>>) -> Json<Drift> {
+    let Json(req) = body.unwrap_or_default();
+    Json(admin.chaos.drift(req.register, req.amount))
+}
+
+async fn latency(
+    State(admin): State<AdminState>,
+    Json(latency): Json<Latency>,
+) -> Result<Json<ChaosStatus>, ApiError> {
+    latency
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    admin.chaos.set_latency(latency);
+    Ok(Json(status_of(&admin.chaos)))
+}
+
+async fn connections(
+    State(admin): State<AdminState>,
+    Json(connections): Json<ConnectionChaos>,
+) -> Result<Json<ChaosStatus>, ApiError> {
+    connections
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    admin.chaos.set_connections(connections);
+    Ok(Json(status_of(&admin.chaos)))
+}
+
+async fn corruption(
+    State(admin): State<AdminState>,
+    Json(corruption): Json<Corruption>,
+) -> Result<Json<ChaosStatus>, ApiError> {
+    corruption
+        .validate()
+        .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    admin.chaos.set_corruption(corruption);
+    Ok(Json(status_of(&admin.chaos)))
+}
+
+async fn outage(
+    State(admin): State<AdminState>,
+    Json(req): Json<OutageRequest>,
+) -> Result<Json<ChaosStatus>, ApiError> {
+    if req.secs == 0 {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "secs must be at least 1".to_string(),
+        ));
+    }
+    admin.chaos.start_outage(Duration::from_secs(req.secs));
+    Ok(Json(status_of(&admin.chaos)))
+}
+
+async fn reboot(
+    State(admin): State<AdminState>,
+    Json(req): Json<OutageRequest>,
+) -> Result<Json<ChaosStatus>, ApiError> {
+    if req.secs == 0 {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "secs must be at least 1".to_string(),
+        ));
+    }
+    admin.chaos.reboot(Duration::from_secs(req.secs));
+    Ok(Json(status_of(&admin.chaos)))
+}
+
+async fn read_registers(
+    State(admin): State<AdminState>,
+    Path((kind, address)): Path<(RegisterKind, u16)>,
+    Query(query): Query<RegisterQuery>,
+) -> Result<Json<RegisterValues>, ApiError> {
+    let count = query.count.unwrap_or(1);
+    if count == 0 || count > MAX_ADMIN_VALUES {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("count must be 1-{}", MAX_ADMIN_VALUES),
+        ));
+    }
+
+    let plc = lock(&admin.plc)?;
+    let unit = query.unit.unwrap_or_else(|| plc.primary_unit());
+    let registers = plc
+        .unit(unit)
+        .ok_or((StatusCode::NOT_FOUND, format!("unknown unit {}", unit)))?;
+    let values = registers.read(kind, address, count).ok_or((
+        StatusCode::BAD_REQUEST,
+        "range runs past the end of the address space".to_string(),
+    ))?;
+
+    Ok(Json(RegisterValues {
+        unit,
+        kind,
+        address,
+        values,
+    }))
+}
+
+async fn write_registers(
+    State(admin): State<AdminState>,
+    Path((kind, address)): Path<(RegisterKind, u16)>,
+    Query(query): Query<RegisterQuery>,
+    Json(body): Json<RegisterWrite>,
+) -> Result<Json<RegisterValues>, ApiError> {
+    let values = match body {
+        RegisterWrite::One { value } => vec![value],
+        RegisterWrite::Many { values } => values,
+    };
+    if values.is_empty() || values.len() > MAX_ADMIN_VALUES as usize {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("between 1 and {} values are required", MAX_ADMIN_VALUES),
+        ));
+    }
+
+    let mut plc = lock(&admin.plc)?;
+    let unit = query.unit.unwrap_or_else(|| plc.primary_unit());
+    let registers = plc
+        .unit_mut(unit)
+        .ok_or((StatusCode::NOT_FOUND, format!("unknown unit {}", unit)))?;
+    if !registers.write(kind, address, &values) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "range runs past the end of the address space".to_string(),
+        ));
+    }
+    let values = registers
+        .read(kind, address, values.len() as u16)
+        .unwrap_or_default();
+    info!(
+        "Admin API set unit {} {:?} {} to {:?}",
+        unit, kind, address, values
+    );
+
+    Ok(Json(RegisterValues {
+        unit,
+        kind,
+        address,
+        values,
+    }))
+}
+
+async fn get_fault(State(admin): State<AdminState>) -> Result<Json<Option<Fault>>, ApiError> {
+    Ok(Json(lock(&admin.plc)?.fault.clone()))
+}
+
+async fn set_fault(
+    State(admin): State<AdminState>,
+    Json(fault): Json<Fault>,
+) -> Result<Json<Option<Fault>>, ApiError> {
+    fault.validate().map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    warn!(
+        "⚡ Fault injected: exception {:#04x}{}",
+        fault.exception_code,
+        fault
+            .remaining
+            .map(|n| format!(" for the next {} request(s)", n))
+            .unwrap_or_default()
+    );
+    let mut plc = lock(&admin.plc)?;
+    plc.fault = Some(fault);
+    Ok(Json(plc.fault.clone()))
+}
+
+async fn clear_fault(State(admin): State<AdminState>) -> Result<Json<Option<Fault>>, ApiError> {
+    lock(&admin.plc)?.fault = None;
+    info!("Fault cleared");
+    Ok(Json(None))
+}
+
+async fn list_register_faults(
+    State(admin): State<AdminState>,
+) -> Result<Json<Vec<RegisterFault>>, ApiError> {
+    let plc = lock(&admin.plc)?;
+    let faults = plc
+        .units()
+        .flat_map(|(unit, registers)| {
+            registers
+                .register_faults
+                .iter()
+                .map(move |(&address, &mode)| RegisterFault {
+                    unit,
+                    address,
+                    mode,
+                })
+        })
+        .collect();
+    Ok(Json(faults))
+}
+
+async fn set_register_fault(
+    State(admin): State<AdminState>,
+    Path(address): Path<u16>,
+    Query(query): Query<UnitQuery>,
+    Json(req): Json<RegisterFaultRequest>,
+) -> Result<Json<RegisterFault>, ApiError> {
+    let mut plc = lock(&admin.plc)?;
+    let unit = query.unit.unwrap_or_else(|| plc.primary_unit());
+    let registers = plc
+        .unit_mut(unit)
+        .ok_or((StatusCode::NOT_FOUND, format!("unknown unit {}", unit)))?;
+    registers.register_faults.insert(address, req.mode);
+    warn!(
+        "⚡ Unit {} register {} is now {:?} at {}",
+        unit, address, req.mode, registers.holding_registers[address as usize]
+    );
+
+    Ok(Json(RegisterFault {
+        unit,
+        address,
+        mode: req.mode,
+    }))
+}
+
+async fn clear_register_fault(
+    State(admin): State<AdminState>,
+    Path(address): Path<u16>,
+    Query(query): Query<UnitQuery>,
+) -> Result<StatusCode, ApiError> {
+    let mut plc = lock(&admin.plc)?;
+    let unit = query.unit.unwrap_or_else(|| plc.primary_unit());
+    let registers = plc
+        .unit_mut(unit)
+        .ok_or((StatusCode::NOT_FOUND, format!("unknown unit {}", unit)))?;
+    match registers.register_faults.remove(&address) {
+        Some(_) => {
+            info!("Unit {} register {} fault cleared", unit, address);
+            Ok(StatusCode::NO_CONTENT)
+        }
+        None => Err((
+            StatusCode::NOT_FOUND,
+            format!("unit {} register {} has no fault", unit, address),
+        )),
+    }
+}
+
+/// Result of `PUT /map`
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MapReloaded {
+    /// Values the new map added or changed, in each unit
+    applied: usize,
+}
+
+/// Switch to the register map in the body (YAML or JSON) without a restart
+async fn reload_map(
+    State(admin): State<AdminState>,
+    body: String,
+) -> Result<Json<MapReloaded>, ApiError> {
+    let map = RegisterMap::parse(&body).map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            format!("Invalid register map: {:#}", e),
+        )
+    })?;
+    let applied = map_reload::apply(&admin.plc, &admin.chaos, map);
+    info!("🗺️  Register map replaced ({} value(s) applied)", applied);
+    Ok(Json(MapReloaded { applied }))
+}
+
+/// Ready once the Modbus listener accepts connections. Chaos refusals and
+/// outages do not count, so chaos tests still reach the device.
+async fn ready(State(admin): State<AdminState>) -> Result<&'static str, ApiError> {
+    if lock(&admin.plc)?.is_listening() {
+        Ok("ok")
+    } else {
+        Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Modbus listener is not accepting connections".to_string(),
+        ))
+    }
+}
+
+async fn dump_state(State(admin): State<AdminState>) -> Result<Json<StateDump>, ApiError> {
+    // Chaos status takes the state lock itself, so read it first
+    let chaos = status_of(&admin.chaos);
+    let plc = lock(&admin.plc)?;
+    let units = plc
+        .units()
+        .map(|(id, registers)| {
+            (
+                id,
+                UnitDump {
+                    holding_registers: registers.non_zero(RegisterKind::Holding),
+                    input_registers: registers.non_zero(RegisterKind::Input),
+                    coils: registers.non_zero(RegisterKind::Coil),
+                    discrete_inputs: registers.non_zero(RegisterKind::Discrete),
+                    register_faults: registers.register_faults.clone(),
+                },
+            )
+        })
+        .collect();
+
+    Ok(Json(StateDump {
+        chaos,
+        fault: plc.fault.clone(),
+        units,
+    }))
+}
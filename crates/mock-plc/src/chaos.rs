@@ -1,15 +1,115 @@
-use rand::Rng;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
-use tokio::time::{interval, Duration};
+use crate::server::PLCState;
+use crate::traffic::encode_response;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, Duration, Instant};
+use tokio_modbus::bytes::Bytes;
+use tokio_modbus::prelude::Response;
 use tracing::{info, warn};
 
 /// Chaos mode configuration
-#[derive(Clone)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
 pub struct ChaosConfig {
     pub enabled: bool,
     pub interval_secs: u64,
     pub max_drift: u16,
+    /// Holding registers to drift; one is picked at random each time
+    pub registers: Vec<u16>,
+    /// Response delays, applied whether or not drift is enabled
+    #[serde(default)]
+    pub latency: Latency,
+    /// Connection drops and refusals, applied whether or not drift is enabled
+    #[serde(default)]
+    pub connections: ConnectionChaos,
+    /// Malformed responses, applied whether or not drift is enabled
+    #[serde(default)]
+    pub corruption: Corruption,
+}
+
+/// Delay `percent` of Modbus responses by `delay_ms` plus up to `jitter_ms`
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Latency {
+    #[serde(default)]
+    pub delay_ms: u64,
+    #[serde(default)]
+    pub jitter_ms: u64,
+    #[serde(default = "default_latency_percent")]
+    pub percent: u8,
+}
+
+fn default_latency_percent() -> u8 {
+    100
+}
+
+impl Latency {
+    pub fn is_active(&self) -> bool {
+        self.percent > 0 && (self.delay_ms > 0 || self.jitter_ms > 0)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.percent > 100 {
+            return Err("percent must be 0-100".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Close `drop_percent` of connections mid-transaction, and refuse new
+/// connections for `refuse_for_secs` out of every `refuse_every_secs`
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionChaos {
+    #[serde(default)]
+    pub drop_percent: u8,
+    #[serde(default)]
+    pub refuse_for_secs: u64,
+    #[serde(default)]
+    pub refuse_every_secs: u64,
+}
+
+impl ConnectionChaos {
+    pub fn is_active(&self) -> bool {
+        self.drop_percent > 0 || (self.refuse_for_secs > 0 && self.refuse_every_secs > 0)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.drop_percent > 100 {
+            return Err("dropPercent must be 0-100".to_string());
+        }
+        if self.refuse_for_secs > 0 && self.refuse_every_secs == 0 {
+            return Err("refuseEverySecs is required with refuseForSecs".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Answer `percent` of Modbus requests with a malformed response: a wrong
+/// length, a garbage payload, or the wrong function code
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Corruption {
+    #[serde(default)]
+    pub percent: u8,
+}
+
+impl Corruption {
+    pub fn is_active(&self) -> bool {
+        self.percent > 0
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.percent > 100 {
+            return Err("percent must be 0-100".to_string());
+        }
+        Ok(())
+    }
 }
 
 impl Default for ChaosConfig {
@@ -18,72 +118,397 @@ impl Default for ChaosConfig {
             enabled: false,
             interval_secs: 10,
             max_drift: 500,
+            registers: vec![4001],
+            latency: Latency::default(),
+            connections: ConnectionChaos::default(),
+            corruption: Corruption::default(),
         }
     }
 }
 
-/// Manages chaos mode for simulated PLC drift
+fn log_connection_chaos(connections: &ConnectionChaos) {
+    warn!(
+        "🔌 Dropping {}% of connections mid-transaction; refusing connections for {}s of every {}s",
+        connections.drop_percent, connections.refuse_for_secs, connections.refuse_every_secs
+    );
+}
+
+/// Result of a single drift applied to the register
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Drift {
+    pub register: u16,
+    pub old_value: u16,
+    pub new_value: u16,
+    pub drift: i32,
+}
+
+/// Manages chaos mode for simulated PLC drift.
+/// Cloning is cheap; clones share configuration, register state, and the
+/// random number generator, so a seeded run makes the same choices each time.
+#[derive(Clone)]
 pub struct ChaosEngine {
-    config: ChaosConfig,
-    running: Arc<AtomicBool>,
+    config: Arc<Mutex<ChaosConfig>>,
+    state: Arc<Mutex<PLCState>>,
+    changed: Arc<Notify>,
+    rng: Arc<Mutex<StdRng>>,
+    seed: u64,
+    /// Reference point for periodic refusal windows
+    started: Instant,
+    /// End of an outage started through the admin API
+    outage_until: Arc<Mutex<Option<Instant>>>,
+    /// End of a simulated reboot
+    reboot_until: Arc<Mutex<Option<Instant>>>,
+    /// Bumped when a reboot starts and ends, so connections opened before
+    /// either are dropped
+    generation: Arc<AtomicU64>,
 }
 
 impl ChaosEngine {
-    pub fn new(config: ChaosConfig) -> Self {
+    /// Engine drawing random choices from `seed`, or a random seed when omitted
+    pub fn new(config: ChaosConfig, state: Arc<Mutex<PLCState>>, seed: Option<u64>) -> Self {
+        let seed = seed.unwrap_or_else(rand::random);
         Self {
-            config,
-            running: Arc::new(AtomicBool::new(false)),
+            config: Arc::new(Mutex::new(config)),
+            state,
+            changed: Arc::new(Notify::new()),
+            rng: Arc::new(Mutex::new(StdRng::seed_from_u64(seed))),
+            seed,
+            started: Instant::now(),
+            outage_until: Arc::new(Mutex::new(None)),
+            reboot_until: Arc::new(Mutex::new(None)),
+            generation: Arc::new(AtomicU64::new(0)),
         }
     }
 
-    /// Start the chaos engine in background
-    pub fn spawn(&self, register_value: Arc<std::sync::Mutex<u16>>) {
-        if !self.config.enabled {
+    /// Start the chaos loop in background. It idles while chaos is disabled,
+    /// so it can be switched on and off at runtime through the admin API.
+    pub fn spawn(&self) -> JoinHandle<()> {
+        let engine = self.clone();
+
+        let task = tokio::spawn(async move {
+            loop {
+                let config = engine.config();
+                tokio::select! {
+                    _ = sleep(Duration::from_secs(config.interval_secs.max(1))) => {}
+                    // Restart the wait with the new settings
+                    _ = engine.changed.notified() => continue,
+                }
+
+                let config = engine.config();
+                if config.enabled {
+                    engine.drift(None, None);
+                }
+            }
+        });
+
+        info!(
+            "Chaos seed: {} (pass --chaos-seed {} to replay this run)",
+            self.seed, self.seed
+        );
+        let config = self.config();
+        if config.enabled {
+            info!(
+                "🌀 CHAOS MODE ACTIVATED! Drifting every {}s (max drift: {}, registers: {:?})",
+                config.interval_secs, config.max_drift, config.registers
+            );
+        } else {
             info!("Chaos mode disabled");
-            return;
         }
+        if config.connections.is_active() {
+            log_connection_chaos(&config.connections);
+        }
+        if config.latency.is_active() {
+            warn!(
+                "🐢 Delaying {}% of responses by {}ms (+ up to {}ms jitter)",
+                config.latency.percent, config.latency.delay_ms, config.latency.jitter_ms
+            );
+        }
+        if config.corruption.is_active() {
+            warn!("🧨 Corrupting {}% of responses", config.corruption.percent);
+        }
+        task
+    }
+
+    /// Seed of the random number generator
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Current configuration
+    pub fn config(&self) -> ChaosConfig {
+        self.config.lock().map(|c| c.clone()).unwrap_or_default()
+    }
+
+    /// Enable chaos, optionally changing the interval and maximum drift
+    pub fn enable(&self, interval_secs: Option<u64>, max_drift: Option<u16>) -> ChaosConfig {
+        let mut config = self.config.lock().unwrap();
+        config.enabled = true;
+        if let Some(interval_secs) = interval_secs {
+            config.interval_secs = interval_secs.max(1);
+        }
+        if let Some(max_drift) = max_drift {
+            config.max_drift = max_drift;
+        }
+        info!(
+            "🌀 CHAOS MODE ACTIVATED! Drifting every {}s (max drift: {})",
+            config.interval_secs, config.max_drift
+        );
+        self.changed.notify_one();
+        config.clone()
+    }
 
-        let running = self.running.clone();
-        let interval_secs = self.config.interval_secs;
-        let max_drift = self.config.max_drift;
+    /// Change the holding registers drift picks from
+    pub fn set_registers(&self, registers: Vec<u16>) {
+        if let Ok(mut config) = self.config.lock() {
+            config.registers = registers;
+        }
+    }
 
-        running.store(true, Ordering::SeqCst);
+    /// Change the response latency; a zero delay and jitter turns it off
+    pub fn set_latency(&self, latency: Latency) -> ChaosConfig {
+        let mut config = self.config.lock().unwrap();
+        if latency.is_active() {
+            warn!(
+                "🐢 Delaying {}% of responses by {}ms (+ up to {}ms jitter)",
+                latency.percent, latency.delay_ms, latency.jitter_ms
+            );
+        } else {
+            info!("Response latency disabled");
+        }
+        config.latency = latency;
+        config.clone()
+    }
 
-        // Spawn a blocking task for the RNG since ThreadRng is not Send
-        std::thread::spawn(move || {
-            let rt = tokio::runtime::Runtime::new().unwrap();
-            rt.block_on(async move {
-                let mut ticker = interval(Duration::from_secs(interval_secs));
-                let mut rng = rand::thread_rng();
+    /// How long to hold back the next Modbus response, if at all
+    pub fn response_delay(&self) -> Option<Duration> {
+        let latency = self.config.lock().ok()?.latency.clone();
+        if !latency.is_active() {
+            return None;
+        }
+        let mut rng = self.rng.lock().ok()?;
+        if rng.gen_range(0..100) >= latency.percent as u32 {
+            return None;
+        }
+        let jitter = rng.gen_range(0..=latency.jitter_ms);
+        Some(Duration::from_millis(latency.delay_ms + jitter))
+    }
 
-                info!(
-                    "🌀 CHAOS MODE ACTIVATED! Drifting every {}s (max drift: {})",
-                    interval_secs, max_drift
-                );
+    /// Change connection drops and refusal windows; zeros turn them off
+    pub fn set_connections(&self, connections: ConnectionChaos) -> ChaosConfig {
+        let mut config = self.config.lock().unwrap();
+        if connections.is_active() {
+            log_connection_chaos(&connections);
+        } else {
+            info!("Connection chaos disabled");
+        }
+        config.connections = connections;
+        config.clone()
+    }
 
-                while running.load(Ordering::SeqCst) {
-                    ticker.tick().await;
+    /// Refuse every new connection for `duration`, starting now
+    pub fn start_outage(&self, duration: Duration) {
+        warn!(
+            "🔌 CHAOS OUTAGE! Refusing connections for {}s",
+            duration.as_secs()
+        );
+        *self.outage_until.lock().unwrap() = Some(Instant::now() + duration);
+    }
 
-                    let drift: i16 = rng.gen_range(-(max_drift as i16)..=max_drift as i16);
+    /// Seconds left in an outage started through the admin API
+    pub fn outage_remaining(&self) -> Option<Duration> {
+        let until = (*self.outage_until.lock().ok()?)?;
+        until.checked_duration_since(Instant::now())
+    }
 
-                    if let Ok(mut value) = register_value.lock() {
-                        let old_value = *value;
-                        let new_value = (*value as i16 + drift).clamp(0, i16::MAX) as u16;
-                        *value = new_value;
+    /// Power-cycle the device: drop every connection, stay silent for
+    /// `duration`, then come back with registers at their initial values
+    pub fn reboot(&self, duration: Duration) {
+        warn!("🔌 CHAOS REBOOT! Silent for {}s", duration.as_secs());
+        *self.reboot_until.lock().unwrap() = Some(Instant::now() + duration);
+        let generation = self.generation.fetch_add(1, Ordering::SeqCst) + 1;
 
-                        warn!(
-                            "🌀 CHAOS DRIFT! Register changed: {} → {} (drift: {})",
-                            old_value, new_value, drift
-                        );
-                    }
-                }
-            });
+        let engine = self.clone();
+        tokio::spawn(async move {
+            sleep(duration).await;
+            // A reboot started in the meantime comes back on its own
+            if engine
+                .generation
+                .compare_exchange(
+                    generation,
+                    generation + 1,
+                    Ordering::SeqCst,
+                    Ordering::SeqCst,
+                )
+                .is_err()
+            {
+                return;
+            }
+            *engine.reboot_until.lock().unwrap() = None;
+            if let Ok(mut state) = engine.state.lock() {
+                state.reset();
+            }
+            info!("🔌 Back up after reboot with registers at their initial values");
         });
     }
 
-    #[allow(dead_code)]
-    pub fn stop(&self) {
-        self.running.store(false, Ordering::SeqCst);
+    /// Time left in a reboot
+    pub fn reboot_remaining(&self) -> Option<Duration> {
+        let until = (*self.reboot_until.lock().ok()?)?;
+        until.checked_duration_since(Instant::now())
+    }
+
+    /// Changes whenever a reboot starts or ends
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::SeqCst)
+    }
+
+    /// Whether a new connection should be refused right now
+    pub fn should_refuse(&self) -> bool {
+        if self.outage_remaining().is_some() {
+            return true;
+        }
+        let Ok(config) = self.config.lock() else {
+            return false;
+        };
+        let ConnectionChaos {
+            refuse_for_secs,
+            refuse_every_secs,
+            ..
+        } = config.connections;
+        refuse_for_secs > 0
+            && refuse_every_secs > 0
+            && self.started.elapsed().as_secs() % refuse_every_secs < refuse_for_secs
+    }
+
+    /// Whether to close the connection instead of answering a request
+    pub fn should_drop(&self) -> bool {
+        let drop_percent = self
+            .config
+            .lock()
+            .map(|c| c.connections.drop_percent)
+            .unwrap_or_default();
+        drop_percent > 0
+            && self
+                .rng
+                .lock()
+                .is_ok_and(|mut rng| rng.gen_range(0..100) < drop_percent as u32)
+    }
+
+    /// Change how many responses are corrupted; zero turns it off
+    pub fn set_corruption(&self, corruption: Corruption) -> ChaosConfig {
+        let mut config = self.config.lock().unwrap();
+        if corruption.is_active() {
+            warn!("🧨 Corrupting {}% of responses", corruption.percent);
+        } else {
+            info!("Response corruption disabled");
+        }
+        config.corruption = corruption;
+        config.clone()
+    }
+
+    /// Garble a response when corruption picks it. The result still frames
+    /// as Modbus TCP, so clients have to catch the error in the PDU.
+    pub fn corrupt(&self, response: Response) -> Response {
+        let percent = self
+            .config
+            .lock()
+            .map(|c| c.corruption.percent)
+            .unwrap_or_default();
+        if percent == 0 {
+            return response;
+        }
+        let Ok(mut rng) = self.rng.lock() else {
+            return response;
+        };
+        if rng.gen_range(0..100) >= percent as u32 {
+            return response;
+        }
+
+        let mut pdu = encode_response(&response);
+        let how = match rng.gen_range(0..3) {
+            0 => {
+                // Cut the payload short or run it past its byte count
+                if pdu.len() > 2 && rng.gen_bool(0.5) {
+                    let keep = rng.gen_range(1..pdu.len() - 1);
+                    pdu.truncate(keep);
+                } else {
+                    let extra = rng.gen_range(1..=8);
+                    pdu.extend((0..extra).map(|_| rng.gen::<u8>()));
+                }
+                "wrong length"
+            }
+            1 => {
+                rng.fill(&mut pdu[1..]);
+                "garbage payload"
+            }
+            _ => {
+                // Another function code, never an exception
+                let function = pdu[0];
+                while pdu[0] == function {
+                    pdu[0] = rng.gen_range(1..0x80);
+                }
+                "wrong function code"
+            }
+        };
+        warn!("🧨 CHAOS: sending a response with a {}", how);
+        Response::Custom(pdu[0], Bytes::copy_from_slice(&pdu[1..]))
+    }
+
+    /// Stop drifting the register
+    pub fn disable(&self) -> ChaosConfig {
+        let mut config = self.config.lock().unwrap();
+        config.enabled = false;
         info!("Chaos mode stopped");
+        self.changed.notify_one();
+        config.clone()
+    }
+
+    /// Shift a holding register by `drift`, saturating at the u16 bounds.
+    /// The register and amount are picked at random when omitted.
+    pub fn drift(&self, register: Option<u16>, drift: Option<i32>) -> Drift {
+        let config = self.config();
+        let (register, drift) = {
+            let mut rng = self.rng.lock().unwrap();
+            let register = register.or_else(|| {
+                let registers = &config.registers;
+                (!registers.is_empty()).then(|| registers[rng.gen_range(0..registers.len())])
+            });
+            let max_drift = config.max_drift as i32;
+            let drift = drift.unwrap_or_else(|| rng.gen_range(-max_drift..=max_drift));
+            (register, drift)
+        };
+        let register = register.unwrap_or_else(|| self.register().0);
+
+        let mut state = self.state.lock().unwrap();
+        let old_value = state.holding(register);
+        let mut new_value = i32::from(old_value)
+            .saturating_add(drift)
+            .clamp(0, u16::MAX as i32) as u16;
+        if state.set_holding(register, new_value) {
+            warn!(
+                "🌀 CHAOS DRIFT! Register {} changed: {} → {} (drift: {})",
+                register, old_value, new_value, drift
+            );
+        } else {
+            info!("Register {} is stuck; skipped drift of {}", register, drift);
+            new_value = old_value;
+        }
+
+        Drift {
+            register,
+            old_value,
+            new_value,
+            drift,
+        }
+    }
+
+    /// Current register value and address
+    pub fn register(&self) -> (u16, u16) {
+        let state = self.state.lock().unwrap();
+        (
+            state.register_address,
+            state.holding(state.register_address),
+        )
     }
 }
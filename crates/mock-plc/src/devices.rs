@@ -0,0 +1,339 @@
+use crate::Args;
+use anyhow::Context;
+use mock_plc::admin::start_admin_server;
+use mock_plc::chaos::{ChaosConfig, ChaosEngine, ConnectionChaos, Corruption, Latency};
+use mock_plc::limits::Limits;
+use mock_plc::map_reload;
+use mock_plc::register_map::RegisterMap;
+use mock_plc::replay::Timeline;
+use mock_plc::scenario::Scenario;
+use mock_plc::server::{start_server, PLCState};
+use mock_plc::simulation::Simulator;
+use mock_plc::state_file::StateFile;
+use mock_plc::traffic::TrafficRecorder;
+use serde::Deserialize;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::JoinSet;
+use tokio_rustls::TlsAcceptor;
+use tracing::{error, info};
+
+/// Devices to simulate, loaded with `--devices`, e.g.
+///
+/// ```yaml
+/// devices:
+///   - name: line-1
+///     port: 5601
+///     adminPort: 9601
+///     map: line-1.yaml
+///   - name: line-2
+///     port: 5602
+///     value: 1800
+/// ```
+///
+/// Omitted settings fall back to the command-line flags; map paths are
+/// relative to the devices file. A device without `adminPort` has no admin API.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct DevicesFile {
+    pub devices: Vec<DeviceEntry>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct DeviceEntry {
+    pub name: Option<String>,
+    pub port: u16,
+    pub admin_port: Option<u16>,
+    pub value: Option<u16>,
+    pub register: Option<u16>,
+    pub units: Option<Vec<u8>>,
+    pub map: Option<PathBuf>,
+}
+
+/// Settings of one simulated PLC
+pub struct DeviceSpec {
+    pub name: String,
+    pub port: u16,
+    /// 0 disables the admin API
+    pub admin_port: u16,
+    pub value: u16,
+    pub register: u16,
+    pub units: Vec<u8>,
+    pub map: Option<RegisterMap>,
+    /// File the map came from, watched for changes
+    pub map_path: Option<PathBuf>,
+    pub seed: Option<u64>,
+    /// Whether other devices run in the same process, so per-device files
+    /// (`--state-file`, `--log-requests`, `--capture`) get the name added
+    pub shared: bool,
+}
+
+impl DeviceSpec {
+    /// The single device described by the command-line flags
+    pub fn from_args(args: &Args, map: Option<RegisterMap>) -> Self {
+        Self {
+            name: "mock-plc".to_string(),
+            port: args.port,
+            admin_port: args.admin_port,
+            value: args.value,
+            register: args.register,
+            units: args.units.clone(),
+            map,
+            map_path: args.map.clone(),
+            seed: args.chaos_seed,
+            shared: false,
+        }
+    }
+
+    /// `count` identical devices on consecutive ports. Admin ports follow
+    /// `--admin-port` the same way, and chaos seeds differ per device.
+    pub fn instances(
+        args: &Args,
+        map: Option<RegisterMap>,
+        count: u16,
+    ) -> anyhow::Result<Vec<Self>> {
+        let base_port = args.base_port.unwrap_or(args.port);
+        (0..count)
+            .map(|i| {
+                let port = base_port
+                    .checked_add(i)
+                    .context("--instances runs past port 65535")?;
+                let admin_port = match args.admin_port {
+                    0 => 0,
+                    admin => admin
+                        .checked_add(i)
+                        .context("--instances runs admin ports past 65535")?,
+                };
+                Ok(Self {
+                    name: format!("plc-{}", port),
+                    port,
+                    admin_port,
+                    seed: args.chaos_seed.map(|seed| seed.wrapping_add(i as u64)),
+                    shared: true,
+                    ..Self::from_args(args, map.clone())
+                })
+            })
+            .collect()
+    }
+
+    /// The devices listed in a `--devices` file
+    pub fn load(args: &Args, path: &Path, map: Option<RegisterMap>) -> anyhow::Result<Vec<Self>> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read devices file {}", path.display()))?;
+        let file: DevicesFile = serde_yaml::from_str(&content)
+            .with_context(|| format!("Invalid devices file {}", path.display()))?;
+        let dir = path.parent().unwrap_or(Path::new("."));
+
+        let mut names = BTreeSet::new();
+        let mut specs = Vec::new();
+        for (i, entry) in file.devices.into_iter().enumerate() {
+            let name = entry.name.unwrap_or_else(|| format!("plc-{}", entry.port));
+            if !names.insert(name.clone()) {
+                anyhow::bail!("Devices file {} repeats the name {}", path.display(), name);
+            }
+            let (device_map, map_path) = match entry.map {
+                Some(map_path) => {
+                    let map_path = dir.join(map_path);
+                    (Some(RegisterMap::load(&map_path)?), Some(map_path))
+                }
+                None => (map.clone(), args.map.clone()),
+            };
+            specs.push(Self {
+                name,
+                port: entry.port,
+                admin_port: entry.admin_port.unwrap_or(0),
+                value: entry.value.unwrap_or(args.value),
+                register: entry.register.unwrap_or(args.register),
+                units: entry.units.unwrap_or_else(|| args.units.clone()),
+                map: device_map,
+                map_path,
+                seed: args.chaos_seed.map(|seed| seed.wrapping_add(i as u64)),
+                shared: true,
+            });
+        }
+        if specs.is_empty() {
+            anyhow::bail!("Devices file {} lists no devices", path.display());
+        }
+        Ok(specs)
+    }
+
+    /// Path of a per-device file: `state.json` becomes `state.line-1.json`
+    /// when several devices run
+    fn file(&self, path: &Path) -> PathBuf {
+        if !self.shared {
+            return path.to_path_buf();
+        }
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        let name = match path.extension() {
+            Some(ext) => format!("{}.{}.{}", stem, self.name, ext.to_string_lossy()),
+            None => format!("{}.{}", stem, self.name),
+        };
+        path.with_file_name(name)
+    }
+}
+
+/// A running simulated PLC
+pub struct Device {
+    pub name: String,
+    state: Arc<Mutex<PLCState>>,
+    state_file: Option<Arc<StateFile>>,
+}
+
+impl Device {
+    /// Set up the device's state and background tasks, and add its Modbus
+    /// server to `servers`
+    pub fn start(
+        spec: DeviceSpec,
+        args: &Args,
+        scenario: Option<&Scenario>,
+        replay: Option<&Timeline>,
+        tls: Option<&TlsAcceptor>,
+        servers: &mut JoinSet<anyhow::Result<()>>,
+    ) -> anyhow::Result<Self> {
+        let traffic = TrafficRecorder::create(
+            args.log_requests
+                .as_deref()
+                .map(|p| spec.file(p))
+                .as_deref(),
+            args.capture.as_deref().map(|p| spec.file(p)).as_deref(),
+        )?;
+
+        let mut state = PLCState::new(spec.value, spec.register, &spec.units);
+        if let Some(ref map) = spec.map {
+            state.load_map(map);
+        }
+        let state_file = args
+            .state_file
+            .as_deref()
+            .map(|p| Arc::new(StateFile::new(&spec.file(p))));
+        if let Some(ref state_file) = state_file {
+            if let Some(saved) = state_file.load()? {
+                saved.restore(&mut state);
+                info!(
+                    "💾 {}: restored register state from the state file",
+                    spec.name
+                );
+            }
+        }
+        let state = Arc::new(Mutex::new(state));
+        if let Some(ref state_file) = state_file {
+            state_file.clone().spawn(state.clone());
+        }
+
+        // Chaos drifts the main register and every holding register in the map
+        let mut chaos_registers = BTreeSet::from([spec.register]);
+        if let Some(ref map) = spec.map {
+            chaos_registers.extend(map.holding_registers.keys());
+        }
+
+        // The chaos loop always runs so the admin API can switch it on later
+        let chaos = ChaosEngine::new(
+            ChaosConfig {
+                enabled: args.chaos,
+                interval_secs: args.chaos_interval,
+                max_drift: args.max_drift,
+                registers: chaos_registers.into_iter().collect(),
+                latency: Latency {
+                    delay_ms: args.chaos_latency_ms,
+                    jitter_ms: args.chaos_latency_jitter,
+                    percent: args.chaos_latency_percent,
+                },
+                connections: ConnectionChaos {
+                    drop_percent: args.chaos_drop_percent,
+                    refuse_for_secs: args.chaos_refuse_for,
+                    refuse_every_secs: args.chaos_refuse_every,
+                },
+                corruption: Corruption {
+                    percent: args.chaos_corrupt_percent,
+                },
+            },
+            state.clone(),
+            spec.seed,
+        );
+        chaos.spawn();
+        // Always simulate, since simulations can appear when the map reloads
+        let simulations = spec.map.map(|map| map.simulations).unwrap_or_default();
+        Simulator::new(simulations, chaos.seed())
+            .spawn(state.clone(), Duration::from_millis(args.sim_tick_ms));
+        if let Some(path) = spec.map_path {
+            map_reload::watch(path, state.clone(), chaos.clone());
+        }
+        if let Some(replay) = replay {
+            replay.clone().spawn(state.clone(), args.replay_loop);
+        }
+        if let Some(scenario) = scenario {
+            scenario.clone().spawn(chaos.clone(), state.clone());
+        }
+
+        if spec.admin_port != 0 {
+            let bind = args.bind.clone();
+            let admin_chaos = chaos.clone();
+            let admin_state = state.clone();
+            let name = spec.name.clone();
+            tokio::spawn(async move {
+                if let Err(e) =
+                    start_admin_server(&bind, spec.admin_port, admin_chaos, admin_state).await
+                {
+                    error!("{}: admin API error: {}", name, e);
+                }
+            });
+        }
+
+        let bind = args.bind.clone();
+        let server_state = state.clone();
+        let name = spec.name.clone();
+        let tls = tls.cloned();
+        let limits = Limits::new(
+            args.max_connections.map(|max| max as usize),
+            args.max_requests_per_second,
+        );
+        if let Some(path) = args.serial.clone() {
+            #[cfg(unix)]
+            servers.spawn(async move {
+                mock_plc::rtu::start_rtu_server(
+                    &path,
+                    server_state,
+                    chaos,
+                    Arc::new(traffic),
+                    limits,
+                )
+                .await
+                .with_context(|| format!("{} failed", name))
+            });
+            #[cfg(not(unix))]
+            anyhow::bail!("--serial needs a Unix serial device or pseudo-terminal");
+        } else {
+            servers.spawn(async move {
+                start_server(
+                    &bind,
+                    spec.port,
+                    server_state,
+                    chaos,
+                    Arc::new(traffic),
+                    limits,
+                    tls,
+                )
+                .await
+                .with_context(|| format!("{} failed", name))
+            });
+        }
+
+        Ok(Self {
+            name: spec.name,
+            state,
+            state_file,
+        })
+    }
+
+    /// Write the state file, if any
+    pub fn save(&self) -> anyhow::Result<()> {
+        if let Some(ref state_file) = self.state_file {
+            state_file.save_now(&self.state)?;
+            info!("💾 {}: saved register state", self.name);
+        }
+        Ok(())
+    }
+}
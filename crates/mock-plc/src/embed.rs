@@ -0,0 +1,233 @@
+use crate::admin::serve_admin;
+use crate::chaos::{ChaosConfig, ChaosEngine, ConnectionChaos, Corruption, Latency};
+use crate::limits::Limits;
+use crate::register_map::RegisterMap;
+use crate::server::{serve, PLCState, RegisterKind};
+use crate::simulation::Simulator;
+use crate::traffic::TrafficRecorder;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::task::{JoinHandle, JoinSet};
+use tracing::error;
+
+/// Settings of an embedded mock PLC. The defaults match the `mock-plc`
+/// binary, except that it listens on a free port of the loopback interface.
+#[derive(Clone, Debug)]
+pub struct MockConfig {
+    pub bind: IpAddr,
+    /// Modbus port; 0 picks a free one
+    pub port: u16,
+    /// Initial value of `register`
+    pub value: u16,
+    pub register: u16,
+    /// Unit IDs with their own registers; any unit ID is served when empty
+    pub units: Vec<u8>,
+    pub map: Option<RegisterMap>,
+    /// Chaos settings; drift is off and targets `register` when omitted
+    pub chaos: Option<ChaosConfig>,
+    /// Seed for chaos randomness, random when omitted
+    pub seed: Option<u64>,
+    pub max_connections: Option<usize>,
+    pub max_requests_per_second: Option<u32>,
+    /// Also serve the HTTP admin API on a free port
+    pub admin: bool,
+}
+
+impl Default for MockConfig {
+    fn default() -> Self {
+        Self {
+            bind: IpAddr::V4(Ipv4Addr::LOCALHOST),
+            port: 0,
+            value: 2500,
+            register: 4001,
+            units: Vec::new(),
+            map: None,
+            chaos: None,
+            seed: None,
+            max_connections: None,
+            max_requests_per_second: None,
+            admin: false,
+        }
+    }
+}
+
+/// A running embedded mock PLC, stopped by [`MockHandle::stop`] or on drop
+pub struct MockHandle {
+    addr: SocketAddr,
+    admin_addr: Option<SocketAddr>,
+    state: Arc<Mutex<PLCState>>,
+    chaos: ChaosEngine,
+    tasks: JoinSet<()>,
+    /// Background loops started by the chaos engine and simulator
+    loops: Vec<JoinHandle<()>>,
+}
+
+/// Start a mock PLC in the current Tokio runtime. The Modbus listener is
+/// bound when this returns, so clients can connect to [`MockHandle::addr`]
+/// straight away.
+pub async fn spawn(config: MockConfig) -> anyhow::Result<MockHandle> {
+    let mut state = PLCState::new(config.value, config.register, &config.units);
+    if let Some(ref map) = config.map {
+        state.load_map(map);
+    }
+    let state = Arc::new(Mutex::new(state));
+
+    let chaos_config = config.chaos.unwrap_or_else(|| {
+        let mut registers = vec![config.register];
+        if let Some(ref map) = config.map {
+            registers.extend(map.holding_registers.keys());
+        }
+        registers.sort_unstable();
+        registers.dedup();
+        ChaosConfig {
+            enabled: false,
+            interval_secs: 10,
+            max_drift: 500,
+            registers,
+            latency: Latency::default(),
+            connections: ConnectionChaos::default(),
+            corruption: Corruption::default(),
+        }
+    });
+    let chaos = ChaosEngine::new(chaos_config, state.clone(), config.seed);
+    let simulations = config.map.map(|map| map.simulations).unwrap_or_default();
+    let loops = vec![
+        chaos.spawn(),
+        Simulator::new(simulations, chaos.seed()).spawn(state.clone(), Duration::from_secs(1)),
+    ];
+
+    let mut tasks = JoinSet::new();
+    let listener = TcpListener::bind((config.bind, config.port)).await?;
+    let addr = listener.local_addr()?;
+    let limits = Limits::new(config.max_connections, config.max_requests_per_second);
+    tasks.spawn(log_failure(
+        "Modbus server",
+        serve(
+            listener,
+            state.clone(),
+            chaos.clone(),
+            Arc::new(TrafficRecorder::create(None, None)?),
+            limits,
+            None,
+        ),
+    ));
+
+    let admin_addr = if config.admin {
+        let listener = TcpListener::bind((config.bind, 0)).await?;
+        let admin_addr = listener.local_addr()?;
+        tasks.spawn(log_failure(
+            "Admin API",
+            serve_admin(listener, chaos.clone(), state.clone()),
+        ));
+        Some(admin_addr)
+    } else {
+        None
+    };
+
+    Ok(MockHandle {
+        addr,
+        admin_addr,
+        state,
+        chaos,
+        tasks,
+        loops,
+    })
+}
+
+async fn log_failure(name: &str, task: impl std::future::Future<Output = anyhow::Result<()>>) {
+    if let Err(e) = task.await {
+        error!("{} failed: {}", name, e);
+    }
+}
+
+impl MockHandle {
+    /// Address of the Modbus TCP listener
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    pub fn port(&self) -> u16 {
+        self.addr.port()
+    }
+
+    /// Address of the HTTP admin API, when enabled
+    pub fn admin_addr(&self) -> Option<SocketAddr> {
+        self.admin_addr
+    }
+
+    /// Holding register of the lowest unit
+    pub fn holding(&self, address: u16) -> u16 {
+        self.state.lock().expect("state lock").holding(address)
+    }
+
+    /// Set a holding register of the lowest unit, bypassing faults and
+    /// read-only ranges
+    pub fn set_holding(&self, address: u16, value: u16) {
+        self.write(None, RegisterKind::Holding, address, &[value]);
+    }
+
+    /// Read values of a unit (the lowest when `None`); coils and discrete
+    /// inputs read as 0 or 1. `None` past the address space or for an
+    /// unknown unit.
+    pub fn read(
+        &self,
+        unit: Option<u8>,
+        kind: RegisterKind,
+        address: u16,
+        count: u16,
+    ) -> Option<Vec<u16>> {
+        let state = self.state.lock().expect("state lock");
+        let unit = unit.unwrap_or_else(|| state.primary_unit());
+        state.unit(unit)?.read(kind, address, count)
+    }
+
+    /// Write values of a unit (the lowest when `None`) like the admin API
+    /// does; `false` past the address space or for an unknown unit
+    pub fn write(
+        &self,
+        unit: Option<u8>,
+        kind: RegisterKind,
+        address: u16,
+        values: &[u16],
+    ) -> bool {
+        let mut state = self.state.lock().expect("state lock");
+        let unit = unit.unwrap_or_else(|| state.primary_unit());
+        state
+            .unit_mut(unit)
+            .is_some_and(|registers| registers.write(kind, address, values))
+    }
+
+    /// Modbus requests received since startup
+    pub fn requests(&self) -> u64 {
+        self.state.lock().expect("state lock").requests()
+    }
+
+    /// Chaos engine, to enable drift or inject latency and connection chaos
+    pub fn chaos(&self) -> &ChaosEngine {
+        &self.chaos
+    }
+
+    /// Shared register state, for anything the other methods do not cover
+    pub fn state(&self) -> &Arc<Mutex<PLCState>> {
+        &self.state
+    }
+
+    /// Stop accepting connections and wait for the listeners to close.
+    /// Connections already open are served until the client closes them.
+    pub async fn stop(mut self) {
+        for task in &self.loops {
+            task.abort();
+        }
+        self.tasks.shutdown().await;
+    }
+}
+
+impl Drop for MockHandle {
+    fn drop(&mut self) {
+        for task in &self.loops {
+            task.abort();
+        }
+    }
+}
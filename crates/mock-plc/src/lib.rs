@@ -0,0 +1,21 @@
+//! Mock PLC with chaos mode for FabGitOps testing. The `mock-plc` binary is
+//! built on this library; tests can embed a device with [`spawn`].
+
+pub mod admin;
+pub mod chaos;
+mod embed;
+pub mod limits;
+pub mod map_reload;
+pub mod register_map;
+pub mod replay;
+#[cfg(unix)]
+pub mod rtu;
+pub mod scenario;
+pub mod server;
+pub mod simulation;
+pub mod state_file;
+pub mod tls;
+pub mod traffic;
+
+pub use embed::{spawn, MockConfig, MockHandle};
+pub use server::RegisterKind;
@@ -0,0 +1,64 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Connection and request-rate limits of one device, emulating the small
+/// TCP stacks of real PLCs
+pub struct Limits {
+    max_connections: Option<usize>,
+    connections: AtomicUsize,
+    max_requests_per_second: Option<u32>,
+    /// Start of the current one-second window and requests served in it
+    window: Mutex<(Instant, u32)>,
+}
+
+/// An open connection counted against `--max-connections`, released on drop
+pub struct ConnectionSlot(Arc<Limits>);
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.0.connections.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl Limits {
+    pub fn new(max_connections: Option<usize>, max_requests_per_second: Option<u32>) -> Arc<Self> {
+        Arc::new(Self {
+            max_connections,
+            connections: AtomicUsize::new(0),
+            max_requests_per_second,
+            window: Mutex::new((Instant::now(), 0)),
+        })
+    }
+
+    /// A slot for a new connection, or `None` when all are in use
+    pub fn connect(self: &Arc<Self>) -> Option<ConnectionSlot> {
+        let open = self.connections.fetch_add(1, Ordering::Relaxed);
+        // Take the slot first so it is released on every path
+        let slot = ConnectionSlot(self.clone());
+        match self.max_connections {
+            Some(max) if open >= max => None,
+            _ => Some(slot),
+        }
+    }
+
+    /// Count a request against the rate limit; `false` when it is over
+    pub fn allow_request(&self) -> bool {
+        let Some(max) = self.max_requests_per_second else {
+            return true;
+        };
+        let Ok(mut window) = self.window.lock() else {
+            return true;
+        };
+        let (ref mut start, ref mut count) = *window;
+        if start.elapsed() >= Duration::from_secs(1) {
+            *start = Instant::now();
+            *count = 0;
+        }
+        if *count >= max {
+            return false;
+        }
+        *count += 1;
+        true
+    }
+}
@@ -1,10 +1,13 @@
-mod chaos;
-mod server;
+mod devices;
 
-use crate::chaos::{ChaosConfig, ChaosEngine};
-use crate::server::{start_server, PLCState};
-use clap::Parser;
-use std::sync::{Arc, Mutex};
+use crate::devices::{Device, DeviceSpec};
+use clap::{Parser, ValueEnum};
+use mock_plc::register_map::RegisterMap;
+use mock_plc::replay::Timeline;
+use mock_plc::scenario::Scenario;
+use mock_plc::tls;
+use std::path::PathBuf;
+use tokio::task::JoinSet;
 use tracing::{info, Level};
 
 #[derive(Parser, Debug)]
@@ -24,6 +27,19 @@ struct Args {
     #[arg(short, long, default_value = "4001")]
     register: u16,
 
+    /// Answer only these unit IDs, each with its own registers, like a
+    /// serial gateway (e.g. `--units 1,2,3`). By default any unit ID is served.
+    #[arg(long, value_delimiter = ',')]
+    units: Vec<u8>,
+
+    /// YAML or JSON file with initial holding-register values
+    #[arg(long)]
+    map: Option<PathBuf>,
+
+    /// Milliseconds between updates of the registers simulated by `--map`
+    #[arg(long, default_value = "1000", value_parser = clap::value_parser!(u64).range(1..))]
+    sim_tick_ms: u64,
+
     /// Enable chaos mode (random drift)
     #[arg(long)]
     chaos: bool,
@@ -35,6 +51,119 @@ struct Args {
     /// Maximum drift amount
     #[arg(long, default_value = "500")]
     max_drift: u16,
+
+    /// Delay Modbus responses by this many milliseconds
+    #[arg(long, default_value = "0")]
+    chaos_latency_ms: u64,
+
+    /// Add a random extra delay of up to this many milliseconds
+    #[arg(long, default_value = "0")]
+    chaos_latency_jitter: u64,
+
+    /// Percentage of responses to delay
+    #[arg(long, default_value = "100", value_parser = clap::value_parser!(u8).range(0..=100))]
+    chaos_latency_percent: u8,
+
+    /// Percentage of Modbus requests answered by closing the connection instead
+    #[arg(long, default_value = "0", value_parser = clap::value_parser!(u8).range(0..=100))]
+    chaos_drop_percent: u8,
+
+    /// Percentage of Modbus responses sent with a wrong length, a garbage
+    /// payload, or the wrong function code
+    #[arg(long, default_value = "0", value_parser = clap::value_parser!(u8).range(0..=100))]
+    chaos_corrupt_percent: u8,
+
+    /// Refuse new connections for this many seconds of every `--chaos-refuse-every`
+    #[arg(long, default_value = "0")]
+    chaos_refuse_for: u64,
+
+    /// Length in seconds of the connection refusal cycle
+    #[arg(long, default_value = "60")]
+    chaos_refuse_every: u64,
+
+    /// Seed for chaos randomness, to replay a run exactly (random by default)
+    #[arg(long)]
+    chaos_seed: Option<u64>,
+
+    /// YAML or JSON file with a timed sequence of chaos events
+    #[arg(long)]
+    scenario: Option<PathBuf>,
+
+    /// Record every Modbus transaction to this JSON Lines file
+    #[arg(long)]
+    log_requests: Option<PathBuf>,
+
+    /// Capture Modbus traffic to this pcap file, for Wireshark
+    #[arg(long)]
+    capture: Option<PathBuf>,
+
+    /// Serve register values following a `--log-requests` file or a CSV of
+    /// `timestamp,register,value` rows, on the recording's timeline
+    #[arg(long)]
+    replay: Option<PathBuf>,
+
+    /// Start the replay over when it reaches the end
+    #[arg(long, requires = "replay")]
+    replay_loop: bool,
+
+    /// JSON file keeping register values across restarts: loaded at startup
+    /// (over `--map`), written on change and on shutdown
+    #[arg(long)]
+    state_file: Option<PathBuf>,
+
+    /// Refuse connections beyond this many open at once, like a PLC with a
+    /// small TCP stack
+    #[arg(long, value_parser = clap::value_parser!(u64).range(1..))]
+    max_connections: Option<u64>,
+
+    /// Answer requests beyond this many per second with exception 0x06
+    /// (Server Device Busy)
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    max_requests_per_second: Option<u32>,
+
+    /// Serve Modbus on this serial device instead of TCP, e.g. one end of a
+    /// pseudo-terminal pair from `socat`
+    #[arg(long, conflicts_with_all = ["devices", "instances", "base_port", "tls_cert", "max_connections"])]
+    serial: Option<PathBuf>,
+
+    /// Framing used on `--serial`
+    #[arg(long, value_enum, default_value = "rtu", requires = "serial")]
+    framing: Framing,
+
+    /// Port for the HTTP admin API used by `fabctl chaos` (0 disables it)
+    #[arg(long, default_value = "9502")]
+    admin_port: u16,
+
+    /// PEM certificate chain; serves Modbus/TCP Security (TLS) instead of plain TCP
+    #[arg(long, requires = "tls_key")]
+    tls_cert: Option<PathBuf>,
+
+    /// PEM private key of `--tls-cert`
+    #[arg(long, requires = "tls_cert")]
+    tls_key: Option<PathBuf>,
+
+    /// PEM CA certificates; clients must present a certificate signed by one
+    #[arg(long, requires = "tls_cert")]
+    tls_client_ca: Option<PathBuf>,
+
+    /// Simulate this many independent PLCs on consecutive ports, with admin
+    /// APIs on consecutive ports from `--admin-port`
+    #[arg(long, default_value = "1", value_parser = clap::value_parser!(u16).range(1..))]
+    instances: u16,
+
+    /// First Modbus port of `--instances` (defaults to `--port`)
+    #[arg(long)]
+    base_port: Option<u16>,
+
+    /// YAML file listing devices to simulate, each with its own port and map
+    #[arg(long, conflicts_with_all = ["instances", "base_port"])]
+    devices: Option<PathBuf>,
+}
+
+/// Modbus framing on a serial line
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum Framing {
+    Rtu,
 }
 
 #[tokio::main]
@@ -42,20 +171,105 @@ async fn main() -> anyhow::Result<()> {
     tracing_subscriber::fmt().with_max_level(Level::INFO).init();
 
     let args = Args::parse();
+    let map = args.map.as_deref().map(RegisterMap::load).transpose()?;
+    let scenario = args.scenario.as_deref().map(Scenario::load).transpose()?;
+    let replay = args.replay.as_deref().map(Timeline::load).transpose()?;
+    let tls = match (&args.tls_cert, &args.tls_key) {
+        (Some(cert), Some(key)) => Some(tls::acceptor(cert, key, args.tls_client_ca.as_deref())?),
+        _ => None,
+    };
+    let specs = match (&args.devices, args.instances) {
+        (Some(path), _) => DeviceSpec::load(&args, path, map.clone())?,
+        (None, 1) => vec![DeviceSpec::from_args(&args, map.clone())],
+        (None, count) => DeviceSpec::instances(&args, map.clone(), count)?,
+    };
 
     info!("╔══════════════════════════════════════╗");
     info!("║     FabGitOps Mock PLC Server        ║");
     info!("╚══════════════════════════════════════╝");
     info!("");
     info!("Configuration:");
-    info!("  Bind Address: {}:{}", args.bind, args.port);
-    info!("  Register: {}", args.register);
-    info!("  Initial Value: {}", args.value);
+    if let [spec] = &specs[..] {
+        match args.serial {
+            Some(ref path) => {
+                let framing = match args.framing {
+                    Framing::Rtu => "RTU",
+                };
+                info!("  Serial Device: {} ({})", path.display(), framing);
+            }
+            None => info!("  Bind Address: {}:{}", args.bind, spec.port),
+        }
+        if !spec.units.is_empty() {
+            let units: Vec<_> = spec.units.iter().map(u8::to_string).collect();
+            info!("  Units: {}", units.join(", "));
+        }
+        info!("  Register: {}", spec.register);
+        info!("  Initial Value: {}", spec.value);
+    } else {
+        info!("  Devices: {}", specs.len());
+        for spec in &specs {
+            let admin = match spec.admin_port {
+                0 => String::new(),
+                port => format!(", admin {}", port),
+            };
+            info!(
+                "    {}: {}:{}{} (register {} = {})",
+                spec.name, args.bind, spec.port, admin, spec.register, spec.value
+            );
+        }
+    }
+    if let (Some(path), Some(map)) = (&args.map, &map) {
+        info!("  Register Map: {} ({} entries)", path.display(), map.len());
+    }
+    if let (Some(path), Some(scenario)) = (&args.scenario, &scenario) {
+        info!(
+            "  Scenario: {} ({} events)",
+            path.display(),
+            scenario.events.len()
+        );
+    }
     info!(
         "  Chaos Mode: {}",
         if args.chaos { "ENABLED" } else { "disabled" }
     );
 
+    if let (Some(path), Some(replay)) = (&args.replay, &replay) {
+        info!(
+            "  Replay: {} ({} values over {}s{})",
+            path.display(),
+            replay.len(),
+            replay.duration().as_secs(),
+            if args.replay_loop { ", looped" } else { "" }
+        );
+    }
+    if let Some(max) = args.max_connections {
+        info!("  Max Connections: {}", max);
+    }
+    if let Some(max) = args.max_requests_per_second {
+        info!("  Max Requests/s: {}", max);
+    }
+    if let Some(ref path) = args.tls_cert {
+        info!("  TLS Certificate: {}", path.display());
+    }
+    if let Some(ref path) = args.tls_client_ca {
+        info!("  TLS Client CA: {}", path.display());
+    }
+    if let Some(ref path) = args.state_file {
+        info!("  State File: {}", path.display());
+    }
+    if let Some(ref path) = args.log_requests {
+        info!("  Request Log: {}", path.display());
+    }
+    if let Some(ref path) = args.capture {
+        info!("  Traffic Capture: {}", path.display());
+    }
+
+    if let [spec] = &specs[..] {
+        if spec.admin_port != 0 {
+            info!("  Admin API: {}:{}", args.bind, spec.admin_port);
+        }
+    }
+
     if args.chaos {
         info!("  Chaos Interval: {}s", args.chaos_interval);
         info!("  Max Drift: {}", args.max_drift);
@@ -63,21 +277,52 @@ async fn main() -> anyhow::Result<()> {
 
     info!("");
 
-    let state = Arc::new(Mutex::new(PLCState::new(args.value, args.register)));
-
-    // Start chaos engine if enabled
-    let _chaos = if args.chaos {
-        let register_value = Arc::new(std::sync::Mutex::new(args.value));
-        let chaos = ChaosEngine::new(ChaosConfig {
-            enabled: true,
-            interval_secs: args.chaos_interval,
-            max_drift: args.max_drift,
-        });
-        chaos.spawn(register_value.clone());
-        Some(chaos)
-    } else {
-        None
-    };
+    let mut servers = JoinSet::new();
+    let devices = specs
+        .into_iter()
+        .map(|spec| {
+            Device::start(
+                spec,
+                &args,
+                scenario.as_ref(),
+                replay.as_ref(),
+                tls.as_ref(),
+                &mut servers,
+            )
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
 
-    start_server(&args.bind, args.port, state).await
+    tokio::select! {
+        Some(result) = servers.join_next() => result??,
+        _ = shutdown_signal() => info!("Shutting down"),
+    }
+
+    for device in &devices {
+        device.save()?;
+    }
+    Ok(())
+}
+
+/// Resolve on Ctrl-C, or on SIGTERM as sent by `docker stop` and Kubernetes
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = terminate.recv() => {}
+                }
+            }
+            Err(_) => {
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
 }
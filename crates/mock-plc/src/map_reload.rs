@@ -0,0 +1,62 @@
+use crate::chaos::ChaosEngine;
+use crate::register_map::RegisterMap;
+use crate::server::PLCState;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use tokio::task::JoinHandle;
+use tokio::time::{interval, Duration};
+use tracing::{info, warn};
+
+/// How often a `--map` file is checked for changes
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Switch a running device to a changed register map: new and changed
+/// values are applied, and chaos drifts the holding registers it lists.
+/// Returns how many values were applied in each unit.
+pub fn apply(state: &Mutex<PLCState>, chaos: &ChaosEngine, map: RegisterMap) -> usize {
+    let Ok(mut state) = state.lock() else {
+        return 0;
+    };
+    let mut registers = BTreeSet::from([state.register_address]);
+    registers.extend(map.holding_registers.keys());
+    let applied = state.reload_map(map);
+    drop(state);
+
+    chaos.set_registers(registers.into_iter().collect());
+    applied
+}
+
+/// Reload the register map whenever its file changes. An invalid file is
+/// reported and the current map kept.
+pub fn watch(path: PathBuf, state: Arc<Mutex<PLCState>>, chaos: ChaosEngine) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut last_modified = modified(&path);
+        let mut ticks = interval(POLL_INTERVAL);
+        loop {
+            ticks.tick().await;
+            let current = modified(&path);
+            if current == last_modified {
+                continue;
+            }
+            last_modified = current;
+
+            match RegisterMap::load(&path) {
+                Ok(map) => {
+                    let applied = apply(&state, &chaos, map);
+                    info!(
+                        "🗺️  Reloaded register map {} ({} value(s) applied)",
+                        path.display(),
+                        applied
+                    );
+                }
+                Err(e) => warn!("Keeping the current register map: {:#}", e),
+            }
+        }
+    })
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
@@ -0,0 +1,217 @@
+use crate::server::RegisterKind;
+use anyhow::Context;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Initial register values loaded with `--map`, e.g.
+///
+/// ```yaml
+/// holdingRegisters:
+///   4001: 2500
+///   4002: 1200
+/// inputRegisters:
+///   3001: 215
+/// coils:
+///   1: true
+/// discreteInputs:
+///   10001: true
+/// readOnly:
+///   holdingRegisters: ["4100-4199", 4200]
+/// simulations:
+///   - register: 3001
+///     firstOrderLag: { setpoint: 4002, timeConstantSecs: 60 }
+/// ```
+///
+/// JSON files work too, since YAML is a superset of JSON.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct RegisterMap {
+    #[serde(default)]
+    pub holding_registers: BTreeMap<u16, u16>,
+
+    #[serde(default)]
+    pub input_registers: BTreeMap<u16, u16>,
+
+    #[serde(default)]
+    pub coils: BTreeMap<u16, bool>,
+
+    #[serde(default)]
+    pub discrete_inputs: BTreeMap<u16, bool>,
+
+    /// Address ranges whose Modbus writes fail with Illegal Data Address
+    #[serde(default)]
+    pub read_only: ReadOnly,
+
+    /// Registers driven by a process model instead of holding still
+    #[serde(default)]
+    pub simulations: Vec<Simulation>,
+}
+
+/// Write-protected ranges of the two writable data models
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct ReadOnly {
+    #[serde(default)]
+    pub holding_registers: Vec<AddressRange>,
+
+    #[serde(default)]
+    pub coils: Vec<AddressRange>,
+}
+
+/// Inclusive address range, written as `4100-4199` or a single address
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(try_from = "RangeSpec")]
+pub struct AddressRange {
+    pub start: u16,
+    pub end: u16,
+}
+
+impl AddressRange {
+    /// Whether any of `count` addresses from `address` falls in the range
+    pub fn overlaps(&self, address: u16, count: usize) -> bool {
+        let first = address as usize;
+        let last = first + count.max(1) - 1;
+        first <= self.end as usize && last >= self.start as usize
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RangeSpec {
+    Single(u16),
+    Range(String),
+}
+
+impl TryFrom<RangeSpec> for AddressRange {
+    type Error = String;
+
+    fn try_from(spec: RangeSpec) -> Result<Self, Self::Error> {
+        let text = match spec {
+            RangeSpec::Single(address) => {
+                return Ok(Self {
+                    start: address,
+                    end: address,
+                })
+            }
+            RangeSpec::Range(text) => text,
+        };
+        let invalid = || format!("invalid address range '{}', expected e.g. 4100-4199", text);
+        let (start, end) = match text.split_once('-') {
+            Some((start, end)) => (start.trim(), end.trim()),
+            None => (text.trim(), text.trim()),
+        };
+        let start: u16 = start.parse().map_err(|_| invalid())?;
+        let end: u16 = end.parse().map_err(|_| invalid())?;
+        if start > end {
+            return Err(invalid());
+        }
+        Ok(Self { start, end })
+    }
+}
+
+/// A register following a process model. The value starts from the map (or
+/// zero) and is updated in every unit on each simulation tick.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Simulation {
+    pub register: u16,
+    /// `input` (the default) or `holding`
+    #[serde(default = "default_simulation_kind")]
+    pub kind: RegisterKind,
+    #[serde(flatten)]
+    pub model: SimulationModel,
+}
+
+impl Simulation {
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if !matches!(self.kind, RegisterKind::Holding | RegisterKind::Input) {
+            return Err("only holding and input registers can be simulated");
+        }
+        match self.model {
+            SimulationModel::FirstOrderLag {
+                time_constant_secs, ..
+            } if !time_constant_secs.is_finite() || time_constant_secs <= 0.0 => {
+                Err("timeConstantSecs must be positive")
+            }
+            SimulationModel::Sine { period_secs, .. }
+                if !period_secs.is_finite() || period_secs <= 0.0 =>
+            {
+                Err("periodSecs must be positive")
+            }
+            SimulationModel::Sine { min, max, .. }
+            | SimulationModel::RandomWalk { min, max, .. }
+                if min > max =>
+            {
+                Err("min must not exceed max")
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+fn default_simulation_kind() -> RegisterKind {
+    RegisterKind::Input
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SimulationModel {
+    /// Approach the value of the `setpoint` holding register, covering about
+    /// 63% of the remaining distance every `time_constant_secs`
+    #[serde(rename_all = "camelCase")]
+    FirstOrderLag {
+        setpoint: u16,
+        time_constant_secs: f64,
+    },
+    /// Oscillate between `min` and `max` once every `period_secs`
+    #[serde(rename_all = "camelCase")]
+    Sine {
+        min: u16,
+        max: u16,
+        period_secs: f64,
+    },
+    /// Move by up to `step` either way each tick, staying within `min..=max`
+    RandomWalk { min: u16, max: u16, step: u16 },
+}
+
+impl RegisterMap {
+    /// Number of entries across all four data models
+    pub fn len(&self) -> usize {
+        self.holding_registers.len()
+            + self.input_registers.len()
+            + self.coils.len()
+            + self.discrete_inputs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Read a register map from a YAML or JSON file
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read register map {}", path.display()))?;
+        Self::parse(&content).with_context(|| format!("Invalid register map {}", path.display()))
+    }
+
+    /// Parse and check a register map in YAML or JSON
+    pub fn parse(content: &str) -> anyhow::Result<Self> {
+        // JSON object keys are strings, which only serde_json reads as addresses
+        let map: Self = if content.trim_start().starts_with('{') {
+            serde_json::from_str(content)?
+        } else {
+            serde_yaml::from_str(content)?
+        };
+        for simulation in &map.simulations {
+            simulation.validate().map_err(|e| {
+                anyhow::anyhow!(
+                    "Invalid simulation of register {}: {}",
+                    simulation.register,
+                    e
+                )
+            })?;
+        }
+        Ok(map)
+    }
+}
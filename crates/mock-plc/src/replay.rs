@@ -0,0 +1,239 @@
+use crate::server::{PLCState, RegisterKind};
+use crate::traffic::{Outcome, Transaction};
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tokio::time::{sleep_until, Duration, Instant};
+use tracing::{info, warn};
+
+/// Register values played back by `--replay`, ordered by time
+#[derive(Clone)]
+pub struct Timeline {
+    points: Vec<Point>,
+}
+
+/// Values a device held at one moment of a recording
+#[derive(Clone)]
+struct Point {
+    /// Time since the start of the recording
+    offset: Duration,
+    /// Unit to update; the lowest unit when not recorded
+    unit: Option<u8>,
+    kind: RegisterKind,
+    address: u16,
+    values: Vec<u16>,
+}
+
+impl Timeline {
+    /// Load a `--log-requests` transaction log, or a CSV file when the
+    /// extension is `.csv`
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read replay file {}", path.display()))?;
+        let is_csv = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+        let points = if is_csv {
+            from_csv(&content)
+        } else {
+            from_transactions(&content)
+        }
+        .with_context(|| format!("Invalid replay file {}", path.display()))?;
+
+        if points.is_empty() {
+            anyhow::bail!("Replay file {} has no register values", path.display());
+        }
+        Ok(Self { points })
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Length of the recording
+    pub fn duration(&self) -> Duration {
+        self.points.last().map(|p| p.offset).unwrap_or_default()
+    }
+
+    /// Apply the recorded values on their original schedule, starting now,
+    /// and start over at the end when `looped`
+    pub fn spawn(self, state: Arc<Mutex<PLCState>>, looped: bool) {
+        tokio::spawn(async move {
+            loop {
+                let start = Instant::now();
+                for point in &self.points {
+                    sleep_until(start + point.offset).await;
+                    let Ok(mut plc) = state.lock() else {
+                        return;
+                    };
+                    let unit = point.unit.unwrap_or_else(|| plc.primary_unit());
+                    let written = plc
+                        .unit_mut(unit)
+                        .is_some_and(|r| r.write(point.kind, point.address, &point.values));
+                    if !written {
+                        warn!(
+                            "Replay skipped unit {} {:?} {}: unknown unit or address",
+                            unit, point.kind, point.address
+                        );
+                    }
+                }
+                if !looped {
+                    info!("⏯️  Replay finished");
+                    return;
+                }
+                // A recording shorter than a second would otherwise spin
+                sleep_until(start + self.duration().max(Duration::from_secs(1))).await;
+                info!("⏯️  Replay restarting");
+            }
+        });
+    }
+}
+
+/// Values read from or successfully written to the device in a transaction log
+fn from_transactions(content: &str) -> anyhow::Result<Vec<Point>> {
+    let mut recorded = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let transaction: Transaction =
+            serde_json::from_str(line).with_context(|| format!("line {}", index + 1))?;
+        let Some(address) = transaction.address else {
+            continue;
+        };
+        let (kind, values) = match (transaction.function, transaction.response) {
+            (0x01, Outcome::Values(values)) => (RegisterKind::Coil, values),
+            (0x02, Outcome::Values(values)) => (RegisterKind::Discrete, values),
+            (0x03, Outcome::Values(values)) => (RegisterKind::Holding, values),
+            (0x04, Outcome::Values(values)) => (RegisterKind::Input, values),
+            (0x05 | 0x0F, Outcome::Ok) => (RegisterKind::Coil, transaction.values),
+            (0x06 | 0x10, Outcome::Ok) => (RegisterKind::Holding, transaction.values),
+            _ => continue,
+        };
+        recorded.push((
+            transaction.timestamp,
+            transaction.unit,
+            kind,
+            address,
+            values,
+        ));
+    }
+
+    let Some(first) = recorded.iter().map(|r| r.0).min() else {
+        return Ok(Vec::new());
+    };
+    let mut points: Vec<Point> = recorded
+        .into_iter()
+        .map(|(timestamp, unit, kind, address, values)| Point {
+            offset: (timestamp - first).to_std().unwrap_or_default(),
+            unit: Some(unit),
+            kind,
+            address,
+            values,
+        })
+        .collect();
+    points.sort_by_key(|p| p.offset);
+    Ok(points)
+}
+
+/// Rows of `timestamp,register,value` with optional `kind` and `unit` columns,
+/// in any order after a header row. Timestamps are seconds since the start of
+/// the recording or RFC 3339 times; kinds default to holding.
+fn from_csv(content: &str) -> anyhow::Result<Vec<Point>> {
+    let mut lines = content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty());
+    let (_, header) = lines.next().context("missing header row")?;
+    let columns: Vec<String> = header
+        .split(',')
+        .map(|c| c.trim().to_ascii_lowercase())
+        .collect();
+    let column = |name: &str| columns.iter().position(|c| c == name);
+    let timestamp_col = column("timestamp").context("missing timestamp column")?;
+    let register_col = column("register").context("missing register column")?;
+    let value_col = column("value").context("missing value column")?;
+    let kind_col = column("kind");
+    let unit_col = column("unit");
+
+    enum Time {
+        Offset(f64),
+        At(DateTime<Utc>),
+    }
+
+    let mut rows = Vec::new();
+    for (index, line) in lines {
+        let line_no = index + 1;
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let field = |col: usize| {
+            fields
+                .get(col)
+                .copied()
+                .filter(|f| !f.is_empty())
+                .with_context(|| format!("line {}: missing field {}", line_no, columns[col]))
+        };
+
+        let timestamp = field(timestamp_col)?;
+        let time = match timestamp.parse::<f64>() {
+            Ok(secs) if secs.is_finite() && secs >= 0.0 => Time::Offset(secs),
+            _ => Time::At(
+                DateTime::parse_from_rfc3339(timestamp)
+                    .with_context(|| format!("line {}: invalid timestamp", line_no))?
+                    .with_timezone(&Utc),
+            ),
+        };
+        let address: u16 = field(register_col)?
+            .parse()
+            .with_context(|| format!("line {}: invalid register", line_no))?;
+        let value: u16 = field(value_col)?
+            .parse()
+            .with_context(|| format!("line {}: invalid value", line_no))?;
+        let kind = match kind_col.and_then(|col| fields.get(col).copied()) {
+            None | Some("") => RegisterKind::Holding,
+            Some(kind) => serde_json::from_value(serde_json::Value::String(kind.to_string()))
+                .with_context(|| {
+                    format!(
+                        "line {}: kind must be holding, input, coil, or discrete",
+                        line_no
+                    )
+                })?,
+        };
+        let unit = match unit_col.and_then(|col| fields.get(col).copied()) {
+            None | Some("") => None,
+            Some(unit) => Some(
+                unit.parse()
+                    .with_context(|| format!("line {}: invalid unit", line_no))?,
+            ),
+        };
+        rows.push((time, unit, kind, address, value));
+    }
+
+    let first = rows
+        .iter()
+        .filter_map(|row| match row.0 {
+            Time::At(at) => Some(at),
+            Time::Offset(_) => None,
+        })
+        .min();
+    let mut points: Vec<Point> = rows
+        .into_iter()
+        .map(|(time, unit, kind, address, value)| Point {
+            offset: match (time, first) {
+                (Time::Offset(secs), _) => Duration::from_secs_f64(secs),
+                (Time::At(at), Some(first)) => (at - first).to_std().unwrap_or_default(),
+                (Time::At(_), None) => Duration::ZERO,
+            },
+            unit,
+            kind,
+            address,
+            values: vec![value],
+        })
+        .collect();
+    points.sort_by_key(|p| p.offset);
+    Ok(points)
+}
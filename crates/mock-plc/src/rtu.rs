@@ -0,0 +1,240 @@
+use crate::chaos::ChaosEngine;
+use crate::limits::Limits;
+use crate::server::{exception, Listening, ModbusService, PLCState, ILLEGAL_DATA_VALUE};
+use crate::traffic::{encode_response, TrafficRecorder};
+use anyhow::Context;
+use std::borrow::Cow;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::unix::AsyncFd;
+use tokio_modbus::prelude::*;
+use tokio_modbus::server::Service;
+use tracing::{info, warn};
+
+/// Silence that ends a frame whose length cannot be told from its header.
+/// Real devices use 3.5 character times; a pty has no baud rate to derive
+/// it from.
+const FRAME_GAP: Duration = Duration::from_millis(20);
+
+/// Largest RTU frame: address, 253-byte PDU and CRC
+const MAX_FRAME: usize = 256;
+
+/// Serve Modbus RTU on a serial device, usually one end of a pseudo-terminal
+/// pair, as the slave addressed by the configured unit IDs
+pub async fn start_rtu_server(
+    path: &Path,
+    state: Arc<Mutex<PLCState>>,
+    chaos: ChaosEngine,
+    traffic: Arc<TrafficRecorder>,
+    limits: Arc<Limits>,
+) -> anyhow::Result<()> {
+    let port = SerialPort::open(path)
+        .with_context(|| format!("Failed to open serial device {}", path.display()))?;
+    info!("Starting mock PLC RTU slave on {}", path.display());
+    let _listening = Listening::start(&state);
+
+    let service = ModbusService::serial(state.clone(), chaos, traffic, limits);
+    let mut buffer = Vec::with_capacity(MAX_FRAME);
+    let mut chunk = [0u8; MAX_FRAME];
+    loop {
+        if let Some(len) = frame_length(&buffer).filter(|&len| len <= buffer.len()) {
+            let frame: Vec<u8> = buffer.drain(..len).collect();
+            answer(&port, &service, &state, &frame).await?;
+            continue;
+        }
+
+        let read = if buffer.is_empty() {
+            port.read(&mut chunk).await
+        } else {
+            match tokio::time::timeout(FRAME_GAP, port.read(&mut chunk)).await {
+                Ok(read) => read,
+                Err(_) => {
+                    // Silence ends the frame; the CRC tells whether it is whole
+                    let frame = std::mem::take(&mut buffer);
+                    answer(&port, &service, &state, &frame).await?;
+                    continue;
+                }
+            }
+        };
+        let read = read.with_context(|| format!("Failed to read {}", path.display()))?;
+        buffer.extend_from_slice(&chunk[..read]);
+        if buffer.len() > MAX_FRAME {
+            warn!("Discarding {} bytes of RTU line noise", buffer.len());
+            buffer.clear();
+        }
+    }
+}
+
+/// Handle one received frame. Like a real slave, frames with a bad CRC,
+/// for other units, or broadcast to unit 0 get no answer.
+async fn answer(
+    port: &SerialPort,
+    service: &ModbusService,
+    state: &Mutex<PLCState>,
+    frame: &[u8],
+) -> anyhow::Result<()> {
+    let Some((body, checksum)) = frame
+        .split_last_chunk::<2>()
+        .filter(|(body, _)| body.len() >= 2)
+    else {
+        warn!("Discarding {}-byte RTU frame", frame.len());
+        return Ok(());
+    };
+    if crc16(body).to_le_bytes() != *checksum {
+        warn!("Discarding RTU frame with a bad CRC");
+        return Ok(());
+    }
+    let (&slave, pdu) = body.split_first().expect("checked length");
+    let addressed = slave == 0
+        || state
+            .lock()
+            .map(|state| state.unit(slave).is_some())
+            .unwrap_or(false);
+    if !addressed {
+        return Ok(());
+    }
+
+    let response = match decode_request(pdu) {
+        // A chaos drop leaves the request unanswered
+        Some(request) => match service.call(SlaveRequest { slave, request }).await {
+            Ok(response) => response,
+            Err(_) => return Ok(()),
+        },
+        None => exception(pdu[0], ILLEGAL_DATA_VALUE),
+    };
+    if slave == 0 {
+        return Ok(());
+    }
+
+    let mut reply = vec![slave];
+    reply.extend(encode_response(&response));
+    reply.extend(crc16(&reply).to_le_bytes());
+    port.write_all(&reply)
+        .await
+        .context("Failed to write RTU response")
+}
+
+/// Length of the request frame starting `buffer`, when its function code
+/// tells it and enough of the header has arrived
+fn frame_length(buffer: &[u8]) -> Option<usize> {
+    match *buffer.get(1)? {
+        0x01..=0x06 => Some(8),
+        0x0F | 0x10 => Some(9 + *buffer.get(6)? as usize),
+        0x16 => Some(10),
+        0x17 => Some(13 + *buffer.get(10)? as usize),
+        _ => None,
+    }
+}
+
+/// Decode a request PDU; `None` when it is malformed. Unknown function codes
+/// decode as custom requests, which the service rejects.
+fn decode_request(pdu: &[u8]) -> Option<Request<'static>> {
+    let (&function, data) = pdu.split_first()?;
+    let word = |offset: usize| {
+        data.get(offset..offset + 2)
+            .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+    };
+    let request = match function {
+        0x01 => Request::ReadCoils(word(0)?, word(2)?),
+        0x02 => Request::ReadDiscreteInputs(word(0)?, word(2)?),
+        0x03 => Request::ReadHoldingRegisters(word(0)?, word(2)?),
+        0x04 => Request::ReadInputRegisters(word(0)?, word(2)?),
+        0x05 => {
+            let value = match word(2)? {
+                0xFF00 => true,
+                0x0000 => false,
+                _ => return None,
+            };
+            Request::WriteSingleCoil(word(0)?, value)
+        }
+        0x06 => Request::WriteSingleRegister(word(0)?, word(2)?),
+        0x0F => {
+            let count = word(2)? as usize;
+            let bytes = data.get(5..5 + count.div_ceil(8))?;
+            let coils = (0..count)
+                .map(|i| bytes[i / 8] >> (i % 8) & 1 == 1)
+                .collect();
+            Request::WriteMultipleCoils(word(0)?, Cow::Owned(coils))
+        }
+        0x10 => {
+            let values = (0..word(2)? as usize)
+                .map(|i| word(5 + 2 * i))
+                .collect::<Option<Vec<_>>>()?;
+            Request::WriteMultipleRegisters(word(0)?, Cow::Owned(values))
+        }
+        _ => Request::Custom(function, Cow::Owned(data.to_vec())),
+    };
+    Some(request)
+}
+
+/// Modbus RTU CRC-16, sent low byte first
+fn crc16(bytes: &[u8]) -> u16 {
+    bytes.iter().fold(0xFFFF, |crc, &byte| {
+        (0..8).fold(crc ^ u16::from(byte), |crc, _| {
+            if crc & 1 == 1 {
+                (crc >> 1) ^ 0xA001
+            } else {
+                crc >> 1
+            }
+        })
+    })
+}
+
+/// A serial device in raw, non-blocking mode
+struct SerialPort(AsyncFd<File>);
+
+impl SerialPort {
+    fn open(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .custom_flags(libc::O_NOCTTY | libc::O_NONBLOCK)
+            .open(path)?;
+        make_raw(&file)?;
+        // SAFETY: the `File` owns its descriptor and never swaps it
+        let fd = unsafe { AsyncFd::register(file)? };
+        Ok(Self(fd))
+    }
+
+    async fn read(&self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let mut guard = self.0.readable().await?;
+            if let Ok(result) = guard.try_io(|file| file.get_ref().read(buf)) {
+                return result;
+            }
+        }
+    }
+
+    async fn write_all(&self, mut buf: &[u8]) -> io::Result<()> {
+        while !buf.is_empty() {
+            let mut guard = self.0.writable().await?;
+            if let Ok(written) = guard.try_io(|file| file.get_ref().write(buf)) {
+                buf = &buf[written?..];
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Put a terminal into raw mode: no echo, line editing or byte translation
+fn make_raw(file: &File) -> io::Result<()> {
+    let fd = file.as_raw_fd();
+    // SAFETY: `fd` stays open for the life of `file`, and termios is plain
+    // data that tcgetattr fills in before it is read
+    unsafe {
+        let mut termios = std::mem::zeroed::<libc::termios>();
+        if libc::tcgetattr(fd, &mut termios) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        libc::cfmakeraw(&mut termios);
+        if libc::tcsetattr(fd, libc::TCSANOW, &termios) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,198 @@
+use crate::chaos::{ChaosEngine, ConnectionChaos, Corruption, Latency};
+use crate::server::{Fault, PLCState, RegisterFaultMode};
+use anyhow::Context;
+use serde::Deserialize;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tokio::time::{sleep_until, Duration, Instant};
+use tracing::{info, warn};
+
+/// A timed sequence of chaos events loaded with `--scenario`, e.g.
+///
+/// ```yaml
+/// events:
+///   - atSecs: 30
+///     drift: { register: 4001, amount: 300 }
+///   - atSecs: 60
+///     connections: { dropPercent: 100 }
+///   - atSecs: 90
+///     connections: { dropPercent: 0 }
+///   - atSecs: 120
+///     fault: { exceptionCode: 4, remaining: 20 }
+/// ```
+///
+/// Times are seconds after the server starts. Events with the same time run
+/// in file order.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct Scenario {
+    pub events: Vec<ScenarioEvent>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScenarioEvent {
+    pub at_secs: u64,
+    #[serde(flatten)]
+    pub action: ScenarioAction,
+}
+
+/// What happens at a point of the scenario; mirrors the admin API
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ScenarioAction {
+    /// Drift a holding register; random register and amount when omitted
+    Drift {
+        register: Option<u16>,
+        amount: Option<i32>,
+    },
+    /// Set a holding register
+    Write {
+        register: u16,
+        value: u16,
+        unit: Option<u8>,
+    },
+    /// Start or stop periodic drift
+    #[serde(rename_all = "camelCase")]
+    Chaos {
+        enabled: bool,
+        interval_secs: Option<u64>,
+        max_drift: Option<u16>,
+    },
+    Latency(Latency),
+    Connections(ConnectionChaos),
+    Corruption(Corruption),
+    /// Refuse every new connection for a while
+    Outage {
+        secs: u64,
+    },
+    /// Power-cycle: drop connections, go silent, come back reset
+    Reboot {
+        secs: u64,
+    },
+    /// Answer requests with a Modbus exception
+    Fault(Fault),
+    ClearFault,
+    /// Make a holding register stuck or ignore writes; `mode: null` clears it
+    RegisterFault {
+        register: u16,
+        mode: Option<RegisterFaultMode>,
+        unit: Option<u8>,
+    },
+}
+
+impl Scenario {
+    /// Read a scenario from a YAML or JSON file
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read scenario {}", path.display()))?;
+        let mut scenario: Self = serde_yaml::from_str(&content)
+            .with_context(|| format!("Invalid scenario {}", path.display()))?;
+
+        for (index, event) in scenario.events.iter().enumerate() {
+            let result = match &event.action {
+                ScenarioAction::Latency(latency) => latency.validate(),
+                ScenarioAction::Connections(connections) => connections.validate(),
+                ScenarioAction::Corruption(corruption) => corruption.validate(),
+                ScenarioAction::Fault(fault) => fault.validate(),
+                ScenarioAction::Outage { secs: 0 } | ScenarioAction::Reboot { secs: 0 } => {
+                    Err("secs must be at least 1".to_string())
+                }
+                _ => Ok(()),
+            };
+            result.map_err(|e| {
+                anyhow::anyhow!(
+                    "Invalid scenario {} event {}: {}",
+                    path.display(),
+                    index + 1,
+                    e
+                )
+            })?;
+        }
+        // Stable, so events at the same time keep their order
+        scenario.events.sort_by_key(|event| event.at_secs);
+        Ok(scenario)
+    }
+
+    /// Play the events in the background, timed from now
+    pub fn spawn(self, chaos: ChaosEngine, state: Arc<Mutex<PLCState>>) {
+        let start = Instant::now();
+        info!("🎬 Scenario loaded with {} event(s)", self.events.len());
+
+        tokio::spawn(async move {
+            for event in self.events {
+                sleep_until(start + Duration::from_secs(event.at_secs)).await;
+                info!("🎬 Scenario t={}s: {:?}", event.at_secs, event.action);
+                if let Err(e) = run(event.action, &chaos, &state) {
+                    warn!("Scenario event at t={}s failed: {}", event.at_secs, e);
+                }
+            }
+            info!("🎬 Scenario finished");
+        });
+    }
+}
+
+fn run(action: ScenarioAction, chaos: &ChaosEngine, state: &Mutex<PLCState>) -> anyhow::Result<()> {
+    let lock_state = || {
+        state
+            .lock()
+            .map_err(|_| anyhow::anyhow!("PLC state is poisoned"))
+    };
+
+    match action {
+        ScenarioAction::Drift { register, amount } => {
+            chaos.drift(register, amount);
+        }
+        ScenarioAction::Write {
+            register,
+            value,
+            unit,
+        } => {
+            let mut plc = lock_state()?;
+            let unit = unit.unwrap_or_else(|| plc.primary_unit());
+            plc.unit_mut(unit)
+                .with_context(|| format!("unknown unit {}", unit))?
+                .holding_registers[register as usize] = value;
+        }
+        ScenarioAction::Chaos {
+            enabled: true,
+            interval_secs,
+            max_drift,
+        } => {
+            chaos.enable(interval_secs, max_drift);
+        }
+        ScenarioAction::Chaos { enabled: false, .. } => {
+            chaos.disable();
+        }
+        ScenarioAction::Latency(latency) => {
+            chaos.set_latency(latency);
+        }
+        ScenarioAction::Connections(connections) => {
+            chaos.set_connections(connections);
+        }
+        ScenarioAction::Corruption(corruption) => {
+            chaos.set_corruption(corruption);
+        }
+        ScenarioAction::Outage { secs } => chaos.start_outage(Duration::from_secs(secs)),
+        ScenarioAction::Reboot { secs } => chaos.reboot(Duration::from_secs(secs)),
+        ScenarioAction::Fault(fault) => lock_state()?.fault = Some(fault),
+        ScenarioAction::ClearFault => lock_state()?.fault = None,
+        ScenarioAction::RegisterFault {
+            register,
+            mode,
+            unit,
+        } => {
+            let mut plc = lock_state()?;
+            let unit = unit.unwrap_or_else(|| plc.primary_unit());
+            let faults = &mut plc
+                .unit_mut(unit)
+                .with_context(|| format!("unknown unit {}", unit))?
+                .register_faults;
+            match mode {
+                Some(mode) => faults.insert(register, mode),
+                None => faults.remove(&register),
+            };
+        }
+    }
+    Ok(())
+}
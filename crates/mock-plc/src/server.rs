@@ -1,99 +1,853 @@
+use crate::chaos::ChaosEngine;
+use crate::limits::{ConnectionSlot, Limits};
+use crate::register_map::{AddressRange, RegisterMap};
+use crate::traffic::TrafficRecorder;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::io::ErrorKind;
 use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU16, Ordering};
 use std::sync::{Arc, Mutex};
-use tokio::net::TcpListener;
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
 use tokio_modbus::prelude::*;
-use tokio_modbus::server::tcp::{accept_tcp_connection, Server};
-use tracing::{error, info};
+use tokio_modbus::server::tcp::Server;
+use tokio_rustls::TlsAcceptor;
+use tracing::{error, info, warn};
+
+/// Size of each Modbus address space
+const ADDRESS_SPACE: usize = u16::MAX as usize + 1;
+
+/// Most registers a single read may return (Modbus spec limit)
+const MAX_READ_REGISTERS: u16 = 125;
+
+/// Most registers a single write may carry (Modbus spec limit)
+const MAX_WRITE_REGISTERS: usize = 123;
+
+/// Most coils or discrete inputs a single read may return (Modbus spec limit)
+const MAX_READ_BITS: u16 = 2000;
+
+/// Most coils a single write may carry (Modbus spec limit)
+const MAX_WRITE_COILS: usize = 1968;
+
+/// Longest a client may take to complete the TLS handshake
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Modbus exception codes
+const ILLEGAL_FUNCTION: u8 = 0x01;
+const ILLEGAL_DATA_ADDRESS: u8 = 0x02;
+pub const ILLEGAL_DATA_VALUE: u8 = 0x03;
+const SERVER_FAILURE: u8 = 0x04;
+const SERVER_BUSY: u8 = 0x06;
+const GATEWAY_TARGET_FAILED: u8 = 0x0B;
+
+/// One of the four Modbus data models
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RegisterKind {
+    Holding,
+    Input,
+    Coil,
+    Discrete,
+}
+
+/// A fault injected through the admin API: matching requests get a Modbus
+/// exception instead of a normal response
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Fault {
+    pub exception_code: u8,
+    /// Only fail requests for this unit ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unit: Option<u8>,
+    /// Fail this many requests and then clear; forever when omitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remaining: Option<u32>,
+}
+
+impl Fault {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=0x0B).contains(&self.exception_code) {
+            return Err("exceptionCode must be a Modbus exception code (1-11)".to_string());
+        }
+        if self.remaining == Some(0) {
+            return Err("remaining must be at least 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// How a holding register misbehaves, set through the admin API. Modbus
+/// writes to a faulty register are acknowledged but have no effect.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RegisterFaultMode {
+    /// Frozen at its current value: chaos drift skips it as well
+    Stuck,
+    /// Writes are ignored, but chaos drift still moves it
+    IgnoreWrites,
+}
+
+/// The four Modbus data models of one unit, each indexed by address
+pub struct Registers {
+    pub holding_registers: Vec<u16>,
+    pub input_registers: Vec<u16>,
+    pub coils: Vec<bool>,
+    pub discrete_inputs: Vec<bool>,
+    /// Misbehaving holding registers by address
+    pub register_faults: BTreeMap<u16, RegisterFaultMode>,
+    /// Write-protected holding registers and coils from the register map
+    read_only_holding: Vec<AddressRange>,
+    read_only_coils: Vec<AddressRange>,
+}
+
+impl Registers {
+    fn new() -> Self {
+        Self {
+            holding_registers: vec![0; ADDRESS_SPACE],
+            input_registers: vec![0; ADDRESS_SPACE],
+            coils: vec![false; ADDRESS_SPACE],
+            discrete_inputs: vec![false; ADDRESS_SPACE],
+            register_faults: BTreeMap::new(),
+            read_only_holding: Vec::new(),
+            read_only_coils: Vec::new(),
+        }
+    }
+
+    /// Apply the values of `map` that are new or changed since `old`; the
+    /// rest keep their live values. Returns how many were applied.
+    fn apply_map_changes(&mut self, old: &RegisterMap, map: &RegisterMap) -> usize {
+        fn changed<'a, T: PartialEq>(
+            old: &'a BTreeMap<u16, T>,
+            new: &'a BTreeMap<u16, T>,
+        ) -> impl Iterator<Item = (&'a u16, &'a T)> {
+            new.iter()
+                .filter(|(address, value)| old.get(address) != Some(value))
+        }
+
+        let mut applied = 0;
+        for (&address, &value) in changed(&old.holding_registers, &map.holding_registers) {
+            self.holding_registers[address as usize] = value;
+            applied += 1;
+        }
+        for (&address, &value) in changed(&old.input_registers, &map.input_registers) {
+            self.input_registers[address as usize] = value;
+            applied += 1;
+        }
+        for (&address, &value) in changed(&old.coils, &map.coils) {
+            self.coils[address as usize] = value;
+            applied += 1;
+        }
+        for (&address, &value) in changed(&old.discrete_inputs, &map.discrete_inputs) {
+            self.discrete_inputs[address as usize] = value;
+            applied += 1;
+        }
+        self.read_only_holding = map.read_only.holding_registers.clone();
+        self.read_only_coils = map.read_only.coils.clone();
+        applied
+    }
+
+    /// Apply the initial values of a register map
+    fn load_map(&mut self, map: &RegisterMap) {
+        for (&address, &value) in &map.holding_registers {
+            self.holding_registers[address as usize] = value;
+        }
+        for (&address, &value) in &map.input_registers {
+            self.input_registers[address as usize] = value;
+        }
+        for (&address, &value) in &map.coils {
+            self.coils[address as usize] = value;
+        }
+        for (&address, &value) in &map.discrete_inputs {
+            self.discrete_inputs[address as usize] = value;
+        }
+        self.read_only_holding = map.read_only.holding_registers.clone();
+        self.read_only_coils = map.read_only.coils.clone();
+    }
+
+    /// Whether a Modbus write of `count` values from `address` touches a
+    /// write-protected range
+    fn is_read_only(&self, kind: RegisterKind, address: u16, count: usize) -> bool {
+        let ranges = match kind {
+            RegisterKind::Holding => &self.read_only_holding,
+            RegisterKind::Coil => &self.read_only_coils,
+            RegisterKind::Input | RegisterKind::Discrete => return true,
+        };
+        ranges.iter().any(|range| range.overlaps(address, count))
+    }
+
+    /// Write consecutive holding registers from a Modbus request, skipping
+    /// faulty ones; `false` past the end of the address space
+    pub fn write_holding(&mut self, address: u16, values: &[u16]) -> bool {
+        let start = address as usize;
+        if start + values.len() > ADDRESS_SPACE {
+            return false;
+        }
+        for (offset, &value) in values.iter().enumerate() {
+            let target = (start + offset) as u16;
+            match self.register_faults.get(&target) {
+                Some(mode) => warn!(
+                    "⚡ Write of {} to {:?} register {} acknowledged but ignored",
+                    value, mode, target
+                ),
+                None => self.holding_registers[target as usize] = value,
+            }
+        }
+        true
+    }
+
+    /// Read any data model as words; bits read as 0 or 1
+    pub fn read(&self, kind: RegisterKind, address: u16, count: u16) -> Option<Vec<u16>> {
+        match kind {
+            RegisterKind::Holding => read_range(&self.holding_registers, address, count),
+            RegisterKind::Input => read_range(&self.input_registers, address, count),
+            RegisterKind::Coil => read_range(&self.coils, address, count).map(bits_to_words),
+            RegisterKind::Discrete => {
+                read_range(&self.discrete_inputs, address, count).map(bits_to_words)
+            }
+        }
+    }
+
+    /// Write any data model from words; any non-zero value sets a bit
+    pub fn write(&mut self, kind: RegisterKind, address: u16, values: &[u16]) -> bool {
+        let bits: Vec<bool> = values.iter().map(|&v| v != 0).collect();
+        match kind {
+            RegisterKind::Holding => write_range(&mut self.holding_registers, address, values),
+            RegisterKind::Input => write_range(&mut self.input_registers, address, values),
+            RegisterKind::Coil => write_range(&mut self.coils, address, &bits),
+            RegisterKind::Discrete => write_range(&mut self.discrete_inputs, address, &bits),
+        }
+    }
+
+    /// Reset every value of a data model to zero
+    pub fn clear(&mut self, kind: RegisterKind) {
+        match kind {
+            RegisterKind::Holding => self.holding_registers.fill(0),
+            RegisterKind::Input => self.input_registers.fill(0),
+            RegisterKind::Coil => self.coils.fill(false),
+            RegisterKind::Discrete => self.discrete_inputs.fill(false),
+        }
+    }
+
+    /// Every non-zero value of a data model by address
+    pub fn non_zero(&self, kind: RegisterKind) -> BTreeMap<u16, u16> {
+        (0..ADDRESS_SPACE)
+            .map(|address| {
+                let value = match kind {
+                    RegisterKind::Holding => self.holding_registers[address],
+                    RegisterKind::Input => self.input_registers[address],
+                    RegisterKind::Coil => u16::from(self.coils[address]),
+                    RegisterKind::Discrete => u16::from(self.discrete_inputs[address]),
+                };
+                (address as u16, value)
+            })
+            .filter(|(_, value)| *value != 0)
+            .collect()
+    }
+}
+
+fn bits_to_words(bits: Vec<bool>) -> Vec<u16> {
+    bits.into_iter().map(u16::from).collect()
+}
 
 /// Shared state for the mock PLC
 pub struct PLCState {
-    pub register_value: u16,
+    /// Register spaces by unit ID
+    units: BTreeMap<u8, Registers>,
+    /// Answer every unit ID from a single register space, like a plain
+    /// Modbus TCP device; otherwise behave like a serial gateway
+    any_unit: bool,
+    /// Main register, reported by the admin API
     pub register_address: u16,
+    /// Value of the main register at startup
+    initial_value: u16,
+    pub fault: Option<Fault>,
+    /// Whether the Modbus listener is accepting connections
+    listening: bool,
+    /// Register map the registers were last loaded from
+    map: RegisterMap,
+    /// Modbus requests received since startup, answered or not
+    requests: u64,
 }
 
 impl PLCState {
-    pub fn new(initial_value: u16, register_address: u16) -> Self {
+    /// State with one independent register space per unit ID. Without unit
+    /// IDs, a single space answers requests for any unit.
+    pub fn new(initial_value: u16, register_address: u16, unit_ids: &[u8]) -> Self {
+        let any_unit = unit_ids.is_empty();
+        let ids = if any_unit { &[1][..] } else { unit_ids };
+        let units = ids
+            .iter()
+            .map(|&id| {
+                let mut registers = Registers::new();
+                registers.holding_registers[register_address as usize] = initial_value;
+                (id, registers)
+            })
+            .collect();
         Self {
-            register_value: initial_value,
+            units,
+            any_unit,
             register_address,
+            initial_value,
+            fault: None,
+            listening: false,
+            map: RegisterMap::default(),
+            requests: 0,
+        }
+    }
+
+    /// Put every register back to its initial value from the command line
+    /// and register map, as after a power cycle. Faults stay in place.
+    pub fn reset(&mut self) {
+        for registers in self.units.values_mut() {
+            let faults = std::mem::take(&mut registers.register_faults);
+            *registers = Registers::new();
+            registers.holding_registers[self.register_address as usize] = self.initial_value;
+            registers.load_map(&self.map);
+            registers.register_faults = faults;
+        }
+    }
+
+    /// Whether the Modbus listener is accepting connections, for readiness
+    /// probes
+    pub fn is_listening(&self) -> bool {
+        self.listening
+    }
+
+    /// Apply the initial values of a register map to every unit
+    pub fn load_map(&mut self, map: &RegisterMap) {
+        for registers in self.units.values_mut() {
+            registers.load_map(map);
         }
+        self.map = map.clone();
+    }
+
+    /// Switch to a changed register map without a restart. Values the map
+    /// adds or changes are applied to every unit, the others keep their live
+    /// values; read-only ranges and simulations are replaced. Returns how
+    /// many values were applied in each unit.
+    pub fn reload_map(&mut self, map: RegisterMap) -> usize {
+        let mut applied = 0;
+        for registers in self.units.values_mut() {
+            applied = registers.apply_map_changes(&self.map, &map);
+        }
+        self.map = map;
+        applied
+    }
+
+    /// Register map the registers were last loaded from
+    pub fn map(&self) -> &RegisterMap {
+        &self.map
+    }
+
+    /// Register space answering requests for `unit`, if any
+    pub fn unit_mut(&mut self, unit: u8) -> Option<&mut Registers> {
+        if self.any_unit {
+            self.units.values_mut().next()
+        } else {
+            self.units.get_mut(&unit)
+        }
+    }
+
+    /// Register space answering requests for `unit`, if any
+    pub fn unit(&self, unit: u8) -> Option<&Registers> {
+        if self.any_unit {
+            self.units.values().next()
+        } else {
+            self.units.get(&unit)
+        }
+    }
+
+    pub fn units_mut(&mut self) -> impl Iterator<Item = (u8, &mut Registers)> {
+        self.units
+            .iter_mut()
+            .map(|(&id, registers)| (id, registers))
+    }
+
+    /// Configured units with their register spaces
+    pub fn units(&self) -> impl Iterator<Item = (u8, &Registers)> {
+        self.units.iter().map(|(&id, registers)| (id, registers))
+    }
+
+    /// Modbus requests received since startup, answered or not
+    pub fn requests(&self) -> u64 {
+        self.requests
+    }
+
+    /// The lowest configured unit ID
+    pub fn primary_unit(&self) -> u8 {
+        *self.units.keys().next().expect("at least one unit")
+    }
+
+    /// Exception code to answer a request for `unit` with, if a fault applies.
+    /// Counts down and clears a fault limited to a number of requests.
+    fn take_fault(&mut self, unit: u8) -> Option<u8> {
+        let fault = self.fault.as_mut()?;
+        if fault.unit.is_some_and(|u| u != unit) {
+            return None;
+        }
+        let code = fault.exception_code;
+        if let Some(ref mut remaining) = fault.remaining {
+            *remaining = remaining.saturating_sub(1);
+            if *remaining == 0 {
+                self.fault = None;
+            }
+        }
+        Some(code)
+    }
+
+    /// Unit whose register chaos drives: the lowest configured unit ID
+    fn primary(&self) -> &Registers {
+        self.units.values().next().expect("at least one unit")
+    }
+
+    fn primary_mut(&mut self) -> &mut Registers {
+        self.units.values_mut().next().expect("at least one unit")
+    }
+
+    /// Holding register of the lowest unit, the one chaos drifts
+    pub fn holding(&self, address: u16) -> u16 {
+        self.primary().holding_registers[address as usize]
+    }
+
+    /// Set a holding register of the lowest unit unless it is stuck;
+    /// returns whether the value changed
+    pub fn set_holding(&mut self, address: u16, value: u16) -> bool {
+        let registers = self.primary_mut();
+        if registers.register_faults.get(&address) == Some(&RegisterFaultMode::Stuck) {
+            return false;
+        }
+        registers.holding_registers[address as usize] = value;
+        true
     }
 }
 
-/// Start the mock Modbus TCP server
+fn read_range<T: Copy>(space: &[T], address: u16, count: u16) -> Option<Vec<T>> {
+    let start = address as usize;
+    space.get(start..start + count as usize).map(<[T]>::to_vec)
+}
+
+fn write_range<T: Copy>(space: &mut [T], address: u16, values: &[T]) -> bool {
+    let start = address as usize;
+    match space.get_mut(start..start + values.len()) {
+        Some(slots) => {
+            slots.copy_from_slice(values);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Answer a read request with the response built by `ok`, or the matching exception
+fn read_response<T: Copy>(
+    function: u8,
+    space: &[T],
+    address: u16,
+    count: u16,
+    max: u16,
+    ok: fn(Vec<T>) -> Response,
+) -> Response {
+    if count == 0 || count > max {
+        return exception(function, ILLEGAL_DATA_VALUE);
+    }
+    match read_range(space, address, count) {
+        Some(values) => ok(values),
+        None => exception(function, ILLEGAL_DATA_ADDRESS),
+    }
+}
+
+/// Marks the state as listening for Modbus requests until dropped, so a
+/// failed or stopped listener reports not ready
+pub struct Listening(Arc<Mutex<PLCState>>);
+
+impl Listening {
+    pub fn start(state: &Arc<Mutex<PLCState>>) -> Self {
+        if let Ok(mut state) = state.lock() {
+            state.listening = true;
+        }
+        Self(state.clone())
+    }
+}
+
+impl Drop for Listening {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.0.lock() {
+            state.listening = false;
+        }
+    }
+}
+
+/// Start the mock Modbus TCP server, over TLS (Modbus/TCP Security) when
+/// given an acceptor
 pub async fn start_server(
     bind_addr: &str,
     port: u16,
     state: Arc<Mutex<PLCState>>,
+    chaos: ChaosEngine,
+    traffic: Arc<TrafficRecorder>,
+    limits: Arc<Limits>,
+    tls: Option<TlsAcceptor>,
 ) -> anyhow::Result<()> {
     let socket_addr: SocketAddr = format!("{}:{}", bind_addr, port).parse()?;
 
-    info!("Starting mock PLC server on {}", socket_addr);
+    info!(
+        "Starting mock PLC server on {}{}",
+        socket_addr,
+        if tls.is_some() { " (TLS)" } else { "" }
+    );
 
     let listener = TcpListener::bind(socket_addr).await?;
-    let server = Server::new(listener);
+    serve(listener, state, chaos, traffic, limits, tls).await
+}
 
-    let new_service = |_socket_addr| {
-        let state = state.clone();
-        Ok(Some(ModbusService { state }))
-    };
+/// Serve Modbus on a bound listener until it fails
+pub async fn serve(
+    listener: TcpListener,
+    state: Arc<Mutex<PLCState>>,
+    chaos: ChaosEngine,
+    traffic: Arc<TrafficRecorder>,
+    limits: Arc<Limits>,
+    tls: Option<TlsAcceptor>,
+) -> anyhow::Result<()> {
+    let server = Server::new(listener);
+    let _listening = Listening::start(&state);
 
-    let on_connected = |stream, socket_addr| async move {
-        accept_tcp_connection(stream, socket_addr, new_service)
+    // The service for a new connection, unless chaos or the connection
+    // limit refuses it
+    let connect = |stream: &TcpStream, peer: SocketAddr| {
+        // Reset instead of a clean close, like a device that is going down or
+        // out of sockets. A zero linger never blocks, which is what the
+        // deprecation warns about.
+        #[allow(deprecated)]
+        let reset = || {
+            let _ = stream.set_linger(Some(Duration::ZERO));
+        };
+        if chaos.should_refuse() {
+            reset();
+            warn!("🔌 CHAOS: refusing connection from {}", peer);
+            return None;
+        }
+        let Some(slot) = limits.connect() else {
+            reset();
+            warn!("Connection limit reached, refusing {}", peer);
+            return None;
+        };
+        Some(ModbusService {
+            state: state.clone(),
+            chaos: chaos.clone(),
+            traffic: traffic.clone(),
+            peer,
+            local: stream.local_addr().ok()?,
+            transaction_id: Arc::new(AtomicU16::new(0)),
+            limits: limits.clone(),
+            generation: Some(chaos.generation()),
+            _slot: Some(Arc::new(slot)),
+        })
     };
 
-    let on_process_error = |err| {
-        error!("Server error: {}", err);
+    let on_process_error = |err: std::io::Error| {
+        // Connections closed by chaos are expected
+        if err.kind() != ErrorKind::ConnectionAborted {
+            error!("Server error: {}", err);
+        }
     };
 
-    server.serve(&on_connected, on_process_error).await?;
+    match tls {
+        None => {
+            let on_connected = |stream: TcpStream, peer| {
+                let service = connect(&stream, peer);
+                async move { Ok(service.map(|service| (service, stream))) }
+            };
+            server.serve(&on_connected, on_process_error).await?;
+        }
+        Some(acceptor) => {
+            // The server awaits each handshake before accepting the next
+            // connection, so a stalled client is cut off after a while
+            let on_connected = |stream: TcpStream, peer| {
+                let service = connect(&stream, peer);
+                let acceptor = acceptor.clone();
+                async move {
+                    let Some(service) = service else {
+                        return Ok(None);
+                    };
+                    match tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await
+                    {
+                        Ok(Ok(stream)) => Ok(Some((service, stream))),
+                        Ok(Err(e)) => {
+                            warn!("TLS handshake with {} failed: {}", peer, e);
+                            Ok(None)
+                        }
+                        Err(_) => {
+                            warn!("TLS handshake with {} timed out", peer);
+                            Ok(None)
+                        }
+                    }
+                }
+            };
+            server.serve(&on_connected, on_process_error).await?;
+        }
+    }
 
     Ok(())
 }
 
-/// Modbus service implementation
+/// Modbus service implementation, one per connection
 #[derive(Clone)]
-struct ModbusService {
+pub struct ModbusService {
     state: Arc<Mutex<PLCState>>,
+    chaos: ChaosEngine,
+    traffic: Arc<TrafficRecorder>,
+    peer: SocketAddr,
+    /// Address the client connected to
+    local: SocketAddr,
+    /// Stand-in for the MBAP transaction ID in captures, which the server
+    /// framework does not pass on
+    transaction_id: Arc<AtomicU16>,
+    limits: Arc<Limits>,
+    /// Chaos generation when the connection opened; a reboot since then
+    /// has dropped it. Serial lines have no connection to drop.
+    generation: Option<u64>,
+    /// Held for the life of the connection
+    _slot: Option<Arc<ConnectionSlot>>,
 }
 
-impl tokio_modbus::server::Service for ModbusService {
-    type Request = Request<'static>;
-    type Response = Response;
-    type Error = std::io::Error;
-    type Future = std::future::Ready<std::result::Result<Self::Response, Self::Error>>;
+/// Modbus function code of a request
+pub fn function_code(req: &Request) -> u8 {
+    match req {
+        Request::ReadCoils(..) => 0x01,
+        Request::ReadDiscreteInputs(..) => 0x02,
+        Request::ReadHoldingRegisters(..) => 0x03,
+        Request::ReadInputRegisters(..) => 0x04,
+        Request::WriteSingleCoil(..) => 0x05,
+        Request::WriteSingleRegister(..) => 0x06,
+        Request::WriteMultipleCoils(..) => 0x0F,
+        Request::WriteMultipleRegisters(..) => 0x10,
+        Request::MaskWriteRegister(..) => 0x16,
+        Request::ReadWriteMultipleRegisters(..) => 0x17,
+        Request::Custom(code, _) => *code,
+        Request::Disconnect => 0x00,
+    }
+}
 
-    fn call(&self, req: Self::Request) -> Self::Future {
-        use tokio_modbus::bytes::Bytes;
-
-        let response = match req {
-            Request::ReadHoldingRegisters(addr, count) => {
-                if let Ok(state) = self.state.lock() {
-                    if addr == state.register_address && count == 1 {
-                        Response::ReadHoldingRegisters(vec![state.register_value])
-                    } else {
-                        Response::Custom(0x83, Bytes::from_static(&[0x02])) // Illegal data address
-                    }
+/// Exception response for the given function code
+pub fn exception(function: u8, code: u8) -> Response {
+    use tokio_modbus::bytes::Bytes;
+
+    Response::Custom(0x80 | function, Bytes::copy_from_slice(&[code]))
+}
+
+impl ModbusService {
+    /// Service for a serial line, which has no connections to limit. Traffic
+    /// records show unspecified addresses for it.
+    pub fn serial(
+        state: Arc<Mutex<PLCState>>,
+        chaos: ChaosEngine,
+        traffic: Arc<TrafficRecorder>,
+        limits: Arc<Limits>,
+    ) -> Self {
+        let unspecified = SocketAddr::from(([0, 0, 0, 0], 0));
+        Self {
+            state,
+            chaos,
+            traffic,
+            peer: unspecified,
+            local: unspecified,
+            transaction_id: Arc::new(AtomicU16::new(0)),
+            limits,
+            generation: None,
+            _slot: None,
+        }
+    }
+
+    fn respond(&self, req: SlaveRequest<'static>) -> Response {
+        let SlaveRequest { slave, request } = req;
+        let function = function_code(&request);
+        let Ok(mut state) = self.state.lock() else {
+            return exception(function, SERVER_FAILURE);
+        };
+        state.requests += 1;
+        if let Some(code) = state.take_fault(slave) {
+            return exception(function, code);
+        }
+        let Some(registers) = state.unit_mut(slave) else {
+            return exception(function, GATEWAY_TARGET_FAILED);
+        };
+
+        let protected = match &request {
+            Request::WriteSingleCoil(addr, _) => {
+                registers.is_read_only(RegisterKind::Coil, *addr, 1)
+            }
+            Request::WriteMultipleCoils(addr, values) => {
+                registers.is_read_only(RegisterKind::Coil, *addr, values.len())
+            }
+            Request::WriteSingleRegister(addr, _) => {
+                registers.is_read_only(RegisterKind::Holding, *addr, 1)
+            }
+            Request::WriteMultipleRegisters(addr, values) => {
+                registers.is_read_only(RegisterKind::Holding, *addr, values.len())
+            }
+            _ => false,
+        };
+        if protected {
+            warn!("Unit {} rejected a write to a read-only range", slave);
+            return exception(function, ILLEGAL_DATA_ADDRESS);
+        }
+
+        match request {
+            Request::ReadCoils(addr, count) => read_response(
+                function,
+                &registers.coils,
+                addr,
+                count,
+                MAX_READ_BITS,
+                Response::ReadCoils,
+            ),
+            Request::ReadDiscreteInputs(addr, count) => read_response(
+                function,
+                &registers.discrete_inputs,
+                addr,
+                count,
+                MAX_READ_BITS,
+                Response::ReadDiscreteInputs,
+            ),
+            Request::ReadInputRegisters(addr, count) => read_response(
+                function,
+                &registers.input_registers,
+                addr,
+                count,
+                MAX_READ_REGISTERS,
+                Response::ReadInputRegisters,
+            ),
+            Request::ReadHoldingRegisters(addr, count) => read_response(
+                function,
+                &registers.holding_registers,
+                addr,
+                count,
+                MAX_READ_REGISTERS,
+                Response::ReadHoldingRegisters,
+            ),
+            Request::WriteSingleCoil(addr, value) => {
+                registers.coils[addr as usize] = value;
+                info!("Unit {} coil {} written with value: {}", slave, addr, value);
+                Response::WriteSingleCoil(addr, value)
+            }
+            Request::WriteMultipleCoils(addr, values) => {
+                if values.is_empty() || values.len() > MAX_WRITE_COILS {
+                    exception(function, ILLEGAL_DATA_VALUE)
+                } else if write_range(&mut registers.coils, addr, &values) {
+                    info!(
+                        "Unit {} coils {}..{} written",
+                        slave,
+                        addr,
+                        addr as usize + values.len() - 1
+                    );
+                    Response::WriteMultipleCoils(addr, values.len() as u16)
                 } else {
-                    Response::Custom(0x83, Bytes::from_static(&[0x04])) // Server failure
+                    exception(function, ILLEGAL_DATA_ADDRESS)
                 }
             }
             Request::WriteSingleRegister(addr, value) => {
-                if let Ok(mut state) = self.state.lock() {
-                    if addr == state.register_address {
-                        state.register_value = value;
-                        info!("Register {} written with value: {}", addr, value);
-                        Response::WriteSingleRegister(addr, value)
-                    } else {
-                        Response::Custom(0x86, Bytes::from_static(&[0x02])) // Illegal data address
-                    }
+                registers.write_holding(addr, &[value]);
+                info!(
+                    "Unit {} register {} written with value: {}",
+                    slave, addr, value
+                );
+                Response::WriteSingleRegister(addr, value)
+            }
+            Request::WriteMultipleRegisters(addr, values) => {
+                if values.is_empty() || values.len() > MAX_WRITE_REGISTERS {
+                    exception(function, ILLEGAL_DATA_VALUE)
+                } else if registers.write_holding(addr, &values) {
+                    info!(
+                        "Unit {} registers {}..{} written",
+                        slave,
+                        addr,
+                        addr as usize + values.len() - 1
+                    );
+                    Response::WriteMultipleRegisters(addr, values.len() as u16)
                 } else {
-                    Response::Custom(0x86, Bytes::from_static(&[0x04])) // Server failure
+                    exception(function, ILLEGAL_DATA_ADDRESS)
                 }
             }
-            _ => Response::Custom(0x80, Bytes::from_static(&[0x01])), // Illegal function
+            _ => exception(function, ILLEGAL_FUNCTION),
+        }
+    }
+}
+
+impl tokio_modbus::server::Service for ModbusService {
+    type Request = SlaveRequest<'static>;
+    type Response = Response;
+    type Error = std::io::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send + Sync>>;
+
+    fn call(&self, req: Self::Request) -> Self::Future {
+        // Only clone the request when it is going to be recorded
+        let recorded = self
+            .traffic
+            .is_enabled()
+            .then(|| (req.slave, req.request.clone()));
+        let record = |response: Option<&Response>| {
+            if let Some((unit, request)) = &recorded {
+                let transaction_id = self.transaction_id.fetch_add(1, Ordering::Relaxed);
+                self.traffic.record(
+                    self.peer,
+                    self.local,
+                    *unit,
+                    transaction_id,
+                    request,
+                    response,
+                );
+            }
         };
 
-        std::future::ready(Ok(response))
+        // A rebooting device is silent until it comes back, and connections
+        // from before a reboot are gone
+        if let Some(remaining) = self.chaos.reboot_remaining() {
+            record(None);
+            return Box::pin(async move {
+                tokio::time::sleep(remaining).await;
+                Err(std::io::Error::new(
+                    ErrorKind::ConnectionAborted,
+                    "device rebooted",
+                ))
+            });
+        }
+        if self
+            .generation
+            .is_some_and(|generation| generation != self.chaos.generation())
+        {
+            record(None);
+            return Box::pin(std::future::ready(Err(std::io::Error::new(
+                ErrorKind::ConnectionAborted,
+                "connection lost to a reboot",
+            ))));
+        }
+
+        if self.chaos.should_drop() {
+            record(None);
+            warn!("🔌 CHAOS: dropping connection mid-transaction");
+            return Box::pin(std::future::ready(Err(std::io::Error::new(
+                ErrorKind::ConnectionAborted,
+                "connection dropped by chaos",
+            ))));
+        }
+
+        let delay = self.chaos.response_delay();
+        let response = if self.limits.allow_request() {
+            self.respond(req)
+        } else {
+            warn!("Request rate limit reached, answering {} busy", self.peer);
+            exception(function_code(&req.request), SERVER_BUSY)
+        };
+        let response = self.chaos.corrupt(response);
+        record(Some(&response));
+        Box::pin(async move {
+            if let Some(delay) = delay {
+                tokio::time::sleep(delay).await;
+            }
+            Ok(response)
+        })
     }
 }
@@ -0,0 +1,123 @@
+use crate::register_map::{Simulation, SimulationModel};
+use crate::server::{PLCState, RegisterFaultMode, RegisterKind, Registers};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+use std::f64::consts::TAU;
+use std::sync::{Arc, Mutex};
+use tokio::task::JoinHandle;
+use tokio::time::{interval, Duration, Instant};
+use tracing::info;
+
+/// Drives the registers of a register map's `simulations`
+pub struct Simulator {
+    simulations: Vec<Simulation>,
+    /// Unrounded value of each simulated register by (unit, simulation index),
+    /// so slow models still move when a tick changes less than one count
+    exact: HashMap<(u8, usize), f64>,
+    rng: StdRng,
+    started: Instant,
+}
+
+impl Simulator {
+    /// `seed` keeps random walks repeatable; the chaos seed is a good choice
+    pub fn new(simulations: Vec<Simulation>, seed: u64) -> Self {
+        Self {
+            simulations,
+            exact: HashMap::new(),
+            rng: StdRng::seed_from_u64(seed),
+            started: Instant::now(),
+        }
+    }
+
+    /// Update the simulated registers every `tick` in the background,
+    /// following the simulations of the state's register map as it reloads
+    pub fn spawn(mut self, state: Arc<Mutex<PLCState>>, tick: Duration) -> JoinHandle<()> {
+        if !self.simulations.is_empty() {
+            info!(
+                "📈 Simulating {} register(s) every {}ms",
+                self.simulations.len(),
+                tick.as_millis()
+            );
+        }
+
+        tokio::spawn(async move {
+            let mut ticks = interval(tick);
+            let mut last = Instant::now();
+            loop {
+                ticks.tick().await;
+                let now = Instant::now();
+                let dt = now.duration_since(last).as_secs_f64();
+                last = now;
+
+                let Ok(mut state) = state.lock() else {
+                    return;
+                };
+                if state.map().simulations != self.simulations {
+                    self.simulations = state.map().simulations.clone();
+                    self.exact.clear();
+                    info!("📈 Simulating {} register(s)", self.simulations.len());
+                }
+                for (unit, registers) in state.units_mut() {
+                    self.step(unit, registers, dt);
+                }
+            }
+        })
+    }
+
+    fn step(&mut self, unit: u8, registers: &mut Registers, dt: f64) {
+        let elapsed = self.started.elapsed().as_secs_f64();
+
+        for (index, simulation) in self.simulations.iter().enumerate() {
+            let address = simulation.register as usize;
+            let space = match simulation.kind {
+                RegisterKind::Holding => {
+                    if registers.register_faults.get(&simulation.register)
+                        == Some(&RegisterFaultMode::Stuck)
+                    {
+                        continue;
+                    }
+                    &registers.holding_registers
+                }
+                _ => &registers.input_registers,
+            };
+            let current = space[address];
+
+            // Start over from the register whenever something else wrote it
+            let exact = self.exact.entry((unit, index)).or_insert(current as f64);
+            if exact.round() as u16 != current {
+                *exact = current as f64;
+            }
+
+            *exact = match simulation.model {
+                SimulationModel::FirstOrderLag {
+                    setpoint,
+                    time_constant_secs,
+                } => {
+                    let target = registers.holding_registers[setpoint as usize] as f64;
+                    *exact + (target - *exact) * (1.0 - (-dt / time_constant_secs).exp())
+                }
+                SimulationModel::Sine {
+                    min,
+                    max,
+                    period_secs,
+                } => {
+                    let middle = (min as f64 + max as f64) / 2.0;
+                    let amplitude = (max as f64 - min as f64) / 2.0;
+                    middle + amplitude * (TAU * elapsed / period_secs).sin()
+                }
+                SimulationModel::RandomWalk { min, max, step } => {
+                    let step = step as i32;
+                    let delta = self.rng.gen_range(-step..=step) as f64;
+                    (*exact + delta).clamp(min as f64, max as f64)
+                }
+            };
+            let value = exact.round().clamp(0.0, u16::MAX as f64) as u16;
+
+            match simulation.kind {
+                RegisterKind::Holding => registers.holding_registers[address] = value,
+                _ => registers.input_registers[address] = value,
+            }
+        }
+    }
+}
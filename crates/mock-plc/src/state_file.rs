@@ -0,0 +1,152 @@
+use crate::server::{PLCState, RegisterKind};
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::time::{interval, Duration};
+use tracing::{info, warn};
+
+/// How often the state file is checked for changes to write
+const SAVE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Register values kept across restarts with `--state-file`
+#[derive(Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedState {
+    pub units: BTreeMap<u8, SavedUnit>,
+}
+
+/// Non-zero values of one unit; coils and discrete inputs as 0 or 1
+#[derive(Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedUnit {
+    #[serde(default)]
+    pub holding_registers: BTreeMap<u16, u16>,
+    #[serde(default)]
+    pub input_registers: BTreeMap<u16, u16>,
+    #[serde(default)]
+    pub coils: BTreeMap<u16, u16>,
+    #[serde(default)]
+    pub discrete_inputs: BTreeMap<u16, u16>,
+}
+
+const KINDS: [RegisterKind; 4] = [
+    RegisterKind::Holding,
+    RegisterKind::Input,
+    RegisterKind::Coil,
+    RegisterKind::Discrete,
+];
+
+impl SavedUnit {
+    fn values(&self, kind: RegisterKind) -> &BTreeMap<u16, u16> {
+        match kind {
+            RegisterKind::Holding => &self.holding_registers,
+            RegisterKind::Input => &self.input_registers,
+            RegisterKind::Coil => &self.coils,
+            RegisterKind::Discrete => &self.discrete_inputs,
+        }
+    }
+}
+
+impl SavedState {
+    pub fn capture(plc: &PLCState) -> Self {
+        let units = plc
+            .units()
+            .map(|(id, registers)| {
+                (
+                    id,
+                    SavedUnit {
+                        holding_registers: registers.non_zero(RegisterKind::Holding),
+                        input_registers: registers.non_zero(RegisterKind::Input),
+                        coils: registers.non_zero(RegisterKind::Coil),
+                        discrete_inputs: registers.non_zero(RegisterKind::Discrete),
+                    },
+                )
+            })
+            .collect();
+        Self { units }
+    }
+
+    /// Overwrite the saved units' registers, including values that were zero
+    /// when saved; units the mock no longer serves are skipped
+    pub fn restore(&self, plc: &mut PLCState) {
+        for (&unit, saved) in &self.units {
+            let Some(registers) = plc.unit_mut(unit) else {
+                warn!("State file has unit {}, which is not served; skipped", unit);
+                continue;
+            };
+            for kind in KINDS {
+                registers.clear(kind);
+                for (&address, &value) in saved.values(kind) {
+                    registers.write(kind, address, &[value]);
+                }
+            }
+        }
+    }
+}
+
+/// Register state persisted to a JSON file
+pub struct StateFile {
+    path: PathBuf,
+}
+
+impl StateFile {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+        }
+    }
+
+    /// Read the saved state; `None` when the file does not exist yet
+    pub fn load(&self) -> anyhow::Result<Option<SavedState>> {
+        match std::fs::read_to_string(&self.path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map(Some)
+                .with_context(|| format!("Invalid state file {}", self.path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => {
+                Err(e).with_context(|| format!("Failed to read state file {}", self.path.display()))
+            }
+        }
+    }
+
+    /// Write through a temporary file, so a crash never leaves half a file
+    pub fn save(&self, state: &SavedState) -> anyhow::Result<()> {
+        let temp = self.path.with_extension("json.tmp");
+        std::fs::write(&temp, serde_json::to_vec_pretty(state)?)
+            .with_context(|| format!("Failed to write {}", temp.display()))?;
+        std::fs::rename(&temp, &self.path)
+            .with_context(|| format!("Failed to write state file {}", self.path.display()))
+    }
+
+    /// Save the current register values
+    pub fn save_now(&self, plc: &Mutex<PLCState>) -> anyhow::Result<()> {
+        let plc = plc
+            .lock()
+            .map_err(|_| anyhow::anyhow!("PLC state is poisoned"))?;
+        self.save(&SavedState::capture(&plc))
+    }
+
+    /// Write the file in the background whenever the registers change
+    pub fn spawn(self: Arc<Self>, plc: Arc<Mutex<PLCState>>) {
+        info!("💾 Saving register state to {}", self.path.display());
+        tokio::spawn(async move {
+            let mut ticks = interval(SAVE_INTERVAL);
+            let mut last_saved = None;
+            loop {
+                ticks.tick().await;
+                let Ok(state) = plc.lock().map(|plc| SavedState::capture(&plc)) else {
+                    return;
+                };
+                if last_saved.as_ref() == Some(&state) {
+                    continue;
+                }
+                match self.save(&state) {
+                    Ok(()) => last_saved = Some(state),
+                    Err(e) => warn!("{:#}", e),
+                }
+            }
+        });
+    }
+}
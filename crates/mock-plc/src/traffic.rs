@@ -0,0 +1,379 @@
+use crate::server::function_code;
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::net::{IpAddr, SocketAddr};
+use std::path::Path;
+use std::sync::Mutex;
+use tokio_modbus::prelude::{Request, Response};
+use tracing::warn;
+
+/// pcap link type for raw IPv4/IPv6 packets
+const LINKTYPE_RAW: u32 = 101;
+
+/// One Modbus transaction, written as a line of the `--log-requests` file
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Transaction {
+    pub timestamp: DateTime<Utc>,
+    pub peer: SocketAddr,
+    pub unit: u8,
+    pub function: u8,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<u16>,
+    /// Number of values read
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub count: Option<u16>,
+    /// Values written; coils as 0 or 1
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub values: Vec<u16>,
+    pub response: Outcome,
+}
+
+/// How the mock answered a transaction
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Outcome {
+    /// Values read; coils and discrete inputs as 0 or 1
+    Values(Vec<u16>),
+    /// A write was acknowledged
+    Ok,
+    Exception(u8),
+    /// The connection was closed by chaos instead of answering
+    Dropped,
+}
+
+impl Transaction {
+    pub fn new(peer: SocketAddr, unit: u8, request: &Request, response: Option<&Response>) -> Self {
+        let (address, count, values) = describe_request(request);
+        let response = match response {
+            None => Outcome::Dropped,
+            Some(Response::Custom(code, data)) if code & 0x80 != 0 => {
+                Outcome::Exception(data.first().copied().unwrap_or_default())
+            }
+            Some(Response::ReadCoils(bits) | Response::ReadDiscreteInputs(bits)) => {
+                Outcome::Values(bits.iter().map(|&b| u16::from(b)).collect())
+            }
+            Some(
+                Response::ReadHoldingRegisters(words)
+                | Response::ReadInputRegisters(words)
+                | Response::ReadWriteMultipleRegisters(words),
+            ) => Outcome::Values(words.clone()),
+            Some(_) => Outcome::Ok,
+        };
+        Self {
+            timestamp: Utc::now(),
+            peer,
+            unit,
+            function: function_code(request),
+            address,
+            count,
+            values,
+            response,
+        }
+    }
+}
+
+/// Address, read count, and written values of a request
+fn describe_request(request: &Request) -> (Option<u16>, Option<u16>, Vec<u16>) {
+    match request {
+        Request::ReadCoils(addr, count) => (Some(*addr), Some(*count), Vec::new()),
+        Request::ReadDiscreteInputs(addr, count) => (Some(*addr), Some(*count), Vec::new()),
+        Request::ReadHoldingRegisters(addr, count) => (Some(*addr), Some(*count), Vec::new()),
+        Request::ReadInputRegisters(addr, count) => (Some(*addr), Some(*count), Vec::new()),
+        Request::WriteSingleCoil(addr, value) => (Some(*addr), None, vec![u16::from(*value)]),
+        Request::WriteSingleRegister(addr, value) => (Some(*addr), None, vec![*value]),
+        Request::WriteMultipleCoils(addr, bits) => (
+            Some(*addr),
+            None,
+            bits.iter().map(|&b| u16::from(b)).collect(),
+        ),
+        Request::WriteMultipleRegisters(addr, words) => (Some(*addr), None, words.to_vec()),
+        Request::MaskWriteRegister(addr, and, or) => (Some(*addr), None, vec![*and, *or]),
+        Request::ReadWriteMultipleRegisters(addr, count, _, words) => {
+            (Some(*addr), Some(*count), words.to_vec())
+        }
+        Request::Custom(..) | Request::Disconnect => (None, None, Vec::new()),
+    }
+}
+
+/// Records Modbus traffic to a JSON Lines log and/or a pcap capture
+pub struct TrafficRecorder {
+    log: Option<Mutex<BufWriter<File>>>,
+    capture: Option<Mutex<Capture>>,
+}
+
+impl TrafficRecorder {
+    pub fn create(log_path: Option<&Path>, capture_path: Option<&Path>) -> anyhow::Result<Self> {
+        let log = log_path
+            .map(|path| {
+                File::create(path)
+                    .with_context(|| format!("Failed to create request log {}", path.display()))
+            })
+            .transpose()?
+            .map(|file| Mutex::new(BufWriter::new(file)));
+        let capture = capture_path
+            .map(Capture::create)
+            .transpose()?
+            .map(Mutex::new);
+        Ok(Self { log, capture })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.log.is_some() || self.capture.is_some()
+    }
+
+    /// Record one transaction between a client (`peer`) and the address it
+    /// connected to (`server`); `response` is `None` when chaos dropped it
+    pub fn record(
+        &self,
+        peer: SocketAddr,
+        server: SocketAddr,
+        unit: u8,
+        transaction_id: u16,
+        request: &Request,
+        response: Option<&Response>,
+    ) {
+        if let Some(log) = &self.log {
+            let transaction = Transaction::new(peer, unit, request, response);
+            if let Ok(mut log) = log.lock() {
+                let result = serde_json::to_writer(&mut *log, &transaction)
+                    .map_err(std::io::Error::from)
+                    .and_then(|()| log.write_all(b"\n"))
+                    .and_then(|()| log.flush());
+                if let Err(e) = result {
+                    warn!("Failed to write request log: {}", e);
+                }
+            }
+        }
+
+        if let Some(capture) = &self.capture {
+            let Ok(mut capture) = capture.lock() else {
+                return;
+            };
+            let mut result = capture.packet(
+                peer,
+                server,
+                &mbap(transaction_id, unit, &encode_request(request)),
+            );
+            if let (Ok(()), Some(response)) = (&result, response) {
+                result = capture.packet(
+                    server,
+                    peer,
+                    &mbap(transaction_id, unit, &encode_response(response)),
+                );
+            }
+            if let Err(e) = result {
+                warn!("Failed to write traffic capture: {}", e);
+            }
+        }
+    }
+}
+
+/// A pcap file of synthetic IP/TCP packets carrying Modbus TCP frames, so
+/// the traffic opens in Wireshark. Sequence numbers are tracked per direction.
+struct Capture {
+    out: BufWriter<File>,
+    sequence: std::collections::HashMap<(SocketAddr, SocketAddr), u32>,
+}
+
+impl Capture {
+    fn create(path: &Path) -> anyhow::Result<Self> {
+        let file = File::create(path)
+            .with_context(|| format!("Failed to create traffic capture {}", path.display()))?;
+        let mut out = BufWriter::new(file);
+        // Global header: magic, version 2.4, GMT offset, accuracy, snaplen, link type
+        out.write_all(&0xa1b2c3d4u32.to_le_bytes())?;
+        out.write_all(&2u16.to_le_bytes())?;
+        out.write_all(&4u16.to_le_bytes())?;
+        out.write_all(&0i32.to_le_bytes())?;
+        out.write_all(&0u32.to_le_bytes())?;
+        out.write_all(&65535u32.to_le_bytes())?;
+        out.write_all(&LINKTYPE_RAW.to_le_bytes())?;
+        out.flush()?;
+        Ok(Self {
+            out,
+            sequence: Default::default(),
+        })
+    }
+
+    fn packet(&mut self, from: SocketAddr, to: SocketAddr, payload: &[u8]) -> std::io::Result<()> {
+        let ack = self.sequence.get(&(to, from)).copied().unwrap_or(1);
+        let next = self.sequence.entry((from, to)).or_insert(1);
+        let sequence = *next;
+        *next = next.wrapping_add(payload.len() as u32);
+
+        let mut tcp = Vec::with_capacity(20 + payload.len());
+        tcp.extend_from_slice(&from.port().to_be_bytes());
+        tcp.extend_from_slice(&to.port().to_be_bytes());
+        tcp.extend_from_slice(&sequence.to_be_bytes());
+        tcp.extend_from_slice(&ack.to_be_bytes());
+        tcp.extend_from_slice(&[0x50, 0x18]); // 20-byte header; PSH, ACK
+        tcp.extend_from_slice(&65535u16.to_be_bytes()); // window
+        tcp.extend_from_slice(&[0, 0, 0, 0]); // checksum (unset), urgent pointer
+        tcp.extend_from_slice(payload);
+
+        let mut packet = match (from.ip(), to.ip()) {
+            (IpAddr::V4(src), IpAddr::V4(dst)) => {
+                let mut header = vec![0x45, 0];
+                header.extend_from_slice(&((20 + tcp.len()) as u16).to_be_bytes());
+                header.extend_from_slice(&[0, 0, 0x40, 0, 64, 6, 0, 0]);
+                header.extend_from_slice(&src.octets());
+                header.extend_from_slice(&dst.octets());
+                let checksum = ipv4_checksum(&header);
+                header[10..12].copy_from_slice(&checksum.to_be_bytes());
+                header
+            }
+            (src, dst) => {
+                let octets = |ip: IpAddr| match ip {
+                    IpAddr::V4(ip) => ip.to_ipv6_mapped().octets(),
+                    IpAddr::V6(ip) => ip.octets(),
+                };
+                let mut header = vec![0x60, 0, 0, 0];
+                header.extend_from_slice(&(tcp.len() as u16).to_be_bytes());
+                header.extend_from_slice(&[6, 64]);
+                header.extend_from_slice(&octets(src));
+                header.extend_from_slice(&octets(dst));
+                header
+            }
+        };
+        packet.extend_from_slice(&tcp);
+
+        let now = Utc::now();
+        self.out
+            .write_all(&(now.timestamp() as u32).to_le_bytes())?;
+        self.out
+            .write_all(&now.timestamp_subsec_micros().to_le_bytes())?;
+        self.out.write_all(&(packet.len() as u32).to_le_bytes())?;
+        self.out.write_all(&(packet.len() as u32).to_le_bytes())?;
+        self.out.write_all(&packet)?;
+        self.out.flush()
+    }
+}
+
+fn ipv4_checksum(header: &[u8]) -> u16 {
+    let mut sum: u32 = header
+        .chunks(2)
+        .map(|pair| u32::from(u16::from_be_bytes([pair[0], pair[1]])))
+        .sum();
+    while sum > 0xffff {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+/// Modbus TCP frame: MBAP header followed by the PDU
+fn mbap(transaction_id: u16, unit: u8, pdu: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(7 + pdu.len());
+    frame.extend_from_slice(&transaction_id.to_be_bytes());
+    frame.extend_from_slice(&0u16.to_be_bytes());
+    frame.extend_from_slice(&((pdu.len() + 1) as u16).to_be_bytes());
+    frame.push(unit);
+    frame.extend_from_slice(pdu);
+    frame
+}
+
+fn words(words: &[u16]) -> Vec<u8> {
+    words.iter().flat_map(|w| w.to_be_bytes()).collect()
+}
+
+fn packed_bits(bits: &[bool]) -> Vec<u8> {
+    bits.chunks(8)
+        .map(|chunk| {
+            chunk
+                .iter()
+                .enumerate()
+                .fold(0u8, |byte, (i, &bit)| byte | (u8::from(bit) << i))
+        })
+        .collect()
+}
+
+fn coil(value: bool) -> u16 {
+    if value {
+        0xFF00
+    } else {
+        0x0000
+    }
+}
+
+fn encode_request(request: &Request) -> Vec<u8> {
+    let mut pdu = vec![function_code(request)];
+    match request {
+        Request::ReadCoils(addr, count)
+        | Request::ReadDiscreteInputs(addr, count)
+        | Request::ReadHoldingRegisters(addr, count)
+        | Request::ReadInputRegisters(addr, count) => pdu.extend(words(&[*addr, *count])),
+        Request::WriteSingleCoil(addr, value) => pdu.extend(words(&[*addr, coil(*value)])),
+        Request::WriteSingleRegister(addr, value) => pdu.extend(words(&[*addr, *value])),
+        Request::WriteMultipleCoils(addr, bits) => {
+            let packed = packed_bits(bits);
+            pdu.extend(words(&[*addr, bits.len() as u16]));
+            pdu.push(packed.len() as u8);
+            pdu.extend(packed);
+        }
+        Request::WriteMultipleRegisters(addr, values) => {
+            pdu.extend(words(&[*addr, values.len() as u16]));
+            pdu.push((values.len() * 2) as u8);
+            pdu.extend(words(values));
+        }
+        Request::MaskWriteRegister(addr, and, or) => pdu.extend(words(&[*addr, *and, *or])),
+        Request::ReadWriteMultipleRegisters(read_addr, count, write_addr, values) => {
+            pdu.extend(words(&[
+                *read_addr,
+                *count,
+                *write_addr,
+                values.len() as u16,
+            ]));
+            pdu.push((values.len() * 2) as u8);
+            pdu.extend(words(values));
+        }
+        Request::Custom(_, data) => pdu.extend_from_slice(data),
+        Request::Disconnect => {}
+    }
+    pdu
+}
+
+/// PDU of a response, as sent on the wire
+pub fn encode_response(response: &Response) -> Vec<u8> {
+    match response {
+        Response::ReadCoils(bits) | Response::ReadDiscreteInputs(bits) => {
+            let function = if matches!(response, Response::ReadCoils(_)) {
+                0x01
+            } else {
+                0x02
+            };
+            let packed = packed_bits(bits);
+            let mut pdu = vec![function, packed.len() as u8];
+            pdu.extend(packed);
+            pdu
+        }
+        Response::ReadHoldingRegisters(values)
+        | Response::ReadInputRegisters(values)
+        | Response::ReadWriteMultipleRegisters(values) => {
+            let function = match response {
+                Response::ReadHoldingRegisters(_) => 0x03,
+                Response::ReadInputRegisters(_) => 0x04,
+                _ => 0x17,
+            };
+            let mut pdu = vec![function, (values.len() * 2) as u8];
+            pdu.extend(words(values));
+            pdu
+        }
+        Response::WriteSingleCoil(addr, value) => {
+            [vec![0x05], words(&[*addr, coil(*value)])].concat()
+        }
+        Response::WriteMultipleCoils(addr, count) => [vec![0x0F], words(&[*addr, *count])].concat(),
+        Response::WriteSingleRegister(addr, value) => {
+            [vec![0x06], words(&[*addr, *value])].concat()
+        }
+        Response::WriteMultipleRegisters(addr, count) => {
+            [vec![0x10], words(&[*addr, *count])].concat()
+        }
+        Response::MaskWriteRegister(addr, and, or) => {
+            [vec![0x16], words(&[*addr, *and, *or])].concat()
+        }
+        Response::Custom(function, data) => [&[*function][..], data].concat(),
+    }
+}
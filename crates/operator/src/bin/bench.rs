@@ -0,0 +1,185 @@
+//! Throughput benchmark of the controller against embedded mock PLCs.
+//!
+//! For every combination of fleet size and poll interval, starts that many
+//! well-behaved mocks on the loopback interface, creates an IndustrialPLC
+//! for each in the current cluster, and runs the controller in this process.
+//! After a warm-up, it measures reconciles per second from the operator's
+//! metrics and Modbus transactions per second from the mocks' request
+//! counters, then prints one row per combination.
+
+use clap::Parser;
+use kube::Client;
+use mock_plc::MockConfig;
+use operator::fleet::{MockFleet, REGISTER, TARGET};
+use operator::metrics::OperatorMetrics;
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::Level;
+
+#[derive(Parser)]
+#[command(about = "Measure controller throughput against embedded mock PLCs")]
+struct Args {
+    /// Fleet sizes to measure, comma-separated
+    #[arg(long, value_delimiter = ',', default_value = "10,50,100")]
+    plcs: Vec<usize>,
+
+    /// Poll intervals to measure, in seconds, comma-separated
+    #[arg(long, value_delimiter = ',', default_value = "1,5")]
+    poll_interval_secs: Vec<u64>,
+
+    /// How long each combination is measured, in seconds
+    #[arg(long, default_value_t = 30)]
+    duration_secs: u64,
+
+    /// How long each combination runs before it is measured, in seconds
+    #[arg(long, default_value_t = 10)]
+    warmup_secs: u64,
+
+    /// Namespace the PLCs are created in
+    #[arg(short, long, default_value = "default")]
+    namespace: String,
+
+    /// Print the results as JSON, for comparing runs
+    #[arg(long)]
+    json: bool,
+}
+
+/// Throughput of one fleet size and poll interval
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Sample {
+    plcs: usize,
+    poll_interval_secs: u64,
+    reconciles_per_sec: f64,
+    /// Reconciles per second if every PLC is reconciled once per interval
+    expected_per_sec: f64,
+    modbus_per_sec: f64,
+    modbus_per_reconcile: f64,
+    failed_reconciles: u64,
+}
+
+/// Counters read at the start and end of a measurement
+#[derive(Clone, Copy)]
+struct Counts {
+    at: Instant,
+    reconciles: f64,
+    failed: f64,
+    requests: u64,
+}
+
+impl Counts {
+    fn read(fleet: &MockFleet, metrics: &OperatorMetrics) -> Self {
+        let result = |label| {
+            metrics
+                .reconciliations_total
+                .with_label_values(&[label])
+                .get()
+        };
+        Self {
+            at: Instant::now(),
+            reconciles: result("success") + result("failed"),
+            failed: result("failed"),
+            requests: fleet.mocks().iter().map(|mock| mock.requests()).sum(),
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let subscriber = tracing_subscriber::FmtSubscriber::builder()
+        .with_max_level(Level::ERROR)
+        .finish();
+    tracing::subscriber::set_global_default(subscriber)?;
+
+    let args = Args::parse();
+    let run = format!("{:x}", rand::random::<u32>());
+    let client = Client::try_default().await?;
+
+    let mut samples = Vec::new();
+    for &plcs in &args.plcs {
+        for &poll_interval_secs in &args.poll_interval_secs {
+            if !args.json {
+                eprintln!(
+                    "Measuring {} PLC(s) every {}s for {}s",
+                    plcs, poll_interval_secs, args.duration_secs
+                );
+            }
+            let id = format!("bench-{}-{}x{}", run, plcs, poll_interval_secs);
+            samples.push(measure(&client, &id, plcs, poll_interval_secs, &args).await?);
+        }
+    }
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&samples)?);
+    } else {
+        println!(
+            "{:>6}  {:>5}  {:>12}  {:>10}  {:>10}  {:>12}  {:>6}",
+            "PLCS", "POLL", "RECONCILE/S", "EXPECTED/S", "MODBUS/S", "MODBUS/RECON", "FAILED"
+        );
+        for s in &samples {
+            println!(
+                "{:>6}  {:>4}s  {:>12.1}  {:>10.1}  {:>10.1}  {:>12.2}  {:>6}",
+                s.plcs,
+                s.poll_interval_secs,
+                s.reconciles_per_sec,
+                s.expected_per_sec,
+                s.modbus_per_sec,
+                s.modbus_per_reconcile,
+                s.failed_reconciles
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Run one fleet through warm-up and measurement, then tear it down
+async fn measure(
+    client: &Client,
+    id: &str,
+    plcs: usize,
+    poll_interval_secs: u64,
+    args: &Args,
+) -> anyhow::Result<Sample> {
+    let configs = (0..plcs)
+        .map(|_| MockConfig {
+            value: TARGET,
+            register: REGISTER,
+            ..Default::default()
+        })
+        .collect();
+    let fleet = MockFleet::start(
+        client.clone(),
+        &args.namespace,
+        id,
+        configs,
+        poll_interval_secs,
+    )
+    .await?;
+    let metrics = Arc::new(OperatorMetrics::new()?);
+    let controller = fleet.run_controller(client.clone(), metrics.clone());
+
+    tokio::time::sleep(Duration::from_secs(args.warmup_secs)).await;
+    let start = Counts::read(&fleet, &metrics);
+    tokio::time::sleep(Duration::from_secs(args.duration_secs)).await;
+    let end = Counts::read(&fleet, &metrics);
+    controller.abort();
+    fleet.stop().await?;
+
+    let secs = (end.at - start.at).as_secs_f64();
+    let reconciles = end.reconciles - start.reconciles;
+    let requests = (end.requests - start.requests) as f64;
+    Ok(Sample {
+        plcs,
+        poll_interval_secs,
+        reconciles_per_sec: reconciles / secs,
+        expected_per_sec: plcs as f64 / poll_interval_secs.max(1) as f64,
+        modbus_per_sec: requests / secs,
+        modbus_per_reconcile: if reconciles > 0.0 {
+            requests / reconciles
+        } else {
+            0.0
+        },
+        failed_reconciles: (end.failed - start.failed) as u64,
+    })
+}
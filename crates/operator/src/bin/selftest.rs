@@ -0,0 +1,262 @@
+//! Acceptance run of the operator against a fleet of embedded mock PLCs.
+//!
+//! Starts the mocks on the loopback interface with scripted chaos, creates an
+//! IndustrialPLC for each in the current cluster, and runs the controller in
+//! this process for a fixed time. Chaos is then switched off, and once the
+//! fleet has had time to settle the run checks correction rates, status
+//! accuracy and metric consistency. Exits non-zero when a check fails.
+
+use clap::Parser;
+use fabgitops_types::crd::IndustrialPLC;
+use kube::{Client, ResourceExt};
+use mock_plc::chaos::{ChaosConfig, ConnectionChaos, Latency};
+use mock_plc::scenario::{Scenario, ScenarioAction, ScenarioEvent};
+use mock_plc::MockConfig;
+use operator::fleet::{MockFleet, REGISTER, TARGET};
+use operator::metrics::OperatorMetrics;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::Level;
+
+#[derive(Parser)]
+#[command(about = "Run the controller against embedded mock PLCs under chaos")]
+struct Args {
+    /// Number of mock PLCs
+    #[arg(long, default_value_t = 10)]
+    plcs: usize,
+
+    /// How long the fleet runs under chaos, in seconds
+    #[arg(long, default_value_t = 120)]
+    duration_secs: u64,
+
+    /// How long the fleet runs without chaos before it is checked, in seconds
+    #[arg(long, default_value_t = 30)]
+    settle_secs: u64,
+
+    /// Poll interval of every PLC, in seconds
+    #[arg(long, default_value_t = 2)]
+    poll_interval_secs: u64,
+
+    /// Namespace the PLCs are created in
+    #[arg(short, long, default_value = "default")]
+    namespace: String,
+
+    /// Chaos scenario played on every mock instead of the built-in one
+    #[arg(long)]
+    scenario: Option<PathBuf>,
+
+    /// Seed for chaos randomness, to reproduce a failed run
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Lowest share of drift events that must have been corrected
+    #[arg(long, default_value_t = 0.8)]
+    min_correction_rate: f64,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let subscriber = tracing_subscriber::FmtSubscriber::builder()
+        .with_max_level(Level::WARN)
+        .finish();
+    tracing::subscriber::set_global_default(subscriber)?;
+
+    let args = Args::parse();
+    let seed = args.seed.unwrap_or_else(rand::random);
+    let run = format!("{:x}", seed % 0x1_0000_0000);
+    println!(
+        "Self-test {}: {} PLC(s), {}s of chaos, {}s to settle, seed {}",
+        run, args.plcs, args.duration_secs, args.settle_secs, seed
+    );
+
+    let client = Client::try_default().await?;
+    let configs = (0..args.plcs)
+        .map(|i| MockConfig {
+            value: TARGET,
+            register: REGISTER,
+            chaos: Some(ChaosConfig {
+                enabled: true,
+                interval_secs: 10,
+                registers: vec![REGISTER],
+                ..Default::default()
+            }),
+            seed: Some(seed.wrapping_add(i as u64)),
+            ..Default::default()
+        })
+        .collect();
+    let fleet = MockFleet::start(
+        client.clone(),
+        &args.namespace,
+        &run,
+        configs,
+        args.poll_interval_secs,
+    )
+    .await?;
+
+    let metrics = Arc::new(OperatorMetrics::new()?);
+    let result = run_fleet(&client, &fleet, metrics.clone(), &args).await;
+    let cleanup = fleet.stop().await;
+    let failures = result?;
+    cleanup?;
+
+    if failures.is_empty() {
+        println!("PASS");
+        Ok(())
+    } else {
+        for failure in &failures {
+            println!("FAIL {}", failure);
+        }
+        anyhow::bail!("{} check(s) failed (seed {})", failures.len(), seed)
+    }
+}
+
+/// Play the chaos scenarios and run the controller through the chaos and
+/// settle phases, then check the fleet. Returns the failed checks.
+async fn run_fleet(
+    client: &Client,
+    fleet: &MockFleet,
+    metrics: Arc<OperatorMetrics>,
+    args: &Args,
+) -> anyhow::Result<Vec<String>> {
+    for (i, mock) in fleet.mocks().iter().enumerate() {
+        let scenario = match &args.scenario {
+            Some(path) => Scenario::load(path)?,
+            None => builtin_scenario(i, args.duration_secs),
+        };
+        scenario.spawn(mock.chaos().clone(), mock.state().clone());
+    }
+    let controller = fleet.run_controller(client.clone(), metrics.clone());
+
+    tokio::time::sleep(Duration::from_secs(args.duration_secs)).await;
+    for mock in fleet.mocks() {
+        let chaos = mock.chaos();
+        chaos.disable();
+        chaos.set_latency(Latency::default());
+        chaos.set_connections(ConnectionChaos::default());
+    }
+    println!("Chaos off, settling");
+    tokio::time::sleep(Duration::from_secs(args.settle_secs)).await;
+    controller.abort();
+
+    let plcs = fleet.plcs().await?;
+    Ok(check(&plcs, fleet, &metrics, args))
+}
+
+/// Every failed check of the settled fleet
+fn check(
+    plcs: &[IndustrialPLC],
+    fleet: &MockFleet,
+    metrics: &OperatorMetrics,
+    args: &Args,
+) -> Vec<String> {
+    let mut failures = Vec::new();
+    let expected = fleet.mocks().len();
+    if plcs.len() != expected {
+        failures.push(format!("{} of {} PLC(s) found", plcs.len(), expected));
+    }
+
+    let mut drifts = 0;
+    let mut corrections = 0;
+    for plc in plcs {
+        let name = plc.name_any();
+        let Some(mock) = fleet.mock(&name) else {
+            failures.push(format!("{}: no mock PLC", name));
+            continue;
+        };
+        let Some(status) = &plc.status else {
+            failures.push(format!("{}: never reconciled", name));
+            continue;
+        };
+        drifts += status.drift_events;
+        corrections += status.corrections_applied;
+
+        // Status accuracy: settled, in sync, and matching the device
+        let device = mock.holding(REGISTER);
+        if device != TARGET {
+            failures.push(format!("{}: device holds {}, not {}", name, device, TARGET));
+        }
+        if !status.in_sync || status.current_value != Some(device) {
+            failures.push(format!(
+                "{}: status says inSync={} currentValue={:?}, device holds {}",
+                name, status.in_sync, status.current_value, device
+            ));
+        }
+
+        // Metric consistency, per PLC
+        let namespace = plc.namespace().unwrap_or_default();
+        let metric = metrics
+            .plc_drift_events_total
+            .with_label_values(&[&namespace, &name])
+            .get();
+        if metric != f64::from(status.drift_events) {
+            failures.push(format!(
+                "{}: plc_drift_events_total is {} but status.driftEvents is {}",
+                name, metric, status.drift_events
+            ));
+        }
+    }
+
+    // Metric consistency, fleet-wide
+    let metric = metrics.corrections_total.get();
+    if metric != f64::from(corrections) {
+        failures.push(format!(
+            "corrections_total is {} but the statuses add up to {}",
+            metric, corrections
+        ));
+    }
+
+    // Correction rate
+    println!(
+        "{} drift event(s), {} correction(s) applied",
+        drifts, corrections
+    );
+    if drifts == 0 {
+        failures.push("no drift happened; raise --duration-secs".to_string());
+    } else {
+        let rate = f64::from(corrections) / f64::from(drifts);
+        if rate < args.min_correction_rate {
+            failures.push(format!(
+                "correction rate {:.2} is below {:.2}",
+                rate, args.min_correction_rate
+            ));
+        }
+    }
+
+    failures
+}
+
+/// Periodic drift on every mock, plus one kind of trouble in the middle third
+/// of the run on three out of four: slow responses, dropped connections, or
+/// an outage
+fn builtin_scenario(i: usize, duration_secs: u64) -> Scenario {
+    let third = duration_secs / 3;
+    let (start, end) = match i % 4 {
+        1 => (
+            ScenarioAction::Latency(Latency {
+                delay_ms: 200,
+                jitter_ms: 100,
+                percent: 100,
+            }),
+            Some(ScenarioAction::Latency(Latency::default())),
+        ),
+        2 => (
+            ScenarioAction::Connections(ConnectionChaos {
+                drop_percent: 20,
+                ..Default::default()
+            }),
+            Some(ScenarioAction::Connections(ConnectionChaos::default())),
+        ),
+        3 => (ScenarioAction::Outage { secs: third.max(1) }, None),
+        _ => return Scenario { events: Vec::new() },
+    };
+    let mut events = vec![ScenarioEvent {
+        at_secs: third,
+        action: start,
+    }];
+    events.extend(end.map(|action| ScenarioEvent {
+        at_secs: 2 * third,
+        action,
+    }));
+    Scenario { events }
+}
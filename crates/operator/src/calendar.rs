@@ -0,0 +1,50 @@
+use fabgitops_types::crd::{IndustrialPLC, ProductionCalendar};
+use kube::runtime::reflector::{ObjectRef, Store};
+use kube::{Api, Client, ResourceExt};
+
+/// Why the PLC's `productionCalendar` does not allow corrections now, as a
+/// message, or none when it does or the PLC has none. A missing or invalid
+/// calendar allows none.
+pub async fn closed(
+    plc: &IndustrialPLC,
+    client: &Client,
+    namespace: &str,
+) -> kube::Result<Option<String>> {
+    let Some(name) = &plc.spec.production_calendar else {
+        return Ok(None);
+    };
+    let api: Api<ProductionCalendar> = Api::namespaced(client.clone(), namespace);
+    let Some(calendar) = api.get_opt(name).await? else {
+        return Ok(Some(format!("ProductionCalendar {} not found", name)));
+    };
+    let problems = calendar.spec.validate();
+    if !problems.is_empty() {
+        return Ok(Some(format!(
+            "ProductionCalendar {} is invalid: {}",
+            name,
+            problems.join("; ")
+        )));
+    }
+    Ok(calendar
+        .spec
+        .closed(chrono::Utc::now())
+        .map(|reason| format!("ProductionCalendar {}: {}", name, reason)))
+}
+
+/// Maps a change of a ProductionCalendar to the PLCs referencing it, so they
+/// are reconciled right away
+pub fn referencing(
+    store: Store<IndustrialPLC>,
+) -> impl Fn(ProductionCalendar) -> Vec<ObjectRef<IndustrialPLC>> {
+    move |calendar| {
+        let namespace = calendar.namespace();
+        let name = calendar.name_any();
+        store
+            .state()
+            .iter()
+            .filter(|plc| plc.namespace() == namespace)
+            .filter(|plc| plc.spec.production_calendar.as_deref() == Some(name.as_str()))
+            .map(|plc| ObjectRef::from_obj(plc.as_ref()))
+            .collect()
+    }
+}
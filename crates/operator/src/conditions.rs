@@ -0,0 +1,90 @@
+use fabgitops_types::crd::{ConditionKind, CorrectionCondition, IndustrialPLC};
+use k8s_openapi::api::apps::v1::Deployment;
+use k8s_openapi::api::core::v1::ConfigMap;
+use kube::runtime::reflector::{ObjectRef, Store};
+use kube::{Api, Client, Resource, ResourceExt};
+
+/// The first of the PLC's `correctionConditions` that does not hold, as a
+/// message, or none when corrections may go ahead. A missing object does
+/// not meet its condition.
+pub async fn unmet(
+    plc: &IndustrialPLC,
+    client: &Client,
+    namespace: &str,
+) -> kube::Result<Option<String>> {
+    for condition in &plc.spec.correction_conditions {
+        if let Some(unmet) = check(condition, client, namespace).await? {
+            return Ok(Some(unmet));
+        }
+    }
+    Ok(None)
+}
+
+async fn check(
+    condition: &CorrectionCondition,
+    client: &Client,
+    namespace: &str,
+) -> kube::Result<Option<String>> {
+    let name = &condition.name;
+    match condition.kind {
+        ConditionKind::Deployment => {
+            let api: Api<Deployment> = Api::namespaced(client.clone(), namespace);
+            let Some(deployment) = api.get_opt(name).await? else {
+                return Ok(Some(format!("Deployment {} not found", name)));
+            };
+            let min = condition.min_ready_replicas.unwrap_or(1);
+            let ready = deployment
+                .status
+                .and_then(|status| status.ready_replicas)
+                .unwrap_or(0);
+            Ok((ready < min).then(|| {
+                format!(
+                    "Deployment {} has {} ready replica(s), needs {}",
+                    name, ready, min
+                )
+            }))
+        }
+        ConditionKind::ConfigMap => {
+            let api: Api<ConfigMap> = Api::namespaced(client.clone(), namespace);
+            let Some(config_map) = api.get_opt(name).await? else {
+                return Ok(Some(format!("ConfigMap {} not found", name)));
+            };
+            let key = condition.key.as_deref().unwrap_or_default();
+            let wanted = condition.value.as_deref().unwrap_or_default();
+            let actual = config_map.data.as_ref().and_then(|data| data.get(key));
+            Ok((actual.map(String::as_str) != Some(wanted)).then(|| {
+                format!(
+                    "ConfigMap {} has {}={}, needs {}",
+                    name,
+                    key,
+                    actual.map(String::as_str).unwrap_or("<unset>"),
+                    wanted
+                )
+            }))
+        }
+    }
+}
+
+/// Maps a change of a Deployment or ConfigMap to the PLCs whose
+/// `correctionConditions` refer to it, so they are reconciled right away
+pub fn referencing<K: Resource>(
+    store: Store<IndustrialPLC>,
+    kind: ConditionKind,
+) -> impl Fn(K) -> Vec<ObjectRef<IndustrialPLC>> {
+    move |object| {
+        let namespace = object.namespace();
+        let name = object.name_any();
+        store
+            .state()
+            .iter()
+            .filter(|plc| plc.namespace() == namespace)
+            .filter(|plc| {
+                plc.spec
+                    .correction_conditions
+                    .iter()
+                    .any(|condition| condition.kind == kind && condition.name == name)
+            })
+            .map(|plc| ObjectRef::from_obj(plc.as_ref()))
+            .collect()
+    }
+}
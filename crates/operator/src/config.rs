@@ -0,0 +1,254 @@
+use anyhow::Context as _;
+use fabgitops_types::crd::MIN_POLL_INTERVAL_SECS;
+use rand::Rng;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Operator settings, read from environment variables so they can be set
+/// on the Deployment
+#[derive(Clone, Debug)]
+pub struct OperatorConfig {
+    /// Most PLC requests in flight at once to one device address
+    /// (`FABGITOPS_MAX_IN_FLIGHT_PER_DEVICE`, default 4)
+    pub max_in_flight_per_device: usize,
+    /// Minimum gap between the starts of two reconciles of PLCs on the same
+    /// device address (`FABGITOPS_DEVICE_SPACING_MS`, default 50)
+    pub device_spacing: Duration,
+    /// Poll intervals below this are raised to it
+    /// (`FABGITOPS_MIN_POLL_INTERVAL_SECS`, default 1)
+    pub min_poll_interval: Duration,
+    /// Poll intervals above this are lowered to it
+    /// (`FABGITOPS_MAX_POLL_INTERVAL_SECS`, default 3600)
+    pub max_poll_interval: Duration,
+    /// Up to this percentage of every requeue delay is added at random, so
+    /// PLCs with the same interval drift apart
+    /// (`FABGITOPS_REQUEUE_JITTER_PERCENT`, default 10)
+    pub requeue_jitter_percent: u8,
+    /// First reconciliations of existing PLCs run at once during startup
+    /// (`FABGITOPS_WARMUP_CONCURRENCY`, default 16)
+    pub warmup_concurrency: usize,
+    /// Startup warm-up gives up on PLCs not reconciled by then
+    /// (`FABGITOPS_WARMUP_TIMEOUT_SECS`, default 300)
+    pub warmup_timeout: Duration,
+    /// How PLCs are divided among operator replicas
+    /// (`FABGITOPS_SHARDING`, default off)
+    pub sharding: ShardingMode,
+    /// A replica that has not renewed its shard Lease for this long is
+    /// dropped and its PLCs reassigned (`FABGITOPS_SHARD_LEASE_SECS`,
+    /// default 15)
+    pub shard_lease_duration: Duration,
+    /// Metrics are also written here in the Prometheus text format, for
+    /// builds without the HTTP server (`FABGITOPS_METRICS_FILE`, unset by
+    /// default)
+    pub metrics_file: Option<PathBuf>,
+    /// How often the metrics file is rewritten
+    /// (`FABGITOPS_METRICS_FILE_INTERVAL_SECS`, default 15)
+    pub metrics_file_interval: Duration,
+    /// Namespaces whose PLCs are neither watched nor reconciled
+    /// (`FABGITOPS_IGNORED_NAMESPACES`, comma-separated, default none)
+    pub ignored_namespaces: Vec<String>,
+    /// Most PLCs managed at once; the newest beyond it are left
+    /// `Unscheduled` (`FABGITOPS_MAX_MANAGED_PLCS`, default 0 for no cap)
+    pub max_managed_plcs: Option<usize>,
+    /// The reconcile loop counts as stalled once a PLC has been overdue or
+    /// reconciling this long with no reconciliation completing meanwhile
+    /// (`FABGITOPS_STALL_THRESHOLD_SECS`, default 600, 0 to turn the
+    /// watchdog off)
+    pub stall_threshold: Option<Duration>,
+    /// Exit when the reconcile loop stalls, so Kubernetes restarts the
+    /// operator with a fresh controller (`FABGITOPS_RESTART_ON_STALL`,
+    /// default false)
+    pub restart_on_stall: bool,
+}
+
+/// How PLCs are divided among operator replicas
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShardingMode {
+    /// Every replica reconciles every PLC; run a single replica
+    Off,
+    /// Replicas register with Leases and split PLCs by consistent hashing
+    Auto,
+}
+
+impl FromStr for ShardingMode {
+    type Err = ShardingModeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "off" => Ok(Self::Off),
+            "auto" => Ok(Self::Auto),
+            _ => Err(ShardingModeError),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("expected 'off' or 'auto'")]
+pub struct ShardingModeError;
+
+impl Default for OperatorConfig {
+    fn default() -> Self {
+        Self {
+            max_in_flight_per_device: 4,
+            device_spacing: Duration::from_millis(50),
+            min_poll_interval: Duration::from_secs(MIN_POLL_INTERVAL_SECS),
+            max_poll_interval: Duration::from_secs(3600),
+            requeue_jitter_percent: 10,
+            warmup_concurrency: 16,
+            warmup_timeout: Duration::from_secs(300),
+            sharding: ShardingMode::Off,
+            shard_lease_duration: Duration::from_secs(15),
+            metrics_file: None,
+            metrics_file_interval: Duration::from_secs(15),
+            ignored_namespaces: Vec::new(),
+            max_managed_plcs: None,
+            stall_threshold: Some(Duration::from_secs(600)),
+            restart_on_stall: false,
+        }
+    }
+}
+
+impl OperatorConfig {
+    pub fn from_env() -> anyhow::Result<Self> {
+        let defaults = Self::default();
+        let max_in_flight_per_device = env_or(
+            "FABGITOPS_MAX_IN_FLIGHT_PER_DEVICE",
+            defaults.max_in_flight_per_device,
+        )?;
+        if max_in_flight_per_device == 0 {
+            anyhow::bail!("FABGITOPS_MAX_IN_FLIGHT_PER_DEVICE must be at least 1");
+        }
+        let device_spacing = Duration::from_millis(env_or(
+            "FABGITOPS_DEVICE_SPACING_MS",
+            defaults.device_spacing.as_millis() as u64,
+        )?);
+        let min_poll_interval = Duration::from_secs(env_or(
+            "FABGITOPS_MIN_POLL_INTERVAL_SECS",
+            defaults.min_poll_interval.as_secs(),
+        )?);
+        let max_poll_interval = Duration::from_secs(env_or(
+            "FABGITOPS_MAX_POLL_INTERVAL_SECS",
+            defaults.max_poll_interval.as_secs(),
+        )?);
+        if min_poll_interval > max_poll_interval {
+            anyhow::bail!(
+                "FABGITOPS_MIN_POLL_INTERVAL_SECS ({}) exceeds FABGITOPS_MAX_POLL_INTERVAL_SECS ({})",
+                min_poll_interval.as_secs(),
+                max_poll_interval.as_secs()
+            );
+        }
+        let requeue_jitter_percent = env_or(
+            "FABGITOPS_REQUEUE_JITTER_PERCENT",
+            defaults.requeue_jitter_percent,
+        )?;
+        if requeue_jitter_percent > 100 {
+            anyhow::bail!("FABGITOPS_REQUEUE_JITTER_PERCENT must be at most 100");
+        }
+        let warmup_concurrency =
+            env_or("FABGITOPS_WARMUP_CONCURRENCY", defaults.warmup_concurrency)?;
+        if warmup_concurrency == 0 {
+            anyhow::bail!("FABGITOPS_WARMUP_CONCURRENCY must be at least 1");
+        }
+        let warmup_timeout = Duration::from_secs(env_or(
+            "FABGITOPS_WARMUP_TIMEOUT_SECS",
+            defaults.warmup_timeout.as_secs(),
+        )?);
+        let sharding = env_or("FABGITOPS_SHARDING", defaults.sharding)?;
+        let shard_lease_duration = Duration::from_secs(env_or(
+            "FABGITOPS_SHARD_LEASE_SECS",
+            defaults.shard_lease_duration.as_secs(),
+        )?);
+        if shard_lease_duration < Duration::from_secs(3) {
+            anyhow::bail!("FABGITOPS_SHARD_LEASE_SECS must be at least 3");
+        }
+        let metrics_file = std::env::var("FABGITOPS_METRICS_FILE")
+            .ok()
+            .filter(|path| !path.trim().is_empty())
+            .map(PathBuf::from);
+        let metrics_file_interval = Duration::from_secs(env_or(
+            "FABGITOPS_METRICS_FILE_INTERVAL_SECS",
+            defaults.metrics_file_interval.as_secs(),
+        )?);
+        if metrics_file_interval.is_zero() {
+            anyhow::bail!("FABGITOPS_METRICS_FILE_INTERVAL_SECS must be at least 1");
+        }
+        let ignored_namespaces = std::env::var("FABGITOPS_IGNORED_NAMESPACES")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|namespace| !namespace.is_empty())
+            .map(str::to_string)
+            .collect();
+        let max_managed_plcs = match env_or("FABGITOPS_MAX_MANAGED_PLCS", 0)? {
+            0 => None,
+            max => Some(max),
+        };
+        let stall_threshold = match env_or(
+            "FABGITOPS_STALL_THRESHOLD_SECS",
+            defaults.stall_threshold.map_or(0, |t| t.as_secs()),
+        )? {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        };
+        let restart_on_stall = env_or("FABGITOPS_RESTART_ON_STALL", defaults.restart_on_stall)?;
+
+        Ok(Self {
+            max_in_flight_per_device,
+            device_spacing,
+            min_poll_interval,
+            max_poll_interval,
+            requeue_jitter_percent,
+            warmup_concurrency,
+            warmup_timeout,
+            sharding,
+            shard_lease_duration,
+            metrics_file,
+            metrics_file_interval,
+            ignored_namespaces,
+            max_managed_plcs,
+            stall_threshold,
+            restart_on_stall,
+        })
+    }
+
+    /// Field selector leaving out the ignored namespaces, for watches and
+    /// lists of PLCs and CorrectionRequests; empty when none are ignored
+    pub fn namespace_selector(&self) -> String {
+        self.ignored_namespaces
+            .iter()
+            .map(|namespace| format!("metadata.namespace!={}", namespace))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// A PLC's `pollIntervalSecs` held within the configured bounds
+    pub fn poll_interval(&self, secs: u64) -> Duration {
+        Duration::from_secs(secs).clamp(self.min_poll_interval, self.max_poll_interval)
+    }
+
+    /// `delay` plus a random share of up to the jitter percentage. Jitter is
+    /// only ever added, so the minimum poll interval still holds.
+    pub fn jitter(&self, delay: Duration) -> Duration {
+        if self.requeue_jitter_percent == 0 {
+            return delay;
+        }
+        let max_extra = delay.mul_f64(f64::from(self.requeue_jitter_percent) / 100.0);
+        delay + max_extra.mul_f64(rand::thread_rng().gen::<f64>())
+    }
+}
+
+/// Parse an environment variable, falling back to `default` when unset
+fn env_or<T>(name: &str, default: T) -> anyhow::Result<T>
+where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    match std::env::var(name) {
+        Ok(value) => value
+            .trim()
+            .parse()
+            .with_context(|| format!("Invalid {}: '{}'", name, value)),
+        Err(_) => Ok(default),
+    }
+}
@@ -1,14 +1,39 @@
-use crate::crd::{IndustrialPLC, IndustrialPLCStatus};
+use crate::calendar;
+use crate::conditions;
+use crate::config::OperatorConfig;
+use crate::hooks::{HookClient, HookPayload, HookPhase};
 use crate::metrics::OperatorMetrics;
-use crate::plc_client::PLCClient;
-use kube::api::{Api, Patch, PatchParams};
+use crate::queue::ReconcileQueue;
+use crate::quota::PlcQuota;
+use crate::scheduler::DeviceScheduler;
+use crate::sharding::Sharding;
+use crate::warmup::FleetWarmup;
+use crate::watch::ChangeWatcher;
+use fabgitops_modbus::{
+    decode_ascii, encode_ascii, exception_name, trim_padding, write_register_guarded, AuthStep,
+    Encoding, PLCClient, PlcErrorKind, PlcProtocol, PlcSession, WriteOutcome,
+};
+use fabgitops_types::crd::{
+    BlockStatus, CorrectionPolicy, CorrectionRequest, CorrectionRequestSpec,
+    CorrectionRequestState, CorrectionRequestStatus, CorrectionStep, DataType, Decision,
+    DiagnosticReading, IndustrialPLC, IndustrialPLCStatus, PLCPhase, ReadSnapshot, StepState,
+    ValueEncoding, WriteBlock, CLOCK_SKEW, DEPRECATED_SPEC, PLC_NAME_LABEL,
+};
+use fabgitops_types::{
+    HOLD_REASON_ANNOTATION, LAST_CORRECTED_AT_ANNOTATION, LAST_CORRECTED_BY_ANNOTATION,
+    LAST_CORRECTED_FROM_ANNOTATION, PROTECTED_ANNOTATION, PROTECTION_FINALIZER,
+};
+use k8s_openapi::api::core::v1::{ConfigMap, Secret};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use kube::api::{Api, DeleteParams, ListParams, Patch, PatchParams, PostParams};
 use kube::runtime::controller::Action;
 use kube::runtime::events::{Event, EventType, Recorder, Reporter};
 use kube::{Client, Resource, ResourceExt};
-use std::sync::Arc;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
 use tokio::time::Instant;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 /// Context passed to reconciliation
 #[derive(Clone)]
@@ -16,6 +41,16 @@ pub struct Context {
     pub client: Client,
     pub metrics: Arc<OperatorMetrics>,
     pub reporter: Reporter,
+    pub scheduler: Arc<DeviceScheduler>,
+    pub config: OperatorConfig,
+    pub hooks: HookClient,
+    pub queue: Arc<ReconcileQueue>,
+    pub warmup: Arc<FleetWarmup>,
+    pub watcher: Arc<ChangeWatcher>,
+    /// Set when replicas split the PLCs among themselves
+    pub sharding: Option<Arc<Sharding>>,
+    /// Set when the number of managed PLCs is capped
+    pub quota: Option<Arc<PlcQuota>>,
 }
 
 /// Main reconciliation function
@@ -24,48 +59,221 @@ pub async fn reconcile(plc: Arc<IndustrialPLC>, ctx: Arc<Context>) -> Result<Act
     let name = plc.name_any();
     let namespace = plc.namespace().unwrap_or_default();
 
+    // The first pass over existing PLCs at startup is bounded separately
+    let _warmup = ctx.warmup.begin(&namespace, &name).await;
+
+    // Another replica reconciles it; a rebalance reconciles every PLC again
+    if let Some(sharding) = &ctx.sharding {
+        if !sharding.owns(&namespace, &name) {
+            ctx.watcher.stop(&namespace, &name);
+            return Ok(Action::await_change());
+        }
+    }
+
     info!("Reconciling PLC: {}/{}", namespace, name);
+    ctx.queue.reconciling(&namespace, &name);
 
     let api: Api<IndustrialPLC> = Api::namespaced(ctx.client.clone(), &namespace);
-    let mut status = IndustrialPLCStatus::new();
+    let writer = StatusWriter::new(api.clone(), &plc, ctx.metrics.clone());
+    // Carry the previous status forward so counters and history accumulate
+    let mut status = plc.status.clone().unwrap_or_default();
+
+    // Protection applies even to PLCs whose spec is invalid
+    if let Some(action) = sync_protection(&plc, &api, &writer, &ctx).await? {
+        return Ok(action);
+    }
+
+    // Beyond the cap on managed PLCs; a slot frees up when an older one goes
+    if let Some(quota) = &ctx.quota {
+        if !quota.admits(&plc) {
+            ctx.watcher.stop(&namespace, &name);
+            if status.phase != PLCPhase::Unscheduled {
+                let message = format!(
+                    "The operator manages at most {} PLC(s); older ones come first",
+                    quota.max()
+                );
+                warn!("PLC {}/{} unscheduled: {}", namespace, name, message);
+                status.set_unscheduled("QuotaExceeded", message.clone());
+                writer.update(&mut status).await?;
+                Recorder::new(
+                    ctx.client.clone(),
+                    ctx.reporter.clone(),
+                    plc.object_ref(&()),
+                )
+                .publish(Event {
+                    type_: EventType::Warning,
+                    reason: "Unscheduled".to_string(),
+                    note: Some(message),
+                    action: "Reconcile".to_string(),
+                    secondary: None,
+                })
+                .await
+                .ok();
+            }
+            return Ok(Action::requeue(Duration::from_secs(60)));
+        }
+    }
+    check_deprecations(&plc, &mut status, &ctx).await;
 
-    // Update managed PLCs count
-    let all_plcs = Api::<IndustrialPLC>::all(ctx.client.clone());
-    if let Ok(plc_list) = all_plcs.list(&Default::default()).await {
-        ctx.metrics.set_managed_plcs(plc_list.items.len() as i64);
+    // Nothing can be done for a spec that fails validation
+    let problems = plc.spec.validate();
+    if !problems.is_empty() {
+        let error = Error::ConfigInvalid(problems.join("; "));
+        ctx.watcher.stop(&namespace, &name);
+        status.set_value_stale();
+        status.set_failed(error.reason(), error.to_string());
+        mark_deletion_blocked(&plc, &mut status);
+        record_value_age(&ctx, &namespace, &name, &status);
+        writer.update(&mut status).await?;
+        ctx.metrics
+            .record_reconcile(&namespace, &name, start.elapsed().as_secs_f64(), true);
+        return Err(error);
     }
 
+    // Gateway credentials, read every reconcile so rotated ones apply next poll
+    let auth = match auth_steps(&plc, &ctx.client, &namespace).await {
+        Ok(auth) => auth,
+        Err(error) if error.is_kubernetes() => return Err(error),
+        Err(error) => {
+            ctx.watcher.stop(&namespace, &name);
+            status.set_value_stale();
+            status.set_failed(error.reason(), error.to_string());
+            mark_deletion_blocked(&plc, &mut status);
+            record_value_age(&ctx, &namespace, &name, &status);
+            writer.update(&mut status).await?;
+            ctx.metrics
+                .record_reconcile(&namespace, &name, start.elapsed().as_secs_f64(), true);
+            return Err(error);
+        }
+    };
+
+    // Wait for a turn on the device, shared with other PLCs behind it
+    let _permit = ctx
+        .scheduler
+        .acquire(&plc.spec.device_address, plc.spec.port)
+        .await;
+
     // Create PLC client
-    let plc_client = PLCClient::new(&plc.spec.device_address, plc.spec.port);
+    let plc_client = PLCClient::new(&plc.spec.device_address, plc.spec.port)
+        .with_encoding(wire_encoding(plc.spec.encoding))
+        .with_observer(ctx.metrics.clone())
+        .with_auth(auth);
+    ctx.watcher.sync(&plc, &plc_client);
 
-    // Health check
-    match plc_client.health_check().await {
-        Ok(true) => {
+    // Health check, then one login for every read and write this reconcile
+    let connected = match plc_client.health_check().await {
+        Ok(true) => plc_client
+            .session()
+            .await
+            .map_err(|e| Error::plc(PlcErrorKind::of(&e), format!("Failed to connect: {:#}", e))),
+        Ok(false) => Err(Error::Unreachable("PLC unreachable".to_string())),
+        Err(e) => Err(Error::plc(
+            PlcErrorKind::of(&e),
+            format!("PLC unreachable: {:#}", e),
+        )),
+    };
+    let session = match connected {
+        Ok(session) => {
             ctx.metrics.set_connection_status(true);
             info!("PLC {}/{} is reachable", namespace, name);
+            session
         }
-        Ok(false) | Err(_) => {
+        Err(error) => {
             ctx.metrics.set_connection_status(false);
-            status.set_error("PLC unreachable".to_string());
-            update_status(&api, &name, status).await?;
-            return Ok(Action::requeue(Duration::from_secs(10)));
+            status.set_value_stale();
+            status.set_failed(error.reason(), error.to_string());
+            mark_deletion_blocked(&plc, &mut status);
+            record_value_age(&ctx, &namespace, &name, &status);
+            writer.update(&mut status).await?;
+            ctx.metrics
+                .record_reconcile(&namespace, &name, start.elapsed().as_secs_f64(), true);
+            return Err(error);
         }
+    };
+
+    // The production calendar and cluster state can hold corrections back
+    let held = match calendar::closed(&plc, &ctx.client, &namespace).await? {
+        Some(closed) => Some(closed),
+        None => conditions::unmet(&plc, &ctx.client, &namespace).await?,
+    };
+
+    // A technician's hold makes the value read the desired one for a while
+    let hold = plc.hold_until(chrono::Utc::now());
+    let hold_until = hold.map(|until| until.to_rfc3339());
+    if status.hold_until.is_some() != hold.is_some() {
+        let note = match hold {
+            Some(until) => format!(
+                "Holding register {} at its actual value until {}{}",
+                plc.spec.target_register,
+                until.format("%Y-%m-%d %H:%M:%S UTC"),
+                plc.annotations()
+                    .get(HOLD_REASON_ANNOTATION)
+                    .map(|reason| format!(": {}", reason))
+                    .unwrap_or_default()
+            ),
+            None => format!(
+                "Hold ended, register {} is held to targetValue again",
+                plc.spec.target_register
+            ),
+        };
+        info!("{}: {}", name, note);
+        Recorder::new(
+            ctx.client.clone(),
+            ctx.reporter.clone(),
+            plc.object_ref(&()),
+        )
+        .publish(Event {
+            type_: EventType::Normal,
+            reason: if hold.is_some() {
+                "HoldStarted"
+            } else {
+                "HoldEnded"
+            }
+            .to_string(),
+            note: Some(note),
+            action: "Reconcile".to_string(),
+            secondary: None,
+        })
+        .await
+        .ok();
     }
+    status.hold_until = hold_until;
+
+    // Writes needed this reconcile, made together once everything is read
+    let mut plan = Vec::new();
 
     // Read current value from PLC
-    match plc_client.read_register(plc.spec.target_register).await {
+    let outcome = match session.read_register(plc.spec.target_register).await {
         Ok(current_value) => {
             ctx.metrics.set_register_value(current_value);
+            status.set_value_read();
             info!(
                 "Register {} current value: {}, desired: {}",
-                plc.spec.target_register, current_value, plc.spec.target_value
+                plc.spec.target_register,
+                current_value,
+                plc.spec.target()
             );
 
             // Check for drift
-            if current_value != plc.spec.target_value {
+            if let Some(until) = hold {
+                status.set_held(&plc.spec, current_value, until);
+                status.pending_correction = None;
+                Ok(())
+            } else if !plc.spec.matches_target(current_value) {
                 // Drift detected!
-                ctx.metrics.record_drift();
-                status.set_drift(plc.spec.target_value, current_value);
+                ctx.metrics.record_drift(&namespace, &name);
+                let diagnostics = read_diagnostics(&plc, &session).await;
+                let context: Vec<String> = diagnostics.iter().map(|d| d.to_string()).collect();
+                status.set_drift(plc.spec.target(), current_value, diagnostics);
+                if plc.spec.previews_corrections() {
+                    status.set_pending_correction(
+                        plc.spec.target_register,
+                        current_value,
+                        plc.spec.target(),
+                    );
+                } else {
+                    status.pending_correction = None;
+                }
 
                 // Emit event
                 let recorder = Recorder::new(
@@ -78,8 +286,14 @@ pub async fn reconcile(plc: Arc<IndustrialPLC>, ctx: Arc<Context>) -> Result<Act
                         type_: EventType::Warning,
                         reason: "DriftDetected".to_string(),
                         note: Some(format!(
-                            "Register {} drifted: desired={}, actual={}",
-                            plc.spec.target_register, plc.spec.target_value, current_value
+                            "Register {} drifted: desired={}, actual={}{}",
+                            plc.spec.target_register,
+                            plc.spec.describe_value(plc.spec.target()),
+                            plc.spec.describe_value(current_value),
+                            match context.is_empty() {
+                                true => String::new(),
+                                false => format!("; diagnostics: {}", context.join(", ")),
+                            }
                         )),
                         action: "Reconcile".to_string(),
                         secondary: None,
@@ -87,96 +301,1586 @@ pub async fn reconcile(plc: Arc<IndustrialPLC>, ctx: Arc<Context>) -> Result<Act
                     .await
                     .ok();
 
-                // Auto-correct if enabled
-                if plc.spec.auto_correct {
-                    status.set_correcting();
-                    update_status(&api, &name, status.clone()).await?;
-
-                    match plc_client
-                        .write_register(plc.spec.target_register, plc.spec.target_value)
-                        .await
-                    {
-                        Ok(()) => {
-                            ctx.metrics.record_correction();
-                            status.set_corrected(plc.spec.target_value);
-
-                            recorder
-                                .publish(Event {
-                                    type_: EventType::Normal,
-                                    reason: "DriftCorrected".to_string(),
-                                    note: Some(format!(
-                                        "Register {} corrected to {}",
-                                        plc.spec.target_register, plc.spec.target_value
-                                    )),
-                                    action: "Reconcile".to_string(),
-                                    secondary: None,
-                                })
-                                .await
-                                .ok();
-
-                            info!(
-                                "Corrected register {} to {}",
-                                plc.spec.target_register, plc.spec.target_value
-                            );
-                        }
-                        Err(e) => {
-                            status.set_error(format!("Failed to correct: {}", e));
-                            error!("Failed to correct drift: {}", e);
-                        }
+                let maintenance = plc.spec.active_maintenance_window(chrono::Utc::now());
+                if let Some(window) = maintenance {
+                    info!(
+                        "{} is in a maintenance window until {}, deferring correction",
+                        name, window.end
+                    );
+                    status.message = format!(
+                        "{} (maintenance window until {}, correction deferred)",
+                        status.message, window.end
+                    );
+                }
+                if let Some(unmet) = &held {
+                    info!("{}: {}, holding correction", name, unmet);
+                    status.message = format!("{} (correction held: {})", status.message, unmet);
+                }
+
+                match plc.spec.effective_correction_policy() {
+                    _ if maintenance.is_some() || held.is_some() => Ok(()),
+                    _ if plc.spec.dry_run => {
+                        info!(
+                            "Dry run: would write {} to register {} of {}",
+                            plc.spec.target(),
+                            plc.spec.target_register,
+                            name
+                        );
+                        Ok(())
                     }
+                    CorrectionPolicy::Auto => {
+                        plan.push(PlannedWrite {
+                            order: plc.spec.target_order,
+                            kind: WriteKind::Target,
+                        });
+                        Ok(())
+                    }
+                    CorrectionPolicy::ManualApproval => {
+                        request_approval(&plc, &writer, &session, &recorder, &mut status, &ctx)
+                            .await
+                    }
+                    CorrectionPolicy::AlertOnly => Ok(()),
                 }
             } else {
                 // In sync
-                status.set_synced(current_value);
+                status.set_synced(plc.spec.target(), current_value);
+                status.pending_correction = None;
+                if plc.spec.effective_correction_policy() == CorrectionPolicy::ManualApproval {
+                    supersede_pending_requests(&plc, &ctx, "Drift resolved before a decision")
+                        .await?;
+                }
+                Ok(())
             }
         }
         Err(e) => {
-            status.set_error(format!("Failed to read register: {}", e));
-            error!("Failed to read register: {}", e);
+            let error = Error::plc(
+                PlcErrorKind::of(&e),
+                format!("Failed to read register: {:#}", e),
+            );
+            status.set_value_stale();
+            status.set_failed(error.reason(), error.to_string());
+            error!("{}", error);
+            Err(error)
+        }
+    };
+
+    // Blocks share the device turn with the target register
+    let mut blocks = None;
+    let outcome = match outcome {
+        Ok(()) if !plc.spec.write_blocks.is_empty() => {
+            let blocks_checked = reconcile_blocks(
+                &plc,
+                &session,
+                &mut plan,
+                &mut status,
+                held.is_some(),
+                hold.is_some(),
+                &ctx,
+            )
+            .await;
+            match blocks_checked {
+                Ok(checked) => {
+                    blocks = Some(checked);
+                    Ok(())
+                }
+                Err(e) => Err(e),
+            }
+        }
+        outcome => outcome,
+    };
+    let outcome = match outcome {
+        Ok(()) if !plan.is_empty() => {
+            let blocks = blocks.as_deref_mut().unwrap_or_default();
+            execute_plan(&plc, &writer, &session, plan, blocks, &mut status, &ctx).await
         }
+        outcome => outcome,
+    };
+    if let Some(blocks) = blocks {
+        status.set_blocks(blocks);
     }
+    let outcome = match outcome {
+        Ok(()) => sync_snapshot(&plc, &session, &mut status, &ctx).await,
+        outcome => outcome,
+    };
+    if outcome.is_ok() {
+        check_clock(&plc, &session, &mut status, &ctx).await;
+    }
+    session.close().await;
+
+    // Kubernetes errors leave the status as it was
+    let failure = match outcome {
+        Ok(()) => None,
+        Err(e) if e.is_kubernetes() => return Err(e),
+        Err(e) => Some(e),
+    };
 
     // Update status
-    update_status(&api, &name, status).await?;
+    let failed = status.phase == PLCPhase::Failed;
+    mark_deletion_blocked(&plc, &mut status);
+    record_value_age(&ctx, &namespace, &name, &status);
+    writer.update(&mut status).await?;
 
     // Record metrics
     let duration = start.elapsed().as_secs_f64();
-    ctx.metrics.reconciliation_duration.set(duration);
+    ctx.metrics
+        .record_reconcile(&namespace, &name, duration, failed);
+    if let Some(error) = failure {
+        return Err(error);
+    }
 
     // Requeue based on poll interval
-    Ok(Action::requeue(Duration::from_secs(
-        plc.spec.poll_interval_secs,
-    )))
+    let interval = ctx.config.poll_interval(plc.spec.poll_interval_secs);
+    let requeue = ctx.config.jitter(interval);
+    ctx.queue.requeued(&namespace, &name, "success", requeue);
+    Ok(Action::requeue(requeue))
 }
 
-/// Update the status subresource
-async fn update_status(
+/// Appended to the status message while a protected PLC awaits deletion
+const DELETION_BLOCKED: &str = "Deletion blocked by the fabgitops.io/protected annotation.";
+
+/// Keep the protection finalizer on PLCs annotated `fabgitops.io/protected:
+/// "true"`, and only on them. Returns the action to take instead of
+/// reconciling a PLC that is being deleted and no longer protected.
+async fn sync_protection(
+    plc: &IndustrialPLC,
     api: &Api<IndustrialPLC>,
+    writer: &StatusWriter,
+    ctx: &Context,
+) -> Result<Option<Action>, Error> {
+    let name = plc.name_any();
+    let protected = plc.is_protected();
+    let finalized = plc.finalizers().iter().any(|f| f == PROTECTION_FINALIZER);
+
+    if protected != finalized {
+        let mut finalizers: Vec<&String> = plc
+            .finalizers()
+            .iter()
+            .filter(|f| *f != PROTECTION_FINALIZER)
+            .collect();
+        let finalizer = PROTECTION_FINALIZER.to_string();
+        if protected {
+            finalizers.push(&finalizer);
+        }
+        // The resource version makes a concurrent change of finalizers fail
+        let patch = Patch::Merge(serde_json::json!({
+            "metadata": {
+                "finalizers": finalizers,
+                "resourceVersion": plc.resource_version(),
+            }
+        }));
+        let patched = api.patch(&name, &PatchParams::default(), &patch).await?;
+        writer.advance(&patched);
+        info!(
+            "{} deletion protection for {}",
+            if protected { "Enabled" } else { "Disabled" },
+            name
+        );
+    }
+
+    if plc.meta().deletion_timestamp.is_none() {
+        return Ok(None);
+    }
+    if !protected {
+        // Kubernetes completes the deletion now that the finalizer is gone
+        return Ok(Some(Action::await_change()));
+    }
+
+    let reported = plc
+        .status
+        .as_ref()
+        .is_some_and(|s| s.message.contains(DELETION_BLOCKED));
+    if !reported {
+        warn!("Deletion of protected PLC {} is blocked", name);
+        Recorder::new(
+            ctx.client.clone(),
+            ctx.reporter.clone(),
+            plc.object_ref(&()),
+        )
+        .publish(Event {
+            type_: EventType::Warning,
+            reason: "DeletionBlocked".to_string(),
+            note: Some(format!(
+                "Remove the {} annotation to let the deletion complete",
+                PROTECTED_ANNOTATION
+            )),
+            action: "Delete".to_string(),
+            secondary: None,
+        })
+        .await
+        .ok();
+    }
+    Ok(None)
+}
+
+/// Keep the `DeprecatedSpec` condition in line with the deprecated fields
+/// the spec uses, and emit a Warning event with migration guidance when one
+/// is first seen. Deprecated fields keep working.
+async fn check_deprecations(plc: &IndustrialPLC, status: &mut IndustrialPLCStatus, ctx: &Context) {
+    let deprecations = plc.spec.deprecations();
+    if deprecations.is_empty() {
+        status.remove_condition(DEPRECATED_SPEC);
+        return;
+    }
+    let message = deprecations.join("; ");
+    if status.set_condition(
+        DEPRECATED_SPEC,
+        Some(true),
+        "DeprecatedFields",
+        message.clone(),
+    ) {
+        warn!("{} uses deprecated fields: {}", plc.name_any(), message);
+        Recorder::new(
+            ctx.client.clone(),
+            ctx.reporter.clone(),
+            plc.object_ref(&()),
+        )
+        .publish(Event {
+            type_: EventType::Warning,
+            reason: "DeprecatedSpec".to_string(),
+            note: Some(message),
+            action: "Reconcile".to_string(),
+            secondary: None,
+        })
+        .await
+        .ok();
+    }
+}
+
+/// Note in the status message that a protected PLC's deletion is on hold
+fn mark_deletion_blocked(plc: &IndustrialPLC, status: &mut IndustrialPLCStatus) {
+    if plc.meta().deletion_timestamp.is_some()
+        && plc.is_protected()
+        && !status.message.contains(DELETION_BLOCKED)
+    {
+        status.message = format!("{} {}", status.message, DELETION_BLOCKED);
+    }
+}
+
+/// Write the desired value to the PLC and record the outcome in status,
+/// metrics, and events. `approved_by` is set for manually approved writes.
+async fn apply_correction(
+    plc: &IndustrialPLC,
+    writer: &StatusWriter,
+    plc_client: &impl PlcProtocol,
+    recorder: &Recorder,
+    status: &mut IndustrialPLCStatus,
+    ctx: &Context,
+    approved_by: Option<&str>,
+) -> Result<(), Error> {
+    let name = plc.name_any();
+
+    // Never write outside the safe operating range, even if the spec asks to
+    if let Err(e) = plc.spec.check_bounds(plc.spec.target()) {
+        status.set_correction_failed(plc.spec.target(), format!("Refused: {}", e));
+        status.reason = Some("ConfigInvalid".to_string());
+        recorder
+            .publish(Event {
+                type_: EventType::Warning,
+                reason: "UnsafeTargetValue".to_string(),
+                note: Some(format!("Correction refused: {}", e)),
+                action: "Reconcile".to_string(),
+                secondary: None,
+            })
+            .await
+            .ok();
+        error!("Refusing correction for {}: {}", name, e);
+        return Err(Error::ConfigInvalid(e));
+    }
+
+    let payload = hook_payload(
+        plc,
+        plc.spec.target_register,
+        status.current_value.map(|v| vec![v]),
+        vec![plc.spec.target()],
+        None,
+        approved_by,
+    );
+    if let Err(error) = pre_write_hook(plc, ctx, recorder, &payload).await {
+        status.set_correction_failed(plc.spec.target(), error.to_string());
+        status.reason = Some(error.reason().to_string());
+        return Err(error);
+    }
+
+    status.set_correcting();
+    writer.update(status).await?;
+
+    let outcome =
+        write_register_guarded(plc_client, plc.spec.target_register, plc.spec.target()).await;
+    let completed = match &outcome {
+        WriteOutcome::Applied => payload.completed("Applied", None),
+        WriteOutcome::NotApplied { reason, .. } => {
+            payload.completed("NotApplied", Some(reason.clone()))
+        }
+        WriteOutcome::Unknown { reason } => payload.completed("Unknown", Some(reason.clone())),
+    };
+    post_write_hook(plc, ctx, &completed).await;
+
+    match outcome {
+        WriteOutcome::Applied => {
+            let from = match status.current_value {
+                Some(value) => format!("register {}: {}", plc.spec.target_register, value),
+                None => format!("register {}", plc.spec.target_register),
+            };
+            ctx.metrics.record_correction();
+            status.set_corrected(plc.spec.target());
+            annotate_correction(plc, writer, ctx, &from).await;
+
+            let mut note = format!(
+                "Register {} corrected to {}",
+                plc.spec.target_register,
+                plc.spec.describe_value(plc.spec.target())
+            );
+            if let Some(user) = approved_by {
+                note.push_str(&format!(" (approved by {})", user));
+            }
+            recorder
+                .publish(Event {
+                    type_: EventType::Normal,
+                    reason: "DriftCorrected".to_string(),
+                    note: Some(note),
+                    action: "Reconcile".to_string(),
+                    secondary: None,
+                })
+                .await
+                .ok();
+
+            info!(
+                "Corrected register {} to {}",
+                plc.spec.target_register,
+                plc.spec.target()
+            );
+            Ok(())
+        }
+        WriteOutcome::NotApplied { reason, kind } => {
+            let error = Error::plc(kind, reason);
+            status.set_correction_failed(plc.spec.target(), error.to_string());
+            status.reason = Some(error.reason().to_string());
+            error!("Failed to correct drift: {}", error);
+            Err(error)
+        }
+        WriteOutcome::Unknown { reason } => {
+            // Not retried: the next reconcile reads the register and decides
+            let error = Error::VerificationFailed(reason.clone());
+            status.set_correction_failed(plc.spec.target(), error.to_string());
+            status.reason = Some(error.reason().to_string());
+            recorder
+                .publish(Event {
+                    type_: EventType::Warning,
+                    reason: "CorrectionOutcomeUnknown".to_string(),
+                    note: Some(format!(
+                        "Write of {} to register {} may not have been applied: {}",
+                        plc.spec.target(),
+                        plc.spec.target_register,
+                        reason
+                    )),
+                    action: "Reconcile".to_string(),
+                    secondary: None,
+                })
+                .await
+                .ok();
+            error!("Correction outcome unknown for {}: {}", name, reason);
+            Err(error)
+        }
+    }
+}
+
+/// Drift-check every write block and, under the Auto policy outside dry runs
+/// and maintenance windows and unless corrections are `held`, plan a rewrite
+/// of each drifted block with a single FC16 request. While the PLC is on
+/// `hold`, blocks are taken as they are read. Returns the state of the
+/// blocks as read.
+async fn reconcile_blocks<'a>(
+    plc: &'a IndustrialPLC,
+    session: &PlcSession,
+    plan: &mut Vec<PlannedWrite<'a>>,
+    status: &mut IndustrialPLCStatus,
+    held: bool,
+    hold: bool,
+    ctx: &Context,
+) -> Result<Vec<BlockStatus>, Error> {
+    let recorder = Recorder::new(
+        ctx.client.clone(),
+        ctx.reporter.clone(),
+        plc.object_ref(&()),
+    );
+    let correct = plc.spec.effective_correction_policy() == CorrectionPolicy::Auto
+        && !plc.spec.dry_run
+        && !held
+        && plc
+            .spec
+            .active_maintenance_window(chrono::Utc::now())
+            .is_none();
+    // Text is packed byte by byte, whatever encoding numbers use
+    let raw_client = session.clone().with_encoding(Encoding::Binary);
+    let mut blocks = Vec::with_capacity(plc.spec.write_blocks.len());
+
+    for block in &plc.spec.write_blocks {
+        let text = block.text.as_deref().unwrap_or_default();
+        let client = match block.data_type {
+            DataType::Uint16 => session,
+            DataType::String => &raw_client,
+        };
+        let desired = match block.data_type {
+            DataType::Uint16 => block.values.clone(),
+            DataType::String => encode_ascii(text, block.length.unwrap_or_default()),
+        };
+        let actual = match client
+            .read_registers(block.start_register, desired.len() as u16)
+            .await
+        {
+            Ok(actual) => actual,
+            Err(e) => {
+                let error = Error::plc(
+                    PlcErrorKind::of(&e),
+                    format!("Failed to read block {}: {:#}", block.name, e),
+                );
+                status.set_failed(error.reason(), error.to_string());
+                return Err(error);
+            }
+        };
+
+        // Strings compare decoded, so padding differences are not drift
+        let actual_text = match block.data_type {
+            DataType::Uint16 => None,
+            DataType::String => Some(decode_ascii(&actual)),
+        };
+        let in_sync = hold
+            || match actual_text {
+                Some(ref actual_text) => actual_text == trim_padding(text),
+                None => actual == desired,
+            };
+        if in_sync {
+            blocks.push(BlockStatus {
+                name: block.name.clone(),
+                in_sync: true,
+                drifted_offsets: Vec::new(),
+                drifted_values: Vec::new(),
+                actual_text,
+            });
+            continue;
+        }
+        let drifted_offsets: Vec<u16> = desired
+            .iter()
+            .enumerate()
+            .filter(|&(i, value)| actual.get(i) != Some(value))
+            .map(|(i, _)| i as u16)
+            .collect();
+        // Text blocks report what they hold as actualText instead
+        let drifted_values = match block.data_type {
+            DataType::Uint16 => drifted_offsets
+                .iter()
+                .filter_map(|&offset| actual.get(offset as usize).copied())
+                .collect(),
+            DataType::String => Vec::new(),
+        };
+
+        let offsets = drifted_offsets
+            .iter()
+            .map(u16::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+        ctx.metrics
+            .record_drift(&plc.namespace().unwrap_or_default(), &plc.name_any());
+        recorder
+            .publish(Event {
+                type_: EventType::Warning,
+                reason: "BlockDriftDetected".to_string(),
+                note: Some(format!(
+                    "Block {} at register {} drifted at offset(s) {}",
+                    block.name, block.start_register, offsets
+                )),
+                action: "Reconcile".to_string(),
+                secondary: None,
+            })
+            .await
+            .ok();
+
+        if correct {
+            plan.push(PlannedWrite {
+                order: block.order,
+                kind: WriteKind::Block {
+                    block,
+                    index: blocks.len(),
+                    actual,
+                    desired,
+                },
+            });
+        }
+        blocks.push(BlockStatus {
+            name: block.name.clone(),
+            in_sync: false,
+            drifted_offsets,
+            drifted_values,
+            actual_text,
+        });
+    }
+
+    Ok(blocks)
+}
+
+/// A write planned during a reconcile
+struct PlannedWrite<'a> {
+    order: i32,
+    kind: WriteKind<'a>,
+}
+
+enum WriteKind<'a> {
+    /// Correct the target register
+    Target,
+    /// Rewrite `block`, whose state is `index` in the block statuses
+    Block {
+        block: &'a WriteBlock,
+        index: usize,
+        actual: Vec<u16>,
+        desired: Vec<u16>,
+    },
+}
+
+impl PlannedWrite<'_> {
+    fn step(
+        &self,
+        plc: &IndustrialPLC,
+        state: StepState,
+        message: Option<String>,
+    ) -> CorrectionStep {
+        let (name, register) = match &self.kind {
+            WriteKind::Target => ("targetRegister", plc.spec.target_register),
+            WriteKind::Block { block, .. } => (block.name.as_str(), block.start_register),
+        };
+        CorrectionStep {
+            name: name.to_string(),
+            register,
+            order: self.order,
+            state,
+            message,
+            timestamp: (state != StepState::Skipped).then(|| chrono::Utc::now().to_rfc3339()),
+        }
+    }
+}
+
+/// Make the planned writes lowest `order` first over the reconcile's session,
+/// recording each in `status.correctionSteps`. Once a write fails the rest
+/// are skipped, so no write runs after one it is declared to depend on.
+async fn execute_plan(
+    plc: &IndustrialPLC,
+    writer: &StatusWriter,
+    session: &PlcSession,
+    mut plan: Vec<PlannedWrite<'_>>,
+    blocks: &mut [BlockStatus],
+    status: &mut IndustrialPLCStatus,
+    ctx: &Context,
+) -> Result<(), Error> {
+    // Stable, so equal orders keep the order they were planned in
+    plan.sort_by_key(|write| write.order);
+
+    let recorder = Recorder::new(
+        ctx.client.clone(),
+        ctx.reporter.clone(),
+        plc.object_ref(&()),
+    );
+    let mut steps = Vec::with_capacity(plan.len());
+    let mut failure: Option<Error> = None;
+
+    for write in &plan {
+        if let Some(error) = &failure {
+            let message = format!("Not attempted after an earlier step failed: {}", error);
+            steps.push(write.step(plc, StepState::Skipped, Some(message)));
+            continue;
+        }
+        let outcome = match &write.kind {
+            WriteKind::Target => {
+                apply_correction(plc, writer, session, &recorder, status, ctx, None).await
+            }
+            WriteKind::Block {
+                block,
+                index,
+                actual,
+                desired,
+            } => {
+                let outcome = write_block(
+                    plc, writer, session, block, actual, desired, &recorder, status, ctx,
+                )
+                .await;
+                if outcome.is_ok() {
+                    blocks[*index] = BlockStatus {
+                        name: block.name.clone(),
+                        in_sync: true,
+                        drifted_offsets: Vec::new(),
+                        drifted_values: Vec::new(),
+                        actual_text: block.text.clone(),
+                    };
+                }
+                outcome
+            }
+        };
+        match outcome {
+            Ok(()) => steps.push(write.step(plc, StepState::Applied, None)),
+            Err(e) if e.is_kubernetes() => return Err(e),
+            Err(e) => {
+                steps.push(write.step(plc, StepState::Failed, Some(e.to_string())));
+                failure = Some(e);
+            }
+        }
+    }
+
+    status.correction_steps = steps;
+    match failure {
+        Some(error) => Err(error),
+        None => Ok(()),
+    }
+}
+
+/// Record a successful correction in annotations on the PLC, for anyone
+/// with only kubectl. Failing to is logged rather than failing the reconcile,
+/// as the write already happened.
+async fn annotate_correction(
+    plc: &IndustrialPLC,
+    writer: &StatusWriter,
+    ctx: &Context,
+    from: &str,
+) {
+    let name = plc.name_any();
+    let api: Api<IndustrialPLC> =
+        Api::namespaced(ctx.client.clone(), &plc.namespace().unwrap_or_default());
+    let instance = ctx
+        .reporter
+        .instance
+        .clone()
+        .unwrap_or_else(|| ctx.reporter.controller.clone());
+    let patch = Patch::Merge(serde_json::json!({
+        "metadata": {
+            "annotations": {
+                LAST_CORRECTED_AT_ANNOTATION: chrono::Utc::now().to_rfc3339(),
+                LAST_CORRECTED_FROM_ANNOTATION: from,
+                LAST_CORRECTED_BY_ANNOTATION: instance,
+            }
+        }
+    }));
+    match api.patch(&name, &PatchParams::default(), &patch).await {
+        Ok(patched) => writer.advance(&patched),
+        Err(e) => warn!("Could not annotate correction of {}: {}", name, e),
+    }
+}
+
+/// Rewrite a drifted write block with a single FC16 request, after asking
+/// the pre-write hook, and record the outcome in status, metrics, and events
+#[allow(clippy::too_many_arguments)]
+async fn write_block(
+    plc: &IndustrialPLC,
+    writer: &StatusWriter,
+    session: &PlcSession,
+    block: &WriteBlock,
+    actual: &[u16],
+    desired: &[u16],
+    recorder: &Recorder,
+    status: &mut IndustrialPLCStatus,
+    ctx: &Context,
+) -> Result<(), Error> {
+    let payload = hook_payload(
+        plc,
+        block.start_register,
+        Some(actual.to_vec()),
+        desired.to_vec(),
+        Some(&block.name),
+        None,
+    );
+    if let Err(error) = pre_write_hook(plc, ctx, recorder, &payload).await {
+        status.set_failed(error.reason(), error.to_string());
+        return Err(error);
+    }
+
+    // Text is packed byte by byte, whatever encoding numbers use
+    let session = match block.data_type {
+        DataType::Uint16 => session.clone(),
+        DataType::String => session.clone().with_encoding(Encoding::Binary),
+    };
+    // Within MAX_BLOCK_REGISTERS, so a single FC16 request
+    let written = session.write_registers(block.start_register, desired).await;
+    let completed = match &written {
+        Ok(()) => payload.completed("Applied", None),
+        // Only a device that answered is known to have rejected it
+        Err(e) => match PlcErrorKind::of(e) {
+            PlcErrorKind::Exception { .. } => {
+                payload.completed("NotApplied", Some(format!("{:#}", e)))
+            }
+            _ => payload.completed("Unknown", Some(format!("{:#}", e))),
+        },
+    };
+    post_write_hook(plc, ctx, &completed).await;
+
+    if let Err(e) = written {
+        let error = Error::plc(
+            PlcErrorKind::of(&e),
+            format!("Failed to write block {}: {:#}", block.name, e),
+        );
+        status.set_failed(error.reason(), error.to_string());
+        return Err(error);
+    }
+    ctx.metrics.record_correction();
+    status.corrections_applied += 1;
+    let from = actual
+        .iter()
+        .map(u16::to_string)
+        .collect::<Vec<_>>()
+        .join(",");
+    annotate_correction(plc, writer, ctx, &format!("block {}: {}", block.name, from)).await;
+    recorder
+        .publish(Event {
+            type_: EventType::Normal,
+            reason: "BlockCorrected".to_string(),
+            note: Some(format!(
+                "Block {} rewritten ({} registers from {})",
+                block.name,
+                desired.len(),
+                block.start_register
+            )),
+            action: "Reconcile".to_string(),
+            secondary: None,
+        })
+        .await
+        .ok();
+    info!("Corrected block {} of {}", block.name, plc.name_any());
+    Ok(())
+}
+
+/// The body sent to write hooks for a write of `desired` from `register` on
+fn hook_payload(
+    plc: &IndustrialPLC,
+    register: u16,
+    current_values: Option<Vec<u16>>,
+    desired_values: Vec<u16>,
+    block: Option<&str>,
+    approved_by: Option<&str>,
+) -> HookPayload {
+    HookPayload {
+        phase: HookPhase::PreWrite,
+        namespace: plc.namespace().unwrap_or_default(),
+        plc: plc.name_any(),
+        register,
+        current_values,
+        desired_values,
+        block: block.map(str::to_string),
+        approved_by: approved_by.map(str::to_string),
+        outcome: None,
+        message: None,
+    }
+}
+
+/// Ask the pre-write hook, if any, whether a write may go ahead. A hook that
+/// fails to answer with a 2xx status vetoes it.
+async fn pre_write_hook(
+    plc: &IndustrialPLC,
+    ctx: &Context,
+    recorder: &Recorder,
+    payload: &HookPayload,
+) -> Result<(), Error> {
+    let Some(hooks) = &plc.spec.write_hooks else {
+        return Ok(());
+    };
+    let Some(url) = &hooks.pre_write else {
+        return Ok(());
+    };
+    let timeout = Duration::from_secs(hooks.timeout_secs);
+    let Err(veto) = ctx.hooks.call(url, payload, timeout).await else {
+        return Ok(());
+    };
+
+    recorder
+        .publish(Event {
+            type_: EventType::Warning,
+            reason: "CorrectionVetoed".to_string(),
+            note: Some(format!(
+                "Write to register {} vetoed by pre-write hook: {}",
+                payload.register, veto
+            )),
+            action: "Reconcile".to_string(),
+            secondary: None,
+        })
+        .await
+        .ok();
+    warn!("Write to {} vetoed: {}", plc.name_any(), veto);
+    Err(Error::WriteVetoed(veto))
+}
+
+/// Report a write's outcome to the post-write hook, if any. The write has
+/// already happened, so a failing hook is only logged.
+async fn post_write_hook(plc: &IndustrialPLC, ctx: &Context, payload: &HookPayload) {
+    let Some(hooks) = &plc.spec.write_hooks else {
+        return;
+    };
+    if let Some(url) = &hooks.post_write {
+        let timeout = Duration::from_secs(hooks.timeout_secs);
+        if let Err(e) = ctx.hooks.call(url, payload, timeout).await {
+            warn!("Post-write hook for {} failed: {}", plc.name_any(), e);
+        }
+    }
+}
+
+/// Publish the `readSnapshot` range to the PLC's ConfigMap, or delete the
+/// ConfigMap once the range is dropped from the spec. A failed read leaves
+/// the previous snapshot in place; its `readTime` tells consumers its age.
+async fn sync_snapshot(
+    plc: &IndustrialPLC,
+    session: &PlcSession,
+    status: &mut IndustrialPLCStatus,
+    ctx: &Context,
+) -> Result<(), Error> {
+    let name = plc.name_any();
+    let namespace = plc.namespace().unwrap_or_default();
+    let config_maps: Api<ConfigMap> = Api::namespaced(ctx.client.clone(), &namespace);
+
+    let Some(snapshot) = &plc.spec.read_snapshot else {
+        if let Some(previous) = status.snapshot_config_map.take() {
+            match config_maps
+                .delete(&previous, &DeleteParams::default())
+                .await
+            {
+                Ok(_) => info!("Deleted snapshot configmap {}/{}", namespace, previous),
+                Err(kube::Error::Api(e)) if e.code == 404 => {}
+                Err(e) => {
+                    status.snapshot_config_map = Some(previous);
+                    return Err(e.into());
+                }
+            }
+        }
+        return Ok(());
+    };
+
+    // Published raw, whatever encoding the target register uses
+    let raw_client = session.clone().with_encoding(Encoding::Binary);
+    let values = match raw_client
+        .read_registers(snapshot.start_register, snapshot.count)
+        .await
+    {
+        Ok(values) => values,
+        Err(e) => {
+            warn!("Failed to read snapshot of {}/{}: {:#}", namespace, name, e);
+            Recorder::new(
+                ctx.client.clone(),
+                ctx.reporter.clone(),
+                plc.object_ref(&()),
+            )
+            .publish(Event {
+                type_: EventType::Warning,
+                reason: "SnapshotReadFailed".to_string(),
+                note: Some(format!(
+                    "Reading {} registers from {}: {:#}",
+                    snapshot.count, snapshot.start_register, e
+                )),
+                action: "Reconcile".to_string(),
+                secondary: None,
+            })
+            .await
+            .ok();
+            return Ok(());
+        }
+    };
+
+    let mut data: BTreeMap<String, String> = (u32::from(snapshot.start_register)..)
+        .zip(&values)
+        .map(|(register, value)| (register.to_string(), value.to_string()))
+        .collect();
+    data.insert("readTime".to_string(), chrono::Utc::now().to_rfc3339());
+
+    let config_map_name = ReadSnapshot::config_map_name(&name);
+    let mut labels = BTreeMap::new();
+    labels.insert(PLC_NAME_LABEL.to_string(), name.clone());
+    let config_map = ConfigMap {
+        metadata: ObjectMeta {
+            name: Some(config_map_name.clone()),
+            namespace: Some(namespace.clone()),
+            labels: Some(labels),
+            owner_references: plc.controller_owner_ref(&()).map(|o| vec![o]),
+            ..Default::default()
+        },
+        data: Some(data),
+        ..Default::default()
+    };
+    config_maps
+        .patch(
+            &config_map_name,
+            &PatchParams::apply(&ctx.reporter.controller).force(),
+            &Patch::Apply(&config_map),
+        )
+        .await?;
+    status.snapshot_config_map = Some(config_map_name);
+
+    Ok(())
+}
+
+/// Read the `driftDiagnostics` registers. A register that cannot be read
+/// is reported as such without failing the reconcile.
+async fn read_diagnostics(plc: &IndustrialPLC, session: &PlcSession) -> Vec<DiagnosticReading> {
+    if plc.spec.drift_diagnostics.is_empty() {
+        return Vec::new();
+    }
+    // Diagnostic registers are read raw, whatever encoding the target register uses
+    let raw_client = session.clone().with_encoding(Encoding::Binary);
+    let mut readings = Vec::with_capacity(plc.spec.drift_diagnostics.len());
+    for diagnostic in &plc.spec.drift_diagnostics {
+        let value = match raw_client.read_register(diagnostic.register).await {
+            Ok(value) => Some(value),
+            Err(e) => {
+                warn!(
+                    "Failed to read diagnostic register {} ({}) of {}: {:#}",
+                    diagnostic.register,
+                    diagnostic.name,
+                    plc.name_any(),
+                    e
+                );
+                None
+            }
+        };
+        readings.push(DiagnosticReading {
+            name: diagnostic.name.clone(),
+            register: diagnostic.register,
+            value,
+            value_name: value.and_then(|v| diagnostic.value_names.get(&v).cloned()),
+        });
+    }
+    readings
+}
+
+/// Compare the PLC's clock to cluster time and set the `ClockSkew`
+/// condition. A clock that cannot be read leaves the condition Unknown
+/// without failing the reconcile.
+async fn check_clock(
+    plc: &IndustrialPLC,
+    session: &PlcSession,
+    status: &mut IndustrialPLCStatus,
+    ctx: &Context,
+) {
+    let Some(clock) = &plc.spec.clock_check else {
+        status.clock_skew_secs = None;
+        status.remove_condition(CLOCK_SKEW);
+        return;
+    };
+    let name = plc.name_any();
+    let namespace = plc.namespace().unwrap_or_default();
+
+    // Clock registers are read raw, whatever encoding the target register uses
+    let raw_client = session.clone().with_encoding(Encoding::Binary);
+    let before = chrono::Utc::now();
+    let read = raw_client
+        .read_registers(clock.start_register, clock.count())
+        .await;
+    let after = chrono::Utc::now();
+    let plc_time = match read {
+        Ok(raw) => clock.decode(&raw),
+        Err(e) => Err(format!("{:#}", e)),
+    };
+    let plc_time = match plc_time {
+        Ok(plc_time) => plc_time,
+        Err(e) => {
+            warn!("Failed to read the clock of {}/{}: {}", namespace, name, e);
+            status.clock_skew_secs = None;
+            status.set_condition(
+                CLOCK_SKEW,
+                None,
+                "ClockUnreadable",
+                format!("Could not read the PLC clock: {}", e),
+            );
+            return;
+        }
+    };
+
+    // The clock was sampled somewhere during the read
+    let cluster_time = before + (after - before) / 2;
+    let skew = (plc_time - cluster_time).num_seconds();
+    status.clock_skew_secs = Some(skew);
+    ctx.metrics.set_clock_skew(&namespace, &name, skew);
+
+    if skew.unsigned_abs() <= clock.max_skew_secs {
+        status.set_condition(
+            CLOCK_SKEW,
+            Some(false),
+            "WithinTolerance",
+            format!(
+                "PLC clock is within {}s of cluster time",
+                clock.max_skew_secs
+            ),
+        );
+        return;
+    }
+    let message = format!(
+        "PLC clock is {}s {} cluster time (tolerance {}s)",
+        skew.abs(),
+        if skew > 0 { "ahead of" } else { "behind" },
+        clock.max_skew_secs
+    );
+    if status.set_condition(CLOCK_SKEW, Some(true), "SkewExceeded", message.clone()) {
+        warn!("{}/{}: {}", namespace, name, message);
+        Recorder::new(
+            ctx.client.clone(),
+            ctx.reporter.clone(),
+            plc.object_ref(&()),
+        )
+        .publish(Event {
+            type_: EventType::Warning,
+            reason: "ClockSkew".to_string(),
+            note: Some(message),
+            action: "Reconcile".to_string(),
+            secondary: None,
+        })
+        .await
+        .ok();
+    }
+}
+
+/// Export how long ago the target register was last read successfully, so
+/// a value that has stopped updating can be alerted on
+fn record_value_age(ctx: &Context, namespace: &str, name: &str, status: &IndustrialPLCStatus) {
+    if let Some(age) = status.value_age_secs(chrono::Utc::now()) {
+        ctx.metrics.set_value_staleness(namespace, name, age);
+    }
+}
+
+/// The client encoding for a spec's `encoding`
+fn wire_encoding(encoding: ValueEncoding) -> Encoding {
+    match encoding {
+        ValueEncoding::Binary => Encoding::Binary,
+        ValueEncoding::Bcd => Encoding::Bcd,
+    }
+}
+
+/// The login steps of the PLC's `authSequence`, with the values of its
+/// Secret filled in. Errors name the Secret and key but never the value.
+async fn auth_steps(
+    plc: &IndustrialPLC,
+    client: &Client,
+    namespace: &str,
+) -> Result<Vec<AuthStep>, Error> {
+    let Some(auth) = &plc.spec.auth_sequence else {
+        return Ok(Vec::new());
+    };
+    let secrets: Api<Secret> = Api::namespaced(client.clone(), namespace);
+    let secret = secrets.get_opt(&auth.secret_name).await?.ok_or_else(|| {
+        Error::ConfigInvalid(format!(
+            "authSequence Secret {} not found",
+            auth.secret_name
+        ))
+    })?;
+    let data = secret.data.unwrap_or_default();
+
+    let mut steps = Vec::new();
+    for step in &auth.steps {
+        if let Some(value) = step.expect {
+            steps.push(AuthStep::Expect {
+                register: step.register,
+                value,
+            });
+        } else {
+            let values = match (&step.secret_key, step.value) {
+                (Some(key), _) => {
+                    let text = data
+                        .get(key)
+                        .and_then(|bytes| std::str::from_utf8(&bytes.0).ok())
+                        .ok_or_else(|| {
+                            Error::ConfigInvalid(format!(
+                                "authSequence Secret {} has no text key {}",
+                                auth.secret_name, key
+                            ))
+                        })?;
+                    secret_registers(text, step.length).ok_or_else(|| {
+                        Error::ConfigInvalid(format!(
+                            "authSequence Secret {} key {} does not fit {} register(s)",
+                            auth.secret_name,
+                            key,
+                            step.length.unwrap_or_default()
+                        ))
+                    })?
+                }
+                (None, value) => vec![value.unwrap_or_default()],
+            };
+            steps.push(AuthStep::Write {
+                register: step.register,
+                values,
+            });
+        }
+        if step.delay_ms > 0 {
+            steps.push(AuthStep::Pause(Duration::from_millis(step.delay_ms)));
+        }
+    }
+    Ok(steps)
+}
+
+/// Registers written for a Secret value: a number as one register, other
+/// text as ASCII in `length` registers. None when the text does not fit.
+fn secret_registers(text: &str, length: Option<u16>) -> Option<Vec<u16>> {
+    let text = text.trim_end_matches(['\r', '\n']);
+    if length.is_none() {
+        if let Ok(value) = text.parse::<u16>() {
+            return Some(vec![value]);
+        }
+    }
+    if !text.is_ascii() {
+        return None;
+    }
+    let registers = match length {
+        Some(length) if text.len() > usize::from(length) * 2 => return None,
+        Some(length) => length,
+        None => u16::try_from(text.len().div_ceil(2)).ok()?,
+    };
+    Some(encode_ascii(text, registers * 2))
+}
+
+/// Drive the ManualApproval workflow for a drifted PLC: open a
+/// CorrectionRequest for the current drift, apply it once approved, and
+/// stand down if it was rejected.
+async fn request_approval(
+    plc: &IndustrialPLC,
+    writer: &StatusWriter,
+    plc_client: &impl PlcProtocol,
+    recorder: &Recorder,
+    status: &mut IndustrialPLCStatus,
+    ctx: &Context,
+) -> Result<(), Error> {
+    let name = plc.name_any();
+    let namespace = plc.namespace().unwrap_or_default();
+    let requests: Api<CorrectionRequest> = Api::namespaced(ctx.client.clone(), &namespace);
+    let current_value = status.current_value.unwrap_or_default();
+
+    let existing = requests
+        .list(&ListParams::default().labels(&format!("{}={}", PLC_NAME_LABEL, name)))
+        .await?
+        .items;
+
+    // Requests raised for this drift episode and desired value
+    let matching = existing.iter().filter(|r| {
+        r.spec.register == plc.spec.target_register
+            && r.spec.desired_value == plc.spec.target()
+            && r.spec.drift_since == status.drift_since
+    });
+
+    if let Some(request) = matching
+        .clone()
+        .find(|r| r.state() == CorrectionRequestState::Pending)
+    {
+        let request_name = request.name_any();
+        match &request.spec.approval {
+            Some(decision) if decision.decision == Decision::Approved => {
+                let result = apply_correction(
+                    plc,
+                    writer,
+                    plc_client,
+                    recorder,
+                    status,
+                    ctx,
+                    Some(&decision.user),
+                )
+                .await;
+                if matches!(result, Err(ref e) if e.is_kubernetes()) {
+                    return result;
+                }
+                let request_status = if result.is_ok() {
+                    CorrectionRequestStatus::new(
+                        CorrectionRequestState::Applied,
+                        format!("Applied after approval by {}", decision.user),
+                    )
+                } else {
+                    CorrectionRequestStatus::new(
+                        CorrectionRequestState::Failed,
+                        status.last_error.clone().unwrap_or_default(),
+                    )
+                };
+                update_request_status(&requests, &request_name, request_status).await?;
+                return result;
+            }
+            Some(decision) => {
+                let message = format!(
+                    "Rejected by {}{}",
+                    decision.user,
+                    decision
+                        .reason
+                        .as_ref()
+                        .map(|r| format!(": {}", r))
+                        .unwrap_or_default()
+                );
+                update_request_status(
+                    &requests,
+                    &request_name,
+                    CorrectionRequestStatus::new(CorrectionRequestState::Rejected, &message),
+                )
+                .await?;
+                status.message = format!("Correction {}. {}", message, status.message);
+            }
+            None => {
+                status.message = format!(
+                    "{} Awaiting approval of correctionrequest/{}",
+                    status.message, request_name
+                );
+            }
+        }
+        return Ok(());
+    }
+
+    if let Some(rejected) = matching
+        .clone()
+        .find(|r| r.state() == CorrectionRequestState::Rejected)
+    {
+        status.message = format!(
+            "{} Correction rejected (correctionrequest/{})",
+            status.message,
+            rejected.name_any()
+        );
+        return Ok(());
+    }
+
+    // The drift changed (or a previous attempt failed): retire stale
+    // requests and open a fresh one
+    supersede_pending_requests(plc, ctx, "Superseded by a newer drift").await?;
+
+    let mut labels = BTreeMap::new();
+    labels.insert(PLC_NAME_LABEL.to_string(), name.clone());
+    let request = CorrectionRequest {
+        metadata: ObjectMeta {
+            generate_name: Some(format!("{}-", name)),
+            namespace: Some(namespace.clone()),
+            labels: Some(labels),
+            owner_references: plc.controller_owner_ref(&()).map(|o| vec![o]),
+            ..Default::default()
+        },
+        spec: CorrectionRequestSpec {
+            plc_name: name.clone(),
+            register: plc.spec.target_register,
+            current_value,
+            desired_value: plc.spec.target(),
+            drift_since: status.drift_since.clone(),
+            approval: None,
+        },
+        status: None,
+    };
+    let created = requests.create(&PostParams::default(), &request).await?;
+    let request_name = created.name_any();
+
+    recorder
+        .publish(Event {
+            type_: EventType::Normal,
+            reason: "CorrectionRequested".to_string(),
+            note: Some(format!(
+                "correctionrequest/{} awaits approval to write {} to register {}",
+                request_name,
+                plc.spec.target(),
+                plc.spec.target_register
+            )),
+            action: "Reconcile".to_string(),
+            secondary: None,
+        })
+        .await
+        .ok();
+
+    status.message = format!(
+        "{} Awaiting approval of correctionrequest/{}",
+        status.message, request_name
+    );
+    info!(
+        "Opened correction request {}/{} for {}",
+        namespace, request_name, name
+    );
+
+    Ok(())
+}
+
+/// Mark any undecided CorrectionRequests for a PLC as superseded
+async fn supersede_pending_requests(
+    plc: &IndustrialPLC,
+    ctx: &Context,
+    reason: &str,
+) -> Result<(), Error> {
+    let namespace = plc.namespace().unwrap_or_default();
+    let requests: Api<CorrectionRequest> = Api::namespaced(ctx.client.clone(), &namespace);
+    let selector = format!("{}={}", PLC_NAME_LABEL, plc.name_any());
+
+    for request in requests
+        .list(&ListParams::default().labels(&selector))
+        .await?
+        .items
+        .into_iter()
+        .filter(|r| r.state() == CorrectionRequestState::Pending)
+    {
+        update_request_status(
+            &requests,
+            &request.name_any(),
+            CorrectionRequestStatus::new(CorrectionRequestState::Superseded, reason),
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Update the status subresource of a CorrectionRequest
+async fn update_request_status(
+    api: &Api<CorrectionRequest>,
     name: &str,
-    status: IndustrialPLCStatus,
+    status: CorrectionRequestStatus,
 ) -> Result<(), Error> {
     let patch = Patch::Merge(serde_json::json!({
         "status": status
     }));
 
     api.patch_status(name, &PatchParams::default(), &patch)
-        .await
-        .map_err(Error::KubeError)?;
+        .await?;
 
     Ok(())
 }
 
+/// Attempts at a status write before a conflict is returned as an error
+const STATUS_WRITE_ATTEMPTS: usize = 3;
+
+/// Writes the status of one PLC during a reconcile. Each patch carries the
+/// resourceVersion last read or written, so a concurrent change of the PLC
+/// makes it fail with a conflict instead of being overwritten. The PLC is
+/// then read again and the fields this reconcile changed are applied on top
+/// of the fresh status before retrying.
+struct StatusWriter {
+    api: Api<IndustrialPLC>,
+    namespace: String,
+    name: String,
+    metrics: Arc<OperatorMetrics>,
+    /// The spec's `enum`, naming `currentValue` on every write
+    value_names: BTreeMap<u16, String>,
+    /// resourceVersion and status as last read or written
+    last: Mutex<(Option<String>, IndustrialPLCStatus)>,
+}
+
+impl StatusWriter {
+    fn new(api: Api<IndustrialPLC>, plc: &IndustrialPLC, metrics: Arc<OperatorMetrics>) -> Self {
+        Self {
+            api,
+            namespace: plc.namespace().unwrap_or_default(),
+            name: plc.name_any(),
+            metrics,
+            value_names: plc.spec.value_names.clone(),
+            last: Mutex::new((
+                plc.resource_version(),
+                plc.status.clone().unwrap_or_default(),
+            )),
+        }
+    }
+
+    /// Take on the resourceVersion of a metadata patch the reconcile made
+    /// itself, so the next status write does not conflict with it. A status
+    /// changed by another writer in the meantime still goes through the
+    /// conflict and rebase.
+    fn advance(&self, plc: &IndustrialPLC) {
+        let mut last = self.last.lock().unwrap();
+        let stored = serde_json::to_value(plc.status.clone().unwrap_or_default()).ok();
+        if stored == serde_json::to_value(&last.1).ok() {
+            last.0 = plc.resource_version();
+        }
+    }
+
+    /// Update the status subresource. On success `status` becomes the
+    /// status as stored, including changes merged in from other writers.
+    async fn update(&self, status: &mut IndustrialPLCStatus) -> Result<(), Error> {
+        status.current_value_name = status
+            .current_value
+            .and_then(|value| self.value_names.get(&value).cloned());
+        let (mut resource_version, base) = self.last.lock().unwrap().clone();
+        // A drift that ends with this write is timed once it is stored
+        let drift_ended = base
+            .drift_since
+            .as_deref()
+            .filter(|_| status.drift_since.is_none())
+            .and_then(|since| chrono::DateTime::parse_from_rfc3339(since).ok());
+        let base = serde_json::to_value(base)?;
+        let mut desired = serde_json::to_value(&*status)?;
+        // A merge patch leaves out fields as they were, so removals are nulls
+        if let (Some(base), Some(desired)) = (base.as_object(), desired.as_object_mut()) {
+            for field in base.keys() {
+                desired
+                    .entry(field.clone())
+                    .or_insert(serde_json::Value::Null);
+            }
+        }
+
+        for attempt in 1..=STATUS_WRITE_ATTEMPTS {
+            let mut patch = serde_json::json!({ "status": desired });
+            if let Some(resource_version) = &resource_version {
+                patch["metadata"] = serde_json::json!({ "resourceVersion": resource_version });
+            }
+            let error = match self
+                .api
+                .patch_status(&self.name, &PatchParams::default(), &Patch::Merge(&patch))
+                .await
+            {
+                Ok(plc) => {
+                    *status = plc.status.clone().unwrap_or_default();
+                    *self.last.lock().unwrap() = (plc.resource_version(), status.clone());
+                    if let Some(since) = drift_ended {
+                        let duration = chrono::Utc::now() - since.with_timezone(&chrono::Utc);
+                        self.metrics
+                            .record_drift_duration(duration.num_milliseconds() as f64 / 1000.0);
+                    }
+                    return Ok(());
+                }
+                Err(e) => Error::from(e),
+            };
+            if !matches!(error, Error::Conflict(_)) {
+                return Err(error);
+            }
+            self.metrics
+                .record_status_conflict(&self.namespace, &self.name);
+            if attempt == STATUS_WRITE_ATTEMPTS {
+                return Err(error);
+            }
+
+            warn!(
+                "Status of {}/{} changed concurrently, retrying on the latest",
+                self.namespace, self.name
+            );
+            let latest = self.api.get_status(&self.name).await?;
+            resource_version = latest.resource_version();
+            desired = rebase_status(&base, desired, latest.status)?;
+        }
+        unreachable!("the last attempt returns")
+    }
+}
+
+/// `desired` with every top-level field it did not change from `base` taken
+/// from `latest` instead, so changes made by another writer survive. Nulls
+/// in `desired` remove fields.
+fn rebase_status(
+    base: &serde_json::Value,
+    desired: serde_json::Value,
+    latest: Option<IndustrialPLCStatus>,
+) -> Result<serde_json::Value, Error> {
+    let mut merged = serde_json::to_value(latest.unwrap_or_default())?;
+    if let (Some(merged), serde_json::Value::Object(desired)) = (merged.as_object_mut(), desired) {
+        for (field, value) in desired {
+            if base.get(&field) != Some(&value) {
+                merged.insert(field, value);
+            }
+        }
+    }
+    Ok(merged)
+}
+
 /// Error policy for failed reconciliations
-pub fn error_policy(_plc: Arc<IndustrialPLC>, error: &Error, _ctx: Arc<Context>) -> Action {
-    error!("Reconciliation failed: {:?}", error);
-    Action::requeue(Duration::from_secs(5))
+pub fn error_policy(plc: Arc<IndustrialPLC>, error: &Error, ctx: Arc<Context>) -> Action {
+    error!("Reconciliation failed ({}): {}", error.reason(), error);
+    ctx.metrics.record_reconcile_error();
+    let requeue = ctx.config.jitter(error.requeue_after());
+    ctx.queue.requeued(
+        &plc.namespace().unwrap_or_default(),
+        &plc.name_any(),
+        error.reason(),
+        requeue,
+    );
+    Action::requeue(requeue)
 }
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error("Kubernetes error: {0}")]
-    KubeError(#[from] kube::Error),
+    KubeError(kube::Error),
 
     #[error("Serialization error: {0}")]
     SerializationError(#[from] serde_json::Error),
+
+    /// The PLC did not accept a connection in time
+    #[error("{0}")]
+    ConnectTimeout(String),
+
+    /// The PLC refused the connection or the request failed without an answer
+    #[error("{0}")]
+    Unreachable(String),
+
+    /// The PLC answered with a Modbus exception
+    #[error("{message} (exception {code:#04x}: {})", exception_name(*code))]
+    ProtocolException { code: u8, message: String },
+
+    /// A correction could not be confirmed as applied or not
+    #[error("Correction not verified: {0}")]
+    VerificationFailed(String),
+
+    /// The spec cannot be acted on until it is fixed
+    #[error("Invalid spec: {0}")]
+    ConfigInvalid(String),
+
+    /// A pre-write hook refused the correction
+    #[error("Vetoed by pre-write hook: {0}")]
+    WriteVetoed(String),
+
+    /// The gateway rejected the login of `authSequence`
+    #[error("{0}")]
+    AuthFailed(String),
+
+    /// A Kubernetes update lost a race with another writer
+    #[error("Conflict: {0}")]
+    Conflict(kube::Error),
+}
+
+impl From<kube::Error> for Error {
+    fn from(error: kube::Error) -> Self {
+        match error {
+            kube::Error::Api(ref response) if response.code == 409 => Error::Conflict(error),
+            error => Error::KubeError(error),
+        }
+    }
+}
+
+impl Error {
+    /// Classify a failed PLC request, keeping `message` as its description
+    fn plc(kind: PlcErrorKind, message: String) -> Self {
+        match kind {
+            PlcErrorKind::ConnectTimeout => Error::ConnectTimeout(message),
+            PlcErrorKind::Exception { code } => Error::ProtocolException { code, message },
+            PlcErrorKind::InvalidValue => Error::ConfigInvalid(message),
+            PlcErrorKind::AuthFailed => Error::AuthFailed(message),
+            PlcErrorKind::Other => Error::Unreachable(message),
+        }
+    }
+
+    /// Reason recorded in `status.reason`
+    pub fn reason(&self) -> &'static str {
+        match self {
+            Error::KubeError(_) => "KubernetesError",
+            Error::SerializationError(_) => "SerializationError",
+            Error::ConnectTimeout(_) => "ConnectTimeout",
+            Error::Unreachable(_) => "Unreachable",
+            Error::ProtocolException { .. } => "ProtocolException",
+            Error::VerificationFailed(_) => "VerificationFailed",
+            Error::ConfigInvalid(_) => "ConfigInvalid",
+            Error::WriteVetoed(_) => "WriteVetoed",
+            Error::AuthFailed(_) => "AuthFailed",
+            Error::Conflict(_) => "Conflict",
+        }
+    }
+
+    /// Errors from the Kubernetes API, which leave the PLC status untouched
+    fn is_kubernetes(&self) -> bool {
+        matches!(
+            self,
+            Error::KubeError(_) | Error::SerializationError(_) | Error::Conflict(_)
+        )
+    }
+
+    /// How long to wait before reconciling again
+    pub fn requeue_after(&self) -> Duration {
+        match self {
+            // Another writer won the race; retry against the fresh object
+            Error::Conflict(_) => Duration::from_secs(1),
+            // Re-read soon to learn what the PLC holds
+            Error::VerificationFailed(_) => Duration::from_secs(5),
+            Error::ProtocolException { code, .. } => match code {
+                // Acknowledge and busy: the device asks to be retried
+                0x05 | 0x06 => Duration::from_secs(5),
+                // Illegal function, address or value: retrying will not help
+                0x01..=0x03 => Duration::from_secs(300),
+                _ => Duration::from_secs(30),
+            },
+            // A slow network is not helped by more connection attempts
+            Error::ConnectTimeout(_) => Duration::from_secs(30),
+            Error::Unreachable(_) => Duration::from_secs(10),
+            // Give the gating system time to change its mind
+            Error::WriteVetoed(_) => Duration::from_secs(30),
+            // Repeated failed logins can lock the gateway account
+            Error::AuthFailed(_) => Duration::from_secs(60),
+            // Spec changes trigger a reconcile on their own
+            Error::ConfigInvalid(_) => Duration::from_secs(300),
+            Error::KubeError(_) | Error::SerializationError(_) => Duration::from_secs(5),
+        }
+    }
 }
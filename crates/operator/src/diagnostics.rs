@@ -0,0 +1,132 @@
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::runtime::Handle;
+use tokio::time::Instant;
+use tracing::warn;
+
+/// How often the watchdog wakes up
+const TICK: Duration = Duration::from_secs(1);
+
+/// Timer lateness or scheduling delay counted as a stall
+const STALL_THRESHOLD: Duration = Duration::from_millis(100);
+
+/// Most recent warnings kept for `/debug/tasks`
+const MAX_WARNINGS: usize = 20;
+
+/// Runtime summary served by `/debug/tasks`
+#[derive(Clone, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskSummary {
+    /// Worker threads of the runtime
+    pub workers: usize,
+
+    /// Tasks spawned and not yet finished
+    pub alive_tasks: usize,
+
+    /// Tasks waiting in the global run queue
+    pub global_queue_depth: usize,
+
+    /// How long the latest probe task waited to be polled, in milliseconds
+    pub schedule_delay_ms: u64,
+
+    /// Times the watchdog woke up, or its probe task was polled, more than
+    /// 100ms late
+    pub stalls: u64,
+
+    /// Worst lateness seen, in milliseconds
+    pub max_lag_ms: u64,
+
+    /// Recent stalls, newest last
+    pub warnings: Vec<String>,
+}
+
+#[derive(Default)]
+struct State {
+    stalls: u64,
+    max_lag: Duration,
+    schedule_delay: Duration,
+    warnings: Vec<String>,
+}
+
+/// Watches the Tokio runtime for stalls, such as a task blocking a worker
+/// thread with synchronous I/O, which otherwise show up only as
+/// reconciliations that silently stop happening
+#[derive(Default)]
+pub struct RuntimeWatchdog {
+    state: Mutex<State>,
+}
+
+impl RuntimeWatchdog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Tick forever on the current runtime. Each tick checks how late the
+    /// timer fired and how long a freshly spawned task waits to be polled;
+    /// both grow when worker threads are blocked.
+    pub async fn run(&self) {
+        loop {
+            let started = Instant::now();
+            tokio::time::sleep(TICK).await;
+            let lag = started.elapsed().saturating_sub(TICK);
+
+            let spawned = Instant::now();
+            let schedule_delay = tokio::spawn(async move { spawned.elapsed() })
+                .await
+                .unwrap_or_default();
+
+            let mut state = self.state.lock().unwrap();
+            state.schedule_delay = schedule_delay;
+            if lag > STALL_THRESHOLD {
+                state.record_stall(
+                    lag,
+                    format!(
+                        "Timer fired {}ms late; a task may be blocking a worker thread",
+                        lag.as_millis()
+                    ),
+                );
+            }
+            if schedule_delay > STALL_THRESHOLD {
+                state.record_stall(
+                    schedule_delay,
+                    format!(
+                        "A new task waited {}ms to be polled; all workers are busy or blocked",
+                        schedule_delay.as_millis()
+                    ),
+                );
+            }
+        }
+    }
+
+    /// Current counts and the recent warnings
+    pub fn summary(&self) -> TaskSummary {
+        let metrics = Handle::current().metrics();
+        let state = self.state.lock().unwrap();
+        TaskSummary {
+            workers: metrics.num_workers(),
+            alive_tasks: metrics.num_alive_tasks(),
+            global_queue_depth: metrics.global_queue_depth(),
+            schedule_delay_ms: state.schedule_delay.as_millis() as u64,
+            stalls: state.stalls,
+            max_lag_ms: state.max_lag.as_millis() as u64,
+            warnings: state.warnings.clone(),
+        }
+    }
+}
+
+impl State {
+    fn record_stall(&mut self, lag: Duration, message: String) {
+        self.stalls += 1;
+        self.max_lag = self.max_lag.max(lag);
+        warn!("{}", message);
+        self.warnings.push(format!(
+            "{} {}",
+            chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            message
+        ));
+        if self.warnings.len() > MAX_WARNINGS {
+            self.warnings.remove(0);
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use crate::config::OperatorConfig;
+use crate::controller::{error_policy, reconcile, Context};
+use crate::hooks::HookClient;
+use crate::metrics::OperatorMetrics;
+use crate::queue::ReconcileQueue;
+use crate::scheduler::DeviceScheduler;
+use crate::warmup::FleetWarmup;
+use crate::watch::ChangeWatcher;
+use anyhow::Context as _;
+use fabgitops_types::crd::{CorrectionPolicy, IndustrialPLC, IndustrialPLCSpec};
+use futures::StreamExt;
+use kube::api::{Api, DeleteParams, ListParams, PostParams};
+use kube::runtime::events::Reporter;
+use kube::runtime::{watcher, Controller};
+use kube::Client;
+use mock_plc::{MockConfig, MockHandle};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+/// Label on the PLCs of one fleet, so its controller sees only those
+pub const FLEET_LABEL: &str = "fabgitops.io/mock-fleet";
+
+/// Register every mock serves and every PLC manages
+pub const REGISTER: u16 = 4001;
+pub const TARGET: u16 = 2500;
+
+/// Embedded mock PLCs on the loopback interface, each with an IndustrialPLC
+/// in the cluster pointing at it. Used by the `selftest` and `bench`
+/// binaries to run the controller in their own process.
+pub struct MockFleet {
+    /// Value of the fleet label, also part of every PLC name
+    id: String,
+    api: Api<IndustrialPLC>,
+    mocks: Vec<MockHandle>,
+}
+
+impl MockFleet {
+    /// Start one mock per config and create a PLC for each, polled every
+    /// `poll_interval_secs`. Configs should serve `TARGET` at `REGISTER`.
+    pub async fn start(
+        client: Client,
+        namespace: &str,
+        id: &str,
+        configs: Vec<MockConfig>,
+        poll_interval_secs: u64,
+    ) -> anyhow::Result<Self> {
+        let mut fleet = Self {
+            id: id.to_string(),
+            api: Api::namespaced(client, namespace),
+            mocks: Vec::with_capacity(configs.len()),
+        };
+        for config in configs {
+            match mock_plc::spawn(config).await {
+                Ok(mock) => fleet.mocks.push(mock),
+                Err(e) => {
+                    fleet.stop().await.ok();
+                    return Err(e);
+                }
+            }
+        }
+
+        for (i, mock) in fleet.mocks.iter().enumerate() {
+            let mut plc =
+                IndustrialPLC::new(&fleet.plc_name(i), plc_spec(mock, poll_interval_secs));
+            plc.metadata.labels = Some([(FLEET_LABEL.to_string(), fleet.id.clone())].into());
+            if let Err(e) = fleet.api.create(&PostParams::default(), &plc).await {
+                fleet.stop().await.ok();
+                return Err(e).context("Failed to create IndustrialPLC; is the CRD installed?");
+            }
+        }
+        Ok(fleet)
+    }
+
+    /// Label selector matching the fleet's PLCs
+    pub fn selector(&self) -> String {
+        format!("{}={}", FLEET_LABEL, self.id)
+    }
+
+    pub fn plc_name(&self, i: usize) -> String {
+        format!("fleet-{}-{}", self.id, i)
+    }
+
+    pub fn mocks(&self) -> &[MockHandle] {
+        &self.mocks
+    }
+
+    /// The mock behind the PLC called `name`
+    pub fn mock(&self, name: &str) -> Option<&MockHandle> {
+        let i = name.strip_prefix(&format!("fleet-{}-", self.id))?;
+        self.mocks.get(i.parse::<usize>().ok()?)
+    }
+
+    /// The fleet's PLCs as the cluster has them now
+    pub async fn plcs(&self) -> anyhow::Result<Vec<IndustrialPLC>> {
+        Ok(self
+            .api
+            .list(&ListParams::default().labels(&self.selector()))
+            .await?
+            .items)
+    }
+
+    /// Run the controller on the fleet's PLCs until the task is aborted.
+    /// Hooks, sharding and warm-up are left out.
+    pub fn run_controller(&self, client: Client, metrics: Arc<OperatorMetrics>) -> JoinHandle<()> {
+        let reporter = Reporter {
+            controller: "fabgitops-mock-fleet".to_string(),
+            instance: None,
+        };
+        let scheduler = Arc::new(DeviceScheduler::new(4, Duration::from_millis(50)));
+        let ctx = Arc::new(Context {
+            client: client.clone(),
+            metrics: metrics.clone(),
+            reporter: reporter.clone(),
+            scheduler: scheduler.clone(),
+            config: OperatorConfig::default(),
+            hooks: HookClient::new(),
+            queue: Arc::new(ReconcileQueue::new()),
+            warmup: Arc::new(FleetWarmup::new(Vec::new(), 1, metrics.clone())),
+            watcher: Arc::new(ChangeWatcher::new(client, reporter, metrics, scheduler)),
+            sharding: None,
+            quota: None,
+        });
+
+        tokio::spawn(
+            Controller::new(
+                self.api.clone(),
+                watcher::Config::default().labels(&self.selector()),
+            )
+            .run(reconcile, error_policy, ctx)
+            .for_each(|_| async {}),
+        )
+    }
+
+    /// Delete the fleet's PLCs and stop the mocks
+    pub async fn stop(self) -> anyhow::Result<()> {
+        let deleted = self
+            .api
+            .delete_collection(
+                &DeleteParams::default(),
+                &ListParams::default().labels(&self.selector()),
+            )
+            .await;
+        for mock in self.mocks {
+            mock.stop().await;
+        }
+        deleted.context("Failed to delete the fleet's PLCs")?;
+        Ok(())
+    }
+}
+
+fn plc_spec(mock: &MockHandle, poll_interval_secs: u64) -> IndustrialPLCSpec {
+    serde_json::from_value(serde_json::json!({
+        "deviceAddress": mock.addr().ip().to_string(),
+        "port": mock.port(),
+        "targetRegister": REGISTER,
+        "targetValue": TARGET,
+        "pollIntervalSecs": poll_interval_secs,
+        "correctionPolicy": CorrectionPolicy::Auto,
+    }))
+    .expect("valid spec")
+}
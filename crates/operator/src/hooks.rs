@@ -0,0 +1,127 @@
+use hyper::client::HttpConnector;
+use hyper::{Body, Client, Method, Request};
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use serde::Serialize;
+use std::time::Duration;
+
+/// Longest part of a hook's answer quoted in a veto
+const MAX_VETO_BODY: usize = 200;
+
+/// When a hook is called relative to the write
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HookPhase {
+    PreWrite,
+    PostWrite,
+}
+
+/// Body POSTed to a write hook
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HookPayload {
+    pub phase: HookPhase,
+    pub namespace: String,
+    pub plc: String,
+
+    /// First register written
+    pub register: u16,
+
+    /// Register values before the write, when known
+    pub current_values: Option<Vec<u16>>,
+
+    /// Register values being written
+    pub desired_values: Vec<u16>,
+
+    /// Write block being corrected; unset for the target register
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block: Option<String>,
+
+    /// Approver of a ManualApproval correction
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub approved_by: Option<String>,
+
+    /// Post-write only: `Applied`, `NotApplied` or `Unknown`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub outcome: Option<&'static str>,
+
+    /// Post-write only: why the write was not applied
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+impl HookPayload {
+    /// The post-write payload following this pre-write one
+    pub fn completed(&self, outcome: &'static str, message: Option<String>) -> Self {
+        Self {
+            phase: HookPhase::PostWrite,
+            outcome: Some(outcome),
+            message,
+            ..self.clone()
+        }
+    }
+}
+
+/// HTTP(S) client for write hooks, shared by all reconciliations
+#[derive(Clone)]
+pub struct HookClient {
+    client: Client<HttpsConnector<HttpConnector>>,
+}
+
+impl HookClient {
+    pub fn new() -> Self {
+        let connector = HttpsConnectorBuilder::new()
+            .with_native_roots()
+            .https_or_http()
+            .enable_http1()
+            .build();
+        Self {
+            client: Client::builder().build(connector),
+        }
+    }
+
+    /// POST `payload` to `url` as JSON. Errors describe why the hook did not
+    /// answer with a 2xx status, quoting the start of its answer.
+    pub async fn call(
+        &self,
+        url: &str,
+        payload: &HookPayload,
+        timeout: Duration,
+    ) -> Result<(), String> {
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri(url)
+            .header("content-type", "application/json")
+            .body(Body::from(
+                serde_json::to_vec(payload).map_err(|e| e.to_string())?,
+            ))
+            .map_err(|e| format!("invalid hook request: {}", e))?;
+
+        let response = tokio::time::timeout(timeout, self.client.request(request))
+            .await
+            .map_err(|_| format!("{} did not answer within {:?}", url, timeout))?
+            .map_err(|e| format!("{} failed: {}", url, e))?;
+
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        let body = tokio::time::timeout(timeout, hyper::body::to_bytes(response.into_body()))
+            .await
+            .ok()
+            .and_then(Result::ok)
+            .unwrap_or_default();
+        let body = String::from_utf8_lossy(&body);
+        let body: String = body.trim().chars().take(MAX_VETO_BODY).collect();
+        if body.is_empty() {
+            Err(format!("{} answered {}", url, status))
+        } else {
+            Err(format!("{} answered {}: {}", url, status, body))
+        }
+    }
+}
+
+impl Default for HookClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
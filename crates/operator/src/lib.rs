@@ -1,4 +1,25 @@
+pub mod calendar;
+pub mod conditions;
+pub mod config;
 pub mod controller;
-pub mod crd;
+#[cfg(feature = "web")]
+pub mod diagnostics;
+#[cfg(feature = "mock-fleet")]
+pub mod fleet;
+pub mod hooks;
+#[cfg(feature = "metrics")]
 pub mod metrics;
-pub mod plc_client;
+#[cfg(not(feature = "metrics"))]
+#[path = "metrics_disabled.rs"]
+pub mod metrics;
+pub mod permissions;
+#[cfg(feature = "profiling")]
+pub mod profiling;
+pub mod queue;
+pub mod quota;
+pub mod scheduler;
+pub mod sharding;
+pub mod values;
+pub mod warmup;
+pub mod watch;
+pub mod watchdog;
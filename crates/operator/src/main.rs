@@ -1,60 +1,397 @@
-mod controller;
-mod crd;
-mod metrics;
-mod plc_client;
-
-use crate::controller::{error_policy, reconcile, Context};
-use crate::crd::IndustrialPLC;
-use crate::metrics::OperatorMetrics;
-use axum::{routing::get, Router};
+use anyhow::Context as _;
+#[cfg(feature = "web")]
+use axum::{
+    extract::Query,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use fabgitops_types::crd::{
+    ConditionKind, CorrectionRequest, FabGitOpsOperator, FabGitOpsOperatorSpec, IndustrialPLC,
+    ProductionCalendar,
+};
 use futures::StreamExt;
-use kube::runtime::events::Reporter;
-use kube::{Api, Client};
-use prometheus::TextEncoder;
+use k8s_openapi::api::apps::v1::Deployment;
+use k8s_openapi::api::core::v1::ConfigMap;
+use kube::api::ListParams;
+use kube::runtime::controller::Error as ControllerError;
+use kube::runtime::events::{Recorder, Reporter};
+use kube::runtime::reflector::Store;
+use kube::runtime::watcher;
+use kube::{Api, Client, Resource, ResourceExt};
+use operator::calendar;
+use operator::conditions;
+use operator::config::{OperatorConfig, ShardingMode};
+use operator::controller::{error_policy, reconcile, Context};
+#[cfg(feature = "web")]
+use operator::diagnostics::{RuntimeWatchdog, TaskSummary};
+use operator::hooks::HookClient;
+use operator::metrics::OperatorMetrics;
+use operator::permissions;
+#[cfg(feature = "web")]
+use operator::queue::QueueItem;
+use operator::queue::ReconcileQueue;
+use operator::quota::PlcQuota;
+use operator::scheduler::DeviceScheduler;
+use operator::sharding::Sharding;
+#[cfg(feature = "web")]
+use operator::values::{register_values, to_csv};
+use operator::warmup::FleetWarmup;
+use operator::watch::ChangeWatcher;
+use operator::watchdog::ControllerWatchdog;
+use std::collections::HashSet;
+#[cfg(feature = "web")]
 use std::net::SocketAddr;
 use std::sync::Arc;
-use tracing::{error, info, Level};
-use tracing_subscriber::FmtSubscriber;
+use std::time::Duration;
+use tracing::{error, info, warn, Level};
+
+/// Field manager, event reporter and FabGitOpsOperator name
+const OPERATOR_NAME: &str = "fabgitops-operator";
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    // Initialize tracing
-    let subscriber = FmtSubscriber::builder()
-        .with_max_level(Level::INFO)
-        .finish();
-    tracing::subscriber::set_global_default(subscriber)?;
+    init_tracing()?;
 
     info!("Starting FabGitOps Operator...");
 
+    let config = OperatorConfig::from_env()?;
+    info!(
+        "Device scheduling: at most {} request(s) in flight, {:?} apart",
+        config.max_in_flight_per_device, config.device_spacing
+    );
+    info!(
+        "Poll interval bounds: {:?}-{:?}, requeue jitter {}%",
+        config.min_poll_interval, config.max_poll_interval, config.requeue_jitter_percent
+    );
+
     // Initialize Kubernetes client
     let client = Client::try_default().await?;
     info!("Connected to Kubernetes cluster");
 
+    // Refuse to run with permissions that would make reconciles fail one by one
+    let namespace = client.default_namespace().to_string();
+    let report = permissions::check(&client, &namespace).await?;
+    report.log();
+    if let Err(e) = permissions::record(&client, &namespace, OPERATOR_NAME, &report).await {
+        warn!(
+            "Could not record operator status in {}/{}: {:#}",
+            namespace, OPERATOR_NAME, e
+        );
+    }
+    if !report.missing.is_empty() {
+        anyhow::bail!(
+            "ServiceAccount lacks {} permission(s) the operator needs; see the log above",
+            report.missing.len()
+        );
+    }
+
+    // Replicas split the PLCs among themselves
+    let (sharding, rebalances) = match config.sharding {
+        ShardingMode::Off => (None, None),
+        ShardingMode::Auto => {
+            let identity = std::env::var("POD_NAME")
+                .context("FABGITOPS_SHARDING=auto needs POD_NAME to tell replicas apart")?;
+            let (sharding, rebalances) = Sharding::join(
+                client.clone(),
+                &namespace,
+                identity,
+                OPERATOR_NAME,
+                config.shard_lease_duration,
+            )
+            .await?;
+            let sharding = Arc::new(sharding);
+            tokio::spawn({
+                let sharding = sharding.clone();
+                async move { sharding.run().await }
+            });
+            (Some(sharding), Some(rebalances))
+        }
+    };
+
     // Initialize metrics
     let metrics = Arc::new(OperatorMetrics::new()?);
     info!("Metrics initialized");
 
+    let queue = Arc::new(ReconcileQueue::new());
+
+    let plcs = Api::<IndustrialPLC>::all(client.clone());
+
+    // Ignored namespaces are filtered out by the API server
+    let mut watch_config = watcher::Config::default();
+    let mut list_params = ListParams::default();
+    let selector = config.namespace_selector();
+    if !selector.is_empty() {
+        info!(
+            "Ignoring namespaces: {}",
+            config.ignored_namespaces.join(", ")
+        );
+        watch_config = watch_config.fields(&selector);
+        list_params = list_params.fields(&selector);
+    }
+
+    // PLCs present now are warmed up before the operator reports ready
+    let fleet = match plcs.list(&list_params).await {
+        Ok(list) => list
+            .items
+            .iter()
+            .map(|plc| (plc.namespace().unwrap_or_default(), plc.name_any()))
+            .collect(),
+        Err(e) => {
+            info!("CRD may not exist yet: {}", e);
+            Vec::new()
+        }
+    };
+    let warmup = Arc::new(FleetWarmup::new(
+        fleet,
+        config.warmup_concurrency,
+        metrics.clone(),
+    ));
+    tokio::spawn({
+        let warmup = warmup.clone();
+        let timeout = config.warmup_timeout;
+        async move { warmup.expire_after(timeout).await }
+    });
+
+    let reporter = Reporter {
+        controller: OPERATOR_NAME.to_string(),
+        instance: std::env::var("HOSTNAME").ok(),
+    };
+    let scheduler = Arc::new(DeviceScheduler::new(
+        config.max_in_flight_per_device,
+        config.device_spacing,
+    ));
+    let watcher = Arc::new(ChangeWatcher::new(
+        client.clone(),
+        reporter.clone(),
+        metrics.clone(),
+        scheduler.clone(),
+    ));
+
+    // Decisions on CorrectionRequests trigger an immediate reconcile of their PLC
+    let correction_requests = Api::<CorrectionRequest>::all(client.clone());
+
+    let mut controller = kube::runtime::Controller::new(plcs, watch_config.clone())
+        .owns(correction_requests, watch_config.clone())
+        .shutdown_on_signal();
+    // Ownership moved, so every PLC is checked again
+    if let Some(rebalances) = rebalances {
+        controller = controller.reconcile_all_on(rebalances);
+    }
+    let store = controller.store();
+
+    // Objects named in correctionConditions or productionCalendar reconcile
+    // the PLCs waiting on them
+    controller = controller
+        .watches(
+            Api::<Deployment>::all(client.clone()),
+            watch_config.clone(),
+            conditions::referencing(store.clone(), ConditionKind::Deployment),
+        )
+        .watches(
+            Api::<ConfigMap>::all(client.clone()),
+            watch_config.clone(),
+            conditions::referencing(store.clone(), ConditionKind::ConfigMap),
+        )
+        .watches(
+            Api::<ProductionCalendar>::all(client.clone()),
+            watch_config,
+            calendar::referencing(store.clone()),
+        );
+
+    // Only the oldest PLCs up to the cap are managed
+    let quota = config.max_managed_plcs.map(|max| {
+        info!("Managing at most {} PLC(s)", max);
+        Arc::new(PlcQuota::new(max, store.clone()))
+    });
+
+    // A reconcile loop that stops completing reconciles is reported on the
+    // operator's own FabGitOpsOperator object
+    let watchdog = config.stall_threshold.map(|threshold| {
+        let watchdog = Arc::new(ControllerWatchdog::new(threshold, metrics.clone()));
+        let mut object = FabGitOpsOperator::new(OPERATOR_NAME, FabGitOpsOperatorSpec {});
+        object.metadata.namespace = Some(namespace.clone());
+        let recorder = Recorder::new(client.clone(), reporter.clone(), object.object_ref(&()));
+        tokio::spawn({
+            let watchdog = watchdog.clone();
+            let queue = queue.clone();
+            let store = store.clone();
+            let sharding = sharding.clone();
+            async move { watchdog.run(&queue, store, sharding, recorder).await }
+        });
+        watchdog
+    });
+    let restart_on_stall = config.restart_on_stall;
+
     // Create context for controller
     let ctx = Arc::new(Context {
         client: client.clone(),
         metrics: metrics.clone(),
-        reporter: Reporter {
-            controller: "fabgitops-operator".to_string(),
-            instance: std::env::var("HOSTNAME").ok(),
-        },
+        reporter,
+        scheduler,
+        config,
+        hooks: HookClient::new(),
+        queue: queue.clone(),
+        warmup: warmup.clone(),
+        watcher: watcher.clone(),
+        sharding: sharding.clone(),
+        quota: quota.clone(),
+    });
+
+    // Deleted PLCs are never reconciled again, so their watches and metric
+    // series end here
+    tokio::spawn({
+        let store = store.clone();
+        let metrics = metrics.clone();
+        async move {
+            let mut plcs = HashSet::new();
+            let mut devices = HashSet::new();
+            loop {
+                tokio::time::sleep(Duration::from_secs(10)).await;
+                let state = store.state();
+                let known: HashSet<_> = state
+                    .iter()
+                    .map(|plc| (plc.namespace().unwrap_or_default(), plc.name_any()))
+                    .collect();
+                let known_devices: HashSet<_> = state
+                    .iter()
+                    .map(|plc| format!("{}:{}", plc.spec.device_address, plc.spec.port))
+                    .collect();
+                watcher.retain(known.iter().cloned());
+                for (namespace, name) in plcs.difference(&known) {
+                    metrics.forget_plc(namespace, name);
+                }
+                for device in devices.difference(&known_devices) {
+                    metrics.forget_device(device);
+                }
+                plcs = known;
+                devices = known_devices;
+            }
+        }
+    });
+
+    #[cfg(feature = "web")]
+    serve_http(
+        metrics.clone(),
+        queue,
+        warmup,
+        watchdog.clone(),
+        sharding.clone(),
+        store.clone(),
+        quota.clone(),
+    )?;
+
+    if let Some(path) = ctx.config.metrics_file.clone() {
+        write_metrics_file(
+            metrics,
+            store,
+            quota,
+            path,
+            ctx.config.metrics_file_interval,
+        );
+    }
+
+    // Start controller
+    info!("Starting IndustrialPLC controller...");
+    let run = controller
+        .run(reconcile, error_policy, ctx)
+        .for_each(|res| {
+            if let (Some(watchdog), Ok(_) | Err(ControllerError::ReconcilerFailed(..))) =
+                (&watchdog, &res)
+            {
+                watchdog.completed();
+            }
+            async move {
+                match res {
+                    Ok(o) => info!("Reconciled: {:?}", o),
+                    Err(e) => error!("Reconciliation error: {:?}", e),
+                }
+            }
+        });
+    // Dropping the controller abandons the reconciles that are stuck
+    let stalled = async {
+        match &watchdog {
+            Some(watchdog) if restart_on_stall => watchdog.stalled().await,
+            _ => futures::future::pending().await,
+        }
+    };
+    let restart = tokio::select! {
+        _ = run => false,
+        _ = stalled => true,
+    };
+
+    if let Some(sharding) = sharding {
+        sharding.leave().await;
+    }
+    if restart {
+        anyhow::bail!("Reconcile loop stalled; exiting so the operator is restarted");
+    }
+    Ok(())
+}
+
+/// Log to stdout; with the `console` feature, also serve tokio-console
+/// (on 127.0.0.1:6669 unless TOKIO_CONSOLE_BIND says otherwise)
+#[cfg(not(feature = "console"))]
+fn init_tracing() -> anyhow::Result<()> {
+    let subscriber = tracing_subscriber::FmtSubscriber::builder()
+        .with_max_level(Level::INFO)
+        .finish();
+    tracing::subscriber::set_global_default(subscriber)?;
+    Ok(())
+}
+
+#[cfg(feature = "console")]
+fn init_tracing() -> anyhow::Result<()> {
+    use tracing_subscriber::filter::LevelFilter;
+    use tracing_subscriber::prelude::*;
+
+    tracing_subscriber::registry()
+        .with(console_subscriber::spawn())
+        .with(tracing_subscriber::fmt::layer().with_filter(LevelFilter::from_level(Level::INFO)))
+        .try_init()?;
+    Ok(())
+}
+
+/// Serve metrics, the probes and the /debug endpoints on port 8080
+#[cfg(feature = "web")]
+fn serve_http(
+    metrics: Arc<OperatorMetrics>,
+    queue: Arc<ReconcileQueue>,
+    warmup: Arc<FleetWarmup>,
+    stall_watchdog: Option<Arc<ControllerWatchdog>>,
+    sharding: Option<Arc<Sharding>>,
+    store: Store<IndustrialPLC>,
+    quota: Option<Arc<PlcQuota>>,
+) -> anyhow::Result<()> {
+    let watchdog = Arc::new(RuntimeWatchdog::new());
+    tokio::spawn({
+        let watchdog = watchdog.clone();
+        async move { watchdog.run().await }
     });
 
-    // Start metrics server
     let metrics_router = Router::new()
         .route("/metrics", get(metrics_handler))
-        .route("/health", get(health_handler));
+        .route("/health", get(health_handler))
+        .route("/ready", get(ready_handler))
+        .route("/api/v1/values", get(values_handler))
+        .route("/debug/queue", get(queue_handler))
+        .route("/debug/tasks", get(tasks_handler));
+    #[cfg(feature = "profiling")]
+    let metrics_router = metrics_router.merge(operator::profiling::routes());
 
     let metrics_addr: SocketAddr = "0.0.0.0:8080".parse()?;
-    let metrics_clone = metrics.clone();
-
     tokio::spawn(async move {
         info!("Starting metrics server on {}", metrics_addr);
-        let app = metrics_router.layer(axum::Extension(metrics_clone));
+        let app = metrics_router
+            .layer(axum::Extension(metrics))
+            .layer(axum::Extension(queue))
+            .layer(axum::Extension(watchdog))
+            .layer(axum::Extension(warmup))
+            .layer(axum::Extension(stall_watchdog))
+            .layer(axum::Extension(sharding))
+            .layer(axum::Extension(store))
+            .layer(axum::Extension(quota));
         axum::serve(
             tokio::net::TcpListener::bind(metrics_addr).await.unwrap(),
             app,
@@ -62,41 +399,157 @@ async fn main() -> anyhow::Result<()> {
         .await
         .unwrap();
     });
+    Ok(())
+}
 
-    // Start controller
-    info!("Starting IndustrialPLC controller...");
-    let plcs = Api::<IndustrialPLC>::all(client.clone());
-
-    // Ensure CRD exists
-    if let Err(e) = plcs.list(&Default::default()).await {
-        info!("CRD may not exist yet: {}", e);
-    }
-
-    kube::runtime::Controller::new(plcs, Default::default())
-        .run(reconcile, error_policy, ctx)
-        .for_each(|res| async move {
-            match res {
-                Ok(o) => info!("Reconciled: {:?}", o),
-                Err(e) => error!("Reconciliation error: {:?}", e),
+/// Rewrite the metrics file every `interval`, for gateways without the
+/// HTTP server
+#[cfg(feature = "metrics")]
+fn write_metrics_file(
+    metrics: Arc<OperatorMetrics>,
+    store: Store<IndustrialPLC>,
+    quota: Option<Arc<PlcQuota>>,
+    path: std::path::PathBuf,
+    interval: Duration,
+) {
+    info!("Writing metrics to {} every {:?}", path.display(), interval);
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(interval).await;
+            record_fleet_gauges(&metrics, &store, quota.as_deref());
+            if let Err(e) = metrics.write_file(&path) {
+                warn!("{:#}", e);
             }
-        })
-        .await;
+        }
+    });
+}
 
-    Ok(())
+#[cfg(not(feature = "metrics"))]
+fn write_metrics_file(
+    _metrics: Arc<OperatorMetrics>,
+    _store: Store<IndustrialPLC>,
+    _quota: Option<Arc<PlcQuota>>,
+    path: std::path::PathBuf,
+    _interval: Duration,
+) {
+    warn!(
+        "Built without the metrics feature; not writing metrics to {}",
+        path.display()
+    );
 }
 
 /// Handler for /metrics endpoint
+#[cfg(feature = "web")]
 async fn metrics_handler(
     axum::Extension(metrics): axum::Extension<Arc<OperatorMetrics>>,
+    axum::Extension(store): axum::Extension<Store<IndustrialPLC>>,
+    axum::Extension(quota): axum::Extension<Option<Arc<PlcQuota>>>,
 ) -> String {
-    let encoder = TextEncoder::new();
-    let metric_families = metrics.registry.gather();
-    encoder
-        .encode_to_string(&metric_families)
-        .unwrap_or_default()
+    record_fleet_gauges(&metrics, &store, quota.as_deref());
+    metrics.encode()
+}
+
+/// Set the gauges summing up the fleet, counted from the controller's
+/// cache rather than listed per reconcile
+#[cfg(feature = "metrics")]
+fn record_fleet_gauges(
+    metrics: &OperatorMetrics,
+    store: &Store<IndustrialPLC>,
+    quota: Option<&PlcQuota>,
+) {
+    let (managed, unscheduled) = match quota {
+        Some(quota) => quota.split(),
+        None => (store.len(), 0),
+    };
+    metrics.set_managed_plcs(managed as i64);
+    metrics.set_unscheduled_plcs(unscheduled as i64);
+
+    // Unscheduled PLCs keep the drift they had but are no longer corrected
+    let now = chrono::Utc::now();
+    let longest = store
+        .state()
+        .iter()
+        .filter_map(|plc| plc.status.as_ref())
+        .filter(|status| status.phase != fabgitops_types::crd::PLCPhase::Unscheduled)
+        .filter_map(|status| status.drift_secs(now))
+        .max()
+        .unwrap_or(0);
+    metrics.set_longest_current_drift(longest.max(0) as f64);
+}
+
+#[cfg(feature = "web")]
+#[derive(serde::Deserialize)]
+struct ValuesParams {
+    /// `json` (the default) or `csv`
+    format: Option<String>,
+}
+
+/// Handler for /api/v1/values: every managed register of every PLC with
+/// its latest and desired value, as JSON or, with `?format=csv`, CSV
+#[cfg(feature = "web")]
+async fn values_handler(
+    Query(params): Query<ValuesParams>,
+    axum::Extension(store): axum::Extension<Store<IndustrialPLC>>,
+) -> Response {
+    let plcs = store.state();
+    let values = register_values(plcs.iter().map(|plc| plc.as_ref()));
+    match params.format.as_deref() {
+        None | Some("json") => axum::Json(values).into_response(),
+        Some("csv") => (
+            [(header::CONTENT_TYPE, "text/csv; charset=utf-8")],
+            to_csv(&values),
+        )
+            .into_response(),
+        Some(format) => (
+            StatusCode::BAD_REQUEST,
+            format!("Unknown format '{}'; use json or csv", format),
+        )
+            .into_response(),
+    }
+}
+
+/// Handler for /debug/queue: every watched PLC this replica reconciles,
+/// with where it is in the reconcile cycle and when it is due again
+#[cfg(feature = "web")]
+async fn queue_handler(
+    axum::Extension(queue): axum::Extension<Arc<ReconcileQueue>>,
+    axum::Extension(store): axum::Extension<Store<IndustrialPLC>>,
+    axum::Extension(sharding): axum::Extension<Option<Arc<Sharding>>>,
+) -> axum::Json<Vec<QueueItem>> {
+    let known = store
+        .state()
+        .into_iter()
+        .map(|plc| (plc.namespace().unwrap_or_default(), plc.name_any()))
+        .filter(|(namespace, name)| sharding.as_ref().is_none_or(|s| s.owns(namespace, name)));
+    axum::Json(queue.snapshot(known))
+}
+
+/// Handler for /debug/tasks: runtime task counts and recent stalls
+#[cfg(feature = "web")]
+async fn tasks_handler(
+    axum::Extension(watchdog): axum::Extension<Arc<RuntimeWatchdog>>,
+) -> axum::Json<TaskSummary> {
+    axum::Json(watchdog.summary())
 }
 
 /// Handler for /health endpoint
+#[cfg(feature = "web")]
 async fn health_handler() -> &'static str {
     "OK"
 }
+
+/// Handler for /ready endpoint: 503 until the startup warm-up is complete
+/// and while the reconcile loop is stalled
+#[cfg(feature = "web")]
+async fn ready_handler(
+    axum::Extension(warmup): axum::Extension<Arc<FleetWarmup>>,
+    axum::Extension(watchdog): axum::Extension<Option<Arc<ControllerWatchdog>>>,
+) -> (StatusCode, &'static str) {
+    if !warmup.is_complete() {
+        (StatusCode::SERVICE_UNAVAILABLE, "Fleet warm-up in progress")
+    } else if watchdog.is_some_and(|watchdog| watchdog.is_stalled()) {
+        (StatusCode::SERVICE_UNAVAILABLE, "Reconcile loop stalled")
+    } else {
+        (StatusCode::OK, "OK")
+    }
+}
@@ -1,4 +1,13 @@
-use prometheus::{Counter, Gauge, Opts, Registry};
+use anyhow::Context as _;
+use fabgitops_modbus::{ConnectOutcome, ConnectionObserver};
+use prometheus::{
+    Counter, CounterVec, Gauge, GaugeVec, Histogram, HistogramOpts, HistogramVec, Opts, Registry,
+    TextEncoder,
+};
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 /// Metrics exposed by the operator
 #[derive(Clone)]
@@ -12,9 +21,17 @@ pub struct OperatorMetrics {
     pub corrections_total: Counter,
 
     /// Current number of managed PLCs
-    #[allow(dead_code)]
     pub managed_plcs: Gauge,
 
+    /// PLCs beyond the cap on managed PLCs
+    pub unscheduled_plcs: Gauge,
+
+    /// Time from drift detection to correction or resolution
+    pub drift_duration: Histogram,
+
+    /// Age of the oldest drift not yet corrected or resolved
+    pub longest_current_drift: Gauge,
+
     /// Reconciliation loop duration
     pub reconciliation_duration: Gauge,
 
@@ -23,6 +40,51 @@ pub struct OperatorMetrics {
 
     /// Current register value
     pub register_value: Gauge,
+
+    /// Reconciliations by result (success, failed, error)
+    pub reconciliations_total: CounterVec,
+
+    /// Duration of the last reconciliation per PLC
+    pub plc_reconcile_duration: GaugeVec,
+
+    /// Drift events detected per PLC
+    pub plc_drift_events_total: CounterVec,
+
+    /// Seconds since each PLC's target register was last read successfully
+    pub plc_value_staleness: GaugeVec,
+
+    /// Deviations from the target value seen by the change watch per PLC
+    pub plc_value_deviations_total: CounterVec,
+
+    /// PLC clock minus cluster time per PLC with a clock check
+    pub plc_clock_skew: GaugeVec,
+
+    /// Status writes per PLC that conflicted with another writer
+    pub plc_status_conflicts_total: CounterVec,
+
+    /// TCP connection attempts per device by result
+    pub connect_attempts_total: CounterVec,
+
+    /// Successful connections per device following a failed attempt
+    pub reconnects_total: CounterVec,
+
+    /// Time to establish a TCP connection per device
+    pub connect_latency: HistogramVec,
+
+    /// Connections currently open per device
+    pub open_connections: GaugeVec,
+
+    /// 1 once every PLC present at startup has been reconciled
+    pub fleet_warmup_complete: Gauge,
+
+    /// 1 while the controller watchdog considers the reconcile loop stalled
+    pub controller_stalled: Gauge,
+
+    /// Times the reconcile loop was found stalled
+    pub controller_stalls_total: Counter,
+
+    /// Devices whose last connection attempt failed
+    failing_devices: Arc<Mutex<HashSet<String>>>,
 }
 
 impl OperatorMetrics {
@@ -44,6 +106,26 @@ impl OperatorMetrics {
             "Number of IndustrialPLC resources being managed",
         ))?;
 
+        let unscheduled_plcs = Gauge::with_opts(Opts::new(
+            "unscheduled_plcs",
+            "Number of IndustrialPLC resources left unscheduled by the managed PLC cap",
+        ))?;
+
+        let drift_duration = Histogram::with_opts(
+            HistogramOpts::new(
+                "drift_duration_seconds",
+                "Time from drift detection to correction or resolution in seconds",
+            )
+            .buckets(vec![
+                1.0, 5.0, 10.0, 15.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1800.0, 3600.0,
+            ]),
+        )?;
+
+        let longest_current_drift = Gauge::with_opts(Opts::new(
+            "longest_current_drift_seconds",
+            "Age of the oldest drift not yet corrected or resolved in seconds (0 without drift)",
+        ))?;
+
         let reconciliation_duration = Gauge::with_opts(Opts::new(
             "reconciliation_duration_seconds",
             "Duration of last reconciliation loop in seconds",
@@ -59,37 +141,270 @@ impl OperatorMetrics {
             "Current value of the monitored register",
         ))?;
 
+        let reconciliations_total = CounterVec::new(
+            Opts::new(
+                "reconciliations_total",
+                "Total number of reconciliations by result",
+            ),
+            &["result"],
+        )?;
+
+        let plc_reconcile_duration = GaugeVec::new(
+            Opts::new(
+                "plc_reconcile_duration_seconds",
+                "Duration of the last reconciliation of each PLC in seconds",
+            ),
+            &["namespace", "name"],
+        )?;
+
+        let plc_drift_events_total = CounterVec::new(
+            Opts::new(
+                "plc_drift_events_total",
+                "Total number of drift events detected per PLC",
+            ),
+            &["namespace", "name"],
+        )?;
+
+        let plc_value_staleness = GaugeVec::new(
+            Opts::new(
+                "plc_value_staleness_seconds",
+                "Seconds since the target register of each PLC was last read successfully",
+            ),
+            &["namespace", "name"],
+        )?;
+
+        let plc_value_deviations_total = CounterVec::new(
+            Opts::new(
+                "plc_value_deviations_total",
+                "Deviations from the target value seen by the change watch of critical PLCs",
+            ),
+            &["namespace", "name"],
+        )?;
+
+        let plc_clock_skew = GaugeVec::new(
+            Opts::new(
+                "plc_clock_skew_seconds",
+                "PLC clock minus cluster time in seconds for PLCs with a clock check",
+            ),
+            &["namespace", "name"],
+        )?;
+
+        let plc_status_conflicts_total = CounterVec::new(
+            Opts::new(
+                "plc_status_conflicts_total",
+                "Status writes per PLC that conflicted with a concurrent change and were retried",
+            ),
+            &["namespace", "name"],
+        )?;
+
+        let connect_attempts_total = CounterVec::new(
+            Opts::new(
+                "plc_connect_attempts_total",
+                "TCP connection attempts per device by result (connected, timeout, failed)",
+            ),
+            &["device", "result"],
+        )?;
+
+        let reconnects_total = CounterVec::new(
+            Opts::new(
+                "plc_reconnects_total",
+                "Successful connections per device following a failed attempt",
+            ),
+            &["device"],
+        )?;
+
+        let connect_latency = HistogramVec::new(
+            HistogramOpts::new(
+                "plc_connect_latency_seconds",
+                "Time to establish a TCP connection per device in seconds",
+            )
+            .buckets(vec![
+                0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0,
+            ]),
+            &["device"],
+        )?;
+
+        let open_connections = GaugeVec::new(
+            Opts::new(
+                "plc_open_connections",
+                "Modbus TCP connections currently open per device",
+            ),
+            &["device"],
+        )?;
+
+        let fleet_warmup_complete = Gauge::with_opts(Opts::new(
+            "fleet_warmup_complete",
+            "1 once every IndustrialPLC present at startup has been reconciled",
+        ))?;
+
         registry.register(Box::new(drift_events_total.clone()))?;
         registry.register(Box::new(corrections_total.clone()))?;
         registry.register(Box::new(managed_plcs.clone()))?;
+        registry.register(Box::new(unscheduled_plcs.clone()))?;
+        registry.register(Box::new(drift_duration.clone()))?;
+        registry.register(Box::new(longest_current_drift.clone()))?;
         registry.register(Box::new(reconciliation_duration.clone()))?;
         registry.register(Box::new(plc_connection_status.clone()))?;
         registry.register(Box::new(register_value.clone()))?;
+        registry.register(Box::new(reconciliations_total.clone()))?;
+        registry.register(Box::new(plc_reconcile_duration.clone()))?;
+        registry.register(Box::new(plc_drift_events_total.clone()))?;
+        registry.register(Box::new(plc_value_staleness.clone()))?;
+        registry.register(Box::new(plc_value_deviations_total.clone()))?;
+        registry.register(Box::new(plc_clock_skew.clone()))?;
+        registry.register(Box::new(plc_status_conflicts_total.clone()))?;
+        registry.register(Box::new(connect_attempts_total.clone()))?;
+        registry.register(Box::new(reconnects_total.clone()))?;
+        registry.register(Box::new(connect_latency.clone()))?;
+        registry.register(Box::new(open_connections.clone()))?;
+        let controller_stalled = Gauge::with_opts(Opts::new(
+            "controller_stalled",
+            "1 while no reconciliation has completed for longer than the stall threshold",
+        ))?;
+
+        let controller_stalls_total = Counter::with_opts(Opts::new(
+            "controller_stalls_total",
+            "Times the reconcile loop was found stalled",
+        ))?;
+
+        registry.register(Box::new(fleet_warmup_complete.clone()))?;
+        registry.register(Box::new(controller_stalled.clone()))?;
+        registry.register(Box::new(controller_stalls_total.clone()))?;
 
         Ok(Self {
             registry,
             drift_events_total,
             corrections_total,
             managed_plcs,
+            unscheduled_plcs,
+            drift_duration,
+            longest_current_drift,
             reconciliation_duration,
             plc_connection_status,
             register_value,
+            reconciliations_total,
+            plc_reconcile_duration,
+            plc_drift_events_total,
+            plc_value_staleness,
+            plc_value_deviations_total,
+            plc_clock_skew,
+            plc_status_conflicts_total,
+            connect_attempts_total,
+            reconnects_total,
+            connect_latency,
+            open_connections,
+            fleet_warmup_complete,
+            controller_stalled,
+            controller_stalls_total,
+            failing_devices: Arc::default(),
         })
     }
 
-    pub fn record_drift(&self) {
+    pub fn record_drift(&self, namespace: &str, name: &str) {
         self.drift_events_total.inc();
+        self.plc_drift_events_total
+            .with_label_values(&[namespace, name])
+            .inc();
+    }
+
+    pub fn record_deviation(&self, namespace: &str, name: &str) {
+        self.plc_value_deviations_total
+            .with_label_values(&[namespace, name])
+            .inc();
+    }
+
+    /// Record a completed reconciliation; `failed` marks PLC-level failures
+    pub fn record_reconcile(&self, namespace: &str, name: &str, duration_secs: f64, failed: bool) {
+        self.reconciliation_duration.set(duration_secs);
+        self.plc_reconcile_duration
+            .with_label_values(&[namespace, name])
+            .set(duration_secs);
+        self.reconciliations_total
+            .with_label_values(&[if failed { "failed" } else { "success" }])
+            .inc();
+    }
+
+    /// Record a reconciliation that returned a controller error
+    pub fn record_reconcile_error(&self) {
+        self.reconciliations_total
+            .with_label_values(&["error"])
+            .inc();
+    }
+
+    pub fn set_value_staleness(&self, namespace: &str, name: &str, age_secs: i64) {
+        self.plc_value_staleness
+            .with_label_values(&[namespace, name])
+            .set(age_secs as f64);
+    }
+
+    pub fn set_clock_skew(&self, namespace: &str, name: &str, skew_secs: i64) {
+        self.plc_clock_skew
+            .with_label_values(&[namespace, name])
+            .set(skew_secs as f64);
+    }
+
+    pub fn record_status_conflict(&self, namespace: &str, name: &str) {
+        self.plc_status_conflicts_total
+            .with_label_values(&[namespace, name])
+            .inc();
+    }
+
+    /// Drop the per-PLC series of `namespace/name`, once the PLC is gone
+    pub fn forget_plc(&self, namespace: &str, name: &str) {
+        let labels = [namespace, name];
+        self.plc_reconcile_duration
+            .remove_label_values(&labels)
+            .ok();
+        self.plc_drift_events_total
+            .remove_label_values(&labels)
+            .ok();
+        self.plc_value_staleness.remove_label_values(&labels).ok();
+        self.plc_value_deviations_total
+            .remove_label_values(&labels)
+            .ok();
+        self.plc_clock_skew.remove_label_values(&labels).ok();
+        self.plc_status_conflicts_total
+            .remove_label_values(&labels)
+            .ok();
+    }
+
+    /// Drop the per-device series of `device`, once no PLC uses it
+    pub fn forget_device(&self, device: &str) {
+        for outcome in [
+            ConnectOutcome::Connected,
+            ConnectOutcome::TimedOut,
+            ConnectOutcome::Failed,
+        ] {
+            self.connect_attempts_total
+                .remove_label_values(&[device, outcome.as_str()])
+                .ok();
+        }
+        self.reconnects_total.remove_label_values(&[device]).ok();
+        self.connect_latency.remove_label_values(&[device]).ok();
+        self.open_connections.remove_label_values(&[device]).ok();
+        self.failing_devices.lock().unwrap().remove(device);
     }
 
     pub fn record_correction(&self) {
         self.corrections_total.inc();
     }
 
-    #[allow(dead_code)]
     pub fn set_managed_plcs(&self, count: i64) {
         self.managed_plcs.set(count as f64);
     }
 
+    pub fn set_unscheduled_plcs(&self, count: i64) {
+        self.unscheduled_plcs.set(count as f64);
+    }
+
+    pub fn record_drift_duration(&self, secs: f64) {
+        self.drift_duration.observe(secs);
+    }
+
+    pub fn set_longest_current_drift(&self, secs: f64) {
+        self.longest_current_drift.set(secs);
+    }
+
     pub fn set_connection_status(&self, connected: bool) {
         self.plc_connection_status
             .set(if connected { 1.0 } else { 0.0 });
@@ -98,6 +413,62 @@ impl OperatorMetrics {
     pub fn set_register_value(&self, value: u16) {
         self.register_value.set(value as f64);
     }
+
+    pub fn set_warmup_complete(&self) {
+        self.fleet_warmup_complete.set(1.0);
+    }
+
+    /// Mark the reconcile loop stalled or recovered; each stall is counted once
+    pub fn set_controller_stalled(&self, stalled: bool) {
+        if stalled {
+            self.controller_stalls_total.inc();
+        }
+        self.controller_stalled.set(if stalled { 1.0 } else { 0.0 });
+    }
+
+    /// Every metric in the Prometheus text format
+    pub fn encode(&self) -> String {
+        TextEncoder::new()
+            .encode_to_string(&self.registry.gather())
+            .unwrap_or_default()
+    }
+
+    /// Write every metric to `path` in the Prometheus text format, for the
+    /// node exporter's textfile collector. The file is replaced in one step
+    /// so a reader never sees half of it.
+    pub fn write_file(&self, path: &Path) -> anyhow::Result<()> {
+        let tmp = path.with_extension("prom.tmp");
+        std::fs::write(&tmp, self.encode())
+            .with_context(|| format!("Failed to write {}", tmp.display()))?;
+        std::fs::rename(&tmp, path)
+            .with_context(|| format!("Failed to replace {}", path.display()))?;
+        Ok(())
+    }
+}
+
+impl ConnectionObserver for OperatorMetrics {
+    fn connect_attempt(&self, device: &str, outcome: ConnectOutcome, latency: Duration) {
+        self.connect_attempts_total
+            .with_label_values(&[device, outcome.as_str()])
+            .inc();
+
+        let mut failing = self.failing_devices.lock().unwrap();
+        if outcome != ConnectOutcome::Connected {
+            failing.insert(device.to_string());
+            return;
+        }
+        if failing.remove(device) {
+            self.reconnects_total.with_label_values(&[device]).inc();
+        }
+        self.connect_latency
+            .with_label_values(&[device])
+            .observe(latency.as_secs_f64());
+        self.open_connections.with_label_values(&[device]).inc();
+    }
+
+    fn connection_closed(&self, device: &str) {
+        self.open_connections.with_label_values(&[device]).dec();
+    }
 }
 
 impl Default for OperatorMetrics {
@@ -0,0 +1,63 @@
+use fabgitops_modbus::{ConnectOutcome, ConnectionObserver};
+use std::time::Duration;
+
+/// Stand-in for the operator's metrics in builds without the `metrics`
+/// feature. Every method does nothing, so the controller records metrics
+/// the same way in every build.
+#[derive(Clone, Default)]
+pub struct OperatorMetrics;
+
+impl OperatorMetrics {
+    pub fn new() -> anyhow::Result<Self> {
+        Ok(Self)
+    }
+
+    pub fn record_drift(&self, _namespace: &str, _name: &str) {}
+
+    pub fn record_deviation(&self, _namespace: &str, _name: &str) {}
+
+    pub fn record_reconcile(
+        &self,
+        _namespace: &str,
+        _name: &str,
+        _duration_secs: f64,
+        _failed: bool,
+    ) {
+    }
+
+    pub fn record_reconcile_error(&self) {}
+
+    pub fn set_value_staleness(&self, _namespace: &str, _name: &str, _age_secs: i64) {}
+
+    pub fn set_clock_skew(&self, _namespace: &str, _name: &str, _skew_secs: i64) {}
+
+    pub fn record_status_conflict(&self, _namespace: &str, _name: &str) {}
+
+    pub fn forget_plc(&self, _namespace: &str, _name: &str) {}
+
+    pub fn forget_device(&self, _device: &str) {}
+
+    pub fn record_correction(&self) {}
+
+    pub fn set_managed_plcs(&self, _count: i64) {}
+
+    pub fn set_unscheduled_plcs(&self, _count: i64) {}
+
+    pub fn record_drift_duration(&self, _secs: f64) {}
+
+    pub fn set_longest_current_drift(&self, _secs: f64) {}
+
+    pub fn set_connection_status(&self, _connected: bool) {}
+
+    pub fn set_register_value(&self, _value: u16) {}
+
+    pub fn set_warmup_complete(&self) {}
+
+    pub fn set_controller_stalled(&self, _stalled: bool) {}
+}
+
+impl ConnectionObserver for OperatorMetrics {
+    fn connect_attempt(&self, _device: &str, _outcome: ConnectOutcome, _latency: Duration) {}
+
+    fn connection_closed(&self, _device: &str) {}
+}
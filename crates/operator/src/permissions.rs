@@ -0,0 +1,218 @@
+use anyhow::Context as _;
+use fabgitops_types::crd::{
+    FabGitOpsOperator, FabGitOpsOperatorSpec, FabGitOpsOperatorStatus, OperatorCondition,
+    PERMISSIONS_MINIMAL, PERMISSIONS_SUFFICIENT,
+};
+use fabgitops_types::rbac::{self, Permission, OPERATOR_RULES};
+use k8s_openapi::api::authorization::v1::{
+    ResourceAttributes, SelfSubjectAccessReview, SelfSubjectAccessReviewSpec,
+    SelfSubjectRulesReview, SelfSubjectRulesReviewSpec,
+};
+use kube::api::{Api, Patch, PatchParams, PostParams};
+use kube::Client;
+use std::collections::BTreeSet;
+use tracing::{error, info, warn};
+
+/// Granted to every authenticated user by the default `system:basic-user`
+/// role, so never counted as excessive
+const DEFAULT_GRANTS: &[(&str, &str, &str)] = &[
+    ("authentication.k8s.io", "selfsubjectreviews", "create"),
+    ("authorization.k8s.io", "selfsubjectaccessreviews", "create"),
+    ("authorization.k8s.io", "selfsubjectrulesreviews", "create"),
+];
+
+/// Outcome of checking the ServiceAccount against [`OPERATOR_RULES`]
+pub struct PermissionReport {
+    /// Permissions needed but not granted
+    pub missing: Vec<Permission>,
+
+    /// Permissions granted but not needed
+    pub excessive: Vec<Permission>,
+
+    /// Why the granted permissions could not be listed completely, in which
+    /// case `excessive` may be short
+    pub incomplete: Option<String>,
+}
+
+/// Ask the API server which of the operator's permissions are granted, and
+/// which granted permissions it does not need. Permissions are needed
+/// cluster-wide; grants are listed as they apply in `namespace`.
+pub async fn check(client: &Client, namespace: &str) -> anyhow::Result<PermissionReport> {
+    let required = rbac::permissions(OPERATOR_RULES);
+
+    let access_reviews: Api<SelfSubjectAccessReview> = Api::all(client.clone());
+    let mut missing = Vec::new();
+    for permission in &required {
+        let (resource, subresource) = permission.split_resource();
+        let review = SelfSubjectAccessReview {
+            spec: SelfSubjectAccessReviewSpec {
+                resource_attributes: Some(ResourceAttributes {
+                    group: Some(permission.api_group.clone()),
+                    resource: Some(resource.to_string()),
+                    subresource: subresource.map(str::to_string),
+                    verb: Some(permission.verb.clone()),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let allowed = access_reviews
+            .create(&PostParams::default(), &review)
+            .await
+            .with_context(|| format!("Failed to check permission to {}", permission))?
+            .status
+            .is_some_and(|s| s.allowed);
+        if !allowed {
+            missing.push(permission.clone());
+        }
+    }
+
+    let rules_reviews: Api<SelfSubjectRulesReview> = Api::all(client.clone());
+    let review = SelfSubjectRulesReview {
+        spec: SelfSubjectRulesReviewSpec {
+            namespace: Some(namespace.to_string()),
+        },
+        ..Default::default()
+    };
+    let granted = rules_reviews
+        .create(&PostParams::default(), &review)
+        .await
+        .context("Failed to list granted permissions")?
+        .status
+        .unwrap_or_default();
+
+    let required: BTreeSet<Permission> = required.into_iter().collect();
+    let mut excessive = BTreeSet::new();
+    for rule in &granted.resource_rules {
+        for group in rule.api_groups.iter().flatten() {
+            for resource in rule.resources.iter().flatten() {
+                for verb in &rule.verbs {
+                    let permission = Permission::new(group, resource, verb);
+                    let default = DEFAULT_GRANTS
+                        .iter()
+                        .any(|&(g, r, v)| (g, r, v) == (group, resource, verb));
+                    if !default && !required.contains(&permission) {
+                        excessive.insert(permission);
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(PermissionReport {
+        missing,
+        excessive: excessive.into_iter().collect(),
+        incomplete: granted.incomplete.then(|| {
+            granted
+                .evaluation_error
+                .unwrap_or_else(|| "the authorizer could not list every rule".to_string())
+        }),
+    })
+}
+
+impl PermissionReport {
+    /// Log the outcome, as an error for missing permissions
+    pub fn log(&self) {
+        if self.missing.is_empty() {
+            info!("ServiceAccount has every permission the operator needs");
+        } else {
+            error!(
+                "ServiceAccount is missing permissions: {}",
+                join(&self.missing)
+            );
+        }
+        if !self.excessive.is_empty() {
+            warn!(
+                "ServiceAccount has permissions the operator does not need: {}",
+                join(&self.excessive)
+            );
+        }
+        if let Some(reason) = &self.incomplete {
+            warn!("Could not list every granted permission: {}", reason);
+        }
+    }
+
+    /// Status of the FabGitOpsOperator resource reflecting this report
+    pub fn status(&self) -> FabGitOpsOperatorStatus {
+        let sufficient = if self.missing.is_empty() {
+            OperatorCondition::new(
+                PERMISSIONS_SUFFICIENT,
+                Some(true),
+                "AllGranted",
+                "Every permission the operator needs is granted".to_string(),
+            )
+        } else {
+            OperatorCondition::new(
+                PERMISSIONS_SUFFICIENT,
+                Some(false),
+                "MissingPermissions",
+                format!("Not granted: {}", join(&self.missing)),
+            )
+        };
+        let minimal = if !self.excessive.is_empty() {
+            OperatorCondition::new(
+                PERMISSIONS_MINIMAL,
+                Some(false),
+                "ExcessivePermissions",
+                format!("Not needed: {}", join(&self.excessive)),
+            )
+        } else if let Some(reason) = &self.incomplete {
+            OperatorCondition::new(
+                PERMISSIONS_MINIMAL,
+                None,
+                "RulesIncomplete",
+                format!("Could not list every granted permission: {}", reason),
+            )
+        } else {
+            OperatorCondition::new(
+                PERMISSIONS_MINIMAL,
+                Some(true),
+                "NoExcess",
+                "No permissions beyond what the operator needs".to_string(),
+            )
+        };
+
+        FabGitOpsOperatorStatus {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            instance: std::env::var("HOSTNAME").ok(),
+            permissions_checked_at: Some(chrono::Utc::now().to_rfc3339()),
+            missing_permissions: self.missing.iter().map(Permission::to_string).collect(),
+            excessive_permissions: self.excessive.iter().map(Permission::to_string).collect(),
+            conditions: vec![sufficient, minimal],
+        }
+    }
+}
+
+/// Create or update the FabGitOpsOperator `name` in `namespace` with the
+/// outcome of the permission check
+pub async fn record(
+    client: &Client,
+    namespace: &str,
+    name: &str,
+    report: &PermissionReport,
+) -> anyhow::Result<()> {
+    let api: Api<FabGitOpsOperator> = Api::namespaced(client.clone(), namespace);
+    let object = FabGitOpsOperator::new(name, FabGitOpsOperatorSpec {});
+    api.patch(
+        name,
+        &PatchParams::apply(name).force(),
+        &Patch::Apply(&object),
+    )
+    .await?;
+
+    let patch = Patch::Merge(serde_json::json!({
+        "status": report.status()
+    }));
+    api.patch_status(name, &PatchParams::default(), &patch)
+        .await?;
+    Ok(())
+}
+
+fn join(permissions: &[Permission]) -> String {
+    permissions
+        .iter()
+        .map(Permission::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -0,0 +1,111 @@
+use axum::extract::Query;
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use pprof::protos::Message;
+use pprof::{ProfilerGuardBuilder, Report};
+use serde::Deserialize;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tracing::info;
+
+/// Profile length when the request does not say, in seconds
+const DEFAULT_SECONDS: u64 = 30;
+
+/// Longest profile that may be requested, in seconds
+const MAX_SECONDS: u64 = 300;
+
+/// Samples per second; off 100 Hz so sampling does not line up with
+/// periodic work
+const FREQUENCY: i32 = 99;
+
+/// The profiler is process-wide, so one profile is recorded at a time
+static RECORDING: Mutex<()> = Mutex::const_new(());
+
+#[derive(Deserialize)]
+pub struct ProfileParams {
+    /// How long to sample for
+    seconds: Option<u64>,
+}
+
+/// `/debug/pprof/profile`, a CPU profile in pprof's protobuf format as
+/// served by Go's net/http/pprof, and `/debug/pprof/flamegraph`, the same
+/// samples rendered as an SVG flamegraph
+pub fn routes() -> Router {
+    Router::new()
+        .route("/debug/pprof/profile", get(profile_handler))
+        .route("/debug/pprof/flamegraph", get(flamegraph_handler))
+}
+
+async fn profile_handler(Query(params): Query<ProfileParams>) -> Response {
+    let report = match record(params).await {
+        Ok(report) => report,
+        Err(response) => return response,
+    };
+    let mut body = Vec::new();
+    if let Err(e) = report
+        .pprof()
+        .map_err(|e| e.to_string())
+        .and_then(|profile| profile.write_to_vec(&mut body).map_err(|e| e.to_string()))
+    {
+        return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response();
+    }
+    (
+        [
+            (header::CONTENT_TYPE, "application/octet-stream"),
+            (
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"profile.pb\"",
+            ),
+        ],
+        body,
+    )
+        .into_response()
+}
+
+async fn flamegraph_handler(Query(params): Query<ProfileParams>) -> Response {
+    let report = match record(params).await {
+        Ok(report) => report,
+        Err(response) => return response,
+    };
+    let mut body = Vec::new();
+    if let Err(e) = report.flamegraph(&mut body) {
+        return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response();
+    }
+    ([(header::CONTENT_TYPE, "image/svg+xml")], body).into_response()
+}
+
+/// Sample every thread for the requested number of seconds
+async fn record(params: ProfileParams) -> Result<Report, Response> {
+    let seconds = params.seconds.unwrap_or(DEFAULT_SECONDS);
+    if seconds == 0 || seconds > MAX_SECONDS {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("seconds must be 1-{}", MAX_SECONDS),
+        )
+            .into_response());
+    }
+    let Ok(_recording) = RECORDING.try_lock() else {
+        return Err((
+            StatusCode::CONFLICT,
+            "A profile is already being recorded".to_string(),
+        )
+            .into_response());
+    };
+
+    info!("Recording a {}s CPU profile", seconds);
+    // The guard is not Send, so sampling waits on a blocking thread
+    tokio::task::spawn_blocking(move || {
+        let guard = ProfilerGuardBuilder::default()
+            .frequency(FREQUENCY)
+            .blocklist(&["libc", "libgcc", "pthread", "vdso"])
+            .build()?;
+        std::thread::sleep(Duration::from_secs(seconds));
+        guard.report().build()
+    })
+    .await
+    .map_err(|e| e.to_string())
+    .and_then(|report| report.map_err(|e| e.to_string()))
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e).into_response())
+}
@@ -0,0 +1,129 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Where an object is in the reconcile cycle
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum QueueState {
+    /// Known to the controller but not reconciled since the operator started
+    Pending,
+    /// A reconciliation is running
+    Reconciling,
+    /// Waiting for its next scheduled reconciliation
+    Queued,
+}
+
+#[derive(Clone, Debug)]
+struct Entry {
+    state: QueueState,
+    since: DateTime<Utc>,
+    next_reconcile: Option<DateTime<Utc>>,
+    last_result: Option<String>,
+    reconciles: u64,
+}
+
+/// One object as listed by `/debug/queue`
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueItem {
+    pub namespace: String,
+    pub name: String,
+    pub state: QueueState,
+
+    /// Seconds spent in `state`
+    pub age_secs: i64,
+
+    /// When the next reconciliation is due (RFC3339)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_reconcile: Option<String>,
+
+    /// Seconds past `nextReconcile` without a reconciliation starting
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub overdue_secs: Option<i64>,
+
+    /// `success`, or the reason of the last failed reconciliation
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_result: Option<String>,
+
+    /// Reconciliations since the operator started
+    pub reconciles: u64,
+}
+
+/// Record of when each object was reconciled and is due again, kept
+/// because the controller runtime does not expose its work queue
+#[derive(Default)]
+pub struct ReconcileQueue {
+    entries: Mutex<HashMap<(String, String), Entry>>,
+}
+
+impl ReconcileQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A reconciliation of `namespace/name` started
+    pub fn reconciling(&self, namespace: &str, name: &str) {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries
+            .entry((namespace.to_string(), name.to_string()))
+            .or_insert(Entry {
+                state: QueueState::Pending,
+                since: Utc::now(),
+                next_reconcile: None,
+                last_result: None,
+                reconciles: 0,
+            });
+        entry.state = QueueState::Reconciling;
+        entry.since = Utc::now();
+        entry.next_reconcile = None;
+        entry.reconciles += 1;
+    }
+
+    /// A reconciliation of `namespace/name` ended with `result` and asked to
+    /// run again after `after`
+    pub fn requeued(&self, namespace: &str, name: &str, result: &str, after: Duration) {
+        let now = Utc::now();
+        let mut entries = self.entries.lock().unwrap();
+        if let Some(entry) = entries.get_mut(&(namespace.to_string(), name.to_string())) {
+            entry.state = QueueState::Queued;
+            entry.since = now;
+            entry.next_reconcile = chrono::Duration::from_std(after).ok().map(|d| now + d);
+            entry.last_result = Some(result.to_string());
+        }
+    }
+
+    /// Every object in `known`, the objects the controller currently
+    /// watches, most overdue first. Entries for deleted objects are dropped.
+    pub fn snapshot(&self, known: impl IntoIterator<Item = (String, String)>) -> Vec<QueueItem> {
+        let now = Utc::now();
+        let mut entries = self.entries.lock().unwrap();
+        let known: HashSet<(String, String)> = known.into_iter().collect();
+        entries.retain(|key, _| known.contains(key));
+
+        let mut items: Vec<QueueItem> = known
+            .into_iter()
+            .map(|key| {
+                let entry = entries.get(&key);
+                let since = entry.map(|e| e.since);
+                let next_reconcile = entry.and_then(|e| e.next_reconcile);
+                QueueItem {
+                    state: entry.map_or(QueueState::Pending, |e| e.state),
+                    age_secs: since.map_or(0, |since| (now - since).num_seconds()),
+                    next_reconcile: next_reconcile.map(|t| t.to_rfc3339()),
+                    overdue_secs: next_reconcile
+                        .map(|t| (now - t).num_seconds())
+                        .filter(|&secs| secs > 0),
+                    last_result: entry.and_then(|e| e.last_result.clone()),
+                    reconciles: entry.map_or(0, |e| e.reconciles),
+                    namespace: key.0,
+                    name: key.1,
+                }
+            })
+            .collect();
+        items.sort_by_key(|item| Reverse((item.overdue_secs.unwrap_or(0), item.age_secs)));
+        items
+    }
+}
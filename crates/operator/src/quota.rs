@@ -0,0 +1,50 @@
+use fabgitops_types::crd::IndustrialPLC;
+use kube::runtime::reflector::Store;
+use kube::ResourceExt;
+
+/// Cap on the number of PLCs the operator manages. Slots go to the oldest
+/// PLCs by creation time, then namespace and name, so a flood of new PLCs
+/// cannot push out established ones. Every replica ranks the same way, and
+/// PLCs beyond the cap stay `Unscheduled` until older ones are deleted.
+pub struct PlcQuota {
+    max: usize,
+    store: Store<IndustrialPLC>,
+}
+
+impl PlcQuota {
+    /// Rank PLCs from the controller's cache, which holds every PLC
+    pub fn new(max: usize, store: Store<IndustrialPLC>) -> Self {
+        Self { max, store }
+    }
+
+    pub fn max(&self) -> usize {
+        self.max
+    }
+
+    /// Whether `plc` is among the `max` oldest PLCs
+    pub fn admits(&self, plc: &IndustrialPLC) -> bool {
+        let rank = rank(plc);
+        let older = self
+            .store
+            .state()
+            .iter()
+            .filter(|other| self::rank(other) < rank)
+            .count();
+        older < self.max
+    }
+
+    /// PLCs managed and left unscheduled out of every PLC known
+    pub fn split(&self) -> (usize, usize) {
+        let total = self.store.len();
+        let managed = total.min(self.max);
+        (managed, total - managed)
+    }
+}
+
+fn rank(plc: &IndustrialPLC) -> (Option<chrono::DateTime<chrono::Utc>>, String, String) {
+    (
+        plc.creation_timestamp().map(|time| time.0),
+        plc.namespace().unwrap_or_default(),
+        plc.name_any(),
+    )
+}
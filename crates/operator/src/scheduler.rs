@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::{sleep_until, Instant};
+
+/// Spaces out reconciles that talk to the same device address, so PLCs
+/// sharing a gateway do not all poll it at the top of their interval, and
+/// caps how many of them are in flight at once.
+pub struct DeviceScheduler {
+    max_in_flight: usize,
+    spacing: Duration,
+    devices: Mutex<HashMap<String, Arc<Device>>>,
+}
+
+struct Device {
+    in_flight: Arc<Semaphore>,
+    /// Earliest time the next reconcile of this device may start
+    next_start: Mutex<Instant>,
+}
+
+/// A reconcile's turn on a device, released on drop
+pub struct DevicePermit {
+    _permit: OwnedSemaphorePermit,
+}
+
+impl DeviceScheduler {
+    pub fn new(max_in_flight: usize, spacing: Duration) -> Self {
+        Self {
+            max_in_flight: max_in_flight.max(1),
+            spacing,
+            devices: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Wait for a turn on the device at `address:port`: at least the
+    /// configured spacing after the previous reconcile of it started, and
+    /// while fewer than the maximum are in flight
+    pub async fn acquire(&self, address: &str, port: u16) -> DevicePermit {
+        let device = self.device(format!("{}:{}", address, port));
+
+        let start = {
+            let mut next_start = device.next_start.lock().expect("scheduler lock");
+            let start = (*next_start).max(Instant::now());
+            *next_start = start + self.spacing;
+            start
+        };
+        sleep_until(start).await;
+
+        let permit = device
+            .in_flight
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("device semaphore is never closed");
+        DevicePermit { _permit: permit }
+    }
+
+    fn device(&self, key: String) -> Arc<Device> {
+        let mut devices = self.devices.lock().expect("scheduler lock");
+        devices
+            .entry(key)
+            .or_insert_with(|| {
+                Arc::new(Device {
+                    in_flight: Arc::new(Semaphore::new(self.max_in_flight)),
+                    next_start: Mutex::new(Instant::now()),
+                })
+            })
+            .clone()
+    }
+}
@@ -0,0 +1,200 @@
+use anyhow::Context as _;
+use futures::channel::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use k8s_openapi::api::coordination::v1::{Lease, LeaseSpec};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::{MicroTime, ObjectMeta};
+use kube::api::{Api, DeleteParams, ListParams, Patch, PatchParams};
+use kube::Client;
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Label on the Leases through which operator replicas find each other
+pub const MEMBER_LABEL: &str = "fabgitops.io/shard-member";
+
+/// Points each replica takes on the hash ring; more points spread PLCs
+/// more evenly
+const VIRTUAL_NODES: usize = 64;
+
+/// Owners of PLCs by consistent hashing. Adding or removing a replica only
+/// moves the PLCs that hash next to its points.
+#[derive(Debug, Default)]
+struct ShardRing {
+    members: Vec<String>,
+    /// Hash of each point and the index of its member, ascending
+    points: Vec<(u64, usize)>,
+}
+
+impl ShardRing {
+    fn new(mut members: Vec<String>) -> Self {
+        members.sort();
+        members.dedup();
+        let mut points: Vec<(u64, usize)> = members
+            .iter()
+            .enumerate()
+            .flat_map(|(i, member)| {
+                (0..VIRTUAL_NODES).map(move |n| (fnv1a(&format!("{}#{}", member, n)), i))
+            })
+            .collect();
+        points.sort_unstable();
+        Self { members, points }
+    }
+
+    /// The member owning `key`: the one with the first point at or after
+    /// the key's hash, wrapping around
+    fn owner(&self, key: &str) -> Option<&str> {
+        if self.points.is_empty() {
+            return None;
+        }
+        let hash = fnv1a(key);
+        let i = self.points.partition_point(|&(point, _)| point < hash);
+        let (_, member) = self.points[i % self.points.len()];
+        Some(&self.members[member])
+    }
+}
+
+/// 64-bit FNV-1a, stable across builds so every replica hashes alike
+fn fnv1a(key: &str) -> u64 {
+    key.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// This replica's share of the PLCs when several operator replicas run.
+/// Each replica holds a Lease labelled `fabgitops.io/shard-member` in the
+/// operator's namespace and renews it a few times per lease duration.
+/// Replicas whose Lease has expired are dropped, and every replica hashes
+/// `namespace/name` of each PLC onto the ring of live members to decide
+/// whether it is the owner.
+pub struct Sharding {
+    identity: String,
+    /// Field manager of the Lease
+    manager: String,
+    leases: Api<Lease>,
+    lease_duration: Duration,
+    ring: RwLock<ShardRing>,
+    rebalanced: UnboundedSender<()>,
+}
+
+impl Sharding {
+    /// Register as `identity` and learn the current members. The receiver
+    /// yields whenever membership, and with it PLC ownership, changes.
+    pub async fn join(
+        client: Client,
+        namespace: &str,
+        identity: String,
+        manager: &str,
+        lease_duration: Duration,
+    ) -> anyhow::Result<(Self, UnboundedReceiver<()>)> {
+        let (rebalanced, rebalances) = mpsc::unbounded();
+        let sharding = Self {
+            identity,
+            manager: manager.to_string(),
+            leases: Api::namespaced(client, namespace),
+            lease_duration,
+            ring: RwLock::default(),
+            rebalanced,
+        };
+        sharding.renew().await?;
+        sharding.refresh().await?;
+        Ok((sharding, rebalances))
+    }
+
+    /// Whether this replica reconciles the PLC `namespace/name`
+    pub fn owns(&self, namespace: &str, name: &str) -> bool {
+        let ring = self.ring.read().unwrap();
+        let key = format!("{}/{}", namespace, name);
+        // With no live member at all, keep everything rather than nothing
+        ring.owner(&key).is_none_or(|owner| owner == self.identity)
+    }
+
+    /// Renew this replica's Lease and pick up membership changes, forever
+    pub async fn run(&self) {
+        loop {
+            tokio::time::sleep(self.lease_duration / 3).await;
+            if let Err(e) = self.renew().await {
+                warn!("Failed to renew shard lease: {:#}", e);
+            }
+            if let Err(e) = self.refresh().await {
+                warn!("Failed to list shard members: {:#}", e);
+            }
+        }
+    }
+
+    /// Delete this replica's Lease, so the others take over its PLCs at
+    /// once instead of after the lease expires
+    pub async fn leave(&self) {
+        match self
+            .leases
+            .delete(&self.lease_name(), &DeleteParams::default())
+            .await
+        {
+            Ok(_) => info!("Left shard ring as {}", self.identity),
+            Err(e) => warn!("Failed to delete shard lease: {}", e),
+        }
+    }
+
+    fn lease_name(&self) -> String {
+        format!("fabgitops-shard-{}", self.identity)
+    }
+
+    async fn renew(&self) -> anyhow::Result<()> {
+        let name = self.lease_name();
+        let lease = Lease {
+            metadata: ObjectMeta {
+                name: Some(name.clone()),
+                labels: Some(BTreeMap::from([(
+                    MEMBER_LABEL.to_string(),
+                    "true".to_string(),
+                )])),
+                ..Default::default()
+            },
+            spec: Some(LeaseSpec {
+                holder_identity: Some(self.identity.clone()),
+                lease_duration_seconds: Some(self.lease_duration.as_secs() as i32),
+                renew_time: Some(MicroTime(chrono::Utc::now())),
+                ..Default::default()
+            }),
+        };
+        self.leases
+            .patch(
+                &name,
+                &PatchParams::apply(&self.manager).force(),
+                &Patch::Apply(&lease),
+            )
+            .await
+            .with_context(|| format!("Failed to apply lease {}", name))?;
+        Ok(())
+    }
+
+    async fn refresh(&self) -> anyhow::Result<()> {
+        let now = chrono::Utc::now();
+        let leases = self
+            .leases
+            .list(&ListParams::default().labels(MEMBER_LABEL))
+            .await?;
+        let members: Vec<String> = leases
+            .items
+            .into_iter()
+            .filter_map(|lease| {
+                let spec = lease.spec?;
+                let renewed = spec.renew_time?.0;
+                let duration = chrono::Duration::seconds(spec.lease_duration_seconds?.into());
+                (renewed + duration > now).then_some(spec.holder_identity?)
+            })
+            .collect();
+
+        let ring = ShardRing::new(members);
+        let mut current = self.ring.write().unwrap();
+        if ring.members != current.members {
+            info!(
+                "Shard members: {} (this replica: {})",
+                ring.members.join(", "),
+                self.identity
+            );
+            *current = ring;
+            self.rebalanced.unbounded_send(()).ok();
+        }
+        Ok(())
+    }
+}
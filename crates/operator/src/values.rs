@@ -0,0 +1,133 @@
+use fabgitops_modbus::encode_ascii;
+use fabgitops_types::crd::{DataType, IndustrialPLC, ValueQuality};
+use kube::ResourceExt;
+use serde::Serialize;
+
+/// One managed register as listed by `/api/v1/values`
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegisterValue {
+    pub namespace: String,
+    pub name: String,
+
+    /// Write block the register belongs to; none for the target register
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block: Option<String>,
+
+    pub register: u16,
+
+    /// Value as of the last poll; none before the first read
+    pub value: Option<u16>,
+
+    pub desired_value: u16,
+
+    pub in_sync: bool,
+
+    /// Freshness of the target register's value; block registers share it,
+    /// being read on the same polls
+    pub quality: ValueQuality,
+
+    /// When the PLC was last read successfully (RFC3339)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub read_at: Option<String>,
+}
+
+/// Every managed register of `plcs`, from the values their statuses
+/// recorded on the last poll, ordered by PLC and register
+pub fn register_values<'a>(
+    plcs: impl IntoIterator<Item = &'a IndustrialPLC>,
+) -> Vec<RegisterValue> {
+    let mut values = Vec::new();
+    for plc in plcs {
+        let status = plc.status.clone().unwrap_or_default();
+        let row = |block: Option<&str>, register, value: Option<u16>, desired| RegisterValue {
+            namespace: plc.namespace().unwrap_or_default(),
+            name: plc.name_any(),
+            block: block.map(str::to_string),
+            register,
+            value,
+            desired_value: desired,
+            in_sync: value == Some(desired),
+            quality: status.value_quality,
+            read_at: status.last_good_read_time.clone(),
+        };
+
+        values.push(row(
+            None,
+            plc.spec.target_register,
+            status.current_value,
+            plc.spec.target(),
+        ));
+
+        for block in &plc.spec.write_blocks {
+            let desired = match block.data_type {
+                DataType::Uint16 => block.values.clone(),
+                DataType::String => encode_ascii(
+                    block.text.as_deref().unwrap_or_default(),
+                    block.length.unwrap_or_default(),
+                ),
+            };
+            let state = status.blocks.iter().find(|b| b.name == block.name);
+            let actual = state.and_then(|state| match block.data_type {
+                // Registers not listed as drifted hold their desired value
+                DataType::Uint16 => Some(
+                    desired
+                        .iter()
+                        .enumerate()
+                        .map(|(i, &value)| {
+                            state
+                                .drifted_offsets
+                                .iter()
+                                .position(|&offset| usize::from(offset) == i)
+                                .and_then(|j| state.drifted_values.get(j).copied())
+                                .unwrap_or(value)
+                        })
+                        .collect::<Vec<u16>>(),
+                ),
+                DataType::String => state
+                    .actual_text
+                    .as_deref()
+                    .map(|text| encode_ascii(text, block.length.unwrap_or_default())),
+            });
+            for (i, &desired) in desired.iter().enumerate() {
+                let value = actual.as_ref().and_then(|actual| actual.get(i).copied());
+                let register = block.start_register.wrapping_add(i as u16);
+                values.push(row(Some(&block.name), register, value, desired));
+            }
+        }
+    }
+    values.sort_by(|a, b| {
+        (&a.namespace, &a.name, a.register).cmp(&(&b.namespace, &b.name, b.register))
+    });
+    values
+}
+
+/// `values` as CSV with a header row
+pub fn to_csv(values: &[RegisterValue]) -> String {
+    let mut csv =
+        String::from("namespace,name,block,register,value,desiredValue,inSync,quality,readAt\n");
+    for v in values {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{},{:?},{}\n",
+            csv_field(&v.namespace),
+            csv_field(&v.name),
+            csv_field(v.block.as_deref().unwrap_or_default()),
+            v.register,
+            v.value.map(|value| value.to_string()).unwrap_or_default(),
+            v.desired_value,
+            v.in_sync,
+            v.quality,
+            v.read_at.as_deref().unwrap_or_default()
+        ));
+    }
+    csv
+}
+
+/// Quote a field holding a comma, quote or line break
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
@@ -0,0 +1,115 @@
+use crate::metrics::OperatorMetrics;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+type Key = (String, String);
+
+/// The first reconciliation of every PLC that existed when the operator
+/// started. At most `concurrency` run at once, so a large fleet gets its
+/// status and metrics populated quickly without opening hundreds of device
+/// connections at the same moment. The operator reports ready once all of
+/// them have finished.
+pub struct FleetWarmup {
+    pending: Mutex<HashSet<Key>>,
+    permits: Arc<Semaphore>,
+    complete: AtomicBool,
+    started: Instant,
+    metrics: Arc<OperatorMetrics>,
+}
+
+impl FleetWarmup {
+    /// Warm up `fleet`, reporting completion through `metrics`
+    pub fn new(
+        fleet: impl IntoIterator<Item = Key>,
+        concurrency: usize,
+        metrics: Arc<OperatorMetrics>,
+    ) -> Self {
+        let warmup = Self {
+            pending: Mutex::new(fleet.into_iter().collect()),
+            permits: Arc::new(Semaphore::new(concurrency)),
+            complete: AtomicBool::new(false),
+            started: Instant::now(),
+            metrics,
+        };
+        info!(
+            "Warming up {} PLC(s), {} at a time",
+            warmup.pending.lock().unwrap().len(),
+            concurrency
+        );
+        warmup.check_complete(&warmup.pending.lock().unwrap());
+        warmup
+    }
+
+    /// Whether every PLC has been reconciled once
+    pub fn is_complete(&self) -> bool {
+        self.complete.load(Ordering::Relaxed)
+    }
+
+    /// Wait for a warm-up slot if this is the first reconciliation of a PLC
+    /// being warmed up. The PLC counts as warmed up when the guard drops.
+    pub async fn begin(self: &Arc<Self>, namespace: &str, name: &str) -> Option<WarmupGuard> {
+        let key = (namespace.to_string(), name.to_string());
+        if !self.pending.lock().unwrap().contains(&key) {
+            return None;
+        }
+        let permit = self.permits.clone().acquire_owned().await.ok()?;
+        Some(WarmupGuard {
+            warmup: self.clone(),
+            key,
+            _permit: permit,
+        })
+    }
+
+    /// Give up on PLCs not reconciled after `timeout`, such as ones deleted
+    /// since the operator started, so readiness is not held back forever
+    pub async fn expire_after(&self, timeout: Duration) {
+        tokio::time::sleep(timeout).await;
+        let mut pending = self.pending.lock().unwrap();
+        if pending.is_empty() {
+            return;
+        }
+        let names: Vec<String> = pending
+            .iter()
+            .map(|(ns, n)| format!("{}/{}", ns, n))
+            .collect();
+        warn!(
+            "Fleet warm-up timed out after {:?}; not reconciled: {}",
+            timeout,
+            names.join(", ")
+        );
+        pending.clear();
+        self.check_complete(&pending);
+    }
+
+    fn finish(&self, key: &Key) {
+        let mut pending = self.pending.lock().unwrap();
+        if pending.remove(key) {
+            self.check_complete(&pending);
+        }
+    }
+
+    fn check_complete(&self, pending: &HashSet<Key>) {
+        if pending.is_empty() && !self.complete.swap(true, Ordering::Relaxed) {
+            self.metrics.set_warmup_complete();
+            info!("Fleet warm-up complete in {:?}", self.started.elapsed());
+        }
+    }
+}
+
+/// Held for the duration of a PLC's first reconciliation
+pub struct WarmupGuard {
+    warmup: Arc<FleetWarmup>,
+    key: Key,
+    _permit: OwnedSemaphorePermit,
+}
+
+impl Drop for WarmupGuard {
+    fn drop(&mut self) {
+        self.warmup.finish(&self.key);
+    }
+}
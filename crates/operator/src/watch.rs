@@ -0,0 +1,218 @@
+use crate::metrics::OperatorMetrics;
+use crate::scheduler::DeviceScheduler;
+use fabgitops_modbus::PLCClient;
+use fabgitops_types::crd::IndustrialPLC;
+use fabgitops_types::HOLD_UNTIL_ANNOTATION;
+use kube::runtime::events::{Event, EventType, Recorder, Reporter};
+use kube::{Client, Resource, ResourceExt};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::task::AbortHandle;
+use tokio::time::MissedTickBehavior;
+use tracing::{info, warn};
+
+/// Wait before reconnecting after the watch connection fails
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+type Key = (String, String);
+
+/// Reads the target register of `critical` PLCs many times a second, each on
+/// a task of its own, so a deviation from the target value is reported the
+/// moment it happens instead of at the next reconcile. Modbus TCP has no way
+/// for a device to push changes, so this is fast polling over one connection
+/// kept open per watched PLC. Each poll takes a turn on the device from the
+/// same scheduler as reconciles.
+pub struct ChangeWatcher {
+    client: Client,
+    reporter: Reporter,
+    metrics: Arc<OperatorMetrics>,
+    scheduler: Arc<DeviceScheduler>,
+    watches: Mutex<HashMap<Key, Watch>>,
+}
+
+struct Watch {
+    /// `metadata.generation` of the spec being watched
+    generation: Option<i64>,
+    /// The `fabgitops.io/hold-until` annotation the watch started with
+    hold_until: Option<String>,
+    task: AbortHandle,
+}
+
+impl Drop for Watch {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl ChangeWatcher {
+    pub fn new(
+        client: Client,
+        reporter: Reporter,
+        metrics: Arc<OperatorMetrics>,
+        scheduler: Arc<DeviceScheduler>,
+    ) -> Self {
+        Self {
+            client,
+            reporter,
+            metrics,
+            scheduler,
+            watches: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Start watching `plc` if it is critical, restart after its spec or
+    /// hold changed, or stop once it is no longer critical
+    pub fn sync(&self, plc: &IndustrialPLC, plc_client: &PLCClient) {
+        let key = (plc.namespace().unwrap_or_default(), plc.name_any());
+        let mut watches = self.watches.lock().unwrap();
+        if !plc.spec.critical {
+            if watches.remove(&key).is_some() {
+                info!("Stopped change watch of {}/{}", key.0, key.1);
+            }
+            return;
+        }
+        let generation = plc.metadata.generation;
+        let hold_until = plc.annotations().get(HOLD_UNTIL_ANNOTATION).cloned();
+        if watches
+            .get(&key)
+            .is_some_and(|w| w.generation == generation && w.hold_until == hold_until)
+        {
+            return;
+        }
+
+        let recorder = Recorder::new(
+            self.client.clone(),
+            self.reporter.clone(),
+            plc.object_ref(&()),
+        );
+        let task = tokio::spawn(watch(
+            plc.clone(),
+            plc_client.clone(),
+            recorder,
+            self.metrics.clone(),
+            self.scheduler.clone(),
+        ));
+        info!(
+            "Watching register {} of {}/{} every {}ms",
+            plc.spec.target_register, key.0, key.1, plc.spec.change_watch_interval_ms
+        );
+        watches.insert(
+            key,
+            Watch {
+                generation,
+                hold_until,
+                task: task.abort_handle(),
+            },
+        );
+    }
+
+    /// Stop watching the PLC `namespace/name`
+    pub fn stop(&self, namespace: &str, name: &str) {
+        let key = (namespace.to_string(), name.to_string());
+        self.watches.lock().unwrap().remove(&key);
+    }
+
+    /// Stop watching PLCs not in `known`, such as deleted ones
+    pub fn retain(&self, known: impl IntoIterator<Item = Key>) {
+        let known: HashSet<Key> = known.into_iter().collect();
+        self.watches
+            .lock()
+            .unwrap()
+            .retain(|key, _| known.contains(key));
+    }
+}
+
+/// Poll the target register of `plc` until aborted, reporting each change
+/// from matching the target value to deviating from it. A deviation present
+/// when the watch starts was already reported by the reconcile.
+async fn watch(
+    plc: IndustrialPLC,
+    plc_client: PLCClient,
+    recorder: Recorder,
+    metrics: Arc<OperatorMetrics>,
+    scheduler: Arc<DeviceScheduler>,
+) {
+    let namespace = plc.namespace().unwrap_or_default();
+    let name = plc.name_any();
+    let register = plc.spec.target_register;
+    let mut deviating = None;
+    // Failures are logged once until the next successful read
+    let mut healthy = true;
+
+    loop {
+        let session = match plc_client.session().await {
+            Ok(session) => session,
+            Err(e) => {
+                if healthy {
+                    warn!(
+                        "Change watch of {}/{} cannot connect: {:#}",
+                        namespace, name, e
+                    );
+                }
+                healthy = false;
+                tokio::time::sleep(RECONNECT_DELAY).await;
+                continue;
+            }
+        };
+
+        let mut ticks =
+            tokio::time::interval(Duration::from_millis(plc.spec.change_watch_interval_ms));
+        ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            ticks.tick().await;
+            let permit = scheduler
+                .acquire(&plc.spec.device_address, plc.spec.port)
+                .await;
+            let read = session.read_register(register).await;
+            drop(permit);
+            let value = match read {
+                Ok(value) => value,
+                Err(e) => {
+                    if healthy {
+                        warn!(
+                            "Change watch of {}/{} failed to read register {}: {:#}",
+                            namespace, name, register, e
+                        );
+                    }
+                    healthy = false;
+                    break;
+                }
+            };
+            healthy = true;
+
+            // Whatever the register holds is desired while a hold is in force
+            let deviates =
+                !plc.spec.matches_target(value) && plc.hold_until(chrono::Utc::now()).is_none();
+            if deviates && deviating == Some(false) {
+                metrics.record_deviation(&namespace, &name);
+                warn!(
+                    "Register {} of {}/{} deviated: desired={}, actual={}",
+                    register,
+                    namespace,
+                    name,
+                    plc.spec.target(),
+                    value
+                );
+                recorder
+                    .publish(Event {
+                        type_: EventType::Warning,
+                        reason: "ValueDeviated".to_string(),
+                        note: Some(format!(
+                            "Register {} changed to {} (desired {}), seen by the change watch",
+                            register,
+                            value,
+                            plc.spec.target()
+                        )),
+                        action: "ChangeWatch".to_string(),
+                        secondary: None,
+                    })
+                    .await
+                    .ok();
+            }
+            deviating = Some(deviates);
+        }
+        session.close().await;
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
@@ -0,0 +1,134 @@
+use crate::metrics::OperatorMetrics;
+use crate::queue::{QueueState, ReconcileQueue};
+use crate::sharding::Sharding;
+use fabgitops_types::crd::IndustrialPLC;
+use kube::runtime::events::{Event, EventType, Recorder};
+use kube::runtime::reflector::Store;
+use kube::ResourceExt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::time::Instant;
+use tracing::{error, info};
+
+/// Watches the reconcile loop itself. A hung watch, or reconciles all stuck
+/// on a lock or an unresponsive device, leave the operator running while
+/// PLCs silently stop being checked. The watchdog declares the loop stalled
+/// when a PLC has been overdue or reconciling for longer than the threshold
+/// and no reconciliation has completed in that time; it clears once one
+/// completes again.
+pub struct ControllerWatchdog {
+    threshold: Duration,
+    last_completed: Mutex<Instant>,
+    stalled: AtomicBool,
+    stalls: Notify,
+    metrics: Arc<OperatorMetrics>,
+}
+
+impl ControllerWatchdog {
+    pub fn new(threshold: Duration, metrics: Arc<OperatorMetrics>) -> Self {
+        Self {
+            threshold,
+            last_completed: Mutex::new(Instant::now()),
+            stalled: AtomicBool::new(false),
+            stalls: Notify::new(),
+            metrics,
+        }
+    }
+
+    /// A reconciliation finished, successfully or with an error
+    pub fn completed(&self) {
+        *self.last_completed.lock().unwrap() = Instant::now();
+        if self.stalled.swap(false, Ordering::Relaxed) {
+            info!("Reconciliations are completing again; controller no longer stalled");
+            self.metrics.set_controller_stalled(false);
+        }
+    }
+
+    /// Whether the reconcile loop is currently stalled
+    pub fn is_stalled(&self) -> bool {
+        self.stalled.load(Ordering::Relaxed)
+    }
+
+    /// Wait until the reconcile loop is next found stalled
+    pub async fn stalled(&self) {
+        self.stalls.notified().await
+    }
+
+    /// Check the PLCs this replica reconciles, as listed by `queue`, several
+    /// times per threshold, forever. Each stall is logged, counted and
+    /// reported as a Warning event through `recorder`.
+    pub async fn run(
+        &self,
+        queue: &ReconcileQueue,
+        store: Store<IndustrialPLC>,
+        sharding: Option<Arc<Sharding>>,
+        recorder: Recorder,
+    ) {
+        let tick = (self.threshold / 10).clamp(Duration::from_secs(1), Duration::from_secs(30));
+        loop {
+            tokio::time::sleep(tick).await;
+            if self.is_stalled() {
+                continue;
+            }
+            let idle = self.last_completed.lock().unwrap().elapsed();
+            if idle < self.threshold {
+                continue;
+            }
+
+            let known = store
+                .state()
+                .into_iter()
+                .map(|plc| (plc.namespace().unwrap_or_default(), plc.name_any()))
+                .filter(|(namespace, name)| {
+                    sharding.as_ref().is_none_or(|s| s.owns(namespace, name))
+                });
+            let threshold = self.threshold.as_secs() as i64;
+            let waiting: Vec<_> = queue
+                .snapshot(known)
+                .into_iter()
+                .map(|item| {
+                    let secs = match item.state {
+                        QueueState::Reconciling => item.age_secs,
+                        _ => item.overdue_secs.unwrap_or(0),
+                    };
+                    (item, secs)
+                })
+                .filter(|(_, secs)| *secs >= threshold)
+                .collect();
+            let Some((longest, secs)) = waiting.iter().max_by_key(|(_, secs)| *secs) else {
+                continue;
+            };
+
+            let message = format!(
+                "No reconciliation has completed for {}s while {} PLC(s) waited longer than {}s; {}/{} has been {} for {}s",
+                idle.as_secs(),
+                waiting.len(),
+                threshold,
+                longest.namespace,
+                longest.name,
+                if longest.state == QueueState::Reconciling {
+                    "reconciling"
+                } else {
+                    "overdue"
+                },
+                secs
+            );
+            error!("Controller stalled: {}", message);
+            self.stalled.store(true, Ordering::Relaxed);
+            self.metrics.set_controller_stalled(true);
+            self.stalls.notify_one();
+            recorder
+                .publish(Event {
+                    type_: EventType::Warning,
+                    reason: "ControllerStalled".to_string(),
+                    note: Some(message),
+                    action: "Reconcile".to_string(),
+                    secondary: None,
+                })
+                .await
+                .ok();
+        }
+    }
+}